@@ -0,0 +1,130 @@
+//! Defaults for the command line arguments from a repo-local `.subweight.toml`.
+//!
+//! The values of the config are set as default values of the arguments. Arguments that are passed
+//! on the command line therefore take precedence.
+
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = ".subweight.toml";
+
+/// Example:
+///
+/// ```toml
+/// path-pattern = "runtime/*/src/weights/*.rs"
+/// method = "guess-worst"
+/// threshold = 10
+/// ignore-pallets = ["frame_benchmarking_pallet_pov"]
+/// ```
+#[derive(Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+	pub path_pattern: Option<String>,
+	pub method: Option<String>,
+	pub unit: Option<String>,
+	pub threshold: Option<f64>,
+	/// Eg. `200us` or `4KiB`.
+	pub threshold_abs: Option<String>,
+	pub ignore_errors: Option<bool>,
+	pub format: Option<String>,
+	/// Regexes of pallets to skip.
+	#[serde(default)]
+	pub ignore_pallets: Vec<String>,
+	/// Regexes of extrinsics to skip.
+	#[serde(default)]
+	pub ignore_extrinsics: Vec<String>,
+	/// Private SSH key for fetching.
+	pub ssh_key: Option<String>,
+	/// Proxy for fetching over HTTPS.
+	pub proxy: Option<String>,
+}
+
+impl Config {
+	/// Load the config from `path` or from the closest `.subweight.toml` of the current folder.
+	pub fn load(path: Option<&Path>) -> Result<Option<Self>, String> {
+		let path = match path {
+			Some(path) => path.to_path_buf(),
+			None => match find(&std::env::current_dir().map_err(|e| e.to_string())?) {
+				Some(path) => path,
+				None => return Ok(None),
+			},
+		};
+		let content = std::fs::read_to_string(&path)
+			.map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+		let config = toml::from_str(&content)
+			.map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+
+		log::info!("Using config {}", path.display());
+		Ok(Some(config))
+	}
+
+	/// Set the config values as defaults of the arguments with the same name.
+	///
+	/// Most keys only apply to the arguments of their parameter group, eg. `threshold` to the one
+	/// of the [`FilterParams`](subweight_core::FilterParams) but not to the one of `bisect`.
+	pub fn apply(&self, mut cmd: clap::Command) -> clap::Command {
+		for (id, group, values) in self.defaults() {
+			let in_group = |group: &str| {
+				cmd.get_groups().any(|g| g.get_id() == group && g.get_args().any(|a| a == id))
+			};
+			if cmd.get_arguments().any(|a| a.get_id() == id) && group.map_or(true, in_group) {
+				// Clap only takes static default values. The config lives for the whole run anyway.
+				let values = values.into_iter().map(|v| &*Box::leak(v.into_boxed_str()));
+				// A configured value also satisfies a `required_unless_present`.
+				cmd = cmd.mut_arg(id, |a| {
+					a.required(false)
+						.required_unless_present(clap::builder::Resettable::Reset)
+						.default_values(values)
+				});
+			}
+		}
+
+		let subs = cmd.get_subcommands().map(|s| s.get_name().to_string()).collect::<Vec<_>>();
+		for sub in subs {
+			cmd = cmd.mut_subcommand(sub, |s| self.apply(s));
+		}
+		cmd
+	}
+
+	/// The argument ids, the group that they must belong to and their default values.
+	///
+	/// The group of a flattened parameter struct is named after the struct.
+	fn defaults(&self) -> Vec<(&'static str, Option<&'static str>, Vec<String>)> {
+		const COMPARE: Option<&str> = Some("CompareParams");
+		const FILTER: Option<&str> = Some("FilterParams");
+		let mut defaults = vec![
+			("path_pattern", None, self.path_pattern.clone()),
+			("method", COMPARE, self.method.clone()),
+			("unit", COMPARE, self.unit.clone()),
+			("threshold", FILTER, self.threshold.map(|t| t.to_string())),
+			("threshold_abs", FILTER, self.threshold_abs.clone()),
+			("ignore_errors", COMPARE, self.ignore_errors.map(|i| i.to_string())),
+			("format", Some("FormatParams"), self.format.clone()),
+			("ssh_key", None, self.ssh_key.clone()),
+			("proxy", None, self.proxy.clone()),
+		]
+		.into_iter()
+		.filter_map(|(id, group, value)| value.map(|v| (id, group, vec![v])))
+		.collect::<Vec<_>>();
+
+		if !self.ignore_pallets.is_empty() {
+			defaults.push(("ignore_pallet", FILTER, self.ignore_pallets.clone()));
+		}
+		if !self.ignore_extrinsics.is_empty() {
+			defaults.push(("ignore_extrinsic", FILTER, self.ignore_extrinsics.clone()));
+		}
+		defaults
+	}
+}
+
+/// Search the config file in `dir` and its parents.
+fn find(dir: &Path) -> Option<PathBuf> {
+	dir.ancestors().map(|d| d.join(FILE_NAME)).find(|p| p.is_file())
+}
+
+/// The value of `--config` since it is needed before the arguments are parsed.
+pub fn config_arg(args: &[String]) -> Option<PathBuf> {
+	args.windows(2)
+		.find(|w| w[0] == "--config")
+		.map(|w| PathBuf::from(&w[1]))
+		.or_else(|| args.iter().find_map(|a| a.strip_prefix("--config=")).map(PathBuf::from))
+}
@@ -0,0 +1,163 @@
+//! Report the weight changes as GitHub check run with annotations on the weight files.
+//!
+//! The annotations are placed on the lines of the weight functions, so they show up inline in the
+//! diff of a pull request. They are the same findings as the ones of the SARIF output; see
+//! [`crate::sarif`]. Meant to run in GitHub Actions, which sets these variables:
+//!
+//! - `GITHUB_REPOSITORY`: The repository as `owner/name`.
+//! - `GITHUB_SHA`: The commit of the check run. The head of the pull request is used instead if
+//!   `GITHUB_EVENT_PATH` is the event of a pull request, since `GITHUB_SHA` is its merge commit.
+//! - `GITHUB_API_URL`: The API; defaults to `https://api.github.com`.
+//!
+//! The `GITHUB_TOKEN` needs the `checks: write` permission.
+
+use serde_json::{json, Value};
+use subweight_core::{Dimension, ExtrinsicDiff};
+
+use crate::sarif;
+
+/// GitHub takes at most this many annotations per request; the rest is sent in more requests.
+const MAX_ANNOTATIONS: usize = 50;
+
+/// GitHub rejects longer summaries.
+const MAX_SUMMARY: usize = 65535;
+
+/// A commit that check runs are created for; not `Debug` to keep the token out of the logs.
+pub struct Commit {
+	pub api_url: String,
+	pub repository: String,
+	pub sha: String,
+	token: String,
+}
+
+impl Commit {
+	/// Read the commit from the variables of the module docs; unless `sha` is given.
+	pub fn from_env(sha: Option<String>) -> Result<Self, String> {
+		let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+		let sha = match sha.or_else(pull_request_head) {
+			Some(sha) => sha,
+			None => var("GITHUB_SHA").ok_or("Neither --sha nor GITHUB_SHA is set")?,
+		};
+		Ok(Self {
+			api_url: var("GITHUB_API_URL")
+				.unwrap_or_else(|| "https://api.github.com".into())
+				.trim_end_matches('/')
+				.into(),
+			repository: var("GITHUB_REPOSITORY").ok_or("GITHUB_REPOSITORY is not set")?,
+			sha,
+			token: var("GITHUB_TOKEN").ok_or("GITHUB_TOKEN is not set")?,
+		})
+	}
+
+	/// Create a completed check run; see [`check_run`].
+	pub fn create_check_run(&self, run: &Value) -> Result<(), String> {
+		let url = format!("{}/repos/{}/check-runs", self.api_url, self.repository);
+		let mut run = run.clone();
+		run["head_sha"] = json!(self.sha);
+		let annotations = run["output"]["annotations"].take();
+		let mut batches =
+			annotations.as_array().map(|a| a.chunks(MAX_ANNOTATIONS)).into_iter().flatten();
+		run["output"]["annotations"] = json!(batches.next().unwrap_or_default());
+
+		let created = self
+			.request("POST", &url)
+			.send_string(&run.to_string())
+			.map_err(|e| format!("Failed to create a check run at {}: {}", url, e))?
+			.into_json::<Value>()
+			.map_err(|e| format!("Invalid check run of {}: {}", url, e))?;
+		let id = created["id"]
+			.as_u64()
+			.ok_or_else(|| format!("Check run without id: {}", created))?;
+		log::info!("Created check run {}", id);
+
+		// The annotations of each update are added to the ones before.
+		let url = format!("{}/{}", url, id);
+		for batch in batches {
+			let update = json!({ "output": {
+				"title": run["output"]["title"],
+				"summary": run["output"]["summary"],
+				"annotations": batch,
+			}});
+			self.request("PATCH", &url)
+				.send_string(&update.to_string())
+				.map_err(|e| format!("Failed to annotate the check run {}: {}", url, e))?;
+		}
+		Ok(())
+	}
+
+	fn request(&self, method: &str, url: &str) -> ureq::Request {
+		ureq::request(method, url)
+			.set("User-Agent", "subweight")
+			.set("Accept", "application/vnd.github+json")
+			.set("Authorization", &format!("Bearer {}", self.token))
+			.set("Content-Type", "application/json")
+	}
+}
+
+/// The head commit of the pull request of the `GITHUB_EVENT_PATH`; if any.
+fn pull_request_head() -> Option<String> {
+	let path = std::env::var("GITHUB_EVENT_PATH").ok()?;
+	let event = serde_json::from_str::<Value>(&std::fs::read_to_string(path).ok()?).ok()?;
+	event["pull_request"]["head"]["sha"].as_str().map(Into::into)
+}
+
+/// A completed check run without its commit.
+///
+/// It fails if `failed` is set and is neutral if any change has an annotation.
+pub fn check_run(
+	name: &str,
+	summary: &str,
+	diff: &[ExtrinsicDiff],
+	unit: Dimension,
+	failed: bool,
+) -> Value {
+	let annotations = annotations(diff, unit);
+	let conclusion = match (failed, annotations.is_empty()) {
+		(true, _) => "failure",
+		(false, false) => "neutral",
+		(false, true) => "success",
+	};
+	let mut summary = summary.to_string();
+	if summary.len() > MAX_SUMMARY {
+		let mut end = MAX_SUMMARY - 100;
+		while !summary.is_char_boundary(end) {
+			end -= 1;
+		}
+		summary.truncate(end);
+		summary.push_str("\n\nThe summary is truncated; see the annotations for all findings.");
+	}
+
+	json!({
+		"name": name,
+		"status": "completed",
+		"conclusion": conclusion,
+		"output": {
+			"title": format!("{} weight changes, {} annotations", diff.len(), annotations.len()),
+			"summary": summary,
+			"annotations": annotations,
+		},
+	})
+}
+
+/// An annotation on the weight function of each finding; changes without span are skipped.
+fn annotations(diff: &[ExtrinsicDiff], unit: Dimension) -> Vec<Value> {
+	diff.iter()
+		.filter_map(|d| {
+			let (rule, level, message) = sarif::finding(d, unit)?;
+			let span = d.span?;
+			let level = match level {
+				"error" => "failure",
+				"note" => "notice",
+				_ => "warning",
+			};
+			Some(json!({
+				"path": d.file,
+				"start_line": span.function.start,
+				"end_line": span.function.end,
+				"annotation_level": level,
+				"title": format!("{}: {}", d.name, rule),
+				"message": message,
+			}))
+		})
+		.collect()
+}
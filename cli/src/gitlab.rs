@@ -0,0 +1,95 @@
+//! Post the weight changes as note on a GitLab merge request; like the webhook of the web server
+//! does for GitHub pull requests.
+//!
+//! Meant to run in a merge request pipeline of GitLab CI, which sets these variables:
+//!
+//! - `CI_API_V4_URL`: The API, eg. `https://gitlab.com/api/v4`.
+//! - `CI_PROJECT_ID`: The project of the merge request.
+//! - `CI_MERGE_REQUEST_IID`: The merge request within its project.
+//!
+//! The job token of CI cannot write notes, so a project or personal access token with the `api`
+//! scope has to be set as `SUBWEIGHT_GITLAB_TOKEN` or `GITLAB_TOKEN`. Each run updates the note of
+//! the previous one instead of adding another.
+
+use serde::Deserialize;
+
+/// A merge request that notes are posted on; not `Debug` to keep the token out of the logs.
+pub struct MergeRequest {
+	pub api_url: String,
+	pub project: String,
+	pub iid: u64,
+	token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+	id: u64,
+	body: String,
+}
+
+impl MergeRequest {
+	/// Read the merge request from the CI variables of the module docs.
+	pub fn from_env() -> Result<Self, String> {
+		let var = |name: &str| {
+			std::env::var(name)
+				.ok()
+				.filter(|v| !v.is_empty())
+				.ok_or_else(|| format!("{} is not set; is this a merge request pipeline?", name))
+		};
+		let token = var("SUBWEIGHT_GITLAB_TOKEN")
+			.or_else(|_| var("GITLAB_TOKEN"))
+			.map_err(|_| "Neither SUBWEIGHT_GITLAB_TOKEN nor GITLAB_TOKEN is set".to_string())?;
+		let iid = var("CI_MERGE_REQUEST_IID")?;
+		Ok(Self {
+			api_url: var("CI_API_V4_URL")?.trim_end_matches('/').into(),
+			project: var("CI_PROJECT_ID")?,
+			iid: iid.parse().map_err(|_| format!("Invalid CI_MERGE_REQUEST_IID: {}", iid))?,
+			token,
+		})
+	}
+
+	/// Update the note that has the same `tag` or else add a new one.
+	///
+	/// The tag is an invisible comment at the start of the note. Jobs that post their own notes
+	/// need different tags.
+	pub fn upsert_note(&self, tag: &str, body: &str) -> Result<(), String> {
+		let body = note_body(tag, body);
+		let notes =
+			format!("{}/projects/{}/merge_requests/{}/notes", self.api_url, self.project, self.iid);
+		let request = |method: &str, url: &str| {
+			ureq::request(method, url)
+				.set("User-Agent", "subweight")
+				.set("PRIVATE-TOKEN", &self.token)
+		};
+
+		let existing =
+			request("GET", &format!("{}?sort=desc&order_by=updated_at&per_page=100", notes))
+				.call()
+				.map_err(|e| format!("Failed to list the notes of {}: {}", notes, e))?
+				.into_json::<Vec<Note>>()
+				.map_err(|e| format!("Invalid notes of {}: {}", notes, e))?
+				.into_iter()
+				.find(|n| n.body.starts_with(&marker(tag)));
+
+		let json = serde_json::json!({ "body": body }).to_string();
+		let (method, url) = match &existing {
+			Some(note) => ("PUT", format!("{}/{}", notes, note.id)),
+			None => ("POST", notes),
+		};
+		log::info!("{} {}", method, url);
+		request(method, &url)
+			.set("Content-Type", "application/json")
+			.send_string(&json)
+			.map_err(|e| format!("Failed to write the note {}: {}", url, e))?;
+		Ok(())
+	}
+}
+
+/// The note with its `tag`.
+pub fn note_body(tag: &str, body: &str) -> String {
+	format!("{}\n{}", marker(tag), body)
+}
+
+fn marker(tag: &str) -> String {
+	format!("<!-- {} -->", tag)
+}
@@ -1,14 +1,53 @@
-use clap::{Args, Parser};
+use clap::{builder::ArgPredicate, Args, CommandFactory, FromArgMatches, Parser};
 use comfy_table::Table;
-use std::{fmt::Write as _, path::PathBuf};
+use std::{
+	fmt::Write as _,
+	io::{IsTerminal, Read},
+	path::{Path, PathBuf},
+};
 
+#[cfg(feature = "sqlite")]
+use subweight_core::db::{WeightDb, WeightRecord};
 use subweight_core::{
-	compare_commits, compare_files, filter_changes,
-	parse::pallet::{parse_files, try_parse_files},
-	sort_changes, CompareParams, Dimension, FilterParams, Percent, RelativeChange, TotalDiff,
-	VERSION,
+	access::{self, AccessChange, ProofSize},
+	allowlist::{self, Allowlist},
+	auth,
+	budget::{block_shares, budget_usage, BlockLimits, BudgetUsage},
+	compare_commits_since_base, compare_commits_with_progress, compare_files, compare_local,
+	compare_machines, compare_remote, compare_repos_with_progress, compare_workdir,
+	component_scope, diff_stats, evaluate_extrinsic, filter_changes,
+	format::format_file,
+	history::{bisect_regression, commit_before, extrinsic_history, release_tags, HistoryPoint},
+	hooks_dir,
+	lint::{lint_extrinsic, LintFinding, LintParams},
+	parse::{
+		machine, overhead,
+		pallet::{
+			parse_any_content, parse_file, parse_files, parse_paths, try_parse_files,
+			try_parse_paths, ChromaticExtrinsic,
+		},
+		storage, try_parse_file as try_parse_any_file,
+	},
+	parse_abs_value, parse_component, percent,
+	remote::{fetch_url, GithubRepo},
+	schema::{self, VersionedDiff},
+	scope::{ChromaticScope, SimpleScope},
+	sort_changes_by, sparse_clone, summarize_matrix, summarize_pallets, summarize_runtimes,
+	term::SimpleTerm,
+	watch::Watch,
+	CompareParams, DbWeights, DiffStats, Dimension, ExtrinsicDiff, FilterParams, MachineDiff,
+	PalletSummary, Percent, RelativeChange, RuntimeSummary, SortBy, TermChange, TermDiff,
+	TotalDiff, VERSION,
 };
 
+mod config;
+mod github;
+mod gitlab;
+mod progress;
+mod report;
+mod sarif;
+mod template;
+
 #[derive(Debug, Parser)]
 #[clap(author, version(&VERSION[..]))]
 struct MainCmd {
@@ -17,14 +56,54 @@ struct MainCmd {
 
 	#[clap(long)]
 	verbose: bool,
+
+	/// Config file with defaults for the arguments.
+	///
+	/// Defaults to the closest `.subweight.toml` of the current folder or its parents.
+	#[clap(long, global = true, value_name = "FILE")]
+	config: Option<PathBuf>,
+
+	/// Private SSH key for fetching; overrides `SUBWEIGHT_SSH_KEY`.
+	///
+	/// The SSH agent is used without a key. A token for HTTPS remotes is read from
+	/// `SUBWEIGHT_GIT_TOKEN` or `GITHUB_TOKEN`.
+	#[clap(long, global = true, value_name = "FILE")]
+	ssh_key: Option<PathBuf>,
+
+	/// Proxy for fetching over HTTPS, eg. `http://proxy:3128`; overrides `SUBWEIGHT_PROXY`.
+	#[clap(long, global = true, value_name = "URL")]
+	proxy: Option<String>,
 }
 
 #[derive(Debug, clap::Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum SubCommand {
 	#[clap(subcommand)]
 	Compare(CompareCmd),
 	#[clap(subcommand)]
 	Parse(ParseCmd),
+	Show(ShowCmd),
+	History(HistoryCmd),
+	Bisect(BisectCmd),
+	#[clap(subcommand)]
+	Check(CheckCmd),
+	Lint(LintCmd),
+	Format(FormatCmd),
+	Watch(WatchCmd),
+	#[clap(subcommand)]
+	Hook(HookCmd),
+	#[clap(subcommand)]
+	Report(ReportCmd),
+	Merge(MergeCmd),
+	#[clap(subcommand)]
+	Github(GithubCmd),
+	#[clap(subcommand)]
+	Gitlab(GitlabCmd),
+	Stats(StatsCmd),
+	Schema(SchemaCmd),
+	#[cfg(feature = "sqlite")]
+	#[clap(subcommand)]
+	Db(DbCmd),
 }
 
 /// Compare weight files.
@@ -32,89 +111,972 @@ enum SubCommand {
 enum CompareCmd {
 	Files(CompareFilesCmd),
 	Commits(CompareCommitsCmd),
+	Workdir(CompareWorkdirCmd),
+	Runtimes(CompareRuntimesCmd),
+	Overhead(CompareOverheadCmd),
+	StorageWeights(CompareStorageWeightsCmd),
+	Machines(CompareMachinesCmd),
+	Accesses(CompareAccessesCmd),
+}
+
+/// Tries to parse all files in the given file list or folder.
+#[derive(Debug, clap::Subcommand)]
+enum ParseCmd {
+	Files(ParseFilesCmd),
+	Dump(ParseDumpCmd),
+}
+
+/// Check weight files against limits.
+#[derive(Debug, clap::Subcommand)]
+enum CheckCmd {
+	Budget(CheckBudgetCmd),
+}
+
+/// Compare a local set of weight files.
+#[derive(Debug, Parser)]
+struct CompareFilesCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	/// The old weight files, directories or archives of them, or glob patterns.
+	///
+	/// Files are paired by name; the files of a directory or a `.tar`, `.tar.gz` or `.zip` archive
+	/// by their path relative to it. `-` reads a file from stdin and `https://` URLs are
+	/// downloaded, unless `--offline` is set.
+	#[clap(long, required(true), num_args = 0..)]
+	pub old: Vec<PathBuf>,
+
+	/// The new weight files, directories or archives of them, or glob patterns.
+	#[clap(long, required(true), num_args = 0..)]
+	pub new: Vec<PathBuf>,
+}
+
+/// Compare the weight files in the working directory with a commit, eg. after benchmarking.
+///
+/// Uncommitted and untracked files are compared as they are; the working directory is not reset.
+#[derive(Debug, Parser)]
+struct CompareWorkdirCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	/// Old commit/branch/tag.
+	#[clap(long, value_name = "REF", default_value = "master")]
+	pub against: String,
+
+	/// The working directory; can be a sub-folder of the repository.
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	/// Can also be set in the config file.
+	///
+	/// Defaults to `**/*.rs` with `--auto-discover`.
+	#[clap(
+		long,
+		required = false,
+		required_unless_present = "auto_discover",
+		default_value_if("auto_discover", ArgPredicate::IsPresent, "**/*.rs")
+	)]
+	pub path_pattern: String,
+}
+
+/// Compare the weights of two runtimes of a repository at the same commit.
+#[derive(Debug, Parser)]
+struct CompareRuntimesCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	/// Commit/branch/tag of both runtimes.
+	#[clap(long = "ref", value_name = "REF", default_value = "master")]
+	pub refname: String,
+
+	/// Folder of the runtime that is compared as old; relative to `--repo`.
+	#[clap(long, value_name = "FOLDER")]
+	pub a: PathBuf,
+
+	/// Folder of the runtime that is compared as new; relative to `--repo`.
+	#[clap(long, value_name = "FOLDER")]
+	pub b: PathBuf,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	/// The weight files of each runtime; relative to its folder.
+	#[clap(long = "path-pattern", id = "runtime_path_pattern", default_value = "src/weights/*.rs")]
+	pub path_pattern: String,
+}
+
+/// Compare weight files across commits.
+#[derive(Debug, Parser)]
+struct CompareCommitsCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	/// Old commit/branch/tag.
+	#[clap(name = "OLD-COMMIT", index = 1)]
+	pub old: String,
+
+	/// New commit/branch/tag.
+	#[clap(name = "NEW-COMMIT", index = 2, default_value = "master")]
+	pub new: String,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	/// Repository of the old commit. Defaults to `--repo`.
+	///
+	/// Can be a sub-folder of a repository to compare weights that were moved.
+	#[clap(long)]
+	pub old_repo: Option<PathBuf>,
+
+	/// Repository of the new commit. Defaults to `--repo`.
+	///
+	/// Can be a sub-folder of a repository to compare weights that were moved.
+	#[clap(long)]
+	pub new_repo: Option<PathBuf>,
+
+	/// URL of a GitHub repository to fetch the weight files from; instead of using a local clone.
+	///
+	/// Set `GITHUB_TOKEN` to avoid the API rate limit. If `--repo` does not exist, the weight
+	/// files of both commits are cloned into it instead.
+	#[clap(long, value_name = "URL", conflicts_with_all = ["old_repo", "new_repo"])]
+	pub remote: Option<GithubRepo>,
+
+	/// Compare the last commit of the OLD-COMMIT branch before this date, eg. `2024-01-01`.
+	///
+	/// Only the first-parent history of the branch is searched.
+	#[clap(long, value_name = "YYYY-MM-DD", conflicts_with = "remote")]
+	pub old_date: Option<String>,
+
+	/// Compare the last commit of the NEW-COMMIT branch before this date.
+	#[clap(long, value_name = "YYYY-MM-DD", conflicts_with = "remote")]
+	pub new_date: Option<String>,
+
+	/// Only report the changes that NEW-COMMIT made since it branched off this ref.
+	///
+	/// Compares the merge base of both with NEW-COMMIT and skips changes that are already in
+	/// OLD-COMMIT. Useful when a pull request is behind its target branch.
+	#[clap(long, value_name = "REF", conflicts_with_all = ["old_repo", "new_repo", "remote"])]
+	pub base: Option<String>,
+
+	/// Can also be set in the config file.
+	///
+	/// Defaults to `**/*.rs` with `--auto-discover`.
+	#[clap(
+		long,
+		required = false,
+		required_unless_present = "auto_discover",
+		default_value_if("auto_discover", ArgPredicate::IsPresent, "**/*.rs")
+	)]
+	pub path_pattern: String,
+}
+
+/// Compare block execution and extrinsic base weight files.
+///
+/// These are generated by `benchmark overhead` and often named `block_weights.rs` and
+/// `extrinsic_weights.rs`.
+#[derive(Debug, Parser)]
+struct CompareOverheadCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	/// The old overhead weight files.
+	#[clap(long, required(true), num_args = 0..)]
+	pub old: Vec<PathBuf>,
+
+	/// The new overhead weight files.
+	#[clap(long, required(true), num_args = 0..)]
+	pub new: Vec<PathBuf>,
+}
+
+/// Compare the read and write weights of storage DB weight files.
+///
+/// These are generated by `benchmark storage` and often named `rocksdb_weights.rs` and
+/// `paritydb_weights.rs`. Weights are compared per DB backend.
+#[derive(Debug, Parser)]
+struct CompareStorageWeightsCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	/// The old storage weight files.
+	#[clap(long, required(true), num_args = 0..)]
+	pub old: Vec<PathBuf>,
+
+	/// The new storage weight files.
+	#[clap(long, required(true), num_args = 0..)]
+	pub new: Vec<PathBuf>,
+}
+
+/// Compare the hardware scores of two `benchmark machine` runs.
+///
+/// Expects the JSON output of the command. Scores are throughputs - so higher is better.
+#[derive(Debug, Parser)]
+struct CompareMachinesCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	/// Minimal magnitude of a relative change to be relevant.
+	#[clap(long, value_name = "PERCENT", default_value = "5")]
+	pub threshold: Percent,
+
+	/// The old machine benchmark results.
+	#[clap(long)]
+	pub old: PathBuf,
+
+	/// The new machine benchmark results.
+	#[clap(long)]
+	pub new: PathBuf,
+}
+
+/// Compare the storage items that each extrinsic accesses across commits.
+///
+/// Uses the `Storage: Pallet Item (r:1 w:1)` comments of the weight files. Reports the items that
+/// an extrinsic starts or stops to access and changed read or write counts.
+#[derive(Debug, Parser)]
+struct CompareAccessesCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	/// Old commit/branch/tag.
+	#[clap(name = "OLD-COMMIT", index = 1)]
+	pub old: String,
+
+	/// New commit/branch/tag.
+	#[clap(name = "NEW-COMMIT", index = 2, default_value = "master")]
+	pub new: String,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	#[clap(long)]
+	pub path_pattern: String,
+
+	/// Fetch the commits from `origin` first.
+	#[clap(long)]
+	pub git_pull: bool,
+}
+
+#[derive(Debug, Parser)]
+struct ParseFilesCmd {
+	/// The files to parse.
+	#[clap(long, index = 1, required(true), num_args = 0..1000)]
+	pub files: Vec<PathBuf>,
+}
+
+/// Print the extrinsics of weight files with their weight terms and component ranges.
+///
+/// Pallet, storage and overhead weight files are supported.
+#[derive(Debug, Parser)]
+struct ParseDumpCmd {
+	/// The files to parse.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	/// Set the format of the output. Supports `human`, `markdown` and `json`.
+	#[clap(long, value_name = "FORMAT", default_value = "human", ignore_case = true)]
+	pub format: OutputFormat,
+}
+
+/// Evaluate the weight of an extrinsic for specific component values.
+///
+/// Example: `show weights/*.rs --pallet pallet_staking --extrinsic payout_stakers --set n=64`.
+#[derive(Debug, Parser)]
+struct ShowCmd {
+	/// The pallet weight files to search for the extrinsic.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	/// Name of the pallet; its weight file name with or without the `.rs` extension.
+	#[clap(long)]
+	pub pallet: Option<String>,
+
+	#[clap(long)]
+	pub extrinsic: String,
+
+	/// Values of the components, eg. `n=64,m=16`.
+	///
+	/// Components without a value are set to the maximum of their range.
+	#[clap(long, value_name = "NAME=VALUE", value_delimiter = ',', value_parser = parse_component)]
+	pub set: Vec<(String, u128)>,
+
+	/// Time weight of one storage read and write; `rocksdb`, `paritydb` or `READ,WRITE`.
+	#[clap(long, value_name = "PRESET|READ,WRITE", default_value = "rocksdb", value_parser = <DbWeights as std::str::FromStr>::from_str)]
+	pub db_weights: DbWeights,
+}
+
+/// Track the weight of an extrinsic across a range of commits.
+///
+/// Only commits that changed the weight file are listed.
+#[derive(Debug, Parser)]
+struct HistoryCmd {
+	/// Range of commits/branches/tags like `v0.9.30..v1.0.0`.
+	#[clap(index = 1)]
+	pub range: String,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	/// Path of the weight file relative to the repository.
+	#[clap(long)]
+	pub file: PathBuf,
+
+	#[clap(long)]
+	pub extrinsic: String,
+
+	/// Values of the components, eg. `n=64,m=16`.
+	///
+	/// Components without a value are set to the maximum of their range.
+	#[clap(long, value_name = "NAME=VALUE", value_delimiter = ',', value_parser = parse_component)]
+	pub set: Vec<(String, u128)>,
+
+	/// Time weight of one storage read and write; `rocksdb`, `paritydb` or `READ,WRITE`.
+	#[clap(long, value_name = "PRESET|READ,WRITE", default_value = "rocksdb", value_parser = <DbWeights as std::str::FromStr>::from_str)]
+	pub db_weights: DbWeights,
+
+	/// Set the format of the output. Supports `human`, `markdown`, `csv` and `json`.
+	#[clap(long, value_name = "FORMAT", default_value = "human", ignore_case = true)]
+	pub format: OutputFormat,
+}
+
+/// Find the first commit at which the weight of an extrinsic regressed.
+///
+/// Narrows the commits between a good and a bad ref down like `git bisect`.
+#[derive(Debug, Parser)]
+struct BisectCmd {
+	/// Commit/branch/tag at which the weight was still good.
+	#[clap(long)]
+	pub good: String,
+
+	/// Commit/branch/tag at which the weight is regressed.
+	#[clap(long, default_value = "master")]
+	pub bad: String,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	/// Path of the weight file relative to the repository.
+	#[clap(long)]
+	pub file: PathBuf,
+
+	#[clap(long)]
+	pub extrinsic: String,
+
+	/// Values of the components, eg. `n=64,m=16`.
+	///
+	/// Components without a value are set to the maximum of their range.
+	#[clap(long, value_name = "NAME=VALUE", value_delimiter = ',', value_parser = parse_component)]
+	pub set: Vec<(String, u128)>,
+
+	/// Time weight of one storage read and write; `rocksdb`, `paritydb` or `READ,WRITE`.
+	#[clap(long, value_name = "PRESET|READ,WRITE", default_value = "rocksdb", value_parser = <DbWeights as std::str::FromStr>::from_str)]
+	pub db_weights: DbWeights,
+
+	/// Increase over the weight of the good commit that counts as regression.
+	#[clap(long, value_name = "PERCENT", default_value = "5")]
+	pub threshold: Percent,
+
+	#[clap(long, short, value_name = "UNIT", ignore_case = true, default_value = "time")]
+	pub unit: Dimension,
+}
+
+/// Fail if the worst case of any extrinsic uses too much of a block.
+///
+/// Components without a value are set to the maximum of their range.
+#[derive(Debug, Parser)]
+struct CheckBudgetCmd {
+	/// The pallet weight files to check.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	/// Reference time limit of a block.
+	#[clap(long, value_name = "VALUE", default_value = "2s", value_parser = parse_abs_value)]
+	pub max_time: u128,
+
+	/// Proof size limit of a block.
+	#[clap(long, value_name = "VALUE", default_value = "5MiB", value_parser = parse_abs_value)]
+	pub max_proof: u128,
+
+	/// Share of the block in percent that a single extrinsic may use in each dimension.
+	#[clap(long, value_name = "PERCENT", default_value = "75")]
+	pub fraction: Percent,
+
+	/// Values of the components, eg. `n=64,m=16`.
+	#[clap(long, value_name = "NAME=VALUE", value_delimiter = ',', value_parser = parse_component)]
+	pub set: Vec<(String, u128)>,
+
+	/// Time weight of one storage read and write; `rocksdb`, `paritydb` or `READ,WRITE`.
+	#[clap(long, value_name = "PRESET|READ,WRITE", default_value = "rocksdb", value_parser = <DbWeights as std::str::FromStr>::from_str)]
+	pub db_weights: DbWeights,
+
+	/// Skip extrinsics that cannot be evaluated instead of failing.
+	#[clap(long)]
+	pub ignore_errors: bool,
+
+	/// Set the format of the output. Supports `human`, `markdown` and `csv`.
+	#[clap(long, value_name = "FORMAT", default_value = "human", ignore_case = true)]
+	pub format: OutputFormat,
+}
+
+/// Run sanity checks on weight files and fail if any of them finds a problem.
+#[derive(Debug, Parser)]
+struct LintCmd {
+	/// The pallet weight files to check.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: LintParams,
+
+	/// Skip files that cannot be parsed instead of failing.
+	#[clap(long)]
+	pub ignore_errors: bool,
+
+	/// Set the format of the output. Supports `human`, `markdown` and `csv`.
+	#[clap(long, value_name = "FORMAT", default_value = "human", ignore_case = true)]
+	pub format: OutputFormat,
+}
+
+/// Rewrite weight files into a canonical form to make their diffs reviewable.
+///
+/// Sorts the parts of each weight, uses `Weight::from_parts` and separates literals with `_`.
+/// Comments within the weight expressions are dropped.
+#[derive(Debug, Parser)]
+struct FormatCmd {
+	/// The pallet weight files to format in place.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	/// Only check that the files are formatted and fail otherwise; without writing them.
+	#[clap(long)]
+	pub check: bool,
+}
+
+/// Compare the weight files of the working tree with a ref whenever they change.
+///
+/// The files are checked for changes every `--interval` and the diff is printed again after each
+/// change. Stop it with Ctrl-C.
+#[derive(Debug, Parser)]
+struct WatchCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	/// Commit/branch/tag to compare the working tree with.
+	#[clap(long, value_name = "REF", default_value = "master")]
+	pub baseline: String,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	#[clap(long)]
+	pub path_pattern: String,
+
+	/// Milliseconds between two checks for changes.
+	#[clap(long, value_name = "MS", default_value = "500")]
+	pub interval: u64,
+}
+
+/// Check the weight files in a git hook before they are committed or pushed.
+#[derive(Debug, clap::Subcommand)]
+#[allow(clippy::large_enum_variant)]
+enum HookCmd {
+	Install(HookInstallCmd),
+	Run(HookRunCmd),
+}
+
+/// Install a git hook that runs `subweight hook run`; `subweight` has to be in the `PATH`.
+#[derive(Debug, Parser)]
+struct HookInstallCmd {
+	/// The repository; the path pattern is relative to this sub-folder of it.
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	#[clap(long, value_name = "KIND", default_value = "pre-commit", ignore_case = true)]
+	pub kind: HookKind,
+
+	/// Replace an existing hook.
+	#[clap(long)]
+	pub force: bool,
+}
+
+/// Compare the weight files that are about to be committed or pushed and fail on regressions.
+///
+/// A pre-commit hook compares the staged files with `HEAD` and a pre-push hook compares `HEAD` with
+/// its upstream branch. The weights are read from the git objects and nothing is parsed if no
+/// weight file changed. Fails on increases above the threshold unless `--fail-on` is set; the
+/// threshold and path pattern are best set in the config file.
+#[derive(Debug, Parser)]
+struct HookRunCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub format: FormatParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	#[clap(long, value_name = "KIND", default_value = "pre-commit", ignore_case = true)]
+	pub kind: HookKind,
+
+	/// Can also be set in the config file.
+	///
+	/// Defaults to `**/*.rs` with `--auto-discover`.
+	#[clap(
+		long,
+		required = false,
+		required_unless_present = "auto_discover",
+		default_value_if("auto_discover", ArgPredicate::IsPresent, "**/*.rs")
+	)]
+	pub path_pattern: String,
+}
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+	/// Runs before each commit.
+	PreCommit,
+	/// Runs before each push.
+	PrePush,
+}
+
+impl HookKind {
+	/// File name of the hook and value of `--kind`.
+	fn name(&self) -> &'static str {
+		match self {
+			Self::PreCommit => "pre-commit",
+			Self::PrePush => "pre-push",
+		}
+	}
+}
+
+/// Write reports about the weight changes.
+#[derive(Debug, clap::Subcommand)]
+enum ReportCmd {
+	Releases(ReportReleasesCmd),
+}
+
+/// Compare each release with the one before it.
+///
+/// The releases are the tags that match `--tag-pattern`, ordered by their version. The report has
+/// an overview of all releases and one section with the changed extrinsics of each release.
+#[derive(Debug, Parser)]
+struct ReportReleasesCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub params: CompareParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub filter: FilterParams,
+
+	#[clap(long, default_value = ".")]
+	pub repo: PathBuf,
+
+	#[clap(long)]
+	pub path_pattern: String,
+
+	/// Glob pattern of the release tags.
+	#[clap(long, default_value = "v*")]
+	pub tag_pattern: String,
+
+	/// Only report the last N releases.
+	#[clap(long, value_name = "N")]
+	pub last: Option<usize>,
+
+	#[clap(long, value_enum, ignore_case = true, default_value = "markdown")]
+	pub format: report::ReportFormat,
+}
+
+/// Combine comparisons that were exported with `--format json` into one report.
+///
+/// Each file becomes a section that is named after the file, eg. `polkadot` for `polkadot.json`.
+#[derive(Debug, Parser)]
+struct MergeCmd {
+	/// The exported comparisons.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	/// The unit that the comparisons were exported with.
+	#[clap(long, short, value_name = "UNIT", ignore_case = true, default_value = "time")]
+	pub unit: Dimension,
+
+	#[clap(long, value_enum, ignore_case = true, default_value = "markdown")]
+	pub format: report::ReportFormat,
+}
+
+/// Report weight changes on GitHub.
+#[derive(Debug, clap::Subcommand)]
+enum GithubCmd {
+	Check(GithubCheckCmd),
+}
+
+/// Create a check run with annotations on the weight functions of comparisons that were exported
+/// with `--format json`.
+///
+/// Reviewers see increased, added and failed extrinsics inline in the diff of a pull request; this
+/// needs the file paths relative to the repository, as `compare commits` exports them. The summary
+/// is rendered like the Markdown of `merge`. The commit and repository are read from the
+/// `GITHUB_*` variables of GitHub Actions and the `GITHUB_TOKEN` needs `checks: write`.
+#[derive(Debug, Parser)]
+struct GithubCheckCmd {
+	/// The exported comparisons.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	/// The unit that the comparisons were exported with.
+	#[clap(long, short, value_name = "UNIT", ignore_case = true, default_value = "time")]
+	pub unit: Dimension,
+
+	/// Name of the check run.
+	#[clap(long, default_value = "subweight")]
+	pub name: String,
+
+	/// Commit of the check run; defaults to the head of the pull request or `GITHUB_SHA`.
+	#[clap(long)]
+	pub sha: Option<String>,
+
+	/// The check run fails on these kinds of changes; see `--fail-on` of `compare`.
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub gate: GateParams,
+
+	/// Print the check run instead of creating it.
+	#[clap(long)]
+	pub dry_run: bool,
 }
 
-/// Tries to parse all files in the given file list or folder.
+/// Post weight changes on GitLab merge requests.
 #[derive(Debug, clap::Subcommand)]
-enum ParseCmd {
-	Files(ParseFilesCmd),
+enum GitlabCmd {
+	Note(GitlabNoteCmd),
 }
 
-/// Compare a local set of weight files.
+/// Post comparisons that were exported with `--format json` as note on the merge request of a
+/// GitLab CI pipeline.
+///
+/// The note is rendered like the Markdown of `merge` and replaces the note of the previous run. The
+/// merge request is read from the `CI_*` variables of GitLab and the token from
+/// `SUBWEIGHT_GITLAB_TOKEN` or `GITLAB_TOKEN`.
 #[derive(Debug, Parser)]
-struct CompareFilesCmd {
-	#[allow(missing_docs)]
-	#[clap(flatten)]
-	pub params: CompareParams,
+struct GitlabNoteCmd {
+	/// The exported comparisons.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
 
-	#[allow(missing_docs)]
-	#[clap(flatten)]
-	pub filter: FilterParams,
+	/// The unit that the comparisons were exported with.
+	#[clap(long, short, value_name = "UNIT", ignore_case = true, default_value = "time")]
+	pub unit: Dimension,
 
-	#[allow(missing_docs)]
-	#[clap(flatten)]
-	pub format: FormatParams,
+	/// Identifies the note to update; jobs that post separate notes need different tags.
+	#[clap(long, default_value = "subweight")]
+	pub tag: String,
 
-	/// The old weight files.
-	#[clap(long, required(true), num_args = 0..)]
-	pub old: Vec<PathBuf>,
+	/// Print the note instead of posting it.
+	#[clap(long)]
+	pub dry_run: bool,
+}
 
-	/// The new weight files.
-	#[clap(long, required(true), num_args = 0..)]
-	pub new: Vec<PathBuf>,
+/// Print the distribution of the relative changes of comparisons that were exported with
+/// `--format json`.
+#[derive(Debug, Parser)]
+struct StatsCmd {
+	/// The exported comparisons; their changes are combined.
+	#[clap(index = 1, required(true), num_args = 1..)]
+	pub files: Vec<PathBuf>,
+
+	#[clap(long, value_name = "FORMAT", default_value = "human", ignore_case = true)]
+	pub format: OutputFormat,
+
+	/// Disable color output.
+	#[clap(long)]
+	pub no_color: bool,
 }
 
-/// Compare weight files across commits.
+/// Print the JSON Schema of the output of `--format json` for comparisons.
+///
+/// The output contains a `schema_version` that only increases on breaking changes.
 #[derive(Debug, Parser)]
-struct CompareCommitsCmd {
-	#[allow(missing_docs)]
-	#[clap(flatten)]
-	pub params: CompareParams,
+struct SchemaCmd {}
 
-	#[allow(missing_docs)]
-	#[clap(flatten)]
-	pub filter: FilterParams,
+/// Keep a history of the weights in an SQLite database.
+#[cfg(feature = "sqlite")]
+#[derive(Debug, clap::Subcommand)]
+enum DbCmd {
+	Ingest(DbIngestCmd),
+	Query(DbQueryCmd),
+}
 
+/// Evaluate the weights of a commit and store them in the database.
+///
+/// Components without a value are set to the maximum of their range. Ingesting a commit again
+/// replaces its weights.
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Parser)]
+struct DbIngestCmd {
 	#[allow(missing_docs)]
 	#[clap(flatten)]
-	pub format: FormatParams,
-
-	/// Old commit/branch/tag.
-	#[clap(name = "OLD-COMMIT", index = 1)]
-	pub old: String,
+	pub params: CompareParams,
 
-	/// New commit/branch/tag.
-	#[clap(name = "NEW-COMMIT", index = 2, default_value = "master")]
-	pub new: String,
+	/// Commit/branch/tag to ingest.
+	#[clap(name = "REF", index = 1)]
+	pub refname: String,
 
 	#[clap(long, default_value = ".")]
 	pub repo: PathBuf,
 
 	#[clap(long)]
 	pub path_pattern: String,
+
+	/// The database file; it is created if it does not exist.
+	#[clap(long, value_name = "FILE", default_value = "subweight.sqlite")]
+	pub db: PathBuf,
 }
 
+/// Print the stored weights in the order of the commit times.
+#[cfg(feature = "sqlite")]
 #[derive(Debug, Parser)]
-struct ParseFilesCmd {
-	/// The files to parse.
-	#[clap(long, index = 1, required(true), num_args = 0..1000)]
-	pub files: Vec<PathBuf>,
+struct DbQueryCmd {
+	/// Only weights of files whose path contains this.
+	#[clap(long)]
+	pub file: Option<String>,
+
+	/// Only weights of the extrinsic with this name.
+	#[clap(long)]
+	pub extrinsic: Option<String>,
+
+	/// The database file.
+	#[clap(long, value_name = "FILE", default_value = "subweight.sqlite")]
+	pub db: PathBuf,
+
+	/// Set the format of the output. Supports `human`, `markdown`, `csv` and `json`.
+	#[clap(long, value_name = "FORMAT", default_value = "human", ignore_case = true)]
+	pub format: OutputFormat,
 }
 
-/// Parameters for modifying the output representation.
+/// Parameters for failing on changes; eg. in CI.
 #[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct GateParams {
+	/// Exit with an error if any of the reported changes is of these kinds.
+	///
+	/// Changes that are acknowledged by the `--allowlist` are ignored.
+	#[clap(long, value_name = "KIND", ignore_case = true, value_delimiter = ',')]
+	fail_on: Vec<FailOn>,
+}
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum FailOn {
+	/// The weight increased.
+	Increase,
+	/// The weight decreased.
+	Decrease,
+	Added,
+	Removed,
+	/// The extrinsic could not be compared.
+	Error,
+}
+
+impl FailOn {
+	/// Whether any dimension of the extrinsic has a change of this kind.
+	fn matches(&self, diff: &ExtrinsicDiff) -> bool {
+		std::iter::once(&diff.change)
+			.chain(diff.proof_change.as_ref())
+			.any(|d| match d {
+				TermDiff::Failed(_) => *self == Self::Error,
+				TermDiff::Changed(c) | TermDiff::Warning(c, _) => match (self, c.change) {
+					(Self::Increase, RelativeChange::Changed) => c.percent > 0.0,
+					(Self::Decrease, RelativeChange::Changed) => c.percent < 0.0,
+					(Self::Added, RelativeChange::Added) |
+					(Self::Removed, RelativeChange::Removed) => true,
+					_ => false,
+				},
+			})
+	}
+}
+
+/// Parameters for modifying the output representation.
+#[derive(Debug, Clone, PartialEq, Args)]
 pub struct FormatParams {
 	/// Set the format of the output.
 	#[clap(long, value_name = "FORMAT", default_value = "human", ignore_case = true)]
 	pub format: OutputFormat,
 
+	/// Handlebars template that renders the changes for `--format template`.
+	///
+	/// See the README for the fields that the template can use.
+	#[clap(long, value_name = "FILE", required_if_eq("format", "template"))]
+	template: Option<PathBuf>,
+
 	/// Include weight terms in the console output.
 	///
 	/// Note: The output will have _very_ long rows.
 	#[clap(long)]
 	print_terms: bool,
 
-	/// Disable color output.
+	/// Print the weight terms as LaTeX math instead of plain text; eg. for docs.
+	///
+	/// Markdown output wraps them in `$` so that GitHub renders them.
+	#[clap(long, requires("print_terms"))]
+	latex: bool,
+
+	/// Include a diff of the parts of the old and new weight formulas.
+	///
+	/// Example: `base 25.10ms → 26.40ms, READ count 4 → 5, slope of n unchanged`.
+	#[clap(long)]
+	print_formula: bool,
+
+	/// Order of the changes in the output.
+	#[clap(long, value_name = "ORDER", ignore_case = true, default_value = "percent")]
+	sort: SortBy,
+
+	/// Only output the first N changes after sorting.
+	#[clap(long, value_name = "N")]
+	top: Option<usize>,
+
+	/// Also print the summed weight of all extrinsics per runtime.
+	///
+	/// Includes the extrinsics that are filtered out.
+	#[clap(long)]
+	total: bool,
+
+	/// Also print the distribution of the relative changes; with median, p95 and the extremes.
+	#[clap(long)]
+	summary: bool,
+
+	/// Also print how much of a block the old and new weights use; eg. `0.80% → 1.90%`.
+	///
+	/// Only for the time and proof size dimensions. See `--max-time` and `--max-proof`.
+	#[clap(long)]
+	block_share: bool,
+
+	/// Only output the changes whose old or new weight uses at least this share of a block.
+	#[clap(long, value_name = "PERCENT")]
+	min_block_share: Option<Percent>,
+
+	/// Reference time limit of a block for `--block-share`.
+	#[clap(long, value_name = "VALUE", default_value = "2s", value_parser = parse_abs_value)]
+	max_time: u128,
+
+	/// Proof size limit of a block for `--block-share`.
+	#[clap(long, value_name = "VALUE", default_value = "5MiB", value_parser = parse_abs_value)]
+	max_proof: u128,
+
+	/// Aggregate the changes into one row per group instead of one per extrinsic.
+	///
+	/// The groups are sorted by their summed absolute change.
+	#[clap(long, value_name = "GROUP", ignore_case = true)]
+	group_by: Option<GroupBy>,
+
+	/// When to color the changes. `auto` colors only terminals and respects `NO_COLOR`.
+	#[clap(long, value_name = "WHEN", default_value = "auto", ignore_case = true)]
+	color: ColorChoice,
+
+	/// Print one tab separated line per extrinsic for scripts; overrides `--format`.
+	///
+	/// The fields are: file, extrinsic, kind, old, new, percent, old proof, new proof and proof
+	/// percent. Values are unformatted in the base unit of the dimension, eg. picoseconds. The
+	/// proof fields are empty unless comparing both dimensions. This format is kept stable.
+	#[clap(long)]
+	porcelain: bool,
+
+	/// Disable color output. Same as `--color never`.
 	#[clap(long)]
 	no_color: bool,
 
@@ -127,6 +1089,24 @@ pub struct FormatParams {
 }
 
 impl FormatParams {
+	/// Whether the output should contain ANSI colors.
+	pub fn colored(&self) -> bool {
+		if self.no_color {
+			return false
+		}
+		match self.color {
+			ColorChoice::Always => true,
+			ColorChoice::Never => false,
+			ColorChoice::Auto =>
+				std::env::var_os("NO_COLOR").map_or(true, |v| v.is_empty()) &&
+					std::io::stdout().is_terminal(),
+		}
+	}
+
+	pub fn block_limits(&self) -> BlockLimits {
+		BlockLimits { time: self.max_time, proof: self.max_proof }
+	}
+
 	pub fn filter_path(&self, path: String) -> String {
 		match self.strip_path_prefix.as_ref() {
 			Some(prefix) => path.strip_prefix(prefix).unwrap_or(&path).to_string(),
@@ -135,6 +1115,14 @@ impl FormatParams {
 	}
 }
 
+#[derive(Debug, clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+	/// Color if the output is a terminal and `NO_COLOR` is not set.
+	Auto,
+	Always,
+	Never,
+}
+
 #[derive(
 	Debug, serde::Deserialize, clap::ValueEnum, Clone, Eq, Ord, PartialEq, PartialOrd, Copy,
 )]
@@ -150,12 +1138,24 @@ pub enum OutputFormat {
 	JSON,
 	/// Markdown output
 	Markdown,
+	/// SARIF log for code scanning; only for comparisons.
+	Sarif,
+	/// Handlebars template of `--template`; only for comparisons.
+	Template,
+}
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+	/// One row per pallet with the number of changed extrinsics and their total change.
+	Pallet,
+	/// One row per pallet and one column per runtime with the total change of the pallet.
+	Runtime,
 }
 
 impl OutputFormat {
 	/// All possible variants of [`Self`].
 	pub fn variants() -> Vec<&'static str> {
-		vec!["human", "brief-human", "csv", "json", "markdown"]
+		vec!["human", "brief-human", "csv", "json", "markdown", "sarif", "template"]
 	}
 }
 
@@ -169,13 +1169,26 @@ impl std::str::FromStr for OutputFormat {
 			"csv" => Ok(OutputFormat::CSV),
 			"json" => Ok(OutputFormat::JSON),
 			"markdown" => Ok(OutputFormat::Markdown),
+			"sarif" => Ok(OutputFormat::Sarif),
+			"template" => Ok(OutputFormat::Template),
 			_ => Err(format!("Unknown output format: {}", s)),
 		}
 	}
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-	let cmd = MainCmd::parse();
+	let args = std::env::args().collect::<Vec<_>>();
+	let mut command = MainCmd::command();
+	if let Some(config) = config::Config::load(config::config_arg(&args).as_deref())? {
+		command = config.apply(command);
+	}
+	let cmd =
+		MainCmd::from_arg_matches(&command.get_matches_from(args)).unwrap_or_else(|e| e.exit());
+
+	let mut credentials = auth::credentials();
+	credentials.ssh_key = cmd.ssh_key.or(credentials.ssh_key);
+	credentials.proxy = cmd.proxy.or(credentials.proxy);
+	auth::set_credentials(credentials);
 
 	// TODO is is good to not set this up at all?!
 	if cmd.verbose {
@@ -189,56 +1202,704 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 			params,
 			filter,
 			format,
+			gate,
 			old,
 			new,
 		})) => {
-			let olds =
-				if params.ignore_errors { try_parse_files(&old) } else { parse_files(&old)? };
-			let news =
-				if params.ignore_errors { try_parse_files(&new) } else { parse_files(&new)? };
+			if old.iter().chain(&new).filter(|p| p.as_os_str() == "-").count() > 1 {
+				return Err("Only one input can be read from stdin".into())
+			}
+			let olds = parse_inputs(&old, &new, &params)?;
+			let news = parse_inputs(&new, &old, &params)?;
 
-			let mut diff = compare_files(olds, news, &params, &filter)?;
-			diff = filter_changes(diff, &filter);
-			sort_changes(&mut diff);
-			diff.reverse();
-			print_changes(diff, cmd.verbose, format, params.unit)?;
+			let diff = compare_files(olds, news, &params, &filter)?;
+			output_changes(diff, &filter, &gate, None, cmd.verbose, format, params.unit)?;
 		},
 		SubCommand::Compare(CompareCmd::Commits(CompareCommitsCmd {
+			mut params,
+			filter,
+			format,
+			gate,
+			old,
+			new,
+			repo,
+			old_repo,
+			new_repo,
+			remote,
+			old_date,
+			new_date,
+			base,
+			path_pattern,
+		})) => {
+			let pull = params.should_pull();
+			let old = match old_date {
+				Some(date) => commit_before(old_repo.as_ref().unwrap_or(&repo), &old, &date, pull)?,
+				None => old,
+			};
+			let new = match new_date {
+				Some(date) => commit_before(new_repo.as_ref().unwrap_or(&repo), &new, &date, pull)?,
+				None => new,
+			};
+			let clone = remote.as_ref().filter(|_| !repo.exists()).map(GithubRepo::url);
+			if let Some(url) = &clone {
+				sparse_clone(url, &repo, &[&old, &new], &path_pattern)?;
+				// libgit2 cannot fetch into the shallow clone.
+				params.git_pull = false;
+			}
+			let diff = if let Some(remote) = remote.filter(|_| clone.is_none()) {
+				compare_remote(&remote, &old, &new, &params, &filter, &path_pattern, usize::MAX)?
+			} else if let Some(base) = base {
+				let bar = progress::Bar::new(cmd.verbose);
+				let diff = compare_commits_since_base(
+					&repo,
+					&old,
+					&new,
+					&base,
+					&params,
+					&filter,
+					&path_pattern,
+					usize::MAX,
+					&bar,
+				);
+				bar.finish();
+				diff?
+			} else {
+				let old_repo = old_repo.unwrap_or_else(|| repo.clone());
+				let new_repo = new_repo.unwrap_or(repo);
+				let bar = progress::Bar::new(cmd.verbose);
+				let diff = compare_repos_with_progress(
+					(&old_repo, &old),
+					(&new_repo, &new),
+					&params,
+					&filter,
+					&path_pattern,
+					usize::MAX,
+					&bar,
+				);
+				bar.finish();
+				diff?
+			};
+			output_changes(diff, &filter, &gate, Some(&new), cmd.verbose, format, params.unit)?;
+		},
+		SubCommand::Compare(CompareCmd::Workdir(CompareWorkdirCmd {
+			params,
+			filter,
+			format,
+			gate,
+			against,
+			repo,
+			path_pattern,
+		})) => {
+			let bar = progress::Bar::new(cmd.verbose);
+			let diff =
+				compare_workdir(&repo, &against, &params, &filter, &path_pattern, usize::MAX, &bar);
+			bar.finish();
+			output_changes(diff?, &filter, &gate, None, cmd.verbose, format, params.unit)?;
+		},
+		SubCommand::Compare(CompareCmd::Runtimes(CompareRuntimesCmd {
+			params,
+			filter,
+			format,
+			gate,
+			refname,
+			a,
+			b,
+			repo,
+			path_pattern,
+		})) => {
+			let bar = progress::Bar::new(cmd.verbose);
+			let diff = compare_repos_with_progress(
+				(&repo.join(a), &refname),
+				(&repo.join(b), &refname),
+				&params,
+				&filter,
+				&path_pattern,
+				usize::MAX,
+				&bar,
+			);
+			bar.finish();
+			output_changes(diff?, &filter, &gate, None, cmd.verbose, format, params.unit)?;
+		},
+		SubCommand::Compare(CompareCmd::Overhead(CompareOverheadCmd {
+			params,
+			filter,
+			format,
+			gate,
+			old,
+			new,
+		})) => {
+			let olds = if params.ignore_errors {
+				overhead::try_parse_files(&old)
+			} else {
+				overhead::parse_files(&old)?
+			};
+			let news = if params.ignore_errors {
+				overhead::try_parse_files(&new)
+			} else {
+				overhead::parse_files(&new)?
+			};
+
+			let diff = compare_files(olds, news, &params, &filter)?;
+			output_changes(diff, &filter, &gate, None, cmd.verbose, format, params.unit)?;
+		},
+		SubCommand::Compare(CompareCmd::StorageWeights(CompareStorageWeightsCmd {
 			params,
 			filter,
+			format,
+			gate,
+			old,
+			new,
+		})) => {
+			let olds = if params.ignore_errors {
+				storage::try_parse_files(&old)
+			} else {
+				storage::parse_files(&old)?
+			};
+			let news = if params.ignore_errors {
+				storage::try_parse_files(&new)
+			} else {
+				storage::parse_files(&new)?
+			};
+
+			let diff = compare_files(olds, news, &params, &filter)?;
+			output_changes(diff, &filter, &gate, None, cmd.verbose, format, params.unit)?;
+		},
+		SubCommand::Compare(CompareCmd::Accesses(CompareAccessesCmd {
 			format,
 			old,
 			new,
 			repo,
 			path_pattern,
+			git_pull,
+		})) => {
+			let olds = access::parse_commit(&repo, &old, &path_pattern, git_pull)?;
+			let news = access::parse_commit(&repo, &new, &path_pattern, git_pull)?;
+			print_access_changes(access::compare_accesses(&olds, &news), cmd.verbose, format)?;
+		},
+		SubCommand::Compare(CompareCmd::Machines(CompareMachinesCmd {
+			format,
+			threshold,
+			old,
+			new,
 		})) => {
-			let mut diff =
-				compare_commits(&repo, &old, &new, &params, &filter, &path_pattern, usize::MAX)?;
-			diff = filter_changes(diff, &filter);
-			sort_changes(&mut diff);
-			diff.reverse();
-			print_changes(diff, cmd.verbose, format, params.unit)?;
+			let diff = compare_machines(machine::parse_file(&old)?, machine::parse_file(&new)?)
+				.into_iter()
+				.filter(|d| match d.change {
+					RelativeChange::Changed => d.percent.abs() >= threshold,
+					RelativeChange::Unchanged => threshold < 0.000001,
+					_ => true,
+				})
+				.collect::<Vec<_>>();
+			print_machine_changes(diff, cmd.verbose, format)?;
 		},
 		SubCommand::Parse(ParseCmd::Files(ParseFilesCmd { files })) => {
 			println!("Trying to parse {} files...", files.len());
-			let parsed = parse_files(&files)?;
-			println!("Parsed {} files successfully", parsed.len());
+			let mut parsed = 0;
+			for file in files.iter() {
+				// Overhead and storage files are rejected by the pallet parser, so try them as
+				// fallback.
+				match parse_file(file) {
+					Ok(_) => parsed += 1,
+					Err(err) =>
+						if overhead::parse_file(file).is_ok() || storage::parse_file(file).is_ok() {
+							parsed += 1;
+						} else {
+							return Err(err.into())
+						},
+				}
+			}
+			println!("Parsed {} files successfully", parsed);
+		},
+		SubCommand::Show(ShowCmd { files, pallet, extrinsic, set, db_weights }) => {
+			let extrinsics = parse_files(&files)?
+				.into_iter()
+				.filter(|e| e.name == extrinsic)
+				.filter(|e| {
+					pallet
+						.as_ref()
+						.map_or(true, |p| e.pallet == *p || e.pallet == format!("{}.rs", p))
+				})
+				.collect::<Vec<_>>();
+			if extrinsics.is_empty() {
+				return Err(format!("Extrinsic {} not found", extrinsic).into())
+			}
+
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			table.set_header(vec!["File", "Extrinsic", "Components", "Ref Time", "Proof Size"]);
+			for ext in extrinsics.iter() {
+				let scope = component_scope(ext, &SimpleScope::empty().with_components(&set));
+				let used = ext
+					.term
+					.free_vars(&ChromaticScope::empty())
+					.into_iter()
+					.filter_map(|v| scope.get(&v).map(|t| format!("{}={}", v, t)))
+					.collect::<Vec<_>>();
+
+				table.add_row(vec![
+					ext.pallet.to_string(),
+					ext.name.to_string(),
+					used.join(", "),
+					Dimension::fmt_time(evaluate_extrinsic(
+						ext,
+						&scope,
+						Dimension::Time,
+						db_weights,
+					)?),
+					Dimension::fmt_proof(evaluate_extrinsic(
+						ext,
+						&scope,
+						Dimension::Proof,
+						db_weights,
+					)?),
+				]);
+			}
+			print(table.to_string(), cmd.verbose);
+		},
+		SubCommand::History(HistoryCmd {
+			range,
+			repo,
+			file,
+			extrinsic,
+			set,
+			db_weights,
+			format,
+		}) => {
+			let points = extrinsic_history(
+				&repo,
+				&range,
+				&file,
+				&extrinsic,
+				&SimpleScope::empty().with_components(&set),
+				db_weights,
+			)?;
+			if points.is_empty() {
+				return Err(format!("Extrinsic {} not found in {}", extrinsic, range).into())
+			}
+			print(print_history(&points, format)?, cmd.verbose);
+		},
+		SubCommand::Check(CheckCmd::Budget(CheckBudgetCmd {
+			files,
+			max_time,
+			max_proof,
+			fraction,
+			set,
+			db_weights,
+			ignore_errors,
+			format,
+		})) => {
+			let limits = BlockLimits { time: max_time, proof: max_proof };
+			let components = SimpleScope::empty().with_components(&set);
+			let extrinsics =
+				if ignore_errors { try_parse_files(&files) } else { parse_files(&files)? };
+
+			let mut over = Vec::new();
+			for ext in extrinsics.iter() {
+				match budget_usage(ext, &limits, &components, db_weights) {
+					Ok(usage) if usage.max_percent() > fraction => over.push(usage),
+					Ok(_) => (),
+					Err(err) if ignore_errors => log::warn!("Skipping {}: {}", ext.name, err),
+					Err(err) => return Err(err.into()),
+				}
+			}
+			if over.is_empty() {
+				print(
+					format!(
+						"All {} extrinsics use at most {}% of the block.",
+						extrinsics.len(),
+						fraction
+					),
+					cmd.verbose,
+				);
+				return Ok(())
+			}
+
+			over.sort_by(|a, b| b.max_percent().total_cmp(&a.max_percent()));
+			print_budget_usage(&over, cmd.verbose, format)?;
+			return Err(format!(
+				"{} extrinsics use more than {}% of the block",
+				over.len(),
+				fraction
+			)
+			.into())
+		},
+		SubCommand::Lint(LintCmd { files, params, ignore_errors, format }) => {
+			let extrinsics =
+				if ignore_errors { try_parse_files(&files) } else { parse_files(&files)? };
+			let findings =
+				extrinsics.iter().flat_map(|e| lint_extrinsic(e, &params)).collect::<Vec<_>>();
+			if findings.is_empty() {
+				print(
+					format!("All {} extrinsics passed the checks.", extrinsics.len()),
+					cmd.verbose,
+				);
+				return Ok(())
+			}
+
+			print_lint_findings(&findings, cmd.verbose, format)?;
+			return Err(format!("{} problems found", findings.len()).into())
+		},
+		SubCommand::Format(FormatCmd { files, check }) => {
+			let mut unformatted = 0;
+			for file in &files {
+				let formatted = format_file(file)?;
+				if formatted == std::fs::read_to_string(file)? {
+					continue
+				}
+				unformatted += 1;
+				if check {
+					println!("Not formatted: {}", file.display());
+				} else {
+					std::fs::write(file, formatted)?;
+					println!("Formatted: {}", file.display());
+				}
+			}
+			if check && unformatted > 0 {
+				return Err(format!("{} files are not formatted", unformatted).into())
+			}
+		},
+		#[cfg(feature = "sqlite")]
+		SubCommand::Db(DbCmd::Ingest(DbIngestCmd { params, refname, repo, path_pattern, db })) => {
+			let mut db = WeightDb::open(&db)?;
+			let bar = progress::Bar::new(cmd.verbose);
+			let stored = db.ingest(&repo, &refname, &params, &path_pattern, usize::MAX, &bar);
+			bar.finish();
+			print(format!("Stored {} weights of {}", stored?, refname), cmd.verbose);
+		},
+		#[cfg(feature = "sqlite")]
+		SubCommand::Db(DbCmd::Query(DbQueryCmd { file, extrinsic, db, format })) => {
+			if !db.exists() {
+				return Err(format!("Database {} does not exist", db.display()).into())
+			}
+			let records = WeightDb::open(&db)?.query(file.as_deref(), extrinsic.as_deref())?;
+			print(print_weight_records(&records, format)?, cmd.verbose);
+		},
+		SubCommand::Report(ReportCmd::Releases(ReportReleasesCmd {
+			params,
+			filter,
+			repo,
+			path_pattern,
+			tag_pattern,
+			last,
+			format,
+		})) => {
+			let mut tags = release_tags(&repo, &tag_pattern)?;
+			if let Some(last) = last {
+				// One more release to compare the first one with.
+				tags.drain(..tags.len().saturating_sub(last + 1));
+			}
+			if tags.len() < 2 {
+				return Err(format!("Need at least two tags matching '{}'", tag_pattern).into())
+			}
+
+			let mut sections = Vec::new();
+			for pair in tags.windows(2) {
+				let bar = progress::Bar::new(cmd.verbose);
+				let diff = compare_commits_with_progress(
+					&repo,
+					&pair[0],
+					&pair[1],
+					&params,
+					&filter,
+					&path_pattern,
+					usize::MAX,
+					&bar,
+				);
+				bar.finish();
+				let mut diff = filter_changes(diff?, &filter);
+				sort_changes_by(&mut diff, SortBy::Percent);
+				let title = format!("{} → {}", pair[0], pair[1]);
+				sections.push(report::Section { title, diff });
+			}
+			let title = "Weight changes between releases";
+			print!("{}", report::render(title, "Release", &sections, params.unit, format)?);
+		},
+		SubCommand::Merge(MergeCmd { files, unit, format }) => {
+			print!("{}", merge_diffs(&files, unit, format)?);
+		},
+		SubCommand::Github(GithubCmd::Check(GithubCheckCmd {
+			files,
+			unit,
+			name,
+			sha,
+			gate,
+			dry_run,
+		})) => {
+			let summary = merge_diffs(&files, unit, report::ReportFormat::Markdown)?;
+			let mut diff = TotalDiff::new();
+			for file in &files {
+				diff.extend(read_diff(file)?);
+			}
+			let failed = diff
+				.iter()
+				.filter(|d| d.acknowledged.is_none())
+				.filter(|d| gate.fail_on.iter().any(|f| f.matches(d)))
+				.count();
+			let run = github::check_run(&name, &summary, &diff, unit, failed > 0);
+
+			if dry_run {
+				println!("{}", serde_json::to_string_pretty(&run)?);
+			} else {
+				let commit = github::Commit::from_env(sha)?;
+				commit.create_check_run(&run)?;
+				println!("Created the check run {} for {}", name, commit.sha);
+			}
+			if failed > 0 {
+				return Err(format!("{} changes are not acknowledged", failed).into())
+			}
+		},
+		SubCommand::Gitlab(GitlabCmd::Note(GitlabNoteCmd { files, unit, tag, dry_run })) => {
+			let body = merge_diffs(&files, unit, report::ReportFormat::Markdown)?;
+			if dry_run {
+				print!("{}", gitlab::note_body(&tag, &body));
+			} else {
+				let mr = gitlab::MergeRequest::from_env()?;
+				mr.upsert_note(&tag, &body)?;
+				println!("Posted the weight changes on merge request !{}", mr.iid);
+			}
+		},
+		SubCommand::Stats(StatsCmd { files, format, no_color }) => {
+			let mut diff = TotalDiff::new();
+			for file in files {
+				diff.extend(read_diff(&file)?);
+			}
+			let no_color = no_color || !std::io::stdout().is_terminal();
+			print_stats(&diff_stats(&diff), cmd.verbose, format, no_color)?;
+		},
+		SubCommand::Schema(SchemaCmd {}) => {
+			println!("{}", serde_json::to_string_pretty(&schema::json_schema())?);
+		},
+		SubCommand::Watch(WatchCmd {
+			params,
+			filter,
+			format,
+			baseline,
+			repo,
+			path_pattern,
+			interval,
+		}) => {
+			let bar = progress::Bar::new(cmd.verbose);
+			let watch = Watch::new(&repo, &baseline, &params, &path_pattern, usize::MAX, &bar);
+			bar.finish();
+			let mut watch = watch?;
+			let gate = GateParams { fail_on: Vec::new() };
+
+			loop {
+				// Clear the screen like `watch` does.
+				if std::io::stdout().is_terminal() {
+					print!("\x1B[2J\x1B[H");
+				}
+				let diff = watch.compare(&params, &filter)?;
+				output_changes(
+					diff,
+					&filter,
+					&gate,
+					None,
+					cmd.verbose,
+					format.clone(),
+					params.unit,
+				)?;
+				println!("Watching {} against {}", path_pattern, baseline);
+
+				loop {
+					std::thread::sleep(std::time::Duration::from_millis(interval));
+					match watch.poll() {
+						Ok(changed) if changed.is_empty() => continue,
+						Ok(_) => break,
+						// Half-written files fail to parse; wait for the next change.
+						Err(err) => eprintln!("{}", err),
+					}
+				}
+			}
+		},
+		SubCommand::Hook(HookCmd::Install(HookInstallCmd { repo, kind, force })) => {
+			let (dir, sub_dir) = hooks_dir(&repo)?;
+			let path = dir.join(kind.name());
+			if path.exists() && !force {
+				return Err(
+					format!("{} exists already; use --force to replace it", path.display()).into()
+				)
+			}
+			let mut script = format!(
+				"#!/bin/sh\n# Installed by subweight.\nexec subweight hook run --kind {}",
+				kind.name()
+			);
+			if !sub_dir.as_os_str().is_empty() {
+				let sub_dir = sub_dir.display().to_string().replace('\'', r"'\''");
+				write!(script, " --repo '{}'", sub_dir)?;
+			}
+			std::fs::create_dir_all(&dir)?;
+			std::fs::write(&path, script + "\n")?;
+			#[cfg(unix)]
+			{
+				use std::os::unix::fs::PermissionsExt;
+				std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+			}
+			println!("Installed {}", path.display());
+		},
+		SubCommand::Hook(HookCmd::Run(HookRunCmd {
+			params,
+			filter,
+			format,
+			mut gate,
+			repo,
+			kind,
+			path_pattern,
+		})) => {
+			if gate.fail_on.is_empty() {
+				gate.fail_on.push(FailOn::Increase);
+			}
+			let (old, new) = match kind {
+				HookKind::PreCommit => ("HEAD", None),
+				HookKind::PrePush => ("@{upstream}", Some("HEAD")),
+			};
+			let bar = progress::Bar::new(cmd.verbose);
+			let diff =
+				compare_local(&repo, old, new, &params, &filter, &path_pattern, usize::MAX, &bar);
+			bar.finish();
+			output_changes(diff?, &filter, &gate, None, cmd.verbose, format, params.unit)?;
+		},
+		SubCommand::Bisect(BisectCmd {
+			good,
+			bad,
+			repo,
+			file,
+			extrinsic,
+			set,
+			db_weights,
+			threshold,
+			unit,
+		}) => {
+			if matches!(unit, Dimension::Both | Dimension::Fee) {
+				return Err("Bisecting needs the time or proof dimension".into())
+			}
+			let res = bisect_regression(
+				&repo,
+				(&good, &bad),
+				&file,
+				&extrinsic,
+				&SimpleScope::empty().with_components(&set),
+				db_weights,
+				unit,
+				threshold,
+			)?;
+			let value = |p: &HistoryPoint| match unit {
+				Dimension::Time => p.ref_time,
+				_ => p.proof_size,
+			};
+			let (old, new) = (value(&res.good), value(&res.first_bad));
+
+			print(
+				format!(
+					"First bad commit: {} {}\n{} -> {} ({:+.2}%), evaluated {} commits",
+					res.first_bad.commit,
+					res.first_bad.summary,
+					unit.fmt_value(old),
+					unit.fmt_value(new),
+					percent(old, new),
+					res.steps
+				),
+				cmd.verbose,
+			);
+		},
+		SubCommand::Parse(ParseCmd::Dump(ParseDumpCmd { files, format })) => {
+			let mut extrinsics = Vec::new();
+			for file in files.iter() {
+				let parsed = try_parse_any_file(Path::new("."), file).ok_or_else(|| {
+					format!("{}: Not a pallet, storage or overhead weight file", file.display())
+				})?;
+				extrinsics.extend(parsed.into_extrinsics(file.display().to_string().into()));
+			}
+			print(dump_extrinsics(&extrinsics, format)?, cmd.verbose);
 		},
 	}
 
 	Ok(())
 }
 
+/// Filter, sort and print the changes.
+///
+/// `commit` is the new commit for the acknowledgements of the allowlist.
+fn output_changes(
+	mut diff: TotalDiff,
+	filter: &FilterParams,
+	gate: &GateParams,
+	commit: Option<&str>,
+	verbose: bool,
+	format: FormatParams,
+	unit: Dimension,
+) -> Result<(), Box<dyn std::error::Error>> {
+	// The total includes the extrinsics that are filtered out.
+	let totals = (format.total && !format.porcelain).then(|| summarize_runtimes(&diff, unit));
+	let (output, no_color) = (format.format, !format.colored());
+
+	diff = filter_changes(diff, filter);
+	let shares = format.block_share || format.min_block_share.is_some();
+	if shares && !matches!(unit, Dimension::Time | Dimension::Proof | Dimension::Both) {
+		return Err(format!("The {:?} dimension has no block limit", unit).into())
+	}
+	if let Some(min) = format.min_block_share {
+		let limits = format.block_limits();
+		// Failed changes cannot be put in relation to the block.
+		diff.retain(|d| {
+			d.error().is_some() || block_shares(d, unit, &limits).iter().any(|s| s.max() >= min)
+		});
+	}
+	if let Some(path) = &filter.allowlist {
+		Allowlist::load(path)?.acknowledge(&mut diff, &allowlist::today(), commit);
+	}
+	let failed = diff
+		.iter()
+		.filter(|d| d.acknowledged.is_none())
+		.filter(|d| gate.fail_on.iter().any(|f| f.matches(d)))
+		.count();
+
+	let stats = (format.summary && !format.porcelain).then(|| diff_stats(&diff));
+	sort_changes_by(&mut diff, format.sort);
+	// The groups are truncated instead.
+	if let (Some(top), None) = (format.top, format.group_by) {
+		diff.truncate(top);
+	}
+	print_changes(diff, verbose, format, unit)?;
+
+	if let Some(totals) = totals {
+		print_runtime_totals(&totals, verbose, output, no_color)?;
+	}
+	if let Some(stats) = stats {
+		print_stats(&stats, verbose, output, no_color)?;
+	}
+	if failed > 0 {
+		return Err(format!("{} changes are not acknowledged", failed).into())
+	}
+	Ok(())
+}
+
 fn print_changes(
 	per_extrinsic: TotalDiff,
 	verbose: bool,
 	format: FormatParams,
 	unit: Dimension,
 ) -> Result<(), Box<dyn std::error::Error>> {
+	if format.porcelain {
+		print_changes_porcelain(&per_extrinsic, &format);
+		return Ok(())
+	}
+	match format.group_by {
+		Some(GroupBy::Pallet) => return print_pallet_summary(&per_extrinsic, verbose, format, unit),
+		Some(GroupBy::Runtime) => return print_runtime_matrix(&per_extrinsic, verbose, format),
+		None => (),
+	}
 	let output = match format.format {
 		OutputFormat::Human => print_changes_human(per_extrinsic, verbose, format, unit, false),
 		OutputFormat::Markdown => print_changes_human(per_extrinsic, verbose, format, unit, true),
 		OutputFormat::CSV => print_changes_csv(per_extrinsic, verbose, format, unit),
+		OutputFormat::JSON => Ok(serde_json::to_string_pretty(&VersionedDiff::new(per_extrinsic))?),
+		OutputFormat::Sarif =>
+			Ok(serde_json::to_string_pretty(&sarif::to_sarif(&per_extrinsic, unit))?),
+		OutputFormat::Template => match &format.template {
+			Some(path) => Ok(template::render(path, &per_extrinsic, unit)?),
+			None => Err("The template format needs a --template".into()),
+		},
 		_ => Err("Unsupported output format".into()),
 	};
 
@@ -246,6 +1907,35 @@ fn print_changes(
 	Ok(())
 }
 
+/// See [`FormatParams::porcelain`].
+fn print_changes_porcelain(per_extrinsic: &TotalDiff, format: &FormatParams) {
+	let fields = |change: Option<&TermChange>| match change {
+		Some(c) => [
+			c.old_v.map(|v| v.to_string()).unwrap_or_default(),
+			c.new_v.map(|v| v.to_string()).unwrap_or_default(),
+			format!("{:.2}", c.percent),
+		],
+		None => Default::default(),
+	};
+
+	for info in per_extrinsic {
+		let kind = match info.term().map(|c| c.change) {
+			Some(RelativeChange::Unchanged) => "unchanged",
+			Some(RelativeChange::Changed) => "changed",
+			Some(RelativeChange::Added) => "added",
+			Some(RelativeChange::Removed) => "removed",
+			Some(RelativeChange::Renamed) => "renamed",
+			None => "error",
+		};
+		let line = [format.filter_path(info.file.clone()), info.name.to_string(), kind.into()]
+			.into_iter()
+			.chain(fields(info.term()))
+			.chain(fields(info.proof_term()))
+			.collect::<Vec<_>>();
+		println!("{}", line.join("\t"));
+	}
+}
+
 // TODO make meta output format
 fn print_changes_csv(
 	per_extrinsic: TotalDiff,
@@ -258,9 +1948,21 @@ fn print_changes_csv(
 		return Ok(String::new())
 	}
 
+	let both = unit == Dimension::Both;
+	let unit = if both { Dimension::Time } else { unit };
+
 	let mut output = String::new();
 	// Put a csv header
-	output.push_str("File,Extrinsic,Old,New,Change Percent");
+	output.push_str("File,Extrinsic,Old,New,Change Percent,Cause");
+	if both {
+		output.push_str(",Old Proof,New Proof,Proof Change Percent");
+	}
+	if format.block_share {
+		output.push_str(if both { ",Time of Block,Proof of Block" } else { ",Of Block" });
+	}
+	if format.print_formula {
+		output.push_str(",Formula Diff");
+	}
 	if format.print_terms {
 		output.push_str(",Old Weight Term,New Weight Term,Used variables");
 	}
@@ -268,25 +1970,30 @@ fn print_changes_csv(
 
 	for (info, change) in per_extrinsic.iter().filter_map(|p| p.term().map(|t| (p, t))) {
 		let mut row = format!(
-			"{},{},{},{},{}",
+			"{},{},{},{},{},{}",
 			info.file.clone(),
-			info.name.clone(),
+			info.display_name(),
 			change.old_v.map(|v| unit.fmt_value(v)).unwrap_or_default(),
 			change.new_v.map(|v| unit.fmt_value(v)).unwrap_or_default(),
-			color_percent(change.percent, &change.change, format.no_color),
+			fmt_change(info, change, !format.colored()),
+			fmt_cause(change, unit).join(" ").replace(", ", " "),
 		);
+		if both {
+			let [old, new, percent] = proof_cells(info, !format.colored());
+			write!(row, ",{},{},{}", old, new, percent)?;
+		}
+		if format.block_share {
+			for cell in block_share_cells(info, &format, unit, both) {
+				write!(row, ",{}", cell)?;
+			}
+		}
+		if format.print_formula {
+			write!(row, ",{}", change.fmt_formula(unit).join("; "))?;
+		}
 
 		if format.print_terms {
-			write!(
-				row,
-				"{},",
-				change.old.as_ref().map(|t| format!("{}", t)).unwrap_or_else(|| "-".into())
-			)?;
-			write!(
-				row,
-				"{},",
-				change.new.as_ref().map(|t| format!("{}", t)).unwrap_or_else(|| "-".into())
-			)?;
+			write!(row, "{},", fmt_term(change.old.as_ref(), unit, &format, false))?;
+			write!(row, "{},", fmt_term(change.new.as_ref(), unit, &format, false))?;
 			row.push_str(&format!("{:?}", &change.scope).replace(',', " "));
 		}
 		row.push('\n');
@@ -313,37 +2020,90 @@ fn print_changes_human(
 	if markdown {
 		table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
 	}
-	let mut header = vec!["File", "Extrinsic", "Old", "New", "Change [%]"];
+	let both = unit == Dimension::Both;
+	let unit = if both { Dimension::Time } else { unit };
+
+	let mut header = vec!["File", "Extrinsic", "Old", "New", "Change [%]", "Cause"];
+	if both {
+		header = vec![
+			"File",
+			"Extrinsic",
+			"Old Time",
+			"New Time",
+			"Time [%]",
+			"Cause",
+			"Old Proof",
+			"New Proof",
+			"Proof [%]",
+		];
+	}
+	if format.block_share {
+		header.extend(if both {
+			vec!["Time of Block", "Proof of Block"]
+		} else {
+			vec!["Of Block"]
+		});
+	}
+	if format.print_formula {
+		header.push("Formula Diff");
+	}
 	if format.print_terms {
 		header.extend(vec!["Old Weight Term", "New Weight Term", "Used variables"]);
 	}
 	table.set_header(header);
+	// Right-align the values and changes such that their units line up.
+	let values = if both { vec![2, 3, 4, 6, 7, 8] } else { vec![2, 3, 4] };
+	for index in values {
+		if let Some(column) = table.column_mut(index) {
+			column.set_cell_alignment(comfy_table::CellAlignment::Right);
+		}
+	}
 
 	// Print all errors
 	for (info, _change) in per_extrinsic.iter().filter_map(|p| p.error().map(|t| (p, t))) {
-		let row = vec![
+		let mut row = vec![
 			format.filter_path(info.file.clone()),
-			info.name.clone(),
+			info.name.to_string(),
 			"-".into(),
 			"-".into(),
 			"ERROR".into(),
+			"-".into(),
 		];
+		if both {
+			row.extend(proof_cells(info, !format.colored()));
+		}
+		if format.block_share {
+			row.extend(block_share_cells(info, &format, unit, both));
+		}
+		if format.print_formula {
+			row.push("-".into());
+		}
 		table.add_row(row);
 	}
 
 	for (info, change) in per_extrinsic.iter().filter_map(|p| p.term().map(|t| (p, t))) {
 		let mut row = vec![
 			format.filter_path(info.file.clone()),
-			info.name.clone(),
+			info.display_name(),
 			change.old_v.map(|v| unit.fmt_value(v)).unwrap_or_default(),
 			change.new_v.map(|v| unit.fmt_value(v)).unwrap_or_default(),
-			color_percent(change.percent, &change.change, format.no_color),
+			fmt_change(info, change, !format.colored()),
+			fmt_cause(change, unit).join(", "),
 		];
+		if both {
+			row.extend(proof_cells(info, !format.colored()));
+		}
+		if format.block_share {
+			row.extend(block_share_cells(info, &format, unit, both));
+		}
+		if format.print_formula {
+			row.push(change.fmt_formula(unit).join(", "));
+		}
 
 		if format.print_terms {
 			row.extend(vec![
-				change.old.as_ref().map(|t| format!("{}", t)).unwrap_or_else(|| "-".into()),
-				change.new.as_ref().map(|t| format!("{}", t)).unwrap_or_else(|| "-".into()),
+				fmt_term(change.old.as_ref(), unit, &format, markdown),
+				fmt_term(change.new.as_ref(), unit, &format, markdown),
 				format!("{:?}", &change.scope),
 			]);
 		}
@@ -352,6 +2112,740 @@ fn print_changes_human(
 	Ok(table.to_string())
 }
 
+/// A weight term as readable formula; see [`FormatParams::latex`].
+fn fmt_term(
+	term: Option<&SimpleTerm>,
+	unit: Dimension,
+	format: &FormatParams,
+	markdown: bool,
+) -> String {
+	match term {
+		Some(term) if format.latex && markdown => format!("${}$", term.fmt_latex(unit)),
+		Some(term) if format.latex => term.fmt_latex(unit),
+		Some(term) => term.fmt_math(unit),
+		None => "-".into(),
+	}
+}
+
+/// The parts of the formula that changed and the component values of the worst case.
+fn fmt_cause(change: &TermChange, unit: Dimension) -> Vec<String> {
+	let mut cause = change.fmt_attribution(unit);
+	cause.extend(change.fmt_worst_case());
+	cause
+}
+
+/// The relative change; marked if it is acknowledged.
+fn fmt_change(info: &ExtrinsicDiff, change: &TermChange, no_color: bool) -> String {
+	let percent = color_percent(change.percent, &change.change, no_color);
+	match info.acknowledged {
+		Some(_) => format!("{} (acknowledged)", percent),
+		None => percent,
+	}
+}
+
+/// Old value, new value and relative change of the proof size of an extrinsic.
+fn proof_cells(info: &ExtrinsicDiff, no_color: bool) -> [String; 3] {
+	match (&info.proof_change, info.proof_term()) {
+		(_, Some(change)) => [
+			change.old_v.map(Dimension::fmt_proof).unwrap_or_default(),
+			change.new_v.map(Dimension::fmt_proof).unwrap_or_default(),
+			color_percent(change.percent, &change.change, no_color),
+		],
+		(Some(TermDiff::Failed(_)), _) => ["-".into(), "-".into(), "ERROR".into()],
+		_ => ["-".into(), "-".into(), "-".into()],
+	}
+}
+
+/// The share of the block of each dimension; see [`FormatParams::block_share`].
+///
+/// `unit` is the time if comparing both dimensions.
+fn block_share_cells(
+	info: &ExtrinsicDiff,
+	format: &FormatParams,
+	unit: Dimension,
+	both: bool,
+) -> Vec<String> {
+	let unit = if both { Dimension::Both } else { unit };
+	let shares = block_shares(info, unit, &format.block_limits());
+	let mut cells = shares.iter().map(ToString::to_string).collect::<Vec<_>>();
+	cells.resize(if both { 2 } else { 1 }, "-".into());
+	cells
+}
+
+fn print_history(
+	points: &[HistoryPoint],
+	format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+	match format {
+		OutputFormat::JSON => Ok(serde_json::to_string_pretty(points)?),
+		OutputFormat::CSV => {
+			let mut output = String::from("Commit,Time,Ref Time,Proof Size\n");
+			for p in points {
+				writeln!(output, "{},{},{},{}", p.commit, p.time, p.ref_time, p.proof_size)?;
+			}
+			Ok(output)
+		},
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(vec!["Commit", "Summary", "Ref Time", "Proof Size", "Change [%]"]);
+
+			let mut last: Option<&HistoryPoint> = None;
+			for p in points {
+				// Relative change of the reference time to the previous commit.
+				let change = last
+					.filter(|l| l.ref_time != 0)
+					.map(|l| format!("{:+.2}", percent(l.ref_time, p.ref_time)))
+					.unwrap_or_default();
+				table.add_row(vec![
+					p.commit[..8].to_string(),
+					p.summary.clone(),
+					Dimension::fmt_time(p.ref_time),
+					Dimension::fmt_proof(p.proof_size),
+					change,
+				]);
+				last = Some(p);
+			}
+			Ok(table.to_string())
+		},
+		_ => Err("Unsupported output format".into()),
+	}
+}
+
+#[cfg(feature = "sqlite")]
+fn print_weight_records(
+	records: &[WeightRecord],
+	format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+	match format {
+		OutputFormat::JSON => Ok(serde_json::to_string_pretty(records)?),
+		OutputFormat::CSV => {
+			let mut output = String::from("Commit,Ref,Time,File,Extrinsic,Ref Time,Proof Size\n");
+			for r in records {
+				writeln!(
+					output,
+					"{},{},{},{},{},{},{}",
+					r.commit, r.refname, r.time, r.file, r.extrinsic, r.ref_time, r.proof_size
+				)?;
+			}
+			Ok(output)
+		},
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(vec![
+				"Ref",
+				"Commit",
+				"File",
+				"Extrinsic",
+				"Ref Time",
+				"Proof Size",
+				"Change [%]",
+			]);
+
+			let mut last = std::collections::HashMap::<(&str, &str), u128>::new();
+			for r in records {
+				// Relative change of the reference time to the previous commit.
+				let change = last
+					.insert((&r.file, &r.extrinsic), r.ref_time)
+					.filter(|l| *l != 0)
+					.map(|l| format!("{:+.2}", percent(l, r.ref_time)))
+					.unwrap_or_default();
+				table.add_row(vec![
+					r.refname.clone(),
+					r.commit[..8].to_string(),
+					r.file.clone(),
+					r.extrinsic.clone(),
+					Dimension::fmt_time(r.ref_time),
+					Dimension::fmt_proof(r.proof_size),
+					change,
+				]);
+			}
+			Ok(table.to_string())
+		},
+		_ => Err("Unsupported output format".into()),
+	}
+}
+
+fn dump_extrinsics(
+	extrinsics: &[ChromaticExtrinsic],
+	format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+	match format {
+		OutputFormat::JSON => Ok(serde_json::to_string_pretty(extrinsics)?),
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(vec!["File", "Extrinsic", "Weight Term", "Components"]);
+
+			for extrinsic in extrinsics {
+				let mut ranges = extrinsic.comp_ranges.clone().unwrap_or_default();
+				let mut ranges = ranges.drain().collect::<Vec<_>>();
+				ranges.sort_by(|a, b| a.0.cmp(&b.0));
+				let ranges = ranges
+					.iter()
+					.map(|(c, r)| format!("{}: [{}, {}]", c, r.min, r.max))
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				table.add_row(vec![
+					extrinsic.pallet.to_string(),
+					extrinsic.name.to_string(),
+					extrinsic.term.to_string(),
+					ranges,
+				]);
+			}
+			Ok(table.to_string())
+		},
+		_ => Err("Unsupported output format".into()),
+	}
+}
+
+fn print_access_changes(
+	diff: Vec<AccessChange>,
+	verbose: bool,
+	format: FormatParams,
+) -> Result<(), Box<dyn std::error::Error>> {
+	if diff.is_empty() {
+		print("No changes found.".into(), verbose);
+		return Ok(())
+	}
+	let fmt_access = |a: Option<(u32, u32)>| a.map(|(r, w)| format!("r:{} w:{}", r, w));
+	let fmt_proof = |p: &Option<ProofSize>| match p {
+		Some(ProofSize { mode, added: Some(added) }) =>
+			format!("{} {}", mode, Dimension::fmt_proof(*added as u128)),
+		Some(ProofSize { mode, added: None }) => mode.clone(),
+		None => String::new(),
+	};
+	// Older weight templates have no proof comments.
+	let proofs = diff.iter().any(|c| c.old_proof.is_some() || c.new_proof.is_some());
+	let fmt_change = |c: &AccessChange| {
+		let no_color = !format.colored();
+		match c.change {
+			RelativeChange::Added =>
+				maybe_color(AnsiColor::Red, "Added", Intensity::Normal, no_color),
+			RelativeChange::Removed =>
+				maybe_color(AnsiColor::Green, "Removed", Intensity::Normal, no_color),
+			_ => "Changed".into(),
+		}
+	};
+	let row = |c: &AccessChange| {
+		let mut row = vec![
+			format.filter_path(c.file.to_string()),
+			c.extrinsic.to_string(),
+			c.item.clone(),
+			fmt_access(c.old).unwrap_or_default(),
+			fmt_access(c.new).unwrap_or_default(),
+		];
+		if proofs {
+			row.extend([fmt_proof(&c.old_proof), fmt_proof(&c.new_proof)]);
+		}
+		row.push(fmt_change(c));
+		row
+	};
+	let mut header = vec!["File", "Extrinsic", "Storage Item", "Old", "New"];
+	if proofs {
+		header.extend(["Old PoV", "New PoV"]);
+	}
+	header.push("Change");
+
+	let output = match format.format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format.format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(header);
+			for c in diff.iter() {
+				table.add_row(row(c));
+			}
+			table.to_string()
+		},
+		OutputFormat::CSV => {
+			let mut output = header.join(",") + "\n";
+			for c in diff.iter() {
+				writeln!(output, "{}", row(c).join(","))?;
+			}
+			output
+		},
+		OutputFormat::JSON => serde_json::to_string_pretty(&diff)?,
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
+fn print_machine_changes(
+	diff: Vec<MachineDiff>,
+	verbose: bool,
+	format: FormatParams,
+) -> Result<(), Box<dyn std::error::Error>> {
+	if diff.is_empty() {
+		print("No changes found.".into(), verbose);
+		return Ok(())
+	}
+	let fmt_score = |s: Option<f64>| s.map(|s| format!("{:.2} MiB/s", s)).unwrap_or_default();
+	let fmt_change = |d: &MachineDiff| color_throughput(d.percent, &d.change, !format.colored());
+
+	let output = match format.format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format.format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(vec!["Metric", "Old", "New", "Change [%]"]);
+			for d in diff.iter() {
+				table.add_row(vec![
+					d.metric.clone(),
+					fmt_score(d.old),
+					fmt_score(d.new),
+					fmt_change(d),
+				]);
+			}
+			table.to_string()
+		},
+		OutputFormat::CSV => {
+			let mut output = String::from("Metric,Old,New,Change Percent\n");
+			for d in diff.iter() {
+				writeln!(
+					output,
+					"{},{},{},{}",
+					d.metric,
+					fmt_score(d.old),
+					fmt_score(d.new),
+					fmt_change(d)
+				)?;
+			}
+			output
+		},
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
+fn print_pallet_summary(
+	diff: &TotalDiff,
+	verbose: bool,
+	format: FormatParams,
+	unit: Dimension,
+) -> Result<(), Box<dyn std::error::Error>> {
+	if diff.is_empty() {
+		print("No changes found.".into(), verbose);
+		return Ok(())
+	}
+	let unit = if unit == Dimension::Both { Dimension::Time } else { unit };
+	let mut pallets = summarize_pallets(diff);
+	if let Some(top) = format.top {
+		pallets.truncate(top);
+	}
+	let fmt_change = |p: &PalletSummary| {
+		let change =
+			if p.old == p.new { RelativeChange::Unchanged } else { RelativeChange::Changed };
+		color_percent(p.percent(), &change, !format.colored())
+	};
+	let header = [
+		"File",
+		"Changed",
+		"Added",
+		"Removed",
+		"Errors",
+		"Old",
+		"New",
+		"Change [%]",
+		"Abs. Change",
+	];
+	let row = |p: &PalletSummary| {
+		vec![
+			format.filter_path(p.file.clone()),
+			p.changed.to_string(),
+			p.added.to_string(),
+			p.removed.to_string(),
+			p.errors.to_string(),
+			unit.fmt_value(p.old),
+			unit.fmt_value(p.new),
+			fmt_change(p),
+			unit.fmt_value(p.abs_change),
+		]
+	};
+
+	let output = match format.format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format.format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(header.to_vec());
+			for p in pallets.iter() {
+				table.add_row(row(p));
+			}
+			table.to_string()
+		},
+		OutputFormat::CSV => {
+			let mut output = header.join(",");
+			for p in pallets.iter() {
+				write!(output, "\n{}", row(p).join(","))?;
+			}
+			output
+		},
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
+fn print_runtime_matrix(
+	diff: &TotalDiff,
+	verbose: bool,
+	format: FormatParams,
+) -> Result<(), Box<dyn std::error::Error>> {
+	if diff.is_empty() {
+		print("No changes found.".into(), verbose);
+		return Ok(())
+	}
+	let mut matrix = summarize_matrix(diff);
+	if let Some(top) = format.top {
+		matrix.pallets.truncate(top);
+	}
+	let header = std::iter::once("Pallet".to_string())
+		.chain(matrix.runtimes.iter().map(|r| format.filter_path(r.clone())))
+		.collect::<Vec<_>>();
+	let row = |(pallet, cells): &(String, Vec<Option<PalletSummary>>)| {
+		std::iter::once(pallet.clone())
+			.chain(cells.iter().map(|cell| match cell {
+				Some(p) => {
+					let change = if p.old == p.new {
+						RelativeChange::Unchanged
+					} else {
+						RelativeChange::Changed
+					};
+					color_percent(p.percent(), &change, !format.colored())
+				},
+				None => "-".into(),
+			}))
+			.collect::<Vec<_>>()
+	};
+
+	let output = match format.format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format.format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(header);
+			for p in matrix.pallets.iter() {
+				table.add_row(row(p));
+			}
+			table.to_string()
+		},
+		OutputFormat::CSV => {
+			let mut output = header.join(",");
+			for p in matrix.pallets.iter() {
+				write!(output, "\n{}", row(p).join(","))?;
+			}
+			output
+		},
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
+fn print_runtime_totals(
+	totals: &[RuntimeSummary],
+	verbose: bool,
+	format: OutputFormat,
+	no_color: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let header = ["Runtime", "Dimension", "Extrinsics", "Old", "New", "Change [%]"];
+	let row = |t: &RuntimeSummary| {
+		let change =
+			if t.old == t.new { RelativeChange::Unchanged } else { RelativeChange::Changed };
+		vec![
+			t.runtime.clone(),
+			format!("{:?}", t.unit),
+			t.extrinsics.to_string(),
+			t.unit.fmt_value(t.old),
+			t.unit.fmt_value(t.new),
+			color_percent(t.percent(), &change, no_color),
+		]
+	};
+
+	let output = match format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(header.to_vec());
+			for t in totals {
+				table.add_row(row(t));
+			}
+			table.to_string()
+		},
+		OutputFormat::CSV => {
+			let mut output = header.join(",");
+			for t in totals {
+				write!(output, "\n{}", row(t).join(","))?;
+			}
+			output
+		},
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
+fn print_stats(
+	stats: &DiffStats,
+	verbose: bool,
+	format: OutputFormat,
+	no_color: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let percent = |p: Percent| color_percent(p, &RelativeChange::Changed, no_color);
+	let output = match format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(vec!["Change [%]", "Extrinsics"]);
+			for (label, count) in DiffStats::bucket_labels().into_iter().zip(&stats.buckets) {
+				table.add_row(vec![label, count.to_string()]);
+			}
+
+			let mut output = format!(
+				"{}\n\n{} changed, {} added, {} removed, {} failed",
+				table, stats.changed, stats.added, stats.removed, stats.errors
+			);
+			if let (Some(median), Some(p95)) = (stats.median, stats.p95) {
+				write!(output, "\nMedian {} %, p95 {} %", percent(median), percent(p95))?;
+			}
+			for (kind, max) in
+				[("increase", &stats.max_increase), ("decrease", &stats.max_decrease)]
+			{
+				if let Some((file, name, p)) = max {
+					write!(output, "\nMax {}: {}::{} {} %", kind, file, name, percent(*p))?;
+				}
+			}
+			output
+		},
+		OutputFormat::JSON => serde_json::to_string_pretty(stats)?,
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
+/// Parse the weight files of the `inputs`; `-` is read from stdin and URLs are downloaded.
+///
+/// A downloaded file is named after the end of its URL. Stdin is named after the only input of the
+/// `other` side, so that `--old - --new pallet_balances.rs` pairs up.
+fn parse_inputs(
+	inputs: &[PathBuf],
+	other: &[PathBuf],
+	params: &CompareParams,
+) -> Result<Vec<ChromaticExtrinsic>, Box<dyn std::error::Error>> {
+	let (mut extrinsics, mut paths) = (Vec::new(), Vec::new());
+	for input in inputs {
+		let (name, content) = if input.as_os_str() == "-" {
+			let name = match other {
+				[other] => input_name(other),
+				_ => None,
+			};
+			let mut content = String::new();
+			std::io::stdin().read_to_string(&mut content)?;
+			(name.unwrap_or_else(|| "stdin".into()), content)
+		} else if is_url(input) {
+			let url = input.to_string_lossy();
+			if params.offline {
+				return Err(format!("Cannot download {} with --offline", url).into())
+			}
+			(input_name(input).unwrap_or_else(|| url.to_string()), fetch_url(&url)?)
+		} else {
+			paths.push(input.clone());
+			continue
+		};
+		match parse_any_content(name.into(), content) {
+			Ok(parsed) => extrinsics.extend(parsed),
+			Err(_) if params.ignore_errors => (),
+			Err(err) => return Err(err.into()),
+		}
+	}
+	let paths = expand_globs(paths)?;
+	extrinsics.extend(if params.ignore_errors {
+		try_parse_paths(&paths)
+	} else {
+		parse_paths(&paths)?
+	});
+	Ok(extrinsics)
+}
+
+fn is_url(input: &Path) -> bool {
+	let input = input.to_string_lossy();
+	input.starts_with("https://") || input.starts_with("http://")
+}
+
+/// File name of a weight file or the last segment of a URL; `None` for stdin and directories.
+fn input_name(input: &Path) -> Option<String> {
+	if is_url(input) {
+		let url = input.to_string_lossy();
+		let path = url.split(['?', '#']).next().unwrap_or_default();
+		return path.rsplit('/').next().filter(|n| !n.is_empty()).map(String::from)
+	}
+	input
+		.is_file()
+		.then(|| input.file_name().map(|n| n.to_string_lossy().into_owned()))
+		.flatten()
+}
+
+/// Expand the glob patterns among `paths`, eg. a quoted `old/*.rs`; other paths stay as they are.
+fn expand_globs(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
+	let mut expanded = Vec::new();
+	for path in paths {
+		let pattern = path.to_string_lossy();
+		if path.exists() || !pattern.contains(['*', '?', '[']) {
+			expanded.push(path);
+			continue
+		}
+		let files = glob::glob(&pattern)
+			.map_err(|e| format!("Invalid glob pattern {}: {}", pattern, e))?
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| format!("Glob pattern error: {}", e))?;
+		if files.is_empty() {
+			return Err(format!("No files match {}", pattern))
+		}
+		expanded.extend(files);
+	}
+	Ok(expanded)
+}
+
+/// Render comparisons that were exported with `--format json` as report with one section each.
+fn merge_diffs(
+	files: &[PathBuf],
+	unit: Dimension,
+	format: report::ReportFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+	let mut sections = Vec::new();
+	for file in files {
+		let mut diff = read_diff(file)?;
+		sort_changes_by(&mut diff, SortBy::Percent);
+		let title = file.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+		sections.push(report::Section { title, diff });
+	}
+	Ok(report::render("Weight changes", "Comparison", &sections, unit, format)?)
+}
+
+/// Read a comparison that was exported with `--format json`.
+fn read_diff(file: &Path) -> Result<TotalDiff, String> {
+	let content = std::fs::read_to_string(file)
+		.map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+	VersionedDiff::from_json(&content)
+		.map_err(|e| format!("Could not parse {}: {}", file.display(), e))
+}
+
+fn print_lint_findings(
+	findings: &[LintFinding],
+	verbose: bool,
+	format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let header = ["File", "Extrinsic", "Check", "Problem"];
+	let row = |f: &LintFinding| {
+		vec![f.pallet.clone(), f.extrinsic.clone(), f.check.to_string(), f.message.clone()]
+	};
+
+	let output = match format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(header.to_vec());
+			for f in findings {
+				table.add_row(row(f));
+			}
+			table.to_string()
+		},
+		OutputFormat::CSV => {
+			let mut output = header.join(",");
+			for f in findings {
+				write!(output, "\n{}", row(f).join(","))?;
+			}
+			output
+		},
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
+fn print_budget_usage(
+	usages: &[BudgetUsage],
+	verbose: bool,
+	format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let header = ["File", "Extrinsic", "Ref Time", "Time [%]", "Proof Size", "Proof [%]"];
+	let row = |u: &BudgetUsage| {
+		vec![
+			u.pallet.clone(),
+			u.extrinsic.clone(),
+			Dimension::fmt_time(u.time),
+			format!("{:.2}", u.time_percent),
+			Dimension::fmt_proof(u.proof),
+			format!("{:.2}", u.proof_percent),
+		]
+	};
+
+	let output = match format {
+		OutputFormat::Human | OutputFormat::Markdown => {
+			let mut table = Table::new();
+			table.set_constraints(vec![comfy_table::ColumnConstraint::ContentWidth]);
+			if format == OutputFormat::Markdown {
+				table.load_preset(comfy_table::presets::ASCII_MARKDOWN);
+			}
+			table.set_header(header.to_vec());
+			for u in usages {
+				table.add_row(row(u));
+			}
+			table.to_string()
+		},
+		OutputFormat::CSV => {
+			let mut output = header.join(",");
+			for u in usages {
+				write!(output, "\n{}", row(u).join(","))?;
+			}
+			output
+		},
+		_ => return Err("Unsupported output format".into()),
+	};
+
+	print(output, verbose);
+	Ok(())
+}
+
 fn print(msg: String, verbose: bool) {
 	if verbose {
 		log::info!("{}", msg);
@@ -366,37 +2860,86 @@ enum AnsiColor {
 	Green,
 }
 
+/// How strongly a change is highlighted; larger changes stand out more.
+#[derive(Clone, Copy)]
+enum Intensity {
+	Faint,
+	Normal,
+	Bold,
+}
+
+impl Intensity {
+	fn of(p: Percent) -> Self {
+		match p.abs() {
+			x if x < 5.0 => Self::Faint,
+			x if x < 25.0 => Self::Normal,
+			_ => Self::Bold,
+		}
+	}
+}
+
 pub fn color_percent(p: Percent, change: &RelativeChange, no_color: bool) -> String {
 	match change {
 		RelativeChange::Unchanged => "Unchanged".to_string(),
-		RelativeChange::Added => maybe_color(AnsiColor::Red, "Added", no_color),
-		RelativeChange::Removed => maybe_color(AnsiColor::Green, "Removed", no_color),
-		RelativeChange::Changed => {
+		RelativeChange::Added => maybe_color(AnsiColor::Red, "Added", Intensity::Normal, no_color),
+		RelativeChange::Removed =>
+			maybe_color(AnsiColor::Green, "Removed", Intensity::Normal, no_color),
+		RelativeChange::Changed | RelativeChange::Renamed => {
+			let s = format!("{:+5.2}", p);
+			match p {
+				x if x < 0.0 => maybe_color(AnsiColor::Green, s, Intensity::of(p), no_color),
+				x if x > 0.0 => maybe_color(AnsiColor::Red, s, Intensity::of(p), no_color),
+				_ => maybe_color(AnsiColor::White, s, Intensity::Normal, no_color),
+			}
+		},
+	}
+}
+
+/// Like [`color_percent`] but for throughputs where an increase is an improvement.
+fn color_throughput(p: Percent, change: &RelativeChange, no_color: bool) -> String {
+	match change {
+		RelativeChange::Unchanged => "Unchanged".to_string(),
+		RelativeChange::Added =>
+			maybe_color(AnsiColor::Green, "Added", Intensity::Normal, no_color),
+		RelativeChange::Removed =>
+			maybe_color(AnsiColor::Red, "Removed", Intensity::Normal, no_color),
+		RelativeChange::Changed | RelativeChange::Renamed => {
 			let s = format!("{:+5.2}", p);
 			match p {
-				x if x < 0.0 => maybe_color(AnsiColor::Green, s, no_color),
-				x if x > 0.0 => maybe_color(AnsiColor::Red, s, no_color),
-				_ => maybe_color(AnsiColor::White, s, no_color),
+				x if x < 0.0 => maybe_color(AnsiColor::Red, s, Intensity::of(p), no_color),
+				x if x > 0.0 => maybe_color(AnsiColor::Green, s, Intensity::of(p), no_color),
+				_ => maybe_color(AnsiColor::White, s, Intensity::Normal, no_color),
 			}
 		},
 	}
 }
 
 impl AnsiColor {
-	fn paint(&self, s: &str) -> String {
-		match self {
-			AnsiColor::White => format!("\x1b[37m{}\x1b[0m", s),
-			AnsiColor::Red => format!("\x1b[31m{}\x1b[0m", s),
-			AnsiColor::Green => format!("\x1b[32m{}\x1b[0m", s),
-		}
+	fn paint(&self, s: &str, intensity: Intensity) -> String {
+		let style = match intensity {
+			Intensity::Faint => "2;",
+			Intensity::Normal => "",
+			Intensity::Bold => "1;",
+		};
+		let color = match self {
+			AnsiColor::White => 37,
+			AnsiColor::Red => 31,
+			AnsiColor::Green => 32,
+		};
+		format!("\x1b[{}{}m{}\x1b[0m", style, color, s)
 	}
 }
 
-fn maybe_color<S: Into<String>>(clr: AnsiColor, msg: S, no_color: bool) -> String {
+fn maybe_color<S: Into<String>>(
+	clr: AnsiColor,
+	msg: S,
+	intensity: Intensity,
+	no_color: bool,
+) -> String {
 	let msg = msg.into();
 	if no_color {
 		msg
 	} else {
-		clr.paint(&msg)
+		clr.paint(&msg, intensity)
 	}
 }
@@ -0,0 +1,52 @@
+//! Progress bar for long running comparisons.
+//!
+//! The bar is drawn to stderr and hidden if that is not a terminal.
+
+use std::path::Path;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use subweight_core::progress::Progress;
+
+pub struct Bar(ProgressBar);
+
+impl Bar {
+	pub fn new(hidden: bool) -> Self {
+		let bar = if hidden { ProgressBar::hidden() } else { ProgressBar::new(0) };
+		bar.set_style(
+			ProgressStyle::with_template("{msg:20} [{bar:40}] {pos}/{len}")
+				.expect("Template is valid")
+				.progress_chars("=> "),
+		);
+		Self(bar)
+	}
+
+	pub fn finish(&self) {
+		self.0.finish_and_clear();
+	}
+
+	fn stage(&self, msg: String, len: usize) {
+		self.0.set_length(len as u64);
+		self.0.set_message(msg);
+	}
+}
+
+impl Progress for Bar {
+	fn fetched(&self, refname: &str, received: usize, total: usize) {
+		self.stage(format!("Fetching {}", refname), total);
+		self.0.set_position(received as u64);
+	}
+
+	fn files_discovered(&self, refname: &str, count: usize) {
+		self.stage(format!("Parsing {}", refname), count);
+		self.0.set_position(0);
+	}
+
+	fn file_parsed(&self, _file: &Path) {
+		self.0.inc(1);
+	}
+
+	fn extrinsic_compared(&self, done: usize, total: usize) {
+		self.stage("Comparing".into(), total);
+		self.0.set_position(done as u64);
+	}
+}
@@ -0,0 +1,164 @@
+//! Reports that combine several comparisons, eg. of consecutive releases or of CI shards.
+//!
+//! A report starts with an overview that counts the changes of each comparison and then has one
+//! section per comparison with a table of its changed extrinsics. It is rendered as Markdown for
+//! release notes, as standalone HTML page or as JSON that keeps all details.
+
+use std::fmt::Write;
+use subweight_core::{Dimension, ExtrinsicDiff, RelativeChange, TermChange, TotalDiff};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+	Markdown,
+	Html,
+	Json,
+}
+
+/// The changes of one comparison.
+#[derive(serde::Serialize)]
+pub struct Section {
+	pub title: String,
+	pub diff: TotalDiff,
+}
+
+/// Render the report of all sections in the given order.
+///
+/// `label` is the header of the column with the section titles in the overview.
+pub fn render(
+	title: &str,
+	label: &str,
+	sections: &[Section],
+	unit: Dimension,
+	format: ReportFormat,
+) -> Result<String, serde_json::Error> {
+	if format == ReportFormat::Json {
+		return serde_json::to_string_pretty(sections)
+	}
+	let mut doc = Doc { html: format == ReportFormat::Html, out: String::new() };
+	doc.heading(1, title);
+
+	let rows = sections
+		.iter()
+		.map(|s| {
+			let count = |f: &dyn Fn(&TermChange) -> bool| {
+				s.diff.iter().filter(|d| d.term().map_or(false, f)).count().to_string()
+			};
+			vec![
+				s.title.clone(),
+				count(&|t| t.change == RelativeChange::Changed && t.percent > 0.0),
+				count(&|t| t.change == RelativeChange::Changed && t.percent < 0.0),
+				count(&|t| t.change == RelativeChange::Added),
+				count(&|t| t.change == RelativeChange::Removed),
+			]
+		})
+		.collect();
+	doc.table(&[label, "Increased", "Decreased", "Added", "Removed"], rows);
+
+	// The time columns are first when comparing both dimensions.
+	let dims = match unit {
+		Dimension::Both => vec![Dimension::Time, Dimension::Proof],
+		unit => vec![unit],
+	};
+	let mut header = vec!["File".to_string(), "Extrinsic".to_string()];
+	for dim in &dims {
+		let name = if unit == Dimension::Both { format!(" {:?}", dim) } else { String::new() };
+		header.extend([
+			format!("Old{}", name),
+			format!("New{}", name),
+			format!("Change{} [%]", name),
+		]);
+	}
+	let header = header.iter().map(String::as_str).collect::<Vec<_>>();
+
+	for section in sections {
+		doc.heading(2, &section.title);
+		if section.diff.is_empty() {
+			doc.paragraph("No changes found.");
+			continue
+		}
+		let rows = section.diff.iter().map(|d| row(d, &dims)).collect();
+		doc.table(&header, rows);
+	}
+	Ok(doc.finish(title))
+}
+
+fn row(diff: &ExtrinsicDiff, dims: &[Dimension]) -> Vec<String> {
+	let mut row = vec![diff.file.clone(), diff.display_name()];
+	for (i, dim) in dims.iter().enumerate() {
+		let term = if i == 0 { diff.term() } else { diff.proof_term() };
+		match term {
+			Some(t) => row.extend([
+				t.old_v.map(|v| dim.fmt_value(v)).unwrap_or_default(),
+				t.new_v.map(|v| dim.fmt_value(v)).unwrap_or_default(),
+				crate::color_percent(t.percent, &t.change, true),
+			]),
+			None => row.extend([String::new(), String::new(), "Error".into()]),
+		}
+	}
+	row
+}
+
+/// The output of a report as Markdown or HTML.
+struct Doc {
+	html: bool,
+	out: String,
+}
+
+impl Doc {
+	fn heading(&mut self, level: usize, text: &str) {
+		let _ = if self.html {
+			writeln!(self.out, "<h{0}>{1}</h{0}>", level, escape(text))
+		} else {
+			writeln!(self.out, "{} {}\n", "#".repeat(level), text)
+		};
+	}
+
+	fn paragraph(&mut self, text: &str) {
+		let _ = if self.html {
+			writeln!(self.out, "<p>{}</p>", escape(text))
+		} else {
+			writeln!(self.out, "{}\n", text)
+		};
+	}
+
+	fn table(&mut self, header: &[&str], rows: Vec<Vec<String>>) {
+		if self.html {
+			let line = |tag: &str, cells: &[String]| {
+				let cells = cells.iter().map(|c| format!("<{0}>{1}</{0}>", tag, escape(c)));
+				format!("<tr>{}</tr>\n", cells.collect::<String>())
+			};
+			self.out.push_str("<table>\n");
+			let header = header.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+			self.out.push_str(&line("th", &header));
+			for row in rows {
+				self.out.push_str(&line("td", &row));
+			}
+			self.out.push_str("</table>\n");
+		} else {
+			let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+			self.out.push_str(&line(header.iter().map(|h| h.to_string()).collect()));
+			self.out.push_str(&line(header.iter().map(|_| "---".into()).collect()));
+			for row in rows {
+				// Pipes would end the cell.
+				self.out.push_str(&line(row.iter().map(|c| c.replace('|', "\\|")).collect()));
+			}
+			self.out.push('\n');
+		}
+	}
+
+	fn finish(self, title: &str) -> String {
+		if !self.html {
+			return self.out
+		}
+		format!(
+			"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+			<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+			escape(title),
+			self.out
+		)
+	}
+}
+
+fn escape(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
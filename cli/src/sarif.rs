@@ -0,0 +1,96 @@
+//! SARIF output for code scanning tools like the one of GitHub.
+//!
+//! Each increased, added or failed extrinsic is reported as result that is attached to the lines of
+//! its weight function. The file paths are relative to the repository when comparing commits, as
+//! GitHub expects.
+
+use serde_json::{json, Value};
+use subweight_core::{Dimension, ExtrinsicDiff, RelativeChange, TermDiff, VERSION};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The rules that a result can violate; `(id, level, description)`.
+const RULES: [(&str, &str, &str); 3] = [
+	("weight-increase", "warning", "The weight of an extrinsic increased."),
+	("weight-added", "note", "An extrinsic with a weight was added."),
+	("weight-error", "error", "The weight of an extrinsic could not be compared."),
+];
+
+/// Convert the changes into a SARIF log with one run.
+pub fn to_sarif(diff: &[ExtrinsicDiff], unit: Dimension) -> Value {
+	let rules = RULES
+		.iter()
+		.map(|(id, level, text)| {
+			json!({
+				"id": id,
+				"shortDescription": { "text": text },
+				"defaultConfiguration": { "level": level },
+			})
+		})
+		.collect::<Vec<_>>();
+	let results = diff.iter().filter_map(|d| to_result(d, unit)).collect::<Vec<_>>();
+
+	json!({
+		"$schema": SCHEMA,
+		"version": "2.1.0",
+		"runs": [{
+			"tool": {
+				"driver": {
+					"name": "subweight",
+					"version": VERSION.as_str(),
+					"informationUri": env!("CARGO_PKG_REPOSITORY"),
+					"rules": rules,
+				}
+			},
+			"results": results,
+		}]
+	})
+}
+
+fn to_result(diff: &ExtrinsicDiff, unit: Dimension) -> Option<Value> {
+	let (rule, level, message) = finding(diff, unit)?;
+	let mut location = json!({ "artifactLocation": { "uri": diff.file } });
+	if let Some(span) = diff.span {
+		location["region"] =
+			json!({ "startLine": span.function.start, "endLine": span.function.end });
+	}
+
+	Some(json!({
+		"ruleId": rule,
+		"level": level,
+		"message": { "text": format!("{}: {}", diff.name, message) },
+		"locations": [{ "physicalLocation": location }],
+	}))
+}
+
+/// The rule that a change violates with its level and a message; `None` if it is fine.
+pub fn finding(
+	diff: &ExtrinsicDiff,
+	unit: Dimension,
+) -> Option<(&'static str, &'static str, String)> {
+	let unit = if unit == Dimension::Both { Dimension::Time } else { unit };
+	let (rule, message) = match &diff.change {
+		TermDiff::Failed(err) => ("weight-error", err.clone()),
+		TermDiff::Changed(c) | TermDiff::Warning(c, _) => match c.change {
+			RelativeChange::Added => (
+				"weight-added",
+				format!("Added with weight {}", unit.fmt_value(c.new_v.unwrap_or_default())),
+			),
+			RelativeChange::Changed if c.percent > 0.0 => (
+				"weight-increase",
+				format!(
+					"Weight increased by {:.2}% from {} to {}",
+					c.percent,
+					unit.fmt_value(c.old_v.unwrap_or_default()),
+					unit.fmt_value(c.new_v.unwrap_or_default())
+				),
+			),
+			_ => return None,
+		},
+	};
+	let level = RULES
+		.iter()
+		.find(|(id, ..)| *id == rule)
+		.map_or("warning", |(_, level, _)| *level);
+	Some((rule, level, message))
+}
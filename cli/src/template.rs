@@ -0,0 +1,63 @@
+//! Render the changes through a user-provided Handlebars template; eg. for PR comments.
+//!
+//! The template gets the `unit` of the comparison and a list of `changes`. Each change has the
+//! `file`, `extrinsic` and `kind` (`changed`, `unchanged`, `added`, `removed` or `error`) of the
+//! extrinsic, the formatted `old` and `new` values and `change` as shown in the tables and the full
+//! `diff` as in the JSON output:
+//!
+//! ```handlebars
+//! {{#each changes}}
+//! - `{{extrinsic}}`: {{old}} → {{new}} ({{change}})
+//! {{/each}}
+//! ```
+//!
+//! The output is not escaped since it is usually Markdown.
+
+use handlebars::Handlebars;
+use serde_json::{json, Value};
+use std::path::Path;
+use subweight_core::{Dimension, ExtrinsicDiff, RelativeChange, TermDiff};
+
+/// Render the template at `path`.
+pub fn render(path: &Path, diff: &[ExtrinsicDiff], unit: Dimension) -> Result<String, String> {
+	let template = std::fs::read_to_string(path)
+		.map_err(|e| format!("Failed to read template {}: {}", path.display(), e))?;
+	let mut handlebars = Handlebars::new();
+	handlebars.register_escape_fn(handlebars::no_escape);
+
+	let context = json!({
+		"unit": format!("{:?}", unit).to_lowercase(),
+		"changes": diff.iter().map(|d| to_context(d, unit)).collect::<Vec<_>>(),
+	});
+	handlebars
+		.render_template(&template, &context)
+		.map_err(|e| format!("Failed to render template {}: {}", path.display(), e))
+}
+
+fn to_context(diff: &ExtrinsicDiff, unit: Dimension) -> Value {
+	let unit = if unit == Dimension::Both { Dimension::Time } else { unit };
+	let fmt = |v: Option<u128>| v.map(|v| unit.fmt_value(v)).unwrap_or_default();
+	let (kind, old, new, change) = match &diff.change {
+		TermDiff::Failed(_) => ("error", String::new(), String::new(), String::new()),
+		TermDiff::Changed(c) | TermDiff::Warning(c, _) => {
+			let kind = match c.change {
+				RelativeChange::Unchanged => "unchanged",
+				RelativeChange::Changed => "changed",
+				RelativeChange::Added => "added",
+				RelativeChange::Removed => "removed",
+				RelativeChange::Renamed => "renamed",
+			};
+			(kind, fmt(c.old_v), fmt(c.new_v), crate::color_percent(c.percent, &c.change, true))
+		},
+	};
+
+	json!({
+		"file": diff.file,
+		"extrinsic": diff.name,
+		"kind": kind,
+		"old": old,
+		"new": new,
+		"change": change,
+		"diff": diff,
+	})
+}
@@ -1,6 +1,9 @@
 use assert_cmd::cargo::CommandCargoExt;
 use serial_test::serial;
-use std::process::Command;
+use std::{
+	io::Write,
+	process::{Command, Stdio},
+};
 
 use subweight_core::testing::{
 	assert_contains, assert_not_contains, assert_version, root_dir, succeeds,
@@ -89,6 +92,74 @@ fn subweight_compare_commits_errors() {
 	assert_contains(&out, "Failed to reset branch");
 }
 
+#[test]
+#[serial]
+#[cfg_attr(not(feature = "polkadot"), ignore)]
+fn subweight_compare_runtimes_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "runtimes", "--method", "base", "--ref", "v0.9.20"])
+		.args(["--a", "runtime/polkadot", "--b", "runtime/kusama"])
+		.args(["--repo", root_dir().join("repos/polkadot").to_str().unwrap()])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "src/weights/pallet_staking.rs");
+}
+
+#[test]
+#[serial]
+#[cfg(feature = "sqlite")]
+#[cfg_attr(not(feature = "polkadot"), ignore)]
+fn subweight_db_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let db = dir.path().join("weights.sqlite");
+	for refname in ["v0.9.19", "v0.9.20"] {
+		let output = Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["db", "ingest", refname, "--method", "base"])
+			.args(["--path-pattern", "runtime/polkadot/src/weights/*.rs"])
+			.args(["--repo", root_dir().join("repos/polkadot").to_str().unwrap()])
+			.args(["--db", db.to_str().unwrap()])
+			.output()
+			.unwrap();
+		succeeds(&output);
+	}
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["db", "query", "--file", "pallet_staking.rs", "--extrinsic", "bond"])
+		.args(["--db", db.to_str().unwrap(), "--format", "csv"])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_eq!(out.lines().count(), 3, "{}", out);
+	assert_contains(&out, ",v0.9.20,");
+}
+
+#[test]
+#[serial]
+#[cfg_attr(not(feature = "polkadot"), ignore)]
+fn subweight_report_releases_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["report", "releases", "--method", "base", "--tag-pattern", "v0.9.1*"])
+		.args(["--last", "2", "--path-pattern", "runtime/polkadot/src/weights/*.rs"])
+		.args(["--repo", root_dir().join("repos/polkadot").to_str().unwrap()])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "## v0.9.17 → v0.9.18");
+	assert_contains(&out, "## v0.9.18 → v0.9.19");
+	assert!(!out.contains("v0.9.16"), "{}", out);
+}
+
 #[test]
 fn subweight_compare_files_works() {
 	let output = Command::cargo_bin("subweight")
@@ -111,6 +182,208 @@ fn subweight_compare_files_works() {
 	assert_not_contains(&out, "Added");
 }
 
+/// Directories pair their files by name; a quoted glob expands like the shell would.
+#[test]
+fn subweight_compare_files_directories_works() {
+	let dir = tempfile::tempdir().unwrap();
+	for version in ["old", "new"] {
+		let weights = dir.path().join(version).join("weights");
+		std::fs::create_dir_all(&weights).unwrap();
+		let file = root_dir().join(format!("test_data/{}/pallet_staking.rs.txt", version));
+		std::fs::copy(file, weights.join("pallet_staking.rs")).unwrap();
+		std::fs::write(weights.join("mod.rs"), "pub mod pallet_staking;").unwrap();
+	}
+	let compare = |old: &str, new: &str| {
+		let output = Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "base", "--format", "csv", "--no-color"])
+			.args(["--old", old, "--new", new])
+			.current_dir(dir.path())
+			.output()
+			.unwrap();
+		succeeds(&output);
+		String::from_utf8_lossy(&output.stdout).trim().to_owned()
+	};
+
+	let out = compare("old", "new");
+	assert_contains(&out, "\nweights/pallet_staking.rs,get_npos_voters,111.28ms,144.12ms");
+	assert_not_contains(&out, "mod.rs");
+
+	let out = compare("old/weights/pallet_*.rs", "new/weights/pallet_?taking.rs");
+	assert_contains(&out, "\npallet_staking.rs,get_npos_voters,111.28ms,144.12ms");
+}
+
+/// Stdin is named after the file of the other side.
+#[test]
+fn subweight_compare_files_stdin_works() {
+	let old = std::fs::read(root_dir().join("test_data/old/pallet_staking.rs.txt")).unwrap();
+	let mut child = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--format", "csv", "--no-color"])
+		.args(["--old", "-", "--new"])
+		.arg(root_dir().join("test_data/new/pallet_staking.rs.txt"))
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	child.stdin.take().unwrap().write_all(&old).unwrap();
+	let output = child.wait_with_output().unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout);
+	assert_contains(&out, "\npallet_staking.rs.txt,get_npos_voters,111.28ms,144.12ms");
+}
+
+#[test]
+fn subweight_compare_files_url_offline_fails() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--offline"])
+		.args(["--old", "https://example.com/pallet_staking.rs", "--new"])
+		.arg(root_dir().join("test_data/new/pallet_staking.rs.txt"))
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+
+	let err = String::from_utf8_lossy(&output.stderr);
+	assert_contains(&err, "Cannot download https://example.com/pallet_staking.rs with --offline");
+}
+
+#[test]
+fn subweight_compare_files_sort_top_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "guess-worst", "--sort", "name", "--top", "2"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			"--format",
+			"csv",
+			"--no-color",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	let names = out.lines().skip(1).map(|l| l.split(',').nth(1).unwrap()).collect::<Vec<_>>();
+	assert_eq!(names, vec!["cancel_deferred_slash", "force_apply_min_commission"]);
+}
+
+/// The cause names the components of the worst case.
+#[test]
+fn subweight_compare_files_worst_case_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args([
+			"compare",
+			"files",
+			"--method",
+			"guess-worst",
+			"--extrinsic",
+			"cancel_deferred_slash",
+		])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			"--format",
+			"markdown",
+			"--no-color",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout);
+	assert!(out.contains("worst at {s=1}"), "{}", out);
+}
+
+#[test]
+fn subweight_compare_files_group_by_pallet_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "guess-worst", "--group-by", "pallet"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			"--format",
+			"csv",
+			"--threshold",
+			"0",
+			"--no-color",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "File,Changed,Added,Removed,Errors");
+	assert_contains(&out, "pallet_staking.rs.txt,29,1,1,0,");
+}
+
+#[test]
+fn subweight_compare_files_group_by_runtime_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "guess-worst", "--group-by", "runtime"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			"--format",
+			"csv",
+			"--threshold",
+			"0",
+			"--no-color",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	let lines = out.lines().collect::<Vec<_>>();
+	assert_eq!(lines.len(), 2, "{}", out);
+	assert!(lines[0].starts_with("Pallet,"), "{}", out);
+	// The old file names its pallet.
+	assert!(lines[1].starts_with("pallet_staking,"), "{}", out);
+}
+
+#[test]
+fn subweight_compare_files_print_terms_works() {
+	let run = |latex: bool| {
+		let output = Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "base", "--print-terms", "--format", "markdown"])
+			.args(latex.then_some("--latex"))
+			.args([
+				"--old",
+				root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+				"--new",
+				root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+				"--extrinsic",
+				"^bond$",
+				"--threshold",
+				"0",
+				"--no-color",
+			])
+			.output()
+			.unwrap();
+		succeeds(&output);
+		String::from_utf8_lossy(&output.stdout).trim().to_owned()
+	};
+
+	assert_contains(&run(false), "+ 4·READ + 4·WRITE");
+	assert_contains(&run(true), "+ 4 \\cdot \\mathrm{READ} + 4 \\cdot \\mathrm{WRITE}$");
+}
+
 #[test]
 fn subweight_compare_files_same_no_changes() {
 	let output = Command::cargo_bin("subweight")
@@ -158,3 +431,845 @@ fn subweight_compare_files_errors() {
 	let out = String::from_utf8_lossy(&output.stderr).trim().to_owned();
 	assert_contains(&out, "Could not find a weight implementation in the passed file");
 }
+
+#[test]
+fn subweight_compare_files_both_dimensions_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--unit", "both", "--no-color"])
+		.args([
+			"--old",
+			root_dir().join("test_data/new/staking_chromatic.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/staking_chromatic.rs.txt").to_str().unwrap(),
+			"--threshold",
+			"0",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "Old Time");
+	assert_contains(&out, "Proof [%]");
+	assert_not_contains(&out, "ERROR");
+}
+
+#[test]
+fn subweight_color_works() {
+	// The output is not a terminal, so `auto` does not color.
+	let cases: [(&[&str], bool); 4] = [
+		(&["--color", "always"], true),
+		(&["--color", "always", "--no-color"], false),
+		(&["--color", "never"], false),
+		(&[], false),
+	];
+	for (args, colored) in cases {
+		let output = Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "base", "--threshold", "0"])
+			.args(args)
+			.args([
+				"--old",
+				root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+				"--new",
+				root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			])
+			.output()
+			.unwrap();
+		succeeds(&output);
+
+		let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+		assert_eq!(out.contains("\x1b["), colored, "{:?}", args);
+	}
+}
+
+#[test]
+fn subweight_porcelain_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--threshold", "0", "--porcelain"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	// Empty fields at the end of the line must be kept.
+	let out = String::from_utf8_lossy(&output.stdout).to_string();
+	assert!(!out.is_empty());
+	for line in out.lines() {
+		let fields = line.split('\t').collect::<Vec<_>>();
+		assert_eq!(fields.len(), 9, "{}", line);
+		assert_eq!(fields[0], "pallet_staking.rs.txt");
+		assert!(["unchanged", "changed", "added", "removed"].contains(&fields[2]), "{}", line);
+		assert!(fields[3].chars().all(|c| c.is_ascii_digit()), "{}", line);
+	}
+}
+
+#[test]
+fn subweight_sarif_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--threshold", "0", "--format", "sarif"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let log: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(log["version"], "2.1.0");
+	let results = log["runs"][0]["results"].as_array().unwrap();
+	assert!(!results.is_empty());
+	for result in results {
+		assert!(["weight-increase", "weight-added"].contains(&result["ruleId"].as_str().unwrap()));
+		let location = &result["locations"][0]["physicalLocation"];
+		assert_eq!(location["artifactLocation"]["uri"], "pallet_staking.rs.txt");
+		assert!(location["region"]["startLine"].as_u64().unwrap() > 1);
+	}
+}
+
+#[test]
+fn subweight_template_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let template = dir.path().join("comment.hbs");
+	std::fs::write(
+		&template,
+		"{{unit}}:\n{{#each changes}}{{kind}} {{extrinsic}} {{old}} -> {{new}} \
+		({{change}}) {{diff.change.changed.method}}\n{{/each}}",
+	)
+	.unwrap();
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--format", "template"])
+		.arg("--template")
+		.arg(&template)
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert!(out.starts_with("time:\n"));
+	assert_contains(&out, "changed get_npos_voters 111.28ms -> 144.12ms (+29.51) base");
+	assert_contains(&out, "added set_min_commission  -> 104.66us (Added)");
+}
+
+#[test]
+fn subweight_detect_renames_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let weights = |name: &str| {
+		format!(
+			"impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {{
+				fn {}() -> Weight {{ Weight::from_parts(10_000_000, 0) }}
+			}}",
+			name
+		)
+	};
+	for (version, name) in [("old", "set_balance"), ("new", "force_set_balance")] {
+		std::fs::create_dir(dir.path().join(version)).unwrap();
+		std::fs::write(dir.path().join(version).join("pallet_balances.rs"), weights(name)).unwrap();
+	}
+	let compare = |detect: bool| {
+		let output = Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "base", "--porcelain"])
+			.args(detect.then_some("--detect-renames"))
+			.arg("--old")
+			.arg(dir.path().join("old/pallet_balances.rs"))
+			.arg("--new")
+			.arg(dir.path().join("new/pallet_balances.rs"))
+			.output()
+			.unwrap();
+		succeeds(&output);
+		String::from_utf8_lossy(&output.stdout).into_owned()
+	};
+
+	assert_contains(&compare(false), "force_set_balance\tadded");
+	assert_contains(&compare(false), "set_balance\tremoved");
+	let out = compare(true);
+	assert_contains(&out, "force_set_balance\trenamed\t10000000\t10000000\t0.00");
+	assert_not_contains(&out, "removed");
+}
+
+#[test]
+fn subweight_schema_works() {
+	let output = Command::cargo_bin("subweight").unwrap().arg("schema").output().unwrap();
+	succeeds(&output);
+	let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(schema["required"], serde_json::json!(["changes", "schema_version"]));
+	assert!(schema["definitions"]["TermChange"].is_object());
+}
+
+#[test]
+fn subweight_compare_overhead_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "overhead", "--method", "base", "--no-color"])
+		.args([
+			"--old",
+			root_dir().join("test_data/new/extrinsic_weights.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir()
+				.join("test_data/chromatic/extrinsic_weights.rs.txt")
+				.to_str()
+				.unwrap(),
+			"--threshold",
+			"0",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "ExtrinsicBaseWeight");
+	assert_contains(&out, "+17.17");
+}
+
+#[test]
+fn subweight_compare_storage_weights_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "storage-weights", "--method", "base", "--no-color"])
+		.args([
+			"--old",
+			root_dir().join("test_data/new/rocksdb_weights.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/rocksdb_weights.rs.txt").to_str().unwrap(),
+			root_dir().join("test_data/new/paritydb_weights.rs.txt").to_str().unwrap(),
+			"--threshold",
+			"0",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_eq!(out.matches("Unchanged").count(), 2, "RocksDB should be unchanged: {}", out);
+	assert_eq!(out.matches("Added").count(), 2, "ParityDB should be added: {}", out);
+}
+
+#[test]
+fn subweight_compare_machines_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "machines", "--no-color", "--threshold", "5"])
+		.args([
+			"--old",
+			root_dir().join("test_data/machine/old.json").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/machine/new.json").to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "DiskSeqWrite");
+	assert_contains(&out, "-21.42");
+	assert_contains(&out, "MemCopy");
+	assert_contains(&out, "+5.96");
+	// Below the threshold:
+	assert_not_contains(&out, "Blake2256 ");
+	assert_not_contains(&out, "Sr25519Verify");
+}
+
+#[test]
+fn subweight_parse_dump_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["parse", "dump"])
+		.arg(root_dir().join("test_data/new/staking_chromatic.rs.txt"))
+		.arg(root_dir().join("test_data/new/rocksdb_weights.rs.txt"))
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "payout_stakers_alive_staked");
+	assert_contains(&out, "n: [0, 256]");
+	assert_contains(&out, "RocksDbWeight");
+}
+
+#[test]
+fn subweight_parse_dump_json_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["parse", "dump", "--format", "json"])
+		.arg(root_dir().join("test_data/new/rocksdb_weights.rs.txt"))
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert!(out.starts_with('['), "Output should be a JSON list: {}", out);
+	assert_contains(&out, "\"name\": \"read\"");
+	assert_contains(&out, "\"name\": \"write\"");
+}
+
+#[test]
+fn subweight_compare_json_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "guess-worst", "--format", "json"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			"--extrinsic",
+			"withdraw_unbonded_kill",
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+	assert_eq!(json["schema_version"], 1);
+	assert_eq!(json["changes"][0]["name"], "withdraw_unbonded_kill");
+	assert_eq!(json["changes"][0]["change"]["changed"]["method"], "guess-worst");
+}
+
+#[test]
+fn subweight_show_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["show", "--extrinsic", "payout_stakers_alive_staked", "--set", "n=0"])
+		.arg(root_dir().join("test_data/new/staking_chromatic.rs.txt"))
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "n=0");
+	assert_contains(&out, "704.03us");
+	assert_contains(&out, "81.63KiB");
+}
+
+/// The storage accesses take the time of the `--db-weights`.
+#[test]
+fn subweight_show_db_weights_works() {
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["show", "--extrinsic", "payout_stakers_alive_staked", "--set", "n=0"])
+		.args(["--db-weights", "paritydb"])
+		.arg(root_dir().join("test_data/new/staking_chromatic.rs.txt"))
+		.output()
+		.unwrap();
+	succeeds(&output);
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "384.03us");
+	assert_contains(&out, "81.63KiB");
+}
+
+#[test]
+fn subweight_check_budget_works() {
+	let file = root_dir().join("test_data/new/staking_chromatic.rs.txt");
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["check", "budget", "--max-proof", "5MiB", "--fraction", "75"])
+		.arg(&file)
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "get_npos_voters");
+	assert_contains(&out, "payout_stakers_alive_staked");
+	assert_not_contains(&out, "get_npos_targets");
+	let err = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+	assert_contains(&err, "2 extrinsics use more than 75% of the block");
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["check", "budget", "--max-proof", "50MiB"])
+		.arg(&file)
+		.output()
+		.unwrap();
+	succeeds(&output);
+}
+
+#[test]
+fn subweight_lint_works() {
+	let file = root_dir().join("test_data/new/staking_chromatic.rs.txt");
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.arg("lint")
+		.arg(&file)
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "get_npos_voters");
+	assert_contains(&out, "proof-limit");
+	let err = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+	assert_contains(&err, "1 problems found");
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["lint", "--checks", "zero-base,missing-ranges"])
+		.arg(&file)
+		.output()
+		.unwrap();
+	succeeds(&output);
+}
+
+#[test]
+fn subweight_format_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let file = dir.path().join("pallet_staking.rs");
+	std::fs::copy(root_dir().join("test_data/new/staking_chromatic.rs.txt"), &file).unwrap();
+	let format = |check: bool| {
+		Command::cargo_bin("subweight")
+			.unwrap()
+			.arg("format")
+			.args(check.then_some("--check"))
+			.arg(&file)
+			.output()
+			.unwrap()
+	};
+
+	let output = format(true);
+	assert!(!output.status.success());
+	let err = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+	assert_contains(&err, "1 files are not formatted");
+
+	succeeds(&format(false));
+	let content = std::fs::read_to_string(&file).unwrap();
+	assert_contains(&content, "Weight::from_parts(31_269_223, 16_026).saturating_mul(n.into())");
+	succeeds(&format(true));
+}
+
+#[test]
+fn subweight_merge_works() {
+	let dir = tempfile::tempdir().unwrap();
+	for (name, old) in [("polkadot", "old"), ("kusama", "new")] {
+		let output = Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "base", "--format", "json"])
+			.args([
+				"--old",
+				root_dir()
+					.join(format!("test_data/{}/pallet_staking.rs.txt", old))
+					.to_str()
+					.unwrap(),
+				"--new",
+				root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			])
+			.output()
+			.unwrap();
+		succeeds(&output);
+		std::fs::write(dir.path().join(format!("{}.json", name)), output.stdout).unwrap();
+	}
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.arg("merge")
+		.args([dir.path().join("polkadot.json"), dir.path().join("kusama.json")])
+		.output()
+		.unwrap();
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "| Comparison | Increased | Decreased | Added | Removed |");
+	assert_contains(&out, "## polkadot");
+	assert_contains(&out, "| pallet_staking.rs.txt | validate |");
+	assert_contains(&out, "## kusama\n\nNo changes found.");
+}
+
+#[test]
+fn subweight_gitlab_note_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--format", "json"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+	let diff = dir.path().join("polkadot.json");
+	std::fs::write(&diff, output.stdout).unwrap();
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["gitlab", "note", "--dry-run"])
+		.arg(&diff)
+		.output()
+		.unwrap();
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).into_owned();
+	assert!(out.starts_with("<!-- subweight -->\n# Weight changes"));
+	assert_contains(&out, "| pallet_staking.rs.txt | validate |");
+
+	// The note of the previous run is updated.
+	let (url, requests) = serve_http(vec![
+		r#"[{"id": 3, "body": "LGTM"}, {"id": 7, "body": "<!-- subweight -->\nOld"}]"#,
+		"{}",
+	]);
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["gitlab", "note"])
+		.arg(&diff)
+		.env("CI_API_V4_URL", format!("{}/api/v4", url))
+		.env("CI_PROJECT_ID", "42")
+		.env("CI_MERGE_REQUEST_IID", "5")
+		.env("SUBWEIGHT_GITLAB_TOKEN", "secret")
+		.output()
+		.unwrap();
+	succeeds(&output);
+	let requests = requests.join().unwrap();
+	assert!(requests[0].starts_with("GET /api/v4/projects/42/merge_requests/5/notes?"));
+	assert!(requests[1].starts_with("PUT /api/v4/projects/42/merge_requests/5/notes/7 "));
+	assert_contains(&requests[1].to_lowercase(), "private-token: secret");
+	assert_contains(&requests[1], r#"{"body":"<!-- subweight -->\n# Weight changes"#);
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["gitlab", "note"])
+		.arg(&diff)
+		.env_remove("CI_MERGE_REQUEST_IID")
+		.env("SUBWEIGHT_GITLAB_TOKEN", "secret")
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	assert_contains(&String::from_utf8_lossy(&output.stderr), "CI_MERGE_REQUEST_IID is not set");
+}
+
+#[test]
+fn subweight_github_check_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["compare", "files", "--method", "base", "--threshold", "0", "--format", "json"])
+		.args([
+			"--old",
+			root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+	let files = ["polkadot.json", "kusama.json"].map(|f| dir.path().join(f));
+	for file in &files {
+		std::fs::write(file, &output.stdout).unwrap();
+	}
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["github", "check", "--dry-run"])
+		.arg(&files[0])
+		.output()
+		.unwrap();
+	succeeds(&output);
+	let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(run["conclusion"], "neutral");
+	assert_eq!(run["output"]["annotations"].as_array().unwrap().len(), 27);
+	assert_eq!(
+		run["output"]["annotations"][0],
+		serde_json::json!({
+			"path": "pallet_staking.rs.txt",
+			"start_line": 658,
+			"end_line": 674,
+			"annotation_level": "warning",
+			"title": "get_npos_voters: weight-increase",
+			"message": "Weight increased by 29.51% from 111.28ms to 144.12ms",
+		})
+	);
+	assert_contains(run["output"]["summary"].as_str().unwrap(), "| get_npos_voters |");
+
+	// More than 50 annotations need a second request.
+	let (url, requests) = serve_http(vec![r#"{"id": 9}"#, "{}"]);
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["github", "check", "--sha", "abc", "--fail-on", "increase"])
+		.args(&files)
+		.env("GITHUB_API_URL", &url)
+		.env("GITHUB_REPOSITORY", "paritytech/polkadot")
+		.env("GITHUB_TOKEN", "secret")
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	assert_contains(&String::from_utf8_lossy(&output.stderr), "changes are not acknowledged");
+
+	let requests = requests.join().unwrap();
+	let body = |request: &str| -> serde_json::Value {
+		serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap()
+	};
+	assert!(requests[0].starts_with("POST /repos/paritytech/polkadot/check-runs "));
+	assert_contains(&requests[0].to_lowercase(), "authorization: bearer secret");
+	let created = body(&requests[0]);
+	assert_eq!(created["head_sha"], "abc");
+	assert_eq!(created["conclusion"], "failure");
+	assert_eq!(created["output"]["annotations"].as_array().unwrap().len(), 50);
+	assert!(requests[1].starts_with("PATCH /repos/paritytech/polkadot/check-runs/9 "));
+	assert_eq!(body(&requests[1])["output"]["annotations"].as_array().unwrap().len(), 4);
+}
+
+/// Answer one HTTP request with each of the JSON `responses` and return the requests.
+fn serve_http(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+	use std::io::{BufRead, BufReader, Read};
+
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("http://{}", listener.local_addr().unwrap());
+	let handle = std::thread::spawn(move || {
+		let mut requests = Vec::new();
+		for response in responses {
+			let (stream, _) = listener.accept().unwrap();
+			let mut reader = BufReader::new(stream);
+			let mut request = String::new();
+			while !request.ends_with("\r\n\r\n") {
+				reader.read_line(&mut request).unwrap();
+			}
+			let length = request
+				.lines()
+				.find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(str::to_owned))
+				.map_or(0, |l| l.parse().unwrap());
+			let mut body = vec![0; length];
+			reader.read_exact(&mut body).unwrap();
+			request.push_str(&String::from_utf8(body).unwrap());
+			requests.push(request);
+
+			write!(
+				reader.get_mut(),
+				"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+				response.len(),
+				response
+			)
+			.unwrap();
+		}
+		requests
+	});
+	(url, handle)
+}
+
+#[test]
+fn subweight_stats_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let compare = |args: &[&str]| {
+		Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "base", "--no-color"])
+			.args([
+				"--old",
+				root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+				"--new",
+				root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			])
+			.args(args)
+			.output()
+			.unwrap()
+	};
+
+	let output = compare(&["--summary"]);
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "13 changed, 1 added, 1 removed, 0 failed");
+	assert_contains(&out, "Max decrease: pallet_staking.rs.txt::cancel_deferred_slash -70.78 %");
+
+	let output = compare(&["--format", "json"]);
+	succeeds(&output);
+	let file = dir.path().join("diff.json");
+	std::fs::write(&file, output.stdout).unwrap();
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.args(["stats", "--format", "json"])
+		.arg(&file)
+		.output()
+		.unwrap();
+	succeeds(&output);
+	let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(stats["changed"], 13);
+	assert_eq!(stats["buckets"].as_array().unwrap().len(), 11);
+}
+
+#[test]
+fn subweight_block_share_works() {
+	let compare = |args: &[&str]| {
+		Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "base", "--no-color", "--threshold", "0"])
+			.args([
+				"--old",
+				root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+				"--new",
+				root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+			])
+			.args(args)
+			.output()
+			.unwrap()
+	};
+
+	let output = compare(&["--block-share", "--max-time", "1ms"]);
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "Of Block");
+	assert_contains(&out, "% → ");
+
+	let all = out.lines().count();
+	let output = compare(&["--block-share", "--max-time", "10ms", "--min-block-share", "10"]);
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert!(out.lines().count() < all);
+	assert_contains(&out, "get_npos_voters");
+	assert_not_contains(&out, "set_payee");
+
+	let output = compare(&["--min-block-share", "10"]);
+	succeeds(&output);
+	assert_contains(&String::from_utf8_lossy(&output.stdout), "No changes found.");
+}
+
+#[test]
+fn subweight_config_file_works() {
+	let dir = tempfile::tempdir().unwrap();
+	std::fs::write(
+		dir.path().join(".subweight.toml"),
+		"method = \"base\"\nthreshold = 0\nformat = \"csv\"\nignore-extrinsics = [\"^bond\"]\n",
+	)
+	.unwrap();
+	let run = |args: &[&str]| {
+		Command::cargo_bin("subweight")
+			.unwrap()
+			.current_dir(dir.path())
+			.args(["compare", "files"])
+			.args([
+				"--old",
+				root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+				"--new",
+				root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+				"--no-color",
+			])
+			.args(args)
+			.output()
+			.unwrap()
+	};
+
+	let output = run(&[]);
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "File,Extrinsic,Old,New");
+	assert_contains(&out, ",unbond,");
+	assert_not_contains(&out, ",bond_extra,");
+
+	// Arguments take precedence over the config.
+	let output = run(&["--format", "human"]);
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_not_contains(&out, "File,Extrinsic,Old,New");
+}
+
+/// The config only applies to the arguments that it documents; not to others of the same name.
+#[test]
+fn subweight_config_file_is_scoped() {
+	let dir = tempfile::tempdir().unwrap();
+	std::fs::write(dir.path().join(".subweight.toml"), "threshold = 50\n").unwrap();
+
+	let output = Command::cargo_bin("subweight")
+		.unwrap()
+		.current_dir(dir.path())
+		.args(["compare", "machines", "--no-color"])
+		.args([
+			"--old",
+			root_dir().join("test_data/machine/old.json").to_str().unwrap(),
+			"--new",
+			root_dir().join("test_data/machine/new.json").to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	succeeds(&output);
+	assert_contains(&String::from_utf8_lossy(&output.stdout), "-21.42");
+}
+
+#[test]
+fn subweight_hook_works() {
+	let dir = tempfile::tempdir().unwrap();
+	git_init(dir.path());
+	std::fs::write(dir.path().join(".subweight.toml"), "path-pattern = \"weights/*.rs\"\n")
+		.unwrap();
+	let run = |args: &[&str]| {
+		Command::cargo_bin("subweight")
+			.unwrap()
+			.current_dir(dir.path())
+			.arg("hook")
+			.args(args)
+			.output()
+			.unwrap()
+	};
+
+	succeeds(&run(&["install", "--kind", "pre-push"]));
+	let hook = std::fs::read_to_string(dir.path().join(".git/hooks/pre-push")).unwrap();
+	assert_contains(&hook, "exec subweight hook run --kind pre-push");
+	assert!(!run(&["install", "--kind", "pre-push"]).status.success());
+	succeeds(&run(&["install", "--kind", "pre-push", "--force"]));
+
+	// Nothing to compare before the first commit; the path pattern is from the config.
+	let output = run(&["run", "--method", "base"]);
+	succeeds(&output);
+	assert_contains(&String::from_utf8_lossy(&output.stdout), "No changes found.");
+}
+
+/// Initialize an empty git repository.
+fn git_init(path: &std::path::Path) {
+	let status = Command::new("git").arg("init").arg("-q").arg(path).status();
+	assert!(status.unwrap().success());
+}
+
+#[test]
+fn subweight_fail_on_allowlist_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let allowlist = dir.path().join("allowlist.toml");
+	std::fs::write(
+		&allowlist,
+		"[[acknowledged]]\npallet = \"pallet_staking.rs.txt\"\nextrinsic = \"withdraw_unbonded_kill\"\n",
+	)
+	.unwrap();
+	let run = |args: &[&str]| {
+		Command::cargo_bin("subweight")
+			.unwrap()
+			.args(["compare", "files", "--method", "guess-worst", "--fail-on", "increase"])
+			.args([
+				"--old",
+				root_dir().join("test_data/old/pallet_staking.rs.txt").to_str().unwrap(),
+				"--new",
+				root_dir().join("test_data/new/pallet_staking.rs.txt").to_str().unwrap(),
+				"--extrinsic",
+				"withdraw_unbonded_kill",
+				"--no-color",
+			])
+			.args(args)
+			.output()
+			.unwrap()
+	};
+
+	let output = run(&[]);
+	assert!(!output.status.success());
+	let err = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+	assert_contains(&err, "1 changes are not acknowledged");
+
+	// Still reported but marked.
+	let output = run(&["--allowlist", allowlist.to_str().unwrap()]);
+	succeeds(&output);
+	let out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	assert_contains(&out, "+692.05 (acknowledged)");
+}
@@ -4,7 +4,8 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughpu
 use std::path::Path;
 
 use subweight_core::parse::{
-	pallet::parse_file as parse_pallet, storage::parse_file as parse_storage,
+	pallet::{parse_content, parse_file as parse_pallet},
+	storage::parse_file as parse_storage,
 };
 
 fn bench_parse_pallet(c: &mut Criterion) {
@@ -19,6 +20,31 @@ fn bench_parse_pallet(c: &mut Criterion) {
 	});
 }
 
+/// Weights that use constants and helper functions; they are inlined before parsing.
+fn bench_parse_inlined(c: &mut Criterion) {
+	let num_ext = 50;
+	let extrinsics = (0..num_ext)
+		.map(|i| format!("fn call_{}(n: u32) -> Weight {{ Self::scaled(BASE, n) }}", i))
+		.collect::<String>();
+	let content = format!(
+		"const BASE: u64 = 1_000;
+		impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {{ {} }}
+		impl<T: frame_system::Config> SubstrateWeight<T> {{
+			fn scaled(base: u64, n: u32) -> Weight {{
+				Weight::from_parts(base, 0).saturating_mul(n.into())
+			}}
+		}}",
+		extrinsics
+	);
+	let mut group = c.benchmark_group("Parse");
+
+	group.sample_size(100);
+	group.throughput(Throughput::Elements(num_ext));
+	group.bench_function("Pallet.Inlined", |b| {
+		b.iter(|| parse_content("pallet".into(), black_box(content.clone())).expect("Must work"))
+	});
+}
+
 fn bench_parse_storage(c: &mut Criterion) {
 	let path = Path::new("../test_data/new/rocksdb_weights.rs.txt");
 	let mut group = c.benchmark_group("Parse");
@@ -33,6 +59,6 @@ fn bench_parse_storage(c: &mut Criterion) {
 criterion_group! {
 	name = benches;
 	config = Criterion::default();
-	targets = bench_parse_pallet, bench_parse_storage
+	targets = bench_parse_pallet, bench_parse_inlined, bench_parse_storage
 }
 criterion_main!(benches);
@@ -0,0 +1,222 @@
+//! Compare the storage items that each extrinsic accesses according to its weight file.
+//!
+//! The benchmarking CLI writes a comment per storage item above each weight function:
+//!
+//! ```ignore
+//! /// Storage: Staking Bonded (r:1 w:1)
+//! /// Storage: `Staking::Ledger` (r:1 w:1)
+//! fn bond() -> Weight {
+//! ```
+//!
+//! The formulas only contain the sum of all reads and writes, so an extrinsic that swaps one item
+//! for another or reads an item more often at the cost of another one looks unchanged there.
+//!
+//! Newer templates follow each storage comment with the PoV mode of the item and how much it adds
+//! to the worst case proof size:
+//!
+//! ```ignore
+//! /// Proof: Staking Bonded (max_values: None, max_size: Some(72), added: 2547, mode: MaxEncodedLen)
+//! /// Proof Skipped: Staking SlashingSpans (max_values: None, max_size: None, mode: Measured)
+//! ```
+
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::{Path, PathBuf},
+};
+
+#[cfg(feature = "git")]
+use crate::{checkout_temp, list_files};
+use crate::{
+	parse::{read_file, PathStripping},
+	ExtrinsicName, PalletName, RelativeChange, SwcError,
+};
+
+/// How often an extrinsic reads and writes a storage item.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StorageAccess {
+	/// The item as `Pallet::Item`; or as written in the comment if it has another form.
+	pub item: String,
+	pub reads: u32,
+	pub writes: u32,
+	/// From the `Proof:` comment of the item; if any.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub proof: Option<ProofSize>,
+}
+
+/// How a storage item is accounted for in the proof size.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProofSize {
+	/// The PoV mode, eg. `MaxEncodedLen` or `Measured`.
+	pub mode: String,
+	/// Bytes that one read adds to the estimated proof size; not known for `Measured` items.
+	pub added: Option<u32>,
+}
+
+/// The storage accesses of each extrinsic of each weight file.
+pub type Accesses = BTreeMap<(PalletName, ExtrinsicName), Vec<StorageAccess>>;
+
+/// A storage item that an extrinsic accesses differently.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AccessChange {
+	pub file: PalletName,
+	pub extrinsic: ExtrinsicName,
+	pub item: String,
+	/// Reads and writes before and after; `None` if the item was not accessed.
+	pub old: Option<(u32, u32)>,
+	pub new: Option<(u32, u32)>,
+	/// Proof size accounting before and after; `None` if not annotated.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub old_proof: Option<ProofSize>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub new_proof: Option<ProofSize>,
+	pub change: RelativeChange,
+}
+
+/// Parse the storage comments of the weight functions of a file.
+///
+/// Each function that has a body is included, even without accesses. Functions that appear twice,
+/// like in the `SubstrateWeight` and `()` impls, keep their first accesses.
+pub fn parse_content(content: &str) -> BTreeMap<ExtrinsicName, Vec<StorageAccess>> {
+	lazy_static! {
+		static ref STORAGE: Regex =
+			Regex::new(r"^\s*///?\s*Storage:\s*(?P<item>.+?)\s*\(r:(?P<r>\d+) w:(?P<w>\d+)\)")
+				.unwrap();
+		static ref PROOF: Regex =
+			Regex::new(r"^\s*///?\s*Proof(?: Skipped)?:\s*(?P<item>.+?)\s*\((?P<attrs>.*)\)\s*$")
+				.unwrap();
+		static ref FUNCTION: Regex = Regex::new(r"^\s*fn\s+(?P<name>\w+)").unwrap();
+	}
+
+	let mut functions = BTreeMap::new();
+	let mut accesses = Vec::new();
+	for line in content.lines() {
+		if let Ok(Some(caps)) = STORAGE.captures(line) {
+			accesses.push(StorageAccess {
+				item: item_name(&caps["item"]),
+				reads: caps["r"].parse().unwrap_or_default(),
+				writes: caps["w"].parse().unwrap_or_default(),
+				proof: None,
+			});
+		} else if let Ok(Some(caps)) = PROOF.captures(line) {
+			let item = item_name(&caps["item"]);
+			// Belongs to the storage comment above it.
+			if let Some(access) = accesses.iter_mut().rev().find(|a| a.item == item) {
+				access.proof = parse_proof(&caps["attrs"]);
+			}
+		} else if let Ok(Some(caps)) = FUNCTION.captures(line) {
+			let accesses = std::mem::take(&mut accesses);
+			// Declarations of the `WeightInfo` trait.
+			if !line.trim_end().ends_with(';') {
+				functions.entry(caps["name"].into()).or_insert(accesses);
+			}
+		} else {
+			// Comments of other items, like a struct, do not belong to the next function.
+			let line = line.trim();
+			if !(line.is_empty() || line.starts_with("//") || line.starts_with("#[")) {
+				accesses.clear();
+			}
+		}
+	}
+	functions
+}
+
+/// Parse attributes like `max_values: None, max_size: Some(72), added: 2547, mode: MaxEncodedLen`.
+fn parse_proof(attrs: &str) -> Option<ProofSize> {
+	let attrs = attrs.replace('`', "");
+	let attr = |name: &str| {
+		attrs
+			.split(", ")
+			.find_map(|a| a.strip_prefix(name)?.strip_prefix(": ").map(str::trim))
+	};
+	Some(ProofSize {
+		mode: attr("mode")?.into(),
+		added: attr("added").and_then(|a| a.parse().ok()),
+	})
+}
+
+/// Unify the item names of the old (`Staking Bonded`) and new (`` `Staking::Bonded` ``) templates.
+fn item_name(raw: &str) -> String {
+	let name = raw.replace('`', "");
+	match name.split_once(' ') {
+		Some((pallet, item)) if !item.contains(' ') && !name.contains("::") =>
+			format!("{}::{}", pallet, item),
+		_ => name,
+	}
+}
+
+/// Parse the storage accesses of weight files; named by their file name.
+pub fn parse_files(paths: &[PathBuf]) -> Result<Accesses, SwcError> {
+	let mut accesses = Accesses::new();
+	for path in paths {
+		let file: PalletName = PathStripping::FileName.strip(Path::new("."), path).into();
+		for (extrinsic, items) in parse_content(&read_file(path)?) {
+			accesses.insert((file.clone(), extrinsic), items);
+		}
+	}
+	Ok(accesses)
+}
+
+/// Parse the storage accesses of the weight files of a commit; named relative to the repository.
+#[cfg(feature = "git")]
+pub fn parse_commit(
+	repo: &Path,
+	refname: &str,
+	path_pattern: &str,
+	pull: bool,
+) -> Result<Accesses, SwcError> {
+	if path_pattern.contains("..") {
+		return Err(SwcError::Glob("Path pattern cannot contain '..'".into()))
+	}
+	let dir = checkout_temp(repo, refname, pull)?;
+	let mut accesses = Accesses::new();
+	for path in list_files(dir.path(), path_pattern, usize::MAX)? {
+		let file: PalletName = PathStripping::RepoRelative.strip(dir.path(), &path).into();
+		for (extrinsic, items) in parse_content(&read_file(&path)?) {
+			accesses.insert((file.clone(), extrinsic), items);
+		}
+	}
+	Ok(accesses)
+}
+
+/// The items that are accessed differently by extrinsics that exist in both versions.
+///
+/// An item also changed if its PoV mode or its proof size changed. Added and removed extrinsics
+/// are left out since all of their items would be reported.
+pub fn compare_accesses(olds: &Accesses, news: &Accesses) -> Vec<AccessChange> {
+	let mut changes = Vec::new();
+	for (key, old) in olds {
+		let Some(new) = news.get(key) else { continue };
+		let find =
+			|items: &[StorageAccess], item: &str| items.iter().find(|a| a.item == item).cloned();
+		let items = old.iter().chain(new).map(|a| a.item.as_str()).collect::<BTreeSet<_>>();
+
+		for item in items {
+			let (old, new) = (find(old, item), find(new, item));
+			let change = match (&old, &new) {
+				(None, Some(_)) => RelativeChange::Added,
+				(Some(_), None) => RelativeChange::Removed,
+				(Some(o), Some(n)) if (o.reads, o.writes) != (n.reads, n.writes) =>
+					RelativeChange::Changed,
+				// Older templates have no proof comments.
+				(
+					Some(StorageAccess { proof: Some(o), .. }),
+					Some(StorageAccess { proof: Some(n), .. }),
+				) if o != n => RelativeChange::Changed,
+				_ => continue,
+			};
+			changes.push(AccessChange {
+				file: key.0.clone(),
+				extrinsic: key.1.clone(),
+				item: item.into(),
+				old: old.as_ref().map(|a| (a.reads, a.writes)),
+				new: new.as_ref().map(|a| (a.reads, a.writes)),
+				old_proof: old.and_then(|a| a.proof),
+				new_proof: new.and_then(|a| a.proof),
+				change,
+			});
+		}
+	}
+	changes
+}
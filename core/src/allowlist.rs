@@ -0,0 +1,104 @@
+//! Acknowledge known regressions so that they do not fail CI again.
+//!
+//! The allowlist is a TOML file with one entry per extrinsic:
+//!
+//! ```toml
+//! [[acknowledged]]
+//! pallet = "pallet_staking"
+//! extrinsic = "payout_stakers"
+//! reason = "More storage reads since #1234"
+//! expires = 2024-01-31
+//! ```
+//!
+//! An entry can expire at a date or only be valid for one `commit`. Acknowledged changes are still
+//! reported, but marked as such.
+
+use std::path::Path;
+
+use crate::{ExtrinsicDiff, TotalDiff};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Allowlist {
+	#[serde(default)]
+	pub acknowledged: Vec<Acknowledged>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Acknowledged {
+	/// Name of the weight file with or without the `.rs` extension.
+	pub pallet: String,
+	pub extrinsic: String,
+	pub reason: Option<String>,
+	/// The last day on which the entry is valid.
+	pub expires: Option<toml::value::Datetime>,
+	/// Only valid when comparing against this commit; can be abbreviated.
+	pub commit: Option<String>,
+}
+
+impl Allowlist {
+	pub fn load(path: &Path) -> Result<Self, String> {
+		let content = std::fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read allowlist {}: {}", path.display(), e))?;
+		toml::from_str(&content).map_err(|e| format!("Invalid allowlist {}: {}", path.display(), e))
+	}
+
+	/// Mark all changes that are on the allowlist as acknowledged.
+	///
+	/// `today` is a date like `2024-01-31` and `commit` is the new commit of the comparison.
+	pub fn acknowledge(&self, diff: &mut TotalDiff, today: &str, commit: Option<&str>) {
+		for ext in diff.iter_mut() {
+			if let Some(ack) = self.acknowledged.iter().find(|a| a.matches(ext, today, commit)) {
+				ext.acknowledged = Some(ack.reason.clone().unwrap_or_default());
+			}
+		}
+	}
+}
+
+impl Acknowledged {
+	fn matches(&self, ext: &ExtrinsicDiff, today: &str, commit: Option<&str>) -> bool {
+		let file = Path::new(&ext.file);
+		let pallet = file.file_stem().map_or(false, |s| s == self.pallet.as_str()) ||
+			file.file_name().map_or(false, |s| s == self.pallet.as_str());
+		if !pallet || ext.name != self.extrinsic {
+			return false
+		}
+
+		if let Some(expires) = self.expires.as_ref().and_then(|e| e.date) {
+			let expires = format!("{:04}-{:02}-{:02}", expires.year, expires.month, expires.day);
+			if today > expires.as_str() {
+				log::warn!("Acknowledgement of {}::{} expired", self.pallet, self.extrinsic);
+				return false
+			}
+		}
+		match (&self.commit, commit) {
+			(Some(ack), Some(commit)) => commit.starts_with(ack.as_str()),
+			(Some(_), None) => false,
+			(None, _) => true,
+		}
+	}
+}
+
+/// The current UTC date like `2024-01-31`.
+pub fn today() -> String {
+	let secs = std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map_or(0, |d| d.as_secs());
+	let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+	format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Convert days since 1970-01-01 into a date. From <http://howardhinnant.github.io/date_algorithms.html>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+	let z = days + 719_468;
+	let era = z.div_euclid(146_097);
+	let doe = z.rem_euclid(146_097);
+	let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+	let year = yoe + era * 400 + i64::from(month <= 2);
+	(year, month, day)
+}
@@ -0,0 +1,62 @@
+//! Read weight files from tar and zip archives, eg. the artifacts of a CI run.
+//!
+//! An archive is extracted into a temporary directory and then parsed like any other directory of
+//! weight files. Archives often wrap their files in one folder that is named after the archive, eg.
+//! `weights-old/`. Such folders are skipped so that the files of two archives still pair up.
+
+use std::{
+	fs::File,
+	path::{Path, PathBuf},
+};
+
+use crate::SwcError;
+
+/// An archive that was extracted into a temporary directory; deleted when dropped.
+pub struct Extracted {
+	_dir: tempfile::TempDir,
+	root: PathBuf,
+}
+
+impl Extracted {
+	/// The folder of the extracted files; below any folders that wrap all of them.
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+}
+
+/// Whether the file is a `.tar`, `.tar.gz`, `.tgz` or `.zip` archive.
+pub fn is_archive(path: &Path) -> bool {
+	let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+	[".tar", ".tar.gz", ".tgz", ".zip"].iter().any(|ext| name.ends_with(ext))
+}
+
+/// Extract the archive into a temporary directory.
+///
+/// Entries can not escape the directory, eg. with a path like `../x.rs`.
+pub fn extract(archive: &Path) -> Result<Extracted, SwcError> {
+	let err = |e: &dyn std::fmt::Display| SwcError::Parse(format!("{}: {}", archive.display(), e));
+	let dir = tempfile::tempdir()?;
+	log::info!("Extracting {} into {}", archive.display(), dir.path().display());
+
+	let file = File::open(archive).map_err(|e| err(&e))?;
+	let name = archive.to_string_lossy().to_lowercase();
+	if name.ends_with(".zip") {
+		let mut zip = zip::ZipArchive::new(file).map_err(|e| err(&e))?;
+		zip.extract(dir.path()).map_err(|e| err(&e))?;
+	} else if name.ends_with(".tar") {
+		tar::Archive::new(file).unpack(dir.path()).map_err(|e| err(&e))?;
+	} else {
+		let gz = flate2::read::GzDecoder::new(file);
+		tar::Archive::new(gz).unpack(dir.path()).map_err(|e| err(&e))?;
+	}
+
+	let mut root = dir.path().to_path_buf();
+	loop {
+		let entries = std::fs::read_dir(&root)?.collect::<Result<Vec<_>, _>>()?;
+		match entries.as_slice() {
+			[single] if single.path().is_dir() => root = single.path(),
+			_ => break,
+		}
+	}
+	Ok(Extracted { _dir: dir, root })
+}
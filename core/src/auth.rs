@@ -0,0 +1,139 @@
+//! Credentials and proxy for fetching from private repositories.
+//!
+//! They are read from the environment by default:
+//!
+//! - `SUBWEIGHT_GIT_TOKEN`, or else `GITHUB_TOKEN`: Token for HTTPS remotes.
+//! - `SUBWEIGHT_GIT_USERNAME`: User of the token; defaults to `x-access-token` which GitHub and
+//!   Gitea accept. GitLab needs `oauth2`.
+//! - `SUBWEIGHT_SSH_KEY` and `SUBWEIGHT_SSH_PASSPHRASE`: Private key for SSH remotes. The SSH agent
+//!   is used without a key.
+//! - `SUBWEIGHT_PROXY`: Proxy for HTTPS remotes, eg. `http://proxy:3128`. Without it, the
+//!   `http.proxy` config of git and the `HTTPS_PROXY` variable apply.
+//!
+//! Both the fetches through libgit2 and the clones through the `git` command use them. The `git`
+//! command gets them as environment variables so that the token does not show up in its arguments;
+//! it cannot decrypt a key with a passphrase though, use the SSH agent for that.
+
+use lazy_static::lazy_static;
+use std::{path::PathBuf, sync::RwLock};
+
+lazy_static! {
+	static ref CREDENTIALS: RwLock<Credentials> = RwLock::new(Credentials::from_env());
+}
+
+/// Credentials for all fetches; not `Debug` to keep the secrets out of the logs.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+	pub token: Option<String>,
+	pub username: Option<String>,
+	pub ssh_key: Option<PathBuf>,
+	pub ssh_passphrase: Option<String>,
+	pub proxy: Option<String>,
+}
+
+/// User name of a token if none is configured.
+const TOKEN_USERNAME: &str = "x-access-token";
+
+impl Credentials {
+	/// Read the credentials from the environment variables of the module docs.
+	pub fn from_env() -> Self {
+		let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+		Self {
+			token: var("SUBWEIGHT_GIT_TOKEN").or_else(|| var("GITHUB_TOKEN")),
+			username: var("SUBWEIGHT_GIT_USERNAME"),
+			ssh_key: var("SUBWEIGHT_SSH_KEY").map(PathBuf::from),
+			ssh_passphrase: var("SUBWEIGHT_SSH_PASSPHRASE"),
+			proxy: var("SUBWEIGHT_PROXY"),
+		}
+	}
+
+	/// Callbacks of a libgit2 fetch that answer its credential requests.
+	pub(crate) fn remote_callbacks(&self) -> git2::RemoteCallbacks<'_> {
+		let mut callbacks = git2::RemoteCallbacks::new();
+		// libgit2 asks again as long as the credentials are rejected.
+		let mut attempts = 0;
+		callbacks.credentials(move |url, username, allowed| {
+			attempts += 1;
+			if attempts > 4 {
+				return Err(git2::Error::from_str(&format!("Authentication failed for {}", url)))
+			}
+			if allowed.contains(git2::CredentialType::USERNAME) {
+				return git2::Cred::username(username.unwrap_or("git"))
+			}
+			if allowed.contains(git2::CredentialType::SSH_KEY) {
+				let username = username.unwrap_or("git");
+				return match &self.ssh_key {
+					Some(key) =>
+						git2::Cred::ssh_key(username, None, key, self.ssh_passphrase.as_deref()),
+					None => git2::Cred::ssh_key_from_agent(username),
+				}
+			}
+			if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+				if let Some(token) = &self.token {
+					let username = self.username.as_deref().unwrap_or(TOKEN_USERNAME);
+					return git2::Cred::userpass_plaintext(username, token)
+				}
+				// The credential helpers that git itself would use.
+				return git2::Cred::credential_helper(&git2::Config::open_default()?, url, username)
+			}
+			Err(git2::Error::from_str(&format!("No supported credentials for {}", url)))
+		});
+		callbacks
+	}
+
+	/// Proxy of a libgit2 fetch.
+	pub(crate) fn proxy_options(&self) -> git2::ProxyOptions<'_> {
+		let mut options = git2::ProxyOptions::new();
+		match &self.proxy {
+			Some(url) => options.url(url),
+			None => options.auto(),
+		};
+		options
+	}
+
+	/// Environment variables that pass the credentials to the `git` command.
+	///
+	/// The config entries are passed as `GIT_CONFIG_*` variables; this needs git 2.31 or newer.
+	pub fn git_env(&self) -> Vec<(String, String)> {
+		let mut env = Vec::new();
+		let mut config = Vec::new();
+		if let Some(key) = &self.ssh_key {
+			let key = key.display().to_string().replace('\'', r"'\''");
+			env.push(("GIT_SSH_COMMAND".into(), format!("ssh -i '{}' -o IdentitiesOnly=yes", key)));
+		}
+		if let Some(token) = &self.token {
+			let username = self.username.as_deref().unwrap_or(TOKEN_USERNAME);
+			env.push(("SUBWEIGHT_GIT_USERNAME".into(), username.into()));
+			env.push(("SUBWEIGHT_GIT_TOKEN".into(), token.clone()));
+			// The empty value replaces the configured helpers.
+			config.push(("credential.helper", String::new()));
+			config.push((
+				"credential.helper",
+				"!f() { echo \"username=$SUBWEIGHT_GIT_USERNAME\"; echo \"password=$SUBWEIGHT_GIT_TOKEN\"; }; f"
+					.into(),
+			));
+		}
+		if let Some(proxy) = &self.proxy {
+			config.push(("http.proxy", proxy.clone()));
+		}
+
+		if !config.is_empty() {
+			env.push(("GIT_CONFIG_COUNT".into(), config.len().to_string()));
+		}
+		for (i, (key, value)) in config.into_iter().enumerate() {
+			env.push((format!("GIT_CONFIG_KEY_{}", i), key.into()));
+			env.push((format!("GIT_CONFIG_VALUE_{}", i), value));
+		}
+		env
+	}
+}
+
+/// Use these credentials for all following fetches instead of the ones from the environment.
+pub fn set_credentials(credentials: Credentials) {
+	*CREDENTIALS.write().expect("Lock is not poisoned") = credentials;
+}
+
+/// The credentials for the next fetch.
+pub fn credentials() -> Credentials {
+	CREDENTIALS.read().expect("Lock is not poisoned").clone()
+}
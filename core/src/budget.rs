@@ -0,0 +1,127 @@
+//! Check the worst case weight of extrinsics against the limits of a block.
+
+use crate::{
+	component_scope, evaluate_extrinsic, parse::pallet::ChromaticExtrinsic, scope::SimpleScope,
+	DbWeights, Dimension, ExtrinsicDiff, Percent,
+};
+
+/// The maximal weight of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockLimits {
+	/// Reference time in picoseconds.
+	pub time: u128,
+	/// Proof size in bytes.
+	pub proof: u128,
+}
+
+impl Default for BlockLimits {
+	/// The limits of a Polkadot block: 2s reference time and 5 MiB proof size.
+	fn default() -> Self {
+		Self { time: 2_000_000_000_000, proof: 5 * 1024 * 1024 }
+	}
+}
+
+impl BlockLimits {
+	/// Share of the limit of `unit` in percent; `None` if the dimension has no block limit.
+	pub fn share(&self, unit: Dimension, value: u128) -> Option<Percent> {
+		let limit = match unit {
+			Dimension::Time => self.time,
+			Dimension::Proof => self.proof,
+			_ => return None,
+		};
+		Some(100.0 * value as f64 / limit as f64)
+	}
+}
+
+/// How much of a block the old and new weight of a change use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockShare {
+	/// `None` if the extrinsic was added.
+	pub old: Option<Percent>,
+	/// `None` if the extrinsic was removed.
+	pub new: Option<Percent>,
+}
+
+impl BlockShare {
+	/// The larger share of the old and new weight.
+	pub fn max(&self) -> Percent {
+		self.old.unwrap_or_default().max(self.new.unwrap_or_default())
+	}
+}
+
+impl std::fmt::Display for BlockShare {
+	/// Eg. `0.80% → 1.90%`.
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let fmt = |p: Option<Percent>| p.map_or("-".into(), |p| format!("{:.2}%", p));
+		write!(f, "{} → {}", fmt(self.old), fmt(self.new))
+	}
+}
+
+/// The block share of each compared dimension of a change; time before proof size.
+///
+/// Empty if the change failed or the dimension has no block limit, eg. for fees.
+pub fn block_shares(
+	diff: &ExtrinsicDiff,
+	unit: Dimension,
+	limits: &BlockLimits,
+) -> Vec<BlockShare> {
+	let terms = match unit {
+		Dimension::Both =>
+			vec![(Dimension::Time, diff.term()), (Dimension::Proof, diff.proof_term())],
+		unit => vec![(unit, diff.term())],
+	};
+	terms
+		.into_iter()
+		.filter(|(unit, _)| matches!(unit, Dimension::Time | Dimension::Proof))
+		.filter_map(|(unit, term)| {
+			let share = |v: Option<u128>| v.and_then(|v| limits.share(unit, v));
+			term.map(|t| BlockShare { old: share(t.old_v), new: share(t.new_v) })
+		})
+		.collect()
+}
+
+/// How much of a block a single extrinsic uses in its worst case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetUsage {
+	pub pallet: String,
+	pub extrinsic: String,
+
+	pub time: u128,
+	pub proof: u128,
+	/// Share of the block time limit.
+	pub time_percent: Percent,
+	/// Share of the block proof size limit.
+	pub proof_percent: Percent,
+}
+
+impl BudgetUsage {
+	/// The larger share of both dimensions.
+	pub fn max_percent(&self) -> Percent {
+		self.time_percent.max(self.proof_percent)
+	}
+}
+
+/// Evaluate the worst case of an extrinsic and put it in relation to the block limits.
+///
+/// Components without a value in `components` are set to the maximum of their range. Storage
+/// accesses take the time of `db_weights`.
+pub fn budget_usage(
+	extrinsic: &ChromaticExtrinsic,
+	limits: &BlockLimits,
+	components: &SimpleScope,
+	db_weights: DbWeights,
+) -> Result<BudgetUsage, String> {
+	let scope = component_scope(extrinsic, components);
+	let time = evaluate_extrinsic(extrinsic, &scope, Dimension::Time, db_weights)?;
+	let proof = evaluate_extrinsic(extrinsic, &scope, Dimension::Proof, db_weights)?;
+	let share = |v: u128, limit: u128| 100.0 * v as f64 / limit as f64;
+
+	Ok(BudgetUsage {
+		pallet: extrinsic.pallet.to_string(),
+		extrinsic: extrinsic.name.to_string(),
+		time,
+		proof,
+		time_percent: share(time, limits.time),
+		proof_percent: share(proof, limits.proof),
+	})
+}
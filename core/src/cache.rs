@@ -0,0 +1,120 @@
+//! On-disk cache of parsed weight files.
+//!
+//! The parsed extrinsics of a commit only depend on the commit itself and on how the files were
+//! selected. They are therefore stored per commit hash and path pattern and can be re-used by any
+//! later comparison against the same commit.
+//!
+//! Within a process, each file is also kept by the hash of its git blob. Two commits mostly share
+//! their weight files; those are then parsed once, eg. all but the changed files of a pull request.
+
+use lazy_static::lazy_static;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+use crate::{parse::pallet::ChromaticExtrinsic, VERSION};
+
+/// Identifies a set of parsed weight files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+	/// Full hash of the commit.
+	pub commit: String,
+	/// Folder inside the repository that the `path_pattern` is relative to.
+	pub sub_dir: PathBuf,
+	pub path_pattern: String,
+	pub ignore_errors: bool,
+	pub auto_discover: bool,
+}
+
+impl CacheKey {
+	pub(crate) fn file_name(&self) -> String {
+		// Any change to the parser invalidates the cache.
+		let key = format!(
+			"{}\0{}\0{}\0{}\0{}\0{}",
+			*VERSION,
+			self.commit,
+			self.sub_dir.display(),
+			self.path_pattern,
+			self.ignore_errors,
+			self.auto_discover
+		);
+		format!("{}-{:016x}.json", self.commit, fnv1a(key.as_bytes()))
+	}
+}
+
+/// The 64 bit FNV-1a hash; unlike the `DefaultHasher` it stays the same across Rust versions.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+	bytes
+		.iter()
+		.fold(0xcbf29ce484222325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// A cache directory like `~/.cache/subweight`.
+#[derive(Debug, Clone)]
+pub struct Cache {
+	dir: PathBuf,
+}
+
+impl Cache {
+	pub fn new(dir: &Path) -> Self {
+		Self { dir: dir.into() }
+	}
+
+	/// Load the extrinsics of a previous [`Self::store`].
+	///
+	/// Returns `None` if there is no such entry or it cannot be read.
+	pub fn load(&self, key: &CacheKey) -> Option<Vec<ChromaticExtrinsic>> {
+		let path = self.dir.join(key.file_name());
+		let content = std::fs::read_to_string(&path).ok()?;
+
+		match serde_json::from_str(&content) {
+			Ok(extrinsics) => {
+				log::info!("Loaded {} from cache {}", key.commit, path.display());
+				Some(extrinsics)
+			},
+			Err(err) => {
+				log::warn!("Ignoring corrupt cache entry {}: {}", path.display(), err);
+				None
+			},
+		}
+	}
+
+	pub fn store(&self, key: &CacheKey, extrinsics: &[ChromaticExtrinsic]) -> Result<(), String> {
+		std::fs::create_dir_all(&self.dir)
+			.map_err(|e| format!("Failed to create cache dir {}: {}", self.dir.display(), e))?;
+		let path = self.dir.join(key.file_name());
+		let content = serde_json::to_string(extrinsics).map_err(|e| e.to_string())?;
+
+		// Write to a temporary file first to not leave a partial entry behind.
+		let tmp = path.with_extension("tmp");
+		std::fs::write(&tmp, content)
+			.and_then(|_| std::fs::rename(&tmp, &path))
+			.map_err(|e| format!("Failed to write cache entry {}: {}", path.display(), e))
+	}
+}
+
+/// Maximal number of files that [`store_blob`] keeps; all are dropped once it is reached.
+pub const MAX_BLOBS: usize = 10_000;
+
+lazy_static! {
+	/// Extrinsics by blob hash and pallet name.
+	static ref BLOBS: Mutex<HashMap<(String, String), Vec<ChromaticExtrinsic>>> =
+		Mutex::new(HashMap::new());
+}
+
+/// The extrinsics of a file that was parsed before with the same content and name.
+pub fn load_blob(blob: &str, pallet: &str) -> Option<Vec<ChromaticExtrinsic>> {
+	let blobs = BLOBS.lock().expect("Lock is not poisoned");
+	blobs.get(&(blob.to_string(), pallet.to_string())).cloned()
+}
+
+/// Keep the extrinsics of a file for later [`load_blob`]s.
+pub fn store_blob(blob: &str, pallet: &str, extrinsics: &[ChromaticExtrinsic]) {
+	let mut blobs = BLOBS.lock().expect("Lock is not poisoned");
+	if blobs.len() >= MAX_BLOBS {
+		blobs.clear();
+	}
+	blobs.insert((blob.into(), pallet.into()), extrinsics.to_vec());
+}
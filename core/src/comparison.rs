@@ -0,0 +1,227 @@
+//! Compare two commits of a repository without assembling the parameters by hand.
+//!
+//! ```no_run
+//! use subweight_core::{CompareMethod, Comparison};
+//!
+//! let diff = Comparison::builder()
+//!     .repo("../polkadot-sdk")
+//!     .old("polkadot-v1.0.0")
+//!     .new("master")
+//!     .method(CompareMethod::GuessWorst)
+//!     .path_pattern("polkadot/runtime/*/src/weights/*.rs")
+//!     .run()?;
+//! # Ok::<(), subweight_core::SwcError>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+	compare_repos, fee::FeeParams, filter_changes, CompareMethod, CompareParams, DbWeights,
+	Dimension, FilterParams, Percent, SwcError, ThresholdMode, TotalDiff,
+};
+
+/// A comparison of two refs of one repository; see [`Comparison::builder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+	pub repo: PathBuf,
+	pub old: String,
+	pub new: String,
+	pub path_pattern: String,
+	pub max_files: usize,
+
+	pub params: CompareParams,
+	pub filter: FilterParams,
+}
+
+impl Comparison {
+	pub fn builder() -> ComparisonBuilder {
+		ComparisonBuilder::default()
+	}
+
+	/// Check out both refs, parse their weight files and compare them.
+	///
+	/// Only the changes that pass the filter are returned.
+	pub fn run(&self) -> Result<TotalDiff, SwcError> {
+		let diff = compare_repos(
+			(&self.repo, &self.old),
+			(&self.repo, &self.new),
+			&self.params,
+			&self.filter,
+			&self.path_pattern,
+			self.max_files,
+		)?;
+		Ok(filter_changes(diff, &self.filter))
+	}
+}
+
+/// Builds a [`Comparison`] with the same defaults as the CLI.
+///
+/// The repository, both refs and the path pattern are required.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonBuilder {
+	repo: Option<PathBuf>,
+	old: Option<String>,
+	new: Option<String>,
+	path_pattern: Option<String>,
+	max_files: usize,
+
+	params: CompareParams,
+	filter: FilterParams,
+}
+
+impl Default for ComparisonBuilder {
+	fn default() -> Self {
+		Self {
+			repo: None,
+			old: None,
+			new: None,
+			path_pattern: None,
+			max_files: usize::MAX,
+			params: CompareParams::default(),
+			filter: FilterParams {
+				threshold: 5.0,
+				threshold_abs: None,
+				threshold_mode: ThresholdMode::And,
+				change: None,
+				extrinsic: None,
+				pallet: None,
+				ignore_extrinsic: Vec::new(),
+				ignore_pallet: Vec::new(),
+				allowlist: None,
+			},
+		}
+	}
+}
+
+impl ComparisonBuilder {
+	/// Path to the repository or to a sub-folder of it.
+	pub fn repo(mut self, repo: impl AsRef<Path>) -> Self {
+		self.repo = Some(repo.as_ref().into());
+		self
+	}
+
+	/// Commit, branch or tag of the old version.
+	pub fn old(mut self, old: impl Into<String>) -> Self {
+		self.old = Some(old.into());
+		self
+	}
+
+	/// Commit, branch or tag of the new version.
+	pub fn new(mut self, new: impl Into<String>) -> Self {
+		self.new = Some(new.into());
+		self
+	}
+
+	/// Comma separated glob patterns of the weight files; relative to the repository.
+	pub fn path_pattern(mut self, pattern: impl Into<String>) -> Self {
+		self.path_pattern = Some(pattern.into());
+		self
+	}
+
+	/// Error if more files than this match the path pattern. Unlimited by default.
+	pub fn max_files(mut self, max_files: usize) -> Self {
+		self.max_files = max_files;
+		self
+	}
+
+	/// Defaults to [`CompareMethod::GuessWorst`].
+	pub fn method(mut self, method: CompareMethod) -> Self {
+		self.params.method = method;
+		self
+	}
+
+	/// Defaults to [`Dimension::Time`].
+	pub fn unit(mut self, unit: Dimension) -> Self {
+		self.params.unit = unit;
+		self
+	}
+
+	/// The `WeightToFee` of the runtime; needed for [`Dimension::Fee`].
+	pub fn fee_params(mut self, fees: FeeParams) -> Self {
+		self.params.fee_params = Some(fees);
+		self
+	}
+
+	/// The weights of storage reads and writes; [`DbWeights::ROCKS_DB`] by default.
+	pub fn db_weights(mut self, weights: DbWeights) -> Self {
+		self.params.db_weights = weights;
+		self
+	}
+
+	/// Report removed and added extrinsics with similar names as renamed; see [`crate::rename`].
+	pub fn detect_renames(mut self, detect: bool) -> Self {
+		self.params.detect_renames = detect;
+		self
+	}
+
+	/// Keep the weights of all evaluated scopes; see [`crate::TermChange::samples`].
+	pub fn keep_samples(mut self, keep: bool) -> Self {
+		self.params.keep_samples = keep;
+		self
+	}
+
+	/// Read the weight files from the git objects instead of checking them out.
+	pub fn git_objects(mut self, objects: bool) -> Self {
+		self.params.git_objects = objects;
+		self
+	}
+
+	/// Find the weight files by their content; the path pattern defaults to `**/*.rs` then.
+	pub fn auto_discover(mut self, discover: bool) -> Self {
+		self.params.auto_discover = discover;
+		self
+	}
+
+	/// Replace all comparison parameters.
+	pub fn params(mut self, params: CompareParams) -> Self {
+		self.params = params;
+		self
+	}
+
+	/// Replace all filter parameters.
+	pub fn filter(mut self, filter: FilterParams) -> Self {
+		self.filter = filter;
+		self
+	}
+
+	/// Minimal relative change in percent. Defaults to 5.
+	pub fn threshold(mut self, threshold: Percent) -> Self {
+		self.filter.threshold = threshold;
+		self
+	}
+
+	pub fn ignore_errors(mut self, ignore: bool) -> Self {
+		self.params.ignore_errors = ignore;
+		self
+	}
+
+	pub fn cache_dir(mut self, dir: impl AsRef<Path>) -> Self {
+		self.params.cache_dir = Some(dir.as_ref().into());
+		self
+	}
+
+	/// Set a component to a fixed value in both versions.
+	pub fn set(mut self, component: impl Into<String>, value: u128) -> Self {
+		self.params.components.push((component.into(), value));
+		self
+	}
+
+	pub fn build(self) -> Result<Comparison, SwcError> {
+		let missing = |what: &str| SwcError::InvalidInput(format!("Comparison needs a {}", what));
+
+		Ok(Comparison {
+			repo: self.repo.ok_or_else(|| missing("repo"))?,
+			old: self.old.ok_or_else(|| missing("old ref"))?,
+			new: self.new.ok_or_else(|| missing("new ref"))?,
+			path_pattern: self.path_pattern.ok_or_else(|| missing("path pattern"))?,
+			max_files: self.max_files,
+			params: self.params,
+			filter: self.filter,
+		})
+	}
+
+	/// Shortcut for [`Self::build`] and [`Comparison::run`].
+	pub fn run(self) -> Result<TotalDiff, SwcError> {
+		self.build()?.run()
+	}
+}
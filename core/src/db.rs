@@ -0,0 +1,148 @@
+//! Keep the evaluated weights of commits in an SQLite database to track them over a long time.
+//!
+//! Each ingested commit stores the worst case weight of all its extrinsics; components without a
+//! value are set to the maximum of their range. Ingesting a commit again replaces its weights.
+//! Querying the weights of an extrinsic returns them in the order of the commit times.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::{
+	component_scope, evaluate_weight, parse_commit, progress::Progress, resolve_ref,
+	scope::SimpleScope, CompareParams, SwcError,
+};
+
+/// The weight of an extrinsic at one ingested commit.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WeightRecord {
+	/// Full hash of the commit.
+	pub commit: String,
+	/// The ref that the commit was last ingested as, eg. a tag.
+	pub refname: String,
+	/// Commit time as UNIX timestamp in seconds.
+	pub time: i64,
+	/// Path of the weight file relative to the repository.
+	pub file: String,
+	pub extrinsic: String,
+
+	pub ref_time: u128,
+	pub proof_size: u128,
+}
+
+pub struct WeightDb {
+	conn: Connection,
+}
+
+impl WeightDb {
+	/// Open or create the database at `path`.
+	pub fn open(path: &Path) -> Result<Self, SwcError> {
+		Self::init(Connection::open(path)?)
+	}
+
+	/// Create a database that only lives in memory.
+	pub fn open_in_memory() -> Result<Self, SwcError> {
+		Self::init(Connection::open_in_memory()?)
+	}
+
+	fn init(conn: Connection) -> Result<Self, SwcError> {
+		conn.execute_batch(
+			"CREATE TABLE IF NOT EXISTS commits (
+				hash TEXT PRIMARY KEY,
+				refname TEXT NOT NULL,
+				time INTEGER NOT NULL
+			);
+			CREATE TABLE IF NOT EXISTS weights (
+				commit_hash TEXT NOT NULL REFERENCES commits (hash),
+				file TEXT NOT NULL,
+				extrinsic TEXT NOT NULL,
+				ref_time INTEGER NOT NULL,
+				proof_size INTEGER NOT NULL,
+				PRIMARY KEY (commit_hash, file, extrinsic)
+			);",
+		)?;
+		Ok(Self { conn })
+	}
+
+	/// Evaluate the weight files of `refname` and store their weights.
+	///
+	/// Extrinsics that cannot be evaluated are skipped. Returns the number of stored weights.
+	pub fn ingest(
+		&mut self,
+		repo: &Path,
+		refname: &str,
+		params: &CompareParams,
+		path_pattern: &str,
+		max_files: usize,
+		progress: &dyn Progress,
+	) -> Result<usize, SwcError> {
+		// Fetches the ref if requested, so it is resolved afterwards.
+		let extrinsics = parse_commit(repo, refname, params, path_pattern, max_files, progress)?;
+		let git = git2::Repository::discover(repo)?;
+		let commit = resolve_ref(&git, refname, false, progress)?.peel_to_commit()?;
+		let hash = commit.id().to_string();
+		let values = SimpleScope::empty().with_components(&params.components);
+
+		let tx = self.conn.transaction()?;
+		tx.execute("DELETE FROM weights WHERE commit_hash = ?1", params![hash])?;
+		tx.execute(
+			"INSERT OR REPLACE INTO commits (hash, refname, time) VALUES (?1, ?2, ?3)",
+			params![hash, refname, commit.time().seconds()],
+		)?;
+		let mut stored = 0;
+		for ext in &extrinsics {
+			let scope = component_scope(ext, &values);
+			let (ref_time, proof_size) = match evaluate_weight(ext, &scope, params.db_weights) {
+				Ok(weight) => weight.into(),
+				Err(err) => {
+					log::warn!("Skipped {}::{}: {}", ext.pallet, ext.name, err);
+					continue
+				},
+			};
+			tx.execute(
+				"INSERT OR REPLACE INTO weights (commit_hash, file, extrinsic, ref_time, proof_size)
+				VALUES (?1, ?2, ?3, ?4, ?5)",
+				params![hash, ext.pallet.as_str(), ext.name.as_str(), to_sql(ref_time)?, to_sql(proof_size)?],
+			)?;
+			stored += 1;
+		}
+		tx.commit()?;
+		Ok(stored)
+	}
+
+	/// The stored weights ordered by commit time.
+	///
+	/// `file` matches any part of the file path and `extrinsic` the whole name.
+	pub fn query(
+		&self,
+		file: Option<&str>,
+		extrinsic: Option<&str>,
+	) -> Result<Vec<WeightRecord>, SwcError> {
+		let mut stmt = self.conn.prepare(
+			"SELECT c.hash, c.refname, c.time, w.file, w.extrinsic, w.ref_time, w.proof_size
+			FROM weights w JOIN commits c ON c.hash = w.commit_hash
+			WHERE (?1 IS NULL OR instr(w.file, ?1) > 0) AND (?2 IS NULL OR w.extrinsic = ?2)
+			ORDER BY c.time, c.rowid, w.file, w.extrinsic",
+		)?;
+		let records = stmt
+			.query_map(params![file, extrinsic], |row| {
+				Ok(WeightRecord {
+					commit: row.get(0)?,
+					refname: row.get(1)?,
+					time: row.get(2)?,
+					file: row.get(3)?,
+					extrinsic: row.get(4)?,
+					ref_time: row.get::<_, i64>(5)? as u128,
+					proof_size: row.get::<_, i64>(6)? as u128,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+		Ok(records)
+	}
+}
+
+/// SQLite only has signed 64 bit integers.
+fn to_sql(value: u128) -> Result<i64, SwcError> {
+	i64::try_from(value)
+		.map_err(|_| SwcError::InvalidInput(format!("Weight {} is too large to store", value)))
+}
@@ -0,0 +1,54 @@
+//! The error type of the library.
+
+use crate::progress::Stage;
+
+/// Anything that can go wrong when listing, parsing or comparing weight files.
+///
+/// The messages are meant for humans; match on the variant to distinguish the causes.
+#[derive(Debug, thiserror::Error)]
+pub enum SwcError {
+	/// A git operation like opening a repository, resolving a ref or checking it out failed.
+	#[error("{0}")]
+	Git(String),
+	/// The path pattern is invalid or matched too many files.
+	#[error("{0}")]
+	Glob(String),
+	/// A weight file could not be read or parsed.
+	#[error("{0}")]
+	Parse(String),
+	/// A weight term could not be evaluated.
+	#[error("{0}")]
+	Eval(String),
+	/// Files could not be fetched from a remote repository.
+	#[error("{0}")]
+	Remote(String),
+	/// A filter regex is invalid.
+	#[error("Invalid regex: {0}")]
+	Regex(#[from] fancy_regex::Error),
+	/// An extrinsic or a regression could not be found.
+	#[error("{0}")]
+	NotFound(String),
+	/// Any other invalid argument.
+	#[error("{0}")]
+	InvalidInput(String),
+	#[error(transparent)]
+	Io(#[from] std::io::Error),
+	/// The comparison was cancelled through
+	/// [`Progress::cancelled`](crate::progress::Progress::cancelled).
+	#[error("Cancelled: {0}")]
+	Cancelled(Stage),
+	/// The comparison did not finish before its deadline.
+	#[error("Timed out: {0}")]
+	TimedOut(Stage),
+	/// The weight database could not be read or written.
+	#[cfg(feature = "sqlite")]
+	#[error("Database error: {0}")]
+	Db(#[from] rusqlite::Error),
+}
+
+#[cfg(feature = "git")]
+impl From<git2::Error> for SwcError {
+	fn from(err: git2::Error) -> Self {
+		Self::Git(err.message().into())
+	}
+}
@@ -0,0 +1,192 @@
+//! Rewrite weight files into a canonical form to make the diffs of regenerated files reviewable.
+//!
+//! The weight expression of each function is replaced by a sum in a fixed order: the base weight,
+//! the weight per component, the storage reads and then the storage writes. Each part uses
+//! `Weight::from_parts` or `DbWeight::get()` and literals are separated with `_`:
+//!
+//! ```ignore
+//! Weight::from_parts(154_033_219, 83_594)
+//!     .saturating_add(Weight::from_parts(31_269_223, 16_026).saturating_mul(n.into()))
+//!     .saturating_add(T::DbWeight::get().reads(10_u64))
+//!     .saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(n.into())))
+//! ```
+//!
+//! Everything outside of the weight expressions stays untouched; comments within them are
+//! dropped. Functions with weights that cannot be expressed like this, eg. the scalar weights of
+//! old Substrate versions, are left as they are.
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+use syn::{ImplItem, Item, Stmt, Type};
+
+use crate::{
+	parse::pallet::{handle_item, parse_expression},
+	scope::{STORAGE_READ_VAR, STORAGE_WRITE_VAR},
+	term::ChromaticTerm,
+	Dimension, PalletName, SwcError,
+};
+
+/// Format the weight file at `path`. Returns the formatted content without writing it.
+pub fn format_file(path: &Path) -> Result<String, SwcError> {
+	let content = crate::parse::read_file(path)?;
+	format_content(&content).map_err(|e| SwcError::Parse(format!("{}: {}", path.display(), e)))
+}
+
+pub fn format_content(content: &str) -> Result<String, String> {
+	let ast =
+		syn::parse_file(content).map_err(|e| format!("syn refused to parse content: {}", e))?;
+	let lines = line_offsets(content);
+
+	// Byte range of each weight expression and its replacement.
+	let mut edits = Vec::new();
+	for item in &ast.items {
+		let Item::Impl(imp) = item else { continue };
+		if handle_item(&PalletName::default(), item).is_err() {
+			continue
+		}
+		let db = match imp.self_ty.as_ref() {
+			Type::Tuple(_) => "RocksDbWeight::get()",
+			_ => "T::DbWeight::get()",
+		};
+
+		for item in &imp.items {
+			let ImplItem::Fn(m) = item else { continue };
+			let [Stmt::Expr(expr, None)] = m.block.stmts.as_slice() else { continue };
+			let span = syn::spanned::Spanned::span(expr);
+			let (start, end) = (span.start(), span.end());
+			let indent = lines[start.line - 1]
+				.1
+				.chars()
+				.take_while(|c| c.is_whitespace())
+				.collect::<String>();
+
+			match parse_expression(expr).and_then(|term| render(&term, db, &indent)) {
+				Ok(code) => edits.push((
+					offset(&lines, start.line, start.column),
+					offset(&lines, end.line, end.column),
+					code,
+				)),
+				Err(err) => log::info!("Not formatting {}: {}", m.sig.ident, err),
+			}
+		}
+	}
+
+	let mut formatted = content.to_string();
+	for (start, end, code) in edits.into_iter().rev() {
+		formatted.replace_range(start..end, &code);
+	}
+	Ok(formatted)
+}
+
+/// Render a weight term as chain of `saturating_add`s; see the module docs.
+fn render(term: &ChromaticTerm, db: &str, indent: &str) -> Result<String, String> {
+	let mut weights = BTreeMap::<Vec<String>, (u128, u128)>::new();
+	let mut accesses = BTreeMap::<(&str, Vec<String>), u128>::new();
+
+	for unit in [Dimension::Time, Dimension::Proof] {
+		for (vars, factor) in term.simplify(unit)?.monomials() {
+			let (storage, components): (Vec<_>, Vec<_>) =
+				vars.into_iter().partition(|v| v == STORAGE_READ_VAR || v == STORAGE_WRITE_VAR);
+			if let Some(var) = components.iter().find(|v| !is_component(v)) {
+				return Err(format!("Unsupported variable {}", var))
+			}
+
+			match storage.as_slice() {
+				[] => {
+					let weight = weights.entry(components).or_default();
+					if unit == Dimension::Time {
+						weight.0 = factor;
+					} else {
+						weight.1 = factor;
+					}
+				},
+				[var] => {
+					let kind = if var == STORAGE_READ_VAR { "reads" } else { "writes" };
+					// The storage accesses are the same in both dimensions.
+					if unit == Dimension::Time {
+						accesses.insert((kind, components), factor);
+					} else if accesses.get(&(kind, components)) != Some(&factor) {
+						return Err("Storage accesses differ between the dimensions".into())
+					}
+				},
+				_ => return Err("Product of storage accesses".into()),
+			}
+		}
+	}
+
+	let (time, proof) = weights.remove(&Vec::new()).unwrap_or_default();
+	let mut code = format!("Weight::from_parts({}, {})", literal(time), literal(proof));
+	let mut add = |part: String| {
+		let _ = write!(code, "\n{}{}.saturating_add({})", indent, continuation(indent), part);
+	};
+	for (components, (time, proof)) in &weights {
+		add(format!(
+			"Weight::from_parts({}, {}){}",
+			literal(*time),
+			literal(*proof),
+			saturating_muls(components)
+		));
+	}
+	// `reads` are sorted before `writes`.
+	for ((kind, components), count) in accesses {
+		if components.is_empty() {
+			add(format!("{}.{}({}_u64)", db, kind, literal(count)));
+		} else {
+			add(format!(
+				"{}.{}(({}_u64){})",
+				db,
+				kind,
+				literal(count),
+				saturating_muls(&components)
+			));
+		}
+	}
+	Ok(code)
+}
+
+fn saturating_muls(components: &[String]) -> String {
+	components.iter().map(|c| format!(".saturating_mul({}.into())", c)).collect()
+}
+
+/// One level deeper than `indent`; with the same kind of whitespace.
+fn continuation(indent: &str) -> &'static str {
+	if indent.starts_with(' ') {
+		"    "
+	} else {
+		"\t"
+	}
+}
+
+/// Whether the variable is a component like `n` and not eg. a constant.
+fn is_component(var: &str) -> bool {
+	var.chars().next().map_or(false, |c| c.is_ascii_lowercase() || c == '_') &&
+		var.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Decimal literal with `_` between each group of three digits.
+fn literal(value: u128) -> String {
+	let digits = value.to_string();
+	let groups = digits.as_bytes().rchunks(3).rev();
+	groups
+		.map(|g| std::str::from_utf8(g).expect("Digits are ASCII"))
+		.collect::<Vec<_>>()
+		.join("_")
+}
+
+/// The byte offset and content of each line.
+fn line_offsets(content: &str) -> Vec<(usize, &str)> {
+	let mut offset = 0;
+	content
+		.split_inclusive('\n')
+		.map(|line| {
+			let start = offset;
+			offset += line.len();
+			(start, line)
+		})
+		.collect()
+}
+
+/// Byte offset of a line and char column as reported by `proc_macro2`.
+fn offset(lines: &[(usize, &str)], line: usize, column: usize) -> usize {
+	let (start, content) = lines[line - 1];
+	start + content.char_indices().nth(column).map_or(content.len(), |(i, _)| i)
+}
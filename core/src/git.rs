@@ -0,0 +1,980 @@
+//! Compare and check out the weight files of git commits.
+//!
+//! Everything that needs a repository lives here and in [`crate::history`]; both require the `git`
+//! feature. Without it, the parsing and comparison of weight files compiles to WebAssembly.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+	auth,
+	cache::{self, Cache, CacheKey},
+	compare_files_with_progress,
+	parse::{
+		json, looks_like_weights,
+		pallet::{self, parse_file_in_repo, ChromaticExtrinsic},
+		xcm, PathStripping,
+	},
+	progress::{Progress, Stage},
+	rename::{self, Move},
+	split_path_pattern, CompareParams, FilterParams, PalletName, SwcError, TotalDiff,
+};
+
+pub fn compare_commits(
+	repo: &Path,
+	old: &str,
+	new: &str,
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+) -> Result<TotalDiff, SwcError> {
+	compare_repos((repo, old), (repo, new), params, filter, path_pattern, max_files)
+}
+
+/// Same as [`compare_commits`] but reports its progress.
+#[allow(clippy::too_many_arguments)]
+pub fn compare_commits_with_progress(
+	repo: &Path,
+	old: &str,
+	new: &str,
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, SwcError> {
+	compare_repos_with_progress(
+		(repo, old),
+		(repo, new),
+		params,
+		filter,
+		path_pattern,
+		max_files,
+		progress,
+	)
+}
+
+/// Compare two commits but only report the changes that `new` made since it branched off `base`.
+///
+/// The merge base of `base` and `new` is compared with `new`. Extrinsics that `new` did not change
+/// since the merge base are skipped, as are changes that `old` already contains. This hides the
+/// changes of `old` when `new` is behind it, eg. for a pull request that is behind master.
+#[allow(clippy::too_many_arguments)]
+pub fn compare_commits_since_base(
+	repo: &Path,
+	old: &str,
+	new: &str,
+	base: &str,
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, SwcError> {
+	if path_pattern.contains("..") {
+		return Err(SwcError::Glob("Path pattern cannot contain '..'".into()))
+	}
+	// Fetches `base` and `new`; the merge base is a commit hash that can not be fetched.
+	let merge_base = merge_base(repo, base, new, params.should_pull())?;
+	let fetched = CompareParams { git_pull: false, ..params.clone() };
+
+	let mut bases = checkout(repo, &merge_base, &fetched, path_pattern, max_files, progress)?;
+	let mut olds = checkout(repo, old, params, path_pattern, max_files, progress)?;
+	let news = checkout(repo, new, &fetched, path_pattern, max_files, progress)?;
+	follow_moves(&mut bases, &news);
+	follow_moves(&mut olds, &news);
+	let (bases, olds, news) = (bases.extrinsics, olds.extrinsics, news.extrinsics);
+
+	let weight_of = |exts: &[ChromaticExtrinsic], pallet: &str, name: &str| {
+		exts.iter()
+			.find(|e| e.pallet == pallet && e.name == name)
+			.map(|e| (e.term.clone(), e.comp_ranges.clone()))
+	};
+	let names = bases
+		.iter()
+		.chain(news.iter())
+		.map(|e| (e.pallet.clone(), e.name.clone()))
+		.collect::<std::collections::BTreeSet<_>>();
+	let changed = names
+		.into_iter()
+		.filter(|(pallet, name)| {
+			let new = weight_of(&news, pallet, name);
+			new != weight_of(&bases, pallet, name) && new != weight_of(&olds, pallet, name)
+		})
+		.collect::<std::collections::BTreeSet<_>>();
+	let retain = |exts: Vec<ChromaticExtrinsic>| {
+		exts.into_iter()
+			.filter(|e| changed.contains(&(e.pallet.clone(), e.name.clone())))
+			.collect::<Vec<_>>()
+	};
+
+	compare_files_with_progress(retain(bases), retain(news), params, filter, progress)
+}
+
+/// The hash of the best common ancestor of two commits.
+pub fn merge_base(path: &Path, a: &str, b: &str, pull: bool) -> Result<String, SwcError> {
+	let repo = git2::Repository::discover(path).map_err(|e| {
+		SwcError::Git(format!("Failed to find repository of {}: {}", path.display(), e.message()))
+	})?;
+	let commit = |refname: &str| {
+		resolve_ref(&repo, refname, pull, &())?.peel_to_commit().map_err(|e| {
+			SwcError::Git(format!("Failed to find commit of {}: {}", refname, e.message()))
+		})
+	};
+	let (a, b) = (commit(a)?, commit(b)?);
+
+	repo.merge_base(a.id(), b.id()).map(|oid| oid.to_string()).map_err(|e| {
+		SwcError::Git(format!(
+			"Failed to find merge base of {} and {}: {}",
+			a.id(),
+			b.id(),
+			e.message()
+		))
+	})
+}
+
+/// Compare a commit of one repository with a commit of another repository.
+///
+/// Each repository is given as path and commit/branch/tag. The path can point to a sub-folder of a
+/// repository; the `path_pattern` and the pallet names are then relative to that sub-folder. This
+/// allows to compare weights that moved within a repository, eg. into a monorepo.
+pub fn compare_repos(
+	old: (&Path, &str),
+	new: (&Path, &str),
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+) -> Result<TotalDiff, SwcError> {
+	compare_repos_with_progress(old, new, params, filter, path_pattern, max_files, &())
+}
+
+/// Same as [`compare_repos`] but reports its progress.
+pub fn compare_repos_with_progress(
+	old: (&Path, &str),
+	new: (&Path, &str),
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, SwcError> {
+	// Both refs are checked out into temporary directories to keep the repos untouched.
+	let (olds, news) = checkout_both(old, new, params, path_pattern, max_files, progress)?;
+
+	compare(olds, news, params, filter, progress)
+}
+
+/// [`checkout`] two commits at the same time.
+///
+/// Fetches into the same repository are not run at the same time since they lock its refs.
+fn checkout_both(
+	old: (&Path, &str),
+	new: (&Path, &str),
+	params: &CompareParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<(ParsedSet, ParsedSet), SwcError> {
+	let checkout = |(repo, refname): (&Path, &str)| {
+		checkout(repo, refname, params, path_pattern, max_files, progress)
+	};
+	let same_repo = old.0.canonicalize().ok() == new.0.canonicalize().ok();
+	if same_repo && params.should_pull() {
+		return Ok((checkout(old)?, checkout(new)?))
+	}
+
+	std::thread::scope(|s| {
+		let olds = s.spawn(|| checkout(old));
+		let news = checkout(new);
+		let olds = olds.join().unwrap_or_else(|err| std::panic::resume_unwind(err));
+		Ok((olds?, news?))
+	})
+}
+
+/// The parsed weight files of one version.
+///
+/// Either from [`checkout`] or from any other source, eg. an archive or another VCS.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedSet {
+	/// Repository and commit/branch/tag that the extrinsics were parsed from.
+	pub origin: Option<(PathBuf, String)>,
+	pub extrinsics: Vec<ChromaticExtrinsic>,
+}
+
+impl From<Vec<ChromaticExtrinsic>> for ParsedSet {
+	fn from(extrinsics: Vec<ChromaticExtrinsic>) -> Self {
+		Self { origin: None, extrinsics }
+	}
+}
+
+/// Check out a commit/branch/tag and parse its weight files that match the `path_pattern`.
+///
+/// The repository stays untouched since the files are checked out into a temporary directory.
+pub fn checkout(
+	repo: &Path,
+	refname: &str,
+	params: &CompareParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<ParsedSet, SwcError> {
+	if path_pattern.contains("..") {
+		return Err(SwcError::Glob("Path pattern cannot contain '..'".into()))
+	}
+	let extrinsics = parse_commit(repo, refname, params, path_pattern, max_files, progress)?;
+	Ok(ParsedSet { origin: Some((repo.into(), refname.into())), extrinsics })
+}
+
+/// Compare the working tree of `repo` with a commit/branch/tag, eg. right after benchmarking.
+///
+/// The working tree is neither reset nor stashed; see [`parse_workdir`].
+#[allow(clippy::too_many_arguments)]
+pub fn compare_workdir(
+	repo: &Path,
+	against: &str,
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, SwcError> {
+	let old = checkout(repo, against, params, path_pattern, max_files, progress)?;
+	let new = parse_workdir(repo, params, path_pattern, max_files, progress)?;
+	compare(old, new, params, filter, progress)
+}
+
+/// Progress name of the working tree.
+const WORKDIR: &str = "working tree";
+
+/// Parse the weight files in the folder `repo` as they are, including any uncommitted changes.
+///
+/// The files are named relative to `repo`, like the ones of [`checkout`]. Untracked files count as
+/// well, eg. freshly generated weight files.
+pub fn parse_workdir(
+	repo: &Path,
+	params: &CompareParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<ParsedSet, SwcError> {
+	if path_pattern.contains("..") {
+		return Err(SwcError::Glob("Path pattern cannot contain '..'".into()))
+	}
+	let limit = if params.auto_discover { usize::MAX } else { max_files };
+	let paths = list_files(repo, path_pattern, limit)?;
+	let paths = discover(paths, params, max_files, crate::parse::read_file)?;
+	progress.files_discovered(WORKDIR, paths.len());
+
+	let start = std::time::Instant::now();
+	// The files are not blobs, so their parsed versions are not reused.
+	let extrinsics = parse_each(
+		&paths,
+		WORKDIR,
+		params,
+		progress,
+		|_| None,
+		|path| parse_file_in_repo(repo, path),
+	)?;
+	progress.files_parsed(WORKDIR, start.elapsed());
+	Ok(extrinsics.into())
+}
+
+/// Compare two local versions without fetching or checking them out, eg. in a git hook.
+///
+/// `new` is a commit/branch/tag or `None` for the staged files of the index. Nothing is compared if
+/// no file that matches the `path_pattern` differs, or if `old` does not exist, eg. before the
+/// first commit or of a branch without upstream.
+#[allow(clippy::too_many_arguments)]
+pub fn compare_local(
+	repo: &Path,
+	old: &str,
+	new: Option<&str>,
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, SwcError> {
+	if path_pattern.contains("..") {
+		return Err(SwcError::Glob("Path pattern cannot contain '..'".into()))
+	}
+	let (git, sub_dir) = discover_repo(repo)?;
+	let old_tree = match git.revparse_single(old).and_then(|o| o.peel_to_tree()) {
+		Ok(tree) => tree,
+		Err(err) => {
+			log::warn!("Nothing to compare, failed to resolve {}: {}", old, err.message());
+			return Ok(Vec::new())
+		},
+	};
+	let new_tree = match new {
+		Some(new) => git
+			.revparse_single(new)
+			.and_then(|o| o.peel_to_tree())
+			.map_err(|e| SwcError::Git(format!("Failed to resolve {}: {}", new, e.message())))?,
+		None => git.find_tree(git.index()?.write_tree()?)?,
+	};
+
+	let matches = path_matcher(path_pattern)?;
+	let diff = git.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+	let changed = diff
+		.deltas()
+		.flat_map(|d| [d.old_file().path(), d.new_file().path()])
+		.flatten()
+		.any(|p| p.strip_prefix(&sub_dir).map_or(false, &matches));
+	if !changed {
+		log::info!("No weight files differ between {} and {}", old, new.unwrap_or(INDEX));
+		return Ok(Vec::new())
+	}
+
+	let olds =
+		parse_tree(&git, &old_tree, &sub_dir, old, params, path_pattern, max_files, progress)?;
+	let news = parse_tree(
+		&git,
+		&new_tree,
+		&sub_dir,
+		new.unwrap_or(INDEX),
+		params,
+		path_pattern,
+		max_files,
+		progress,
+	)?;
+	compare(olds.into(), news.into(), params, filter, progress)
+}
+
+/// Name of the staged files in the progress and logs.
+const INDEX: &str = "index";
+
+/// Compare two parsed versions, eg. of [`checkout`].
+///
+/// Unlike [`compare_files_with_progress`], this follows moved files. Git detects them only if both
+/// versions were checked out from the same repository.
+pub fn compare(
+	old: ParsedSet,
+	new: ParsedSet,
+	params: &CompareParams,
+	filter: &FilterParams,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, SwcError> {
+	let mut old = old;
+	follow_moves(&mut old, &new);
+	compare_files_with_progress(old.extrinsics, new.extrinsics, params, filter, progress)
+}
+
+/// Parse all weight files of a commit that match the `path_pattern`.
+pub(crate) fn parse_commit(
+	path: &Path,
+	refname: &str,
+	params: &CompareParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let (repo, sub_dir) = discover_repo(path)?;
+	let target = resolve_ref(&repo, refname, params.should_pull(), progress)?;
+	let commit = target.peel_to_commit().map_err(|e| {
+		SwcError::Git(format!("Failed to find commit of {}: {}", refname, e.message()))
+	})?;
+
+	let cache = params.cache_dir.as_deref().map(Cache::new);
+	let key = CacheKey {
+		commit: commit.id().to_string(),
+		sub_dir: sub_dir.clone(),
+		path_pattern: path_pattern.into(),
+		ignore_errors: params.ignore_errors,
+		auto_discover: params.auto_discover,
+	};
+	if let Some(extrinsics) = cache.as_ref().and_then(|c| c.load(&key)) {
+		return Ok(extrinsics)
+	}
+
+	let tree = commit.tree()?;
+	let start = std::time::Instant::now();
+	// Only the discovered files count against the limit.
+	let limit = if params.auto_discover { usize::MAX } else { max_files };
+	let extrinsics = if params.git_objects {
+		parse_tree(&repo, &tree, &sub_dir, refname, params, path_pattern, max_files, progress)?
+	} else {
+		let dir = checkout_object_temp(&repo, commit.as_object(), refname)?;
+		let base = dir.path().join(sub_dir);
+		let paths = list_files(&base, path_pattern, limit)?;
+		let paths = discover(paths, params, max_files, crate::parse::read_file)?;
+		progress.files_discovered(refname, paths.len());
+		parse_each(
+			&paths,
+			refname,
+			params,
+			progress,
+			|path| parsed_blob(&tree, dir.path(), &base, path),
+			|path| parse_file_in_repo(&base, path),
+		)?
+	};
+	progress.files_parsed(refname, start.elapsed());
+
+	if let Some(cache) = cache {
+		// A failing cache should not fail the comparison.
+		if let Err(err) = cache.store(&key, &extrinsics) {
+			log::warn!("{}", err);
+		}
+	}
+	Ok(extrinsics)
+}
+
+/// Parse the weight files of a tree straight from the git objects.
+#[allow(clippy::too_many_arguments)]
+fn parse_tree(
+	repo: &git2::Repository,
+	tree: &git2::Tree,
+	sub_dir: &Path,
+	name: &str,
+	params: &CompareParams,
+	path_pattern: &str,
+	max_files: usize,
+	progress: &dyn Progress,
+) -> Result<Vec<ChromaticExtrinsic>, SwcError> {
+	// Only the discovered files count against the limit.
+	let limit = if params.auto_discover { usize::MAX } else { max_files };
+	let paths = list_blobs(repo, tree, sub_dir, path_pattern, limit)?;
+	let paths = discover(paths, params, max_files, |p| read_object(repo, tree, p))?;
+	progress.files_discovered(name, paths.len());
+	parse_each(
+		&paths,
+		name,
+		params,
+		progress,
+		|path| object_blob(repo, tree, sub_dir, path),
+		|path| parse_object(repo, tree, sub_dir, path),
+	)
+}
+
+/// Parse the files of a commit; the ones whose `blob` was parsed before are reused.
+fn parse_each(
+	paths: &[PathBuf],
+	refname: &str,
+	params: &CompareParams,
+	progress: &dyn Progress,
+	blob: impl Fn(&Path) -> Option<(String, String)>,
+	parse: impl Fn(&Path) -> Result<Vec<ChromaticExtrinsic>, SwcError>,
+) -> Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let (mut extrinsics, mut reused) = (Vec::new(), 0);
+	for (i, path) in paths.iter().enumerate() {
+		if progress.cancelled() {
+			let stage = Stage::new(format!("Parsing {}", refname), i, paths.len());
+			return Err(SwcError::Cancelled(stage))
+		}
+		let blob = blob(path);
+		if let Some(parsed) = blob.as_ref().and_then(|(b, name)| cache::load_blob(b, name)) {
+			extrinsics.extend(parsed);
+			reused += 1;
+			progress.file_parsed(path);
+			continue
+		}
+		match parse(path) {
+			Ok(parsed) => {
+				if let Some((blob, name)) = &blob {
+					cache::store_blob(blob, name, &parsed);
+				}
+				extrinsics.extend(parsed)
+			},
+			// Ignore any parsing errors.
+			Err(_) if params.ignore_errors => (),
+			Err(err) if params.auto_discover => log::warn!("Skipping discovered file: {}", err),
+			Err(err) => return Err(err),
+		}
+		progress.file_parsed(path);
+	}
+	log::debug!("Reused {} of {} parsed files for {}", reused, paths.len(), refname);
+	Ok(extrinsics)
+}
+
+/// Keep the files that look like weight files if [`CompareParams::auto_discover`] is set.
+fn discover(
+	paths: Vec<PathBuf>,
+	params: &CompareParams,
+	max_files: usize,
+	read: impl Fn(&Path) -> Result<String, SwcError>,
+) -> Result<Vec<PathBuf>, SwcError> {
+	if !params.auto_discover {
+		return Ok(paths)
+	}
+	let total = paths.len();
+	let paths = paths
+		.into_iter()
+		.filter(|path| read(path).map_or(false, |content| looks_like_weights(&content)))
+		.collect::<Vec<_>>();
+	log::info!("Discovered {} weight files in {} files", paths.len(), total);
+	if paths.len() > max_files {
+		return Err(SwcError::Glob(format!(
+			"Found too many files. Found: {}, Max: {}",
+			paths.len(),
+			max_files
+		)))
+	}
+	Ok(paths)
+}
+
+/// The paths of the blobs in `tree` below `sub_dir` that match the `path_pattern`.
+///
+/// Same as [`list_files`] but the paths are relative to the root of the `tree`. Blobs that are
+/// missing from a partial clone are skipped, like in its checkout.
+pub(crate) fn list_blobs(
+	repo: &git2::Repository,
+	tree: &git2::Tree,
+	sub_dir: &Path,
+	path_pattern: &str,
+	max_files: usize,
+) -> Result<Vec<PathBuf>, SwcError> {
+	let matches = path_matcher(path_pattern)?;
+	let odb = repo.odb()?;
+
+	let mut paths = Vec::new();
+	tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+		if entry.kind() != Some(git2::ObjectType::Blob) ||
+			entry.filemode() == i32::from(git2::FileMode::Link)
+		{
+			return git2::TreeWalkResult::Ok
+		}
+		let path = PathBuf::from(format!("{}{}", root, entry.name().unwrap_or_default()));
+		let Ok(relative) = path.strip_prefix(sub_dir) else { return git2::TreeWalkResult::Ok };
+		if matches(relative) && odb.exists(entry.id()) {
+			paths.push(path);
+		}
+		git2::TreeWalkResult::Ok
+	})?;
+	if paths.len() > max_files {
+		return Err(SwcError::Glob(format!(
+			"Found too many files. Found: {}, Max: {}",
+			paths.len(),
+			max_files
+		)))
+	}
+	paths.sort();
+	Ok(paths)
+}
+
+/// Whether a path relative to the repository matches the `path_pattern`.
+///
+/// The `mod.rs` files are skipped, except the one of the XCM weights.
+fn path_matcher(path_pattern: &str) -> Result<impl Fn(&Path) -> bool, SwcError> {
+	let (include, exclude) = split_path_pattern(path_pattern).map_err(SwcError::Glob)?;
+	let include = include
+		.into_iter()
+		.map(glob::Pattern::new)
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|e| SwcError::Glob(format!("Invalid path pattern: {:?}", e)))?;
+	let options = glob::MatchOptions { require_literal_separator: true, ..Default::default() };
+
+	Ok(move |path: &Path| {
+		include.iter().any(|p| p.matches_path_with(path, options)) &&
+			!exclude.iter().any(|p| p.matches_path_with(path, options)) &&
+			(!path.ends_with("mod.rs") || path.ends_with("xcm/mod.rs"))
+	})
+}
+
+/// The content of the file at `path` of the `tree`.
+fn read_object(
+	repo: &git2::Repository,
+	tree: &git2::Tree,
+	path: &Path,
+) -> Result<String, SwcError> {
+	let blob = repo.find_blob(tree.get_path(path)?.id())?;
+	String::from_utf8(blob.content().to_vec())
+		.map_err(|e| SwcError::Parse(format!("{}: {}", path.display(), e)))
+}
+
+/// Parse the file at `path` of the `tree` like [`parse_file_in_repo`] parses a checked out file.
+fn parse_object(
+	repo: &git2::Repository,
+	tree: &git2::Tree,
+	sub_dir: &Path,
+	path: &Path,
+) -> Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let content = read_object(repo, tree, path)?;
+	let err = |e: String| SwcError::Parse(format!("{}: {}", path.display(), e));
+	if json::is_json(path) {
+		return json::parse_content(&content).map_err(err)
+	}
+	let name: PalletName = PathStripping::RepoRelative.strip(sub_dir, path).into();
+	if !xcm::is_xcm_weight_info(&content) {
+		return pallet::parse_content(name, content)
+	}
+	pallet::parse_content(name.clone(), content.clone()).or_else(|_| {
+		xcm::parse_content(name, content, |module| {
+			let sibling = path.with_file_name(format!("{}.rs", module));
+			let content = read_object(repo, tree, &sibling).map_err(|e| e.to_string())?;
+			pallet::parse_content(format!("{}.rs", module).into(), content)
+				.map_err(|e| e.to_string())
+		})
+		.map_err(err)
+	})
+}
+
+/// The blob hash and pallet name of the file at `path` of the `tree`.
+///
+/// `None` for XCM weights, like [`parsed_blob`].
+fn object_blob(
+	repo: &git2::Repository,
+	tree: &git2::Tree,
+	sub_dir: &Path,
+	path: &Path,
+) -> Option<(String, String)> {
+	let content = read_object(repo, tree, path).ok()?;
+	if xcm::is_xcm_weight_info(&content) {
+		return None
+	}
+	let name = PathStripping::RepoRelative.strip(sub_dir, path);
+	Some((tree.get_path(path).ok()?.id().to_string(), name))
+}
+
+/// The blob hash and pallet name of a file that was checked out into `dir`.
+///
+/// `None` if the file can not be parsed on its own, like XCM weights that include other files.
+fn parsed_blob(
+	tree: &git2::Tree,
+	dir: &Path,
+	base: &Path,
+	path: &Path,
+) -> Option<(String, String)> {
+	let entry = tree.get_path(path.strip_prefix(dir).ok()?).ok()?;
+	let content = std::fs::read_to_string(path).ok()?;
+	if xcm::is_xcm_weight_info(&content) {
+		return None
+	}
+	let name = PathStripping::RepoRelative.strip(base, path);
+	Some((entry.id().to_string(), name))
+}
+
+/// Rename the pallets of `old` whose files moved on the way to the `new` version.
+///
+/// Commits of the same repository are diffed by git, which also finds moved files that changed a
+/// bit. The remaining files are matched by their file name.
+fn follow_moves(old: &mut ParsedSet, new: &ParsedSet) {
+	let (olds, news) = (&mut old.extrinsics, &new.extrinsics);
+	if let (Some((old_repo, old_ref)), Some((new_repo, new_ref))) = (&old.origin, &new.origin) {
+		if old_repo.canonicalize().ok() == new_repo.canonicalize().ok() {
+			match moved_files(old_repo, old_ref, new_ref) {
+				Ok(mut moves) => {
+					// Files that moved out of the path pattern are removed.
+					moves.retain(|m| news.iter().any(|e| e.pallet == m.new));
+					rename::apply_moves(olds, &moves)
+				},
+				// The blobs of a partial clone can be missing.
+				Err(err) => log::warn!("Failed to detect moved files: {}", err),
+			}
+		}
+	}
+	let moves = rename::detect_moves(olds, news);
+	rename::apply_moves(olds, &moves);
+	for m in &moves {
+		log::info!("Moved {} to {}", m.old, m.new);
+	}
+}
+
+/// The files that git detects as renamed between two commits.
+///
+/// The paths are relative to `path`, like the pallet names. Does not fetch the commits.
+pub fn moved_files(path: &Path, old: &str, new: &str) -> Result<Vec<Move>, SwcError> {
+	let (repo, sub_dir) = discover_repo(path)?;
+	let tree = |refname: &str| {
+		resolve_ref(&repo, refname, false, &())?.peel_to_tree().map_err(|e| {
+			SwcError::Git(format!("Failed to find tree of {}: {}", refname, e.message()))
+		})
+	};
+	let (old_tree, new_tree) = (tree(old)?, tree(new)?);
+	let mut diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+	diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+
+	let relative = |file: git2::DiffFile| {
+		let path = file.path()?.strip_prefix(&sub_dir).ok()?;
+		Some(PalletName::from(path.to_string_lossy().as_ref()))
+	};
+	let moves = diff
+		.deltas()
+		.filter(|d| d.status() == git2::Delta::Renamed)
+		.filter_map(|d| Some(Move { old: relative(d.old_file())?, new: relative(d.new_file())? }))
+		.collect::<Vec<_>>();
+	for m in &moves {
+		log::info!("Moved {} to {}", m.old, m.new);
+	}
+	Ok(moves)
+}
+
+/// The folder of the git hooks of the repository that contains `path`, and the path relative to
+/// its root.
+///
+/// This is `core.hooksPath` if configured; linked worktrees share the hooks of the main one.
+pub fn hooks_dir(path: &Path) -> Result<(PathBuf, PathBuf), SwcError> {
+	let (repo, sub_dir) = discover_repo(path)?;
+	let root = repo.workdir().unwrap_or(path);
+	if let Ok(hooks) = repo.config()?.get_path("core.hooksPath") {
+		return Ok((root.join(hooks), sub_dir))
+	}
+	let git_dir = match std::fs::read_to_string(repo.path().join("commondir")) {
+		Ok(common) => repo.path().join(common.trim()),
+		Err(_) => repo.path().to_path_buf(),
+	};
+	Ok((git_dir.join("hooks"), sub_dir))
+}
+
+/// Open the repository that contains `path` and return the path relative to its root.
+fn discover_repo(path: &Path) -> Result<(git2::Repository, PathBuf), SwcError> {
+	let repo = git2::Repository::discover(path).map_err(|e| {
+		SwcError::Git(format!("Failed to find repository of {}: {}", path.display(), e.message()))
+	})?;
+	let root = repo
+		.workdir()
+		.ok_or_else(|| SwcError::Git(format!("Bare repository: {}", path.display())))?;
+	let sub_dir = path
+		.canonicalize()?
+		.strip_prefix(root.canonicalize()?)
+		.map_err(|_| SwcError::Git(format!("{} is outside of its repository", path.display())))?
+		.to_path_buf();
+
+	Ok((open_repo(root)?, sub_dir))
+}
+
+pub fn reset(path: &Path, refname: &str, pull: bool) -> Result<(), SwcError> {
+	let repo = open_repo(path)?;
+	let target = resolve_ref(&repo, refname, pull, &())?;
+
+	repo.reset(&target, git2::ResetType::Hard, None)
+		.map_err(|e| SwcError::Git(format!("Failed to reset branch: {}", e.message())))
+}
+
+/// Check out `refname` of the repository at `path` into a new temporary directory.
+///
+/// The working tree, index and `HEAD` of the repository stay untouched. The directory is deleted
+/// once the returned [`tempfile::TempDir`] is dropped.
+pub fn checkout_temp(
+	path: &Path,
+	refname: &str,
+	pull: bool,
+) -> Result<tempfile::TempDir, SwcError> {
+	let repo = open_repo(path)?;
+	let target = resolve_ref(&repo, refname, pull, &())?;
+	checkout_object_temp(&repo, &target, refname)
+}
+
+fn checkout_object_temp(
+	repo: &git2::Repository,
+	target: &git2::Object,
+	refname: &str,
+) -> Result<tempfile::TempDir, SwcError> {
+	let tree = target.peel_to_tree().map_err(|e| {
+		SwcError::Git(format!("Failed to find tree of {}: {}", refname, e.message()))
+	})?;
+
+	let dir = tempfile::Builder::new()
+		.prefix("subweight-")
+		.tempdir()
+		.map_err(|e| SwcError::Git(format!("Failed to create temporary directory: {}", e)))?;
+	log::info!("Checking out {} into {}", refname, dir.path().display());
+
+	let mut checkout = git2::build::CheckoutBuilder::new();
+	checkout
+		.target_dir(dir.path())
+		.update_index(false)
+		.recreate_missing(true)
+		.force();
+	// Only the blobs of the sparse checkout of a partial clone are available.
+	if repo.config()?.get_bool("remote.origin.promisor").unwrap_or_default() {
+		let paths = local_blobs(repo, &tree)?;
+		if paths.is_empty() {
+			return Ok(dir)
+		}
+		for path in paths {
+			checkout.path(path);
+		}
+	}
+	repo.checkout_tree(tree.as_object(), Some(&mut checkout))
+		.map_err(|e| SwcError::Git(format!("Failed to check out {}: {}", refname, e.message())))?;
+
+	Ok(dir)
+}
+
+/// Paths of the files in `tree` whose blobs exist in the repository.
+fn local_blobs(repo: &git2::Repository, tree: &git2::Tree) -> Result<Vec<String>, SwcError> {
+	let odb = repo.odb()?;
+	let mut paths = Vec::new();
+	tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+		if entry.kind() == Some(git2::ObjectType::Blob) && odb.exists(entry.id()) {
+			paths.push(format!("{}{}", root, entry.name().unwrap_or_default()));
+		}
+		git2::TreeWalkResult::Ok
+	})?;
+	Ok(paths)
+}
+
+/// Clone the repository at `url` into `path` with only the weight files of `refnames`.
+///
+/// The clone is shallow, blob-filtered and its sparse checkout is limited to `path_pattern`, so
+/// only the commits of the refs and the blobs of the matching files are downloaded. The refs are
+/// available as `origin/{refname}` afterwards. Uses the `git` CLI since libgit2 supports neither.
+pub fn sparse_clone(
+	url: &str,
+	path: &Path,
+	refnames: &[&str],
+	path_pattern: &str,
+) -> Result<(), SwcError> {
+	let dir = path.display().to_string();
+	log::info!("Cloning {} into {}", url, dir);
+	git_cli(&["clone", "-q", "--depth=1", "--filter=blob:none", "--no-checkout", url, &dir])?;
+	// Not `git sparse-checkout` since it enables the `worktreeConfig` extension that libgit2
+	// cannot open. Exclusions with `!` have the same meaning in sparse checkout patterns.
+	git_cli(&["-C", &dir, "config", "core.sparseCheckout", "true"])?;
+	let patterns = path_pattern.split(',').map(str::trim).collect::<Vec<_>>();
+	std::fs::create_dir_all(path.join(".git/info"))?;
+	std::fs::write(path.join(".git/info/sparse-checkout"), patterns.join("\n") + "\n")?;
+
+	for refname in refnames {
+		let refspec = format!("+{}:refs/remotes/origin/{}", refname, refname);
+		git_cli(&[
+			"-C",
+			&dir,
+			"fetch",
+			"-q",
+			"--depth=1",
+			"--filter=blob:none",
+			"origin",
+			&refspec,
+		])?;
+		// Downloads the blobs of the sparse checkout.
+		git_cli(&["-C", &dir, "checkout", "-q", "--detach", &format!("origin/{}", refname)])?;
+	}
+	Ok(())
+}
+
+fn git_cli(args: &[&str]) -> Result<(), SwcError> {
+	let output = std::process::Command::new("git")
+		.args(args)
+		.envs(auth::credentials().git_env())
+		.output()
+		.map_err(|e| SwcError::Git(format!("Failed to run git: {}", e)))?;
+	if !output.status.success() {
+		return Err(SwcError::Git(format!(
+			"git {} failed: {}",
+			args.join(" "),
+			String::from_utf8_lossy(&output.stderr).trim()
+		)))
+	}
+	Ok(())
+}
+
+pub(crate) fn open_repo(path: &Path) -> Result<git2::Repository, SwcError> {
+	git2::Repository::open(path).map_err(|e| {
+		SwcError::Git(format!("Failed to open repository {}: {}", path.display(), e.message()))
+	})
+}
+
+/// Find the object that `refname` points to; optionally fetching it from `origin` first.
+///
+/// Refs that are not fetched by default, like `pull/1234/head`, are fetched to
+/// `origin/pull/1234/head`.
+pub(crate) fn resolve_ref<'a>(
+	repo: &'a git2::Repository,
+	refname: &str,
+	pull: bool,
+	progress: &dyn Progress,
+) -> Result<git2::Object<'a>, SwcError> {
+	let hidden = hidden_ref(refname);
+	if pull {
+		log::info!("Fetching branch {}", refname);
+		let refspec = match hidden {
+			Some(name) => format!("+refs/{0}:refs/remotes/origin/{0}", name),
+			None => refname.to_string(),
+		};
+
+		let mut remote = repo
+			.find_remote("origin")
+			.map_err(|e| SwcError::Git(format!("Failed to fetch branch: {}", e.message())))?;
+		let received = std::cell::Cell::new((0, 0));
+		let credentials = auth::credentials();
+		let mut callbacks = credentials.remote_callbacks();
+		callbacks.transfer_progress(|p| {
+			received.set((p.received_objects(), p.total_objects()));
+			progress.fetched(refname, p.received_objects(), p.total_objects());
+			// Aborts the fetch.
+			!progress.cancelled()
+		});
+		let mut options = git2::FetchOptions::new();
+		options.remote_callbacks(callbacks).proxy_options(credentials.proxy_options());
+		let start = std::time::Instant::now();
+		remote.fetch(&[&refspec], Some(&mut options), None).map_err(|e| {
+			if progress.cancelled() {
+				let (done, total) = received.get();
+				SwcError::Cancelled(Stage::new(format!("Fetching {}", refname), done, total))
+			} else {
+				SwcError::Git(format!("Failed to fetch branch: {}", e.message()))
+			}
+		})?;
+		progress.fetch_finished(refname, start.elapsed());
+	} else {
+		log::debug!("Not fetching branch {} (should_fetch={})", refname, pull);
+	}
+	// try to resolve with remote...
+	let name = hidden.unwrap_or(refname);
+	log::info!("Resolving origin/{}", name);
+	match repo.revparse_single(&format!("origin/{}", name)) {
+		Ok(target) => Ok(target),
+		// Ignore any errors and try again without `origin/` prefix.
+		Err(err) => {
+			log::warn!("Failed to resolve origin/{}: {}", name, err.message());
+			log::info!("Fallback: Resolving {}", refname);
+			repo.revparse_single(refname)
+				.map_err(|e| SwcError::Git(format!("Failed to reset branch: {}", e.message())))
+		},
+	}
+}
+
+/// The name of a ref that is not fetched by default, without the `refs/` prefix.
+///
+/// These are pull requests like `pull/1234/merge`, merge requests and any other full ref that is
+/// not a branch or tag, eg. `refs/changes/34/1234/1`.
+pub(crate) fn hidden_ref(refname: &str) -> Option<&str> {
+	let name = refname.strip_prefix("refs/");
+	let hidden = match name {
+		Some(name) => !["heads/", "tags/", "remotes/"].iter().any(|p| name.starts_with(p)),
+		None => refname.starts_with("pull/") || refname.starts_with("merge-requests/"),
+	};
+	hidden.then_some(name.unwrap_or(refname))
+}
+
+pub(crate) fn list_files(
+	base_path: &Path,
+	regex: &str,
+	max_files: usize,
+) -> Result<Vec<PathBuf>, SwcError> {
+	let (regex, exclude) = split_path_pattern(regex).map_err(SwcError::Glob)?;
+	let options = glob::MatchOptions { require_literal_separator: true, ..Default::default() };
+
+	let mut paths = Vec::new();
+	for regex in regex {
+		let regex = format!("{}/{}", base_path.display(), regex);
+		log::info!("Listing files matching: {:?}", &regex);
+		let files = glob::glob(&regex)
+			.map_err(|e| SwcError::Glob(format!("Invalid path pattern: {:?}", e)))?;
+		let files = files
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| SwcError::Glob(format!("Path pattern error: {:?}", e)))?;
+		let files: Vec<_> = files
+			.iter()
+			.cloned()
+			.filter(|f| !f.ends_with("mod.rs") || f.ends_with("xcm/mod.rs"))
+			.filter(|f| {
+				let relative = f.strip_prefix(base_path).unwrap_or(f);
+				!exclude.iter().any(|p| p.matches_path_with(relative, options))
+			})
+			.collect();
+		paths.extend(files);
+		if paths.len() > max_files {
+			return Err(SwcError::Glob(format!(
+				"Found too many files. Found: {}, Max: {}",
+				paths.len(),
+				max_files
+			)))
+		}
+	}
+	paths.sort();
+	paths.dedup();
+	Ok(paths)
+}
@@ -0,0 +1,270 @@
+//! Track the weight of an extrinsic across the history of a repository.
+//!
+//! The weight files are read directly from the git objects, so nothing is checked out.
+
+use std::path::Path;
+
+use crate::{
+	component_scope, evaluate_weight, open_repo,
+	parse::{pallet::parse_content, PathStripping},
+	resolve_ref,
+	scope::SimpleScope,
+	DbWeights, Dimension, Percent, SwcError,
+};
+
+/// The weight of an extrinsic at one commit.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HistoryPoint {
+	/// Full hash of the commit.
+	pub commit: String,
+	/// First line of the commit message.
+	pub summary: String,
+	/// Commit time as UNIX timestamp in seconds.
+	pub time: i64,
+
+	pub ref_time: u128,
+	pub proof_size: u128,
+}
+
+/// Evaluate an extrinsic at every commit of `range` that changed its weight file.
+///
+/// The range has the form `FROM..TO` and follows the first parent of each commit. `FROM` itself
+/// is included. Commits at which the file or extrinsic does not exist are skipped. Components
+/// without a value in `components` are set to their maximum and storage accesses take the time of
+/// `db_weights`.
+pub fn extrinsic_history(
+	repo: &Path,
+	range: &str,
+	file: &Path,
+	extrinsic: &str,
+	components: &SimpleScope,
+	db_weights: DbWeights,
+) -> Result<Vec<HistoryPoint>, SwcError> {
+	let repo = open_repo(repo)?;
+	let (from, to) = range.split_once("..").ok_or_else(|| {
+		SwcError::InvalidInput(format!("Invalid range, expected FROM..TO: {}", range))
+	})?;
+
+	let mut last_blob = None;
+	let mut points = Vec::new();
+	for id in first_parent_commits(&repo, from, to)? {
+		let commit = repo.find_commit(id)?;
+		let blob = file_at(&commit, file)?;
+		// Only evaluate commits that changed the file.
+		if blob.is_none() || blob == last_blob {
+			continue
+		}
+		last_blob = blob;
+
+		if let Some(point) = evaluate_at(&repo, &commit, file, extrinsic, components, db_weights)? {
+			points.push(point);
+		}
+	}
+
+	Ok(points)
+}
+
+/// Outcome of [`bisect_regression`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BisectResult {
+	/// The weight at the good commit that the threshold is relative to.
+	pub good: HistoryPoint,
+	/// The first commit whose weight exceeds the threshold.
+	pub first_bad: HistoryPoint,
+	/// Number of commits that were evaluated.
+	pub steps: usize,
+}
+
+/// Find the first commit between `good` and `bad` at which the weight of an extrinsic increased
+/// by more than `threshold` percent over its weight at `good`.
+///
+/// Like `git bisect` it assumes that the weight stays regressed once it regressed. Only the
+/// first-parent history is searched. The weight is compared in the `unit` dimension.
+#[allow(clippy::too_many_arguments)]
+pub fn bisect_regression(
+	repo: &Path,
+	(good, bad): (&str, &str),
+	file: &Path,
+	extrinsic: &str,
+	components: &SimpleScope,
+	db_weights: DbWeights,
+	unit: Dimension,
+	threshold: Percent,
+) -> Result<BisectResult, SwcError> {
+	let repo = open_repo(repo)?;
+	let commits = first_parent_commits(&repo, good, bad)?;
+	let value = |p: &HistoryPoint| if unit == Dimension::Time { p.ref_time } else { p.proof_size };
+
+	let mut steps = 0;
+	let mut eval = |id: git2::Oid| -> Result<Option<HistoryPoint>, SwcError> {
+		steps += 1;
+		let commit = repo.find_commit(id)?;
+		evaluate_at(&repo, &commit, file, extrinsic, components, db_weights)
+	};
+
+	let good = eval(commits[0])?.ok_or_else(|| {
+		SwcError::NotFound(format!("Extrinsic {} not found at the good commit", extrinsic))
+	})?;
+	let limit = value(&good) as f64 * (1.0 + threshold / 100.0);
+	// A missing extrinsic counts as not regressed.
+	let mut is_bad = |id| eval(id).map(|p| p.filter(|p| value(p) as f64 > limit));
+
+	let (mut lo, mut hi) = (0, commits.len() - 1);
+	let mut first_bad = is_bad(commits[hi])?.ok_or_else(|| {
+		SwcError::NotFound(format!("No regression of more than {}% found", threshold))
+	})?;
+	// Invariant: `lo` is good and `hi` is bad.
+	while hi - lo > 1 {
+		let mid = lo + (hi - lo) / 2;
+		match is_bad(commits[mid])? {
+			Some(point) => {
+				hi = mid;
+				first_bad = point;
+			},
+			None => lo = mid,
+		}
+	}
+
+	Ok(BisectResult { good, first_bad, steps })
+}
+
+/// The hash of the last commit of `branch` before `date`, eg. `2024-01-01`.
+///
+/// Like `git rev-list -1 --first-parent --before=DATE` but the date is midnight UTC. Only the
+/// first-parent history of the branch is searched.
+pub fn commit_before(
+	repo: &Path,
+	branch: &str,
+	date: &str,
+	pull: bool,
+) -> Result<String, SwcError> {
+	let before = parse_date(date).map_err(SwcError::InvalidInput)?;
+	let repo = git2::Repository::discover(repo)?;
+	let head = resolve_ref(&repo, branch, pull, &())?.peel_to_commit()?;
+
+	let mut walk = repo.revwalk()?;
+	walk.simplify_first_parent()?;
+	walk.push(head.id())?;
+	for id in walk {
+		let commit = repo.find_commit(id?)?;
+		if commit.time().seconds() < before {
+			log::info!("Last commit of {} before {} is {}", branch, date, commit.id());
+			return Ok(commit.id().to_string())
+		}
+	}
+	Err(SwcError::NotFound(format!("No commit of {} before {}", branch, date)))
+}
+
+/// The tags that match the glob `pattern`, eg. `v*`, in the order of their releases.
+///
+/// Tags are ordered by their version, like `v1.10.0` after `v1.9.0`; anything before the first
+/// digit is ignored. If any tag is not a version, all are ordered by the time of their commits.
+pub fn release_tags(repo: &Path, pattern: &str) -> Result<Vec<String>, SwcError> {
+	let repo = git2::Repository::discover(repo)?;
+	let mut tags = repo
+		.tag_names(Some(pattern))?
+		.iter()
+		.flatten()
+		.map(String::from)
+		.collect::<Vec<_>>();
+
+	if tags.iter().all(|t| version_of(t).is_some()) {
+		tags.sort_by_cached_key(|t| version_of(t));
+		return Ok(tags)
+	}
+	let mut timed = tags
+		.into_iter()
+		.map(|t| Ok((repo.revparse_single(&t)?.peel_to_commit()?.time().seconds(), t)))
+		.collect::<Result<Vec<_>, SwcError>>()?;
+	timed.sort();
+	Ok(timed.into_iter().map(|(_, t)| t).collect())
+}
+
+/// The version of a tag like `polkadot-v1.2.0`. A missing patch version counts as zero.
+fn version_of(tag: &str) -> Option<semver::Version> {
+	let version = &tag[tag.find(|c: char| c.is_ascii_digit())?..];
+	match version.matches('.').count() {
+		1 => semver::Version::parse(&format!("{}.0", version)).ok(),
+		_ => semver::Version::parse(version).ok(),
+	}
+}
+
+/// UNIX timestamp of midnight UTC of a `YYYY-MM-DD` date.
+fn parse_date(date: &str) -> Result<i64, String> {
+	let invalid = || format!("Invalid date, expected YYYY-MM-DD: {}", date);
+	let parts = date.split('-').map(|p| p.parse::<i64>().ok()).collect::<Vec<_>>();
+	let [Some(year), Some(month @ 1..=12), Some(day @ 1..=31)] = parts[..] else {
+		return Err(invalid())
+	};
+	Ok(days_from_civil(year, month, day) * 86_400)
+}
+
+/// Days since 1970-01-01 of a date. From <http://howardhinnant.github.io/date_algorithms.html>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let yoe = year.rem_euclid(400);
+	let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	era * 146_097 + doe - 719_468
+}
+
+/// The first-parent history from `from` to `to`; oldest first and including both.
+fn first_parent_commits(
+	repo: &git2::Repository,
+	from: &str,
+	to: &str,
+) -> Result<Vec<git2::Oid>, SwcError> {
+	let from = resolve_ref(repo, from, false, &())?.peel_to_commit()?;
+	let to = resolve_ref(repo, to, false, &())?.peel_to_commit()?;
+
+	let mut walk = repo.revwalk()?;
+	walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+	walk.simplify_first_parent()?;
+	walk.push(to.id())?;
+	walk.hide(from.id())?;
+
+	Ok(std::iter::once(Ok(from.id())).chain(walk).collect::<Result<_, _>>()?)
+}
+
+/// The blob of `file` at the commit if it exists.
+fn file_at(commit: &git2::Commit, file: &Path) -> Result<Option<git2::Oid>, SwcError> {
+	let tree = commit.tree()?;
+	Ok(tree.get_path(file).ok().map(|entry| entry.id()))
+}
+
+/// Evaluate the extrinsic at the commit; `None` if the file or extrinsic does not exist.
+fn evaluate_at(
+	repo: &git2::Repository,
+	commit: &git2::Commit,
+	file: &Path,
+	extrinsic: &str,
+	components: &SimpleScope,
+	db_weights: DbWeights,
+) -> Result<Option<HistoryPoint>, SwcError> {
+	let Some(blob) = file_at(commit, file)? else {
+		log::debug!("{} does not exist at {}", file.display(), commit.id());
+		return Ok(None)
+	};
+	let blob = repo.find_blob(blob)?;
+	let content = String::from_utf8_lossy(blob.content()).into_owned();
+
+	let pallet = PathStripping::FileName.strip(Path::new("."), file);
+	let Some(ext) = parse_content(pallet.into(), content)
+		.map_err(|e| log::warn!("Failed to parse {} at {}: {}", file.display(), commit.id(), e))
+		.ok()
+		.and_then(|exts| exts.into_iter().find(|e| e.name == extrinsic))
+	else {
+		return Ok(None)
+	};
+
+	let weight = evaluate_weight(&ext, &component_scope(&ext, components), db_weights)
+		.map_err(SwcError::Eval)?;
+	Ok(Some(HistoryPoint {
+		commit: commit.id().to_string(),
+		summary: commit.summary().unwrap_or_default().into(),
+		time: commit.time().seconds(),
+		ref_time: weight.ref_time,
+		proof_size: weight.proof_size,
+	}))
+}
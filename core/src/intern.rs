@@ -0,0 +1,148 @@
+//! Interned names of pallets, extrinsics, components and storage items.
+//!
+//! Each parsed weight file repeats the same few component names in all its terms and every compared
+//! version repeats the names of its pallets and extrinsics. Interning keeps a single copy of each
+//! name; clones only count a reference and equal names share their memory.
+//!
+//! Every thread has its own names, so that files are parsed in parallel without waiting for a
+//! lock. A name that is parsed on multiple threads therefore has one copy per thread.
+
+use std::{
+	borrow::Borrow,
+	cell::RefCell,
+	collections::HashSet,
+	hash::{Hash, Hasher},
+	sync::Arc,
+};
+
+thread_local! {
+	/// Names are never freed; there are only a few thousand distinct ones in large repositories.
+	static NAMES: RefCell<HashSet<Entry>> = RefCell::new(HashSet::new());
+}
+
+/// Looks up an interned name by its `str`.
+#[derive(PartialEq, Eq)]
+struct Entry(Arc<String>);
+
+impl Hash for Entry {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		// Must hash like the `str` that it is borrowed as.
+		self.0.as_str().hash(state)
+	}
+}
+
+impl Borrow<str> for Entry {
+	fn borrow(&self) -> &str {
+		&self.0
+	}
+}
+
+/// The shared copy of `name`.
+pub fn intern(name: &str) -> Arc<String> {
+	NAMES.with(|names| {
+		let mut names = names.borrow_mut();
+		if let Some(name) = names.get(name) {
+			return name.0.clone()
+		}
+		let name = Arc::new(name.to_string());
+		names.insert(Entry(name.clone()));
+		name
+	})
+}
+
+/// An interned name of a pallet or extrinsic; serializes as plain string.
+#[derive(
+	Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(from = "String", into = "String")]
+pub struct Name(Arc<String>);
+
+impl From<&str> for Name {
+	fn from(s: &str) -> Self {
+		Self(intern(s))
+	}
+}
+
+impl From<String> for Name {
+	fn from(s: String) -> Self {
+		s.as_str().into()
+	}
+}
+
+impl From<&String> for Name {
+	fn from(s: &String) -> Self {
+		s.as_str().into()
+	}
+}
+
+impl From<Name> for String {
+	fn from(n: Name) -> String {
+		n.0.as_ref().clone()
+	}
+}
+
+impl std::ops::Deref for Name {
+	type Target = String;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl Borrow<str> for Name {
+	fn borrow(&self) -> &str {
+		&self.0
+	}
+}
+
+impl AsRef<str> for Name {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+impl std::fmt::Display for Name {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+impl std::fmt::Debug for Name {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+impl PartialEq<str> for Name {
+	fn eq(&self, other: &str) -> bool {
+		self.as_str() == other
+	}
+}
+
+impl PartialEq<&str> for Name {
+	fn eq(&self, other: &&str) -> bool {
+		self.as_str() == *other
+	}
+}
+
+impl PartialEq<String> for Name {
+	fn eq(&self, other: &String) -> bool {
+		self.as_str() == other
+	}
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Name {
+	fn schema_name() -> String {
+		"Name".into()
+	}
+
+	fn is_referenceable() -> bool {
+		// Inline like the `String` that it was before.
+		false
+	}
+
+	fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+		String::json_schema(gen)
+	}
+}
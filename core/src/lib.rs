@@ -2,34 +2,73 @@
 
 #![deny(rustdoc::broken_intra_doc_links)]
 
-use clap::Args;
 use fancy_regex::Regex;
 use git_version::git_version;
 use lazy_static::lazy_static;
 
 use std::{
 	cmp::Ordering,
-	collections::{BTreeSet, HashMap, HashSet},
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
 	path::{Path, PathBuf},
-	process::Command,
 };
 use syn::{Expr, Item, Type};
 
+pub mod access;
+pub mod allowlist;
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "git")]
+pub mod auth;
+pub mod budget;
+pub mod cache;
+#[cfg(feature = "git")]
+pub mod comparison;
+#[cfg(feature = "sqlite")]
+pub mod db;
+pub mod error;
+pub mod fee;
+pub mod format;
+#[cfg(feature = "git")]
+pub mod git;
+#[cfg(feature = "git")]
+pub mod history;
+pub mod intern;
+pub mod lint;
+pub mod monotonicity;
 pub mod parse;
+pub mod progress;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod rename;
+pub mod schema;
 pub mod scope;
+#[cfg(feature = "async")]
+pub mod task;
 pub mod term;
 pub mod testing;
 pub mod traits;
+#[cfg(feature = "git")]
+pub mod watch;
 
 #[cfg(test)]
 mod test;
 
-use parse::pallet::{
-	parse_files_in_repo, try_parse_files_in_repo, ChromaticExtrinsic, ComponentRange,
-	SimpleExtrinsic,
+#[cfg(feature = "git")]
+pub use comparison::Comparison;
+pub use error::SwcError;
+#[cfg(feature = "git")]
+pub use git::*;
+use monotonicity::{component_monotonicity, Monotonicity};
+use parse::{
+	machine::MachineScore,
+	pallet::{ChromaticExtrinsic, ComponentRange, SimpleExtrinsic},
 };
-use scope::SimpleScope;
-use term::SimpleTerm;
+use progress::Progress;
+#[cfg(feature = "remote")]
+pub use remote::compare_remote;
+use scope::{ChromaticScope, SimpleScope, STORAGE_READ_VAR, STORAGE_WRITE_VAR};
+use term::{ChromaticTerm, EvalError, SimpleTerm};
+use traits::Weight;
 
 lazy_static! {
 	/// Version of the library. Example: `swc 0.2.0+78a04b2-dirty`.
@@ -40,24 +79,42 @@ lazy_static! {
 	};
 }
 
-pub type PalletName = String;
-pub type ExtrinsicName = String;
+pub type PalletName = intern::Name;
+pub type ExtrinsicName = intern::Name;
 pub type TotalDiff = Vec<ExtrinsicDiff>;
 
 pub type Percent = f64;
 pub const WEIGHT_PER_NANOS: u128 = 1_000;
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "bloat", derive(Debug))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtrinsicDiff {
 	pub name: ExtrinsicName,
 	pub file: String,
 
 	pub change: TermDiff,
+	/// Change of the proof size when comparing with [`Dimension::Both`].
+	///
+	/// The [`Self::change`] then contains the reference time.
+	pub proof_change: Option<TermDiff>,
+	/// Reason why the change is acknowledged by an [`allowlist::Allowlist`].
+	pub acknowledged: Option<String>,
+	/// Location of the new extrinsic in its weight file, or of the old one if it was removed.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub span: Option<parse::pallet::SourceSpan>,
+	/// Old name of a [`RelativeChange::Renamed`] extrinsic.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub renamed_from: Option<ExtrinsicName>,
+	/// The pallet of the [`Self::file`]; if its weight file names it.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub pallet_id: Option<parse::pallet::PalletId>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "bloat", derive(Debug))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
 pub enum TermDiff {
 	Changed(TermChange),
 	Warning(TermChange, String),
@@ -65,6 +122,33 @@ pub enum TermDiff {
 }
 
 impl ExtrinsicDiff {
+	/// Convert the compared reference times into fees.
+	///
+	/// The attribution and formula diff are dropped since they are in units of time.
+	pub fn into_fees(mut self, fees: &fee::FeeParams) -> Self {
+		if let TermDiff::Changed(change) | TermDiff::Warning(change, _) = &mut self.change {
+			change.old_v = change.old_v.map(|v| fees.fee(v));
+			change.new_v = change.new_v.map(|v| fees.fee(v));
+			change.percent =
+				percent(change.old_v.unwrap_or_default(), change.new_v.unwrap_or_default());
+			change.attribution.clear();
+			change.formula.clear();
+			for sample in &mut change.samples {
+				sample.old_v = sample.old_v.map(|v| fees.fee(v));
+				sample.new_v = sample.new_v.map(|v| fees.fee(v));
+			}
+		}
+		self
+	}
+
+	/// The name and the old name if it was renamed, eg. `set_balance → force_set_balance`.
+	pub fn display_name(&self) -> String {
+		match &self.renamed_from {
+			Some(old) => format!("{} → {}", old, self.name),
+			None => self.name.to_string(),
+		}
+	}
+
 	pub fn term(&self) -> Option<&TermChange> {
 		match &self.change {
 			TermDiff::Changed(change) => Some(change),
@@ -86,11 +170,28 @@ impl ExtrinsicDiff {
 			_ => None,
 		}
 	}
+
+	/// The proof size change when comparing with [`Dimension::Both`].
+	pub fn proof_term(&self) -> Option<&TermChange> {
+		match &self.proof_change {
+			Some(TermDiff::Changed(change)) | Some(TermDiff::Warning(change, _)) => Some(change),
+			_ => None,
+		}
+	}
+
+	/// The more severe change of all compared dimensions.
+	pub fn max_change(&self) -> &TermDiff {
+		match &self.proof_change {
+			Some(proof) if proof.cmp(&self.change) == Ordering::Greater => proof,
+			_ => &self.change,
+		}
+	}
 }
 
 // Uses options since extrinsics can be added or removed and any time.
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "bloat", derive(Debug))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TermChange {
 	pub old: Option<SimpleTerm>,
 	pub old_v: Option<u128>,
@@ -99,195 +200,423 @@ pub struct TermChange {
 	pub new_v: Option<u128>,
 
 	pub scope: SimpleScope,
+	#[serde(with = "serde_percent")]
+	#[cfg_attr(feature = "schema", schemars(schema_with = "serde_percent::schema"))]
 	pub percent: Percent,
 	pub change: RelativeChange,
 	pub method: CompareMethod,
+
+	/// Which parts of the formula caused the change.
+	pub attribution: Vec<Attribution>,
+	/// Structural diff of the old and new formula; empty if one of them is missing.
+	pub formula: Vec<PartDiff>,
+	/// Whether the worst case is a guess; see [`MAX_COMBINED_COMPONENTS`].
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub approximate: bool,
+	/// The weights in all evaluated scopes if [`CompareParams::keep_samples`] is set.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub samples: Vec<Sample>,
+}
+
+/// The old and new weight in one of the evaluated scopes of a [`TermChange`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Sample {
+	pub scope: SimpleScope,
+	pub old_v: Option<u128>,
+	pub new_v: Option<u128>,
+}
+
+/// One product of a weight formula in the old and new version.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PartDiff {
+	/// The sorted variables of the product; empty for the base weight.
+	pub vars: Vec<String>,
+	/// Constant factor of the product or zero if it does not exist.
+	pub old: u128,
+	pub new: u128,
+}
+
+impl PartDiff {
+	/// Eg: `base 25.10ms → 26.40ms`, `READ count 4 → 5` or `slope of n unchanged`.
+	pub fn fmt(&self, unit: Dimension) -> String {
+		let is_storage = |v: &String| v == STORAGE_READ_VAR || v == STORAGE_WRITE_VAR;
+		let (storage, components): (Vec<_>, Vec<_>) = self.vars.iter().partition(|v| is_storage(v));
+
+		let name = match (storage.as_slice(), components.as_slice()) {
+			([], []) => "base".into(),
+			([s], []) => format!("{} count", s),
+			([s], c) =>
+				format!("{} per {}", s, c.iter().map(|c| c.as_str()).collect::<Vec<_>>().join("*")),
+			([], [c]) => format!("slope of {}", c),
+			(_, _) => format!("factor of {}", self.vars.join("*")),
+		};
+		if self.old == self.new {
+			return format!("{} unchanged", name)
+		}
+		// Storage factors are counts and not weights.
+		let fmt =
+			|v: u128| if storage.is_empty() && v != 0 { unit.fmt_value(v) } else { v.to_string() };
+		format!("{} {} → {}", name, fmt(self.old), fmt(self.new))
+	}
+}
+
+/// Diff the products of two formulas.
+///
+/// The formulas are expanded into a sum of products with [`SimpleTerm::monomials`] and
+/// products with the same variables are paired up.
+pub fn diff_formula(old: &SimpleTerm, new: &SimpleTerm) -> Vec<PartDiff> {
+	let (old, new) = (old.monomials(), new.monomials());
+	old.keys()
+		.chain(new.keys())
+		.collect::<std::collections::BTreeSet<_>>()
+		.into_iter()
+		.map(|vars| PartDiff {
+			vars: vars.clone(),
+			old: old.get(vars).copied().unwrap_or_default(),
+			new: new.get(vars).copied().unwrap_or_default(),
+		})
+		.collect()
+}
+
+/// The change of the evaluated weight that is caused by one part of the formula.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Attribution {
+	pub part: FormulaPart,
+	/// Change of the weight in the compared dimension.
+	pub delta: i128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum FormulaPart {
+	/// The weight when all components are zero.
+	Base,
+	/// The slope of a component times its value.
+	Component(String),
+	/// Products of multiple components.
+	Other,
+}
+
+impl TermChange {
+	/// Absolute difference of the old and new value; a missing value counts as zero.
+	pub fn abs_change(&self) -> u128 {
+		self.old_v.unwrap_or_default().abs_diff(self.new_v.unwrap_or_default())
+	}
+
+	/// The parts of the formula that changed; largest first. Eg: `base +1.20us`.
+	pub fn fmt_attribution(&self, unit: Dimension) -> Vec<String> {
+		let mut parts = self.attribution.iter().filter(|a| a.delta != 0).collect::<Vec<_>>();
+		parts.sort_by_key(|a| std::cmp::Reverse(a.delta.unsigned_abs()));
+		parts
+			.iter()
+			.map(|a| {
+				let sign = if a.delta < 0 { "-" } else { "+" };
+				format!("{} {}{}", a.part, sign, unit.fmt_value(a.delta.unsigned_abs()))
+			})
+			.collect()
+	}
+
+	/// See [`PartDiff::fmt`].
+	pub fn fmt_formula(&self, unit: Dimension) -> Vec<String> {
+		self.formula.iter().map(|p| p.fmt(unit)).collect()
+	}
+
+	/// The component values of the worst case, eg. `worst at {n=64, m=128}`.
+	///
+	/// `None` for the methods that do not search the worst case or without components.
+	pub fn fmt_worst_case(&self) -> Option<String> {
+		if !matches!(self.method, CompareMethod::ExactWorst | CompareMethod::GuessWorst) {
+			return None
+		}
+		let mut components = BTreeSet::new();
+		for term in [&self.old, &self.new].into_iter().flatten() {
+			components.extend(term.free_vars(&SimpleScope::empty()));
+		}
+		components.remove(STORAGE_READ_VAR);
+		components.remove(STORAGE_WRITE_VAR);
+
+		let values = components
+			.into_iter()
+			.filter_map(|c| Some(format!("{}={}", c, self.scope.get(&c)?.eval(&self.scope).ok()?)))
+			.collect::<Vec<_>>();
+		(!values.is_empty()).then(|| format!("worst at {{{}}}", values.join(", ")))
+	}
+}
+
+impl core::fmt::Display for FormulaPart {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Self::Base => write!(f, "base"),
+			Self::Component(c) => write!(f, "{}", c),
+			Self::Other => write!(f, "other"),
+		}
+	}
 }
 
 // TODO rename
 #[derive(
-	Debug, serde::Deserialize, clap::ValueEnum, Clone, Eq, Ord, PartialEq, PartialOrd, Copy,
+	Debug, serde::Serialize, serde::Deserialize, Clone, Eq, Ord, PartialEq, PartialOrd, Copy,
 )]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum RelativeChange {
 	Unchanged,
 	Added,
 	Removed,
 	Changed,
+	/// Matched with an extrinsic of another name; see [`rename`].
+	Renamed,
 }
 
 /// Parameters for modifying the benchmark behaviour.
-#[derive(Debug, Clone, PartialEq, Eq, Args)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
 pub struct CompareParams {
-	#[clap(long, short, value_name = "METHOD", ignore_case = true)]
+	#[cfg_attr(feature = "clap", clap(long, short, value_name = "METHOD", ignore_case = true))]
 	pub method: CompareMethod,
 
-	#[clap(long, short, value_name = "UNIT", ignore_case = true, default_value = "time")]
+	#[cfg_attr(
+		feature = "clap",
+		clap(long, short, value_name = "UNIT", ignore_case = true, default_value = "time")
+	)]
 	pub unit: Dimension,
 
-	#[clap(long)]
+	#[cfg_attr(feature = "clap", clap(long))]
 	pub ignore_errors: bool,
 
 	/// Do a 'git pull' after checking out the refname.
 	///
 	/// This ensures that you get the newest commit on a branch.
-	#[clap(long)]
+	#[cfg_attr(feature = "clap", clap(long))]
 	pub git_pull: bool,
 
 	/// Don't access the network.
 	///
 	/// This overrides any other options like `--git-pull`.
-	#[clap(long)]
+	#[cfg_attr(feature = "clap", clap(long))]
 	pub offline: bool,
+
+	/// Cache the parsed weight files of each commit in this directory, eg. `~/.cache/subweight`.
+	///
+	/// Comparing against a cached commit skips its checkout and parsing.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "DIR"))]
+	pub cache_dir: Option<PathBuf>,
+
+	/// Evaluate both versions with these component values, eg. `n=1000,m=16`.
+	///
+	/// The other components are still instantiated according to the `--method`.
+	#[cfg_attr(feature = "clap", clap(long = "set", value_name = "NAME=VALUE", value_delimiter = ',', value_parser = parse_component))]
+	pub components: Vec<(String, u128)>,
+
+	/// `WeightToFee` of the runtime for `--unit fee`; a preset like `polkadot` or a TOML file.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "PRESET|FILE", value_parser = <fee::FeeParams as std::str::FromStr>::from_str))]
+	pub fee_params: Option<fee::FeeParams>,
+
+	/// Time weight of one storage read and write; `rocksdb`, `paritydb` or `READ,WRITE`.
+	///
+	/// Values are in picoseconds unless they have a unit, eg. `20us,80us`.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "PRESET|READ,WRITE", default_value = "rocksdb", value_parser = <DbWeights as std::str::FromStr>::from_str))]
+	pub db_weights: DbWeights,
+
+	/// Compare a removed extrinsic with an added one of a similar name and the same components.
+	///
+	/// They are reported as renamed instead of as removed and added; see [`rename`].
+	#[cfg_attr(feature = "clap", clap(long))]
+	pub detect_renames: bool,
+
+	/// Keep the old and new weight of every evaluated scope; they are part of `--format json`.
+	///
+	/// Only the worst case is kept by default.
+	#[cfg_attr(feature = "clap", clap(long))]
+	pub keep_samples: bool,
+
+	/// Read the weight files of the commits from the git objects instead of checking them out.
+	///
+	/// This is faster and never touches a working tree, but git filters like `autocrlf` or LFS do
+	/// not apply to the files.
+	#[cfg_attr(feature = "clap", clap(long))]
+	pub git_objects: bool,
+
+	/// Find the weight files of the commits by their content instead of by a precise path pattern.
+	///
+	/// Files of the path pattern that do not implement a `WeightInfo` trait with functions that
+	/// return a `Weight` are skipped, and so are discovered files that fail to parse.
+	#[cfg_attr(feature = "clap", clap(long))]
+	pub auto_discover: bool,
+}
+
+/// The reference time of a storage read and write. Substitutes `READ` and `WRITE` in the formulas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DbWeights {
+	pub read: u128,
+	pub write: u128,
+}
+
+impl DbWeights {
+	/// The `RocksDbWeight` of Substrate.
+	pub const ROCKS_DB: Self = Self { read: 25_000_000, write: 100_000_000 };
+	/// The `ParityDbWeight` of Substrate.
+	pub const PARITY_DB: Self = Self { read: 8_000_000, write: 50_000_000 };
+}
+
+impl Default for DbWeights {
+	fn default() -> Self {
+		Self::ROCKS_DB
+	}
+}
+
+impl std::str::FromStr for DbWeights {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, String> {
+		match s.trim().to_lowercase().as_str() {
+			"rocksdb" => Ok(Self::ROCKS_DB),
+			"paritydb" => Ok(Self::PARITY_DB),
+			_ => {
+				let (read, write) = s.split_once(',').ok_or("Expected a preset or READ,WRITE")?;
+				Ok(Self { read: parse_abs_value(read)?, write: parse_abs_value(write)? })
+			},
+		}
+	}
+}
+
+/// Parse a component value of the form `NAME=VALUE`.
+pub fn parse_component(s: &str) -> Result<(String, u128), String> {
+	let (name, value) = s.split_once('=').ok_or("Expected NAME=VALUE")?;
+	let value = value.trim().parse().map_err(|e| format!("Invalid value of {}: {}", name, e))?;
+	Ok((name.trim().into(), value))
 }
 
-#[derive(Debug, Clone, PartialEq, Args)]
+/// Parse an absolute weight value like `200us`, `1.5ms` or `4KiB`.
+pub fn parse_abs_value(s: &str) -> Result<u128, String> {
+	let s = s.trim();
+	let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+	let (value, unit) = s.split_at(split);
+	let value: f64 = value.parse().map_err(|e| format!("Invalid value {}: {}", s, e))?;
+	let factor: u128 = match unit.trim() {
+		"" | "ps" | "B" => 1,
+		"ns" => 1_000,
+		"us" | "µs" => 1_000_000,
+		"ms" => 1_000_000_000,
+		"s" => 1_000_000_000_000,
+		"KiB" => 1024,
+		"MiB" => 1024 * 1024,
+		"GiB" => 1024 * 1024 * 1024,
+		unit => return Err(format!("Unknown unit: {}", unit)),
+	};
+	Ok((value * factor as f64).round() as u128)
+}
+
+/// How the relative and absolute thresholds are combined.
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ThresholdMode {
+	/// A change must pass both thresholds.
+	#[default]
+	And,
+	/// A change must pass any of the thresholds.
+	Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
 #[cfg_attr(feature = "bloat", derive(Default))]
 pub struct FilterParams {
 	/// Minimal magnitude of a relative change to be relevant.
-	#[clap(long, value_name = "PERCENT", default_value = "5")]
+	#[cfg_attr(feature = "clap", clap(long, value_name = "PERCENT", default_value = "5"))]
 	pub threshold: Percent,
 
+	/// Minimal magnitude of an absolute change to be relevant.
+	///
+	/// In the unit of the dimension; eg. `200us` for time or `4KiB` for proof size. A plain number
+	/// is in picoseconds or bytes.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "VALUE", value_parser = parse_abs_value))]
+	pub threshold_abs: Option<u128>,
+
+	/// Whether a change must pass both or any of `--threshold` and `--threshold-abs`.
+	#[cfg_attr(
+		feature = "clap",
+		clap(long, value_name = "MODE", ignore_case = true, default_value = "and")
+	)]
+	pub threshold_mode: ThresholdMode,
+
 	/// Only include a subset of change-types.
-	#[clap(long, ignore_case = true, num_args = 0.., value_name = "CHANGE-TYPE")]
+	#[cfg_attr(feature = "clap", clap(long, ignore_case = true, num_args = 0.., value_name = "CHANGE-TYPE"))]
 	pub change: Option<Vec<RelativeChange>>,
 
-	#[clap(long, ignore_case = true, value_name = "REGEX")]
+	#[cfg_attr(feature = "clap", clap(long, ignore_case = true, value_name = "REGEX"))]
 	pub extrinsic: Option<String>,
 
-	#[clap(long, alias("file"), ignore_case = true, value_name = "REGEX")]
+	#[cfg_attr(
+		feature = "clap",
+		clap(long, alias("file"), ignore_case = true, value_name = "REGEX")
+	)]
 	pub pallet: Option<String>,
-}
 
-impl CompareParams {
-	pub fn should_pull(&self) -> bool {
-		self.git_pull && !self.offline
-	}
-}
+	/// Skip the extrinsics that match this regex. Can be given multiple times.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "REGEX"))]
+	pub ignore_extrinsic: Vec<String>,
 
-pub fn compare_commits(
-	repo: &Path,
-	old: &str,
-	new: &str,
-	params: &CompareParams,
-	filter: &FilterParams,
-	path_pattern: &str,
-	max_files: usize,
-) -> Result<TotalDiff, Box<dyn std::error::Error>> {
-	if path_pattern.contains("..") {
-		return Err("Path pattern cannot contain '..'".into())
-	}
-	// Parse the old files.
-	if let Err(err) = reset(repo, old, params.should_pull()) {
-		return Err(format!("{:?}", err).into())
-	}
-	let paths = list_files(repo, path_pattern, max_files)?;
-	// Ignore any parsing errors.
-	let olds = if params.ignore_errors {
-		try_parse_files_in_repo(repo, &paths)
-	} else {
-		// TODO use option for repo
-		parse_files_in_repo(repo, &paths)?
-	};
+	/// Skip the pallets that match this regex. Can be given multiple times.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "REGEX"))]
+	pub ignore_pallet: Vec<String>,
 
-	// Parse the new files.
-	if let Err(err) = reset(repo, new, params.should_pull()) {
-		return Err(format!("{:?}", err).into())
-	}
-	let paths = list_files(repo, path_pattern, max_files)?;
-	// Ignore any parsing errors.
-	let news = if params.ignore_errors {
-		try_parse_files_in_repo(repo, &paths)
-	} else {
-		parse_files_in_repo(repo, &paths)?
-	};
-
-	compare_files(olds, news, params, filter)
+	/// TOML file of known regressions that are acknowledged; see [`allowlist`].
+	#[cfg_attr(feature = "clap", clap(long, value_name = "FILE"))]
+	pub allowlist: Option<PathBuf>,
 }
 
-pub fn reset(path: &Path, refname: &str, pull: bool) -> Result<(), String> {
-	if pull {
-		log::info!("Fetching branch {}", refname);
-
-		let output = Command::new("git")
-			.arg("fetch")
-			.arg("origin")
-			.arg(refname)
-			.current_dir(path)
-			.output()
-			.map_err(|e| format!("Failed to fetch branch: {:?}", &e))?;
-		if !output.status.success() {
-			return Err(format!(
-				"Failed to fetch branch: {}",
-				String::from_utf8_lossy(&output.stderr),
-			))
+impl Default for CompareParams {
+	/// Guesses the worst case of the reference time, like the defaults of the command line.
+	fn default() -> Self {
+		Self {
+			method: CompareMethod::GuessWorst,
+			unit: Dimension::Time,
+			ignore_errors: false,
+			git_pull: false,
+			offline: false,
+			cache_dir: None,
+			components: Vec::new(),
+			fee_params: None,
+			db_weights: DbWeights::default(),
+			detect_renames: false,
+			keep_samples: false,
+			git_objects: false,
+			auto_discover: false,
 		}
-	} else {
-		log::debug!("Not fetching branch {} (should_fetch={})", refname, pull);
-	}
-	// try to reset with remote...
-	log::info!("Resetting to origin/{}", refname);
-	let output = Command::new("git")
-		.arg("reset")
-		.arg("--hard")
-		.arg(format!("origin/{}", refname))
-		.current_dir(path)
-		.output();
-	// Ignore any errors and try again without `origin/` prefix.
-	match output {
-		Err(err) => log::info!("Failed to reset to origin/{}: {}", refname, err),
-		Ok(output) =>
-			if !output.status.success() {
-				log::warn!("Failed to reset to: origin/{}", String::from_utf8_lossy(&output.stderr))
-			} else {
-				return Ok(())
-			},
 	}
-	// Try resetting without remote.
-	log::info!("Fallback: Resetting to {}", refname);
-	let output = Command::new("git")
-		.arg("reset")
-		.arg("--hard")
-		.arg(refname)
-		.current_dir(path)
-		.output()
-		.map_err(|e| format!("Failed to reset branch: {:?}", e))?;
+}
 
-	if !output.status.success() {
-		return Err(format!("Failed to reset branch: {}", String::from_utf8_lossy(&output.stderr)))
+impl CompareParams {
+	pub fn should_pull(&self) -> bool {
+		self.git_pull && !self.offline
 	}
-	Ok(())
 }
 
-fn list_files(
-	base_path: &Path,
-	regex: &str,
-	max_files: usize,
-) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
-	let regex = regex.split(',');
-
-	let mut paths = Vec::new();
-	for regex in regex {
-		let regex = format!("{}/{}", base_path.display(), regex);
-		log::info!("Listing files matching: {:?}", &regex);
-		let files = glob::glob(&regex).map_err(|e| format!("Invalid path pattern: {:?}", e))?;
-		let files = files
-			.collect::<Result<Vec<_>, _>>()
-			.map_err(|e| format!("Path pattern error: {:?}", e))?;
-		let files: Vec<_> = files.iter().cloned().filter(|f| !f.ends_with("mod.rs")).collect();
-		paths.extend(files);
-		if paths.len() > max_files {
-			return Err(
-				format!("Found too many files. Found: {}, Max: {}", paths.len(), max_files).into()
-			)
-		}
-	}
-	paths.sort();
-	paths.dedup();
-	Ok(paths)
+/// Split a comma separated path pattern into the included globs and the excluded patterns.
+///
+/// Excluded patterns start with `!`, eg. `runtime/*/src/weights/*.rs,!**/xcm/*`. They are matched
+/// against the path relative to the repository.
+#[cfg(any(feature = "git", feature = "remote"))]
+pub(crate) fn split_path_pattern(pattern: &str) -> Result<(Vec<&str>, Vec<glob::Pattern>), String> {
+	let (exclude, include): (Vec<_>, Vec<_>) =
+		pattern.split(',').map(str::trim).partition(|p| p.starts_with('!'));
+	let exclude = exclude
+		.into_iter()
+		.map(|p| glob::Pattern::new(&p[1..]))
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|e| format!("Invalid path pattern: {:?}", e))?;
+	Ok((include, exclude))
 }
 
-#[derive(serde::Deserialize, clap::ValueEnum, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum CompareMethod {
 	/// The constant base weight of the extrinsic.
@@ -299,6 +628,9 @@ pub enum CompareMethod {
 	GuessWorst,
 	/// Set all components to their exact maximum value.
 	Asymptotic,
+	/// Set all components to the midpoint of their range as a typical case. Guesses if any
+	/// component misses a range annotation.
+	Median,
 }
 
 impl CompareMethod {
@@ -307,6 +639,7 @@ impl CompareMethod {
 			Self::Base | Self::GuessWorst => ComponentInstanceStrategy::guess_min(),
 			Self::ExactWorst => ComponentInstanceStrategy::exact_min(),
 			Self::Asymptotic => ComponentInstanceStrategy::exact_max(),
+			Self::Median => ComponentInstanceStrategy::guess_median(),
 		}
 	}
 
@@ -315,6 +648,7 @@ impl CompareMethod {
 			Self::Base => ComponentInstanceStrategy::guess_min(),
 			Self::GuessWorst => ComponentInstanceStrategy::guess_max(),
 			Self::ExactWorst | Self::Asymptotic => ComponentInstanceStrategy::exact_max(),
+			Self::Median => ComponentInstanceStrategy::guess_median(),
 		}
 	}
 }
@@ -341,12 +675,19 @@ impl ComponentInstanceStrategy {
 	pub const fn guess_max() -> Self {
 		Self { exact: false, min_or_max: MinOrMax::Max }
 	}
+
+	pub const fn guess_median() -> Self {
+		Self { exact: false, min_or_max: MinOrMax::Median }
+	}
 }
 
-#[derive(serde::Deserialize, clap::ValueEnum, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(serde::Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum MinOrMax {
 	Min,
 	Max,
+	/// The midpoint between min and max.
+	Median,
 }
 
 impl core::fmt::Display for MinOrMax {
@@ -354,12 +695,14 @@ impl core::fmt::Display for MinOrMax {
 		match self {
 			MinOrMax::Min => write!(f, "min"),
 			MinOrMax::Max => write!(f, "max"),
+			MinOrMax::Median => write!(f, "median"),
 		}
 	}
 }
 
 // We call this *Unit* for ease of use but it is actually a *dimension* and a unit.
-#[derive(serde::Deserialize, clap::ValueEnum, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 #[serde(rename_all = "kebab-case")]
 pub enum Dimension {
 	/// Reference time. Alias to `weight` for backwards compatibility.
@@ -368,6 +711,19 @@ pub enum Dimension {
 
 	/// Proof-of-validity (PoV) size.
 	Proof,
+
+	/// Reference time and proof size. Each is compared and classified on its own, in two passes
+	/// over the extrinsics.
+	Both,
+
+	/// Estimated transaction fee of the reference time. Needs `--fee-params`.
+	Fee,
+
+	/// Number of storage reads; independent of their weight.
+	Reads,
+
+	/// Number of storage writes; independent of their weight.
+	Writes,
 }
 
 impl std::str::FromStr for CompareMethod {
@@ -379,6 +735,7 @@ impl std::str::FromStr for CompareMethod {
 			"guess-worst" => Ok(CompareMethod::GuessWorst),
 			"exact-worst" => Ok(CompareMethod::ExactWorst),
 			"asymptotic" => Ok(CompareMethod::Asymptotic),
+			"median" => Ok(CompareMethod::Median),
 			_ => Err(format!("Unknown method: {}", s)),
 		}
 	}
@@ -386,11 +743,11 @@ impl std::str::FromStr for CompareMethod {
 
 impl CompareMethod {
 	pub fn all() -> Vec<Self> {
-		vec![Self::Base, Self::GuessWorst, Self::ExactWorst, Self::Asymptotic]
+		vec![Self::Base, Self::GuessWorst, Self::ExactWorst, Self::Asymptotic, Self::Median]
 	}
 
 	pub fn variants() -> Vec<&'static str> {
-		vec!["base", "guess-worst", "exact-worst", "asymptotic"]
+		vec!["base", "guess-worst", "exact-worst", "asymptotic", "median"]
 	}
 
 	pub fn reflect() -> Vec<(Self, &'static str)> {
@@ -405,6 +762,10 @@ impl std::str::FromStr for Dimension {
 		match s {
 			"time" | "weight" => Ok(Self::Time),
 			"proof" => Ok(Self::Proof),
+			"both" => Ok(Self::Both),
+			"fee" => Ok(Self::Fee),
+			"reads" => Ok(Self::Reads),
+			"writes" => Ok(Self::Writes),
 			_ => Err(format!("Unknown method: {}", s)),
 		}
 	}
@@ -414,6 +775,38 @@ impl FilterParams {
 	pub fn included(&self, change: &RelativeChange) -> bool {
 		self.change.as_ref().map_or(true, |s| s.contains(change))
 	}
+
+	/// Whether the change passes the change-type and threshold filters.
+	pub fn relevant(&self, diff: &TermDiff) -> bool {
+		match diff {
+			TermDiff::Failed(_) => true,
+			TermDiff::Warning(ref change, ..) | TermDiff::Changed(ref change) => {
+				if !self.included(&change.change) {
+					return false
+				}
+
+				match change.change {
+					RelativeChange::Changed => self.passes_thresholds(change),
+					RelativeChange::Unchanged if self.threshold >= 0.000001 => false,
+					// Renames are reported even if their weight did not change.
+					_ => true,
+				}
+			},
+		}
+	}
+}
+
+impl FilterParams {
+	fn passes_thresholds(&self, change: &TermChange) -> bool {
+		let rel = change.percent.abs() >= self.threshold;
+		let Some(threshold_abs) = self.threshold_abs else { return rel };
+		let abs = change.abs_change() >= threshold_abs;
+
+		match self.threshold_mode {
+			ThresholdMode::And => rel && abs,
+			ThresholdMode::Or => rel || abs,
+		}
+	}
 }
 
 impl std::str::FromStr for RelativeChange {
@@ -425,6 +818,7 @@ impl std::str::FromStr for RelativeChange {
 			"changed" => Ok(Self::Changed),
 			"added" => Ok(Self::Added),
 			"removed" => Ok(Self::Removed),
+			"renamed" => Ok(Self::Renamed),
 			_ => Err(format!("Unknown change: {}", s)),
 		}
 	}
@@ -432,21 +826,94 @@ impl std::str::FromStr for RelativeChange {
 
 impl RelativeChange {
 	pub fn variants() -> Vec<&'static str> {
-		vec!["unchanged", "changed", "added", "removed"]
+		vec!["unchanged", "changed", "added", "removed", "renamed"]
 	}
 }
 
+/// Scope with the weights of storage reads and writes for the dimension.
+fn storage_scope(unit: Dimension, db: DbWeights) -> SimpleScope {
+	let scope = scope::SimpleScope::empty();
+	if unit == Dimension::Time {
+		scope.with_storage_weights(SimpleTerm::Scalar(db.read), SimpleTerm::Scalar(db.write))
+	} else {
+		scope.with_storage_weights(SimpleTerm::Scalar(0), SimpleTerm::Scalar(0))
+	}
+}
+
+/// Scope with the given component values; all other components are set to their maximum.
+pub fn component_scope(extrinsic: &ChromaticExtrinsic, values: &SimpleScope) -> SimpleScope {
+	let mut scope = SimpleScope::empty();
+	for (name, range) in extrinsic.comp_ranges.iter().flatten() {
+		scope.put_var(name, SimpleTerm::Value(range.max as u128));
+	}
+	scope.merge(values.clone())
+}
+
+/// Evaluate the weight of an extrinsic in one dimension for the given component values.
+///
+/// Storage reads and writes are weighed with `db_weights` unless `components` contains values for
+/// `READ` and `WRITE`.
+pub fn evaluate_extrinsic(
+	extrinsic: &ChromaticExtrinsic,
+	components: &SimpleScope,
+	unit: Dimension,
+	db_weights: DbWeights,
+) -> Result<u128, String> {
+	let term = extrinsic.term.simplify(unit)?;
+	let scope = storage_scope(unit, db_weights).merge(components.clone());
+
+	let free = term.free_vars(&scope);
+	if !free.is_empty() {
+		return Err(format!(
+			"Missing values for components of {}::{}: {}",
+			extrinsic.pallet,
+			extrinsic.name,
+			free.into_iter().collect::<Vec<_>>().join(", ")
+		))
+	}
+	Ok(term.eval(&scope)?)
+}
+
+/// Evaluate the weight of an extrinsic in both dimensions at once; see [`evaluate_extrinsic`].
+///
+/// Values of `READ` and `WRITE` in `components` are reference time.
+pub fn evaluate_weight(
+	extrinsic: &ChromaticExtrinsic,
+	components: &SimpleScope,
+	db_weights: DbWeights,
+) -> Result<Weight, String> {
+	let mut scope = ChromaticScope::empty();
+	scope.put_var(STORAGE_READ_VAR, ChromaticTerm::Value(db_weights.read.into()));
+	scope.put_var(STORAGE_WRITE_VAR, ChromaticTerm::Value(db_weights.write.into()));
+	for (name, value) in components.as_vec() {
+		let value = value.eval(components)?;
+		let value = if name == STORAGE_READ_VAR || name == STORAGE_WRITE_VAR {
+			ChromaticTerm::Value(value.into())
+		} else {
+			ChromaticTerm::Scalar(value)
+		};
+		scope.put_var(&name, value);
+	}
+
+	let free = extrinsic.term.free_vars(&scope);
+	if !free.is_empty() {
+		return Err(format!(
+			"Missing values for components of {}::{}: {}",
+			extrinsic.pallet,
+			extrinsic.name,
+			free.into_iter().collect::<Vec<_>>().join(", ")
+		))
+	}
+	Ok(extrinsic.term.eval(&scope)?)
+}
+
 pub fn compare_extrinsics(
 	mut old: Option<SimpleExtrinsic>,
 	mut new: Option<SimpleExtrinsic>,
 	params: &CompareParams,
 ) -> Result<TermChange, String> {
-	let mut scope = scope::SimpleScope::empty();
-	if params.unit == Dimension::Time {
-		scope = scope
-			.with_storage_weights(SimpleTerm::Scalar(25_000_000), SimpleTerm::Scalar(100_000_000));
-	} else {
-		scope = scope.with_storage_weights(SimpleTerm::Scalar(0), SimpleTerm::Scalar(0));
+	let scope = storage_scope(params.unit, params.db_weights).with_components(&params.components);
+	if params.unit != Dimension::Time {
 		// OMG this code is stupid... but since READ and WRITE done incur proof size cost, we ignore
 		// them.
 		old = old.map(|mut o| {
@@ -467,7 +934,7 @@ pub fn compare_extrinsics(
 		});
 	}
 	let (new, old) = (new.as_ref(), old.as_ref());
-	let scopes = extend_scoped_components(old, new, params.method, &scope)?;
+	let (scopes, approximate) = extend_scoped_components(old, new, params.method, &scope)?;
 	let name = old.map(|o| o.name.clone()).or_else(|| new.map(|n| n.name.clone())).unwrap();
 	let pallet = old.map(|o| o.pallet.clone()).or_else(|| new.map(|n| n.pallet.clone())).unwrap();
 
@@ -503,26 +970,67 @@ pub fn compare_extrinsics(
 		.iter()
 		.all(|r| matches!(r.change, RelativeChange::Added | RelativeChange::Removed));
 
-	if all_added_or_removed {
+	let samples = if params.keep_samples {
+		results
+			.iter()
+			.map(|r| Sample { scope: r.scope.clone(), old_v: r.old_v, new_v: r.new_v })
+			.collect()
+	} else {
+		Vec::new()
+	};
+	let mut result = if all_added_or_removed {
 		// Just pick the first one
-		Ok(results.into_iter().next().unwrap())
+		results.into_iter().next().unwrap()
 	} else if all_increase_or_decrease {
-		Ok(results.into_iter().max_by(|a, b| a.cmp(b)).unwrap())
+		results.into_iter().max_by(|a, b| a.cmp(b)).unwrap()
 	} else {
 		unreachable!(
 			"Inconclusive: all_increase_or_decrease: {}, all_added_or_removed: {}",
 			all_increase_or_decrease, all_added_or_removed
 		);
-	}
+	};
+
+	// The components are instantiated by the method or set by the user.
+	let components = result
+		.scope
+		.as_vec()
+		.into_iter()
+		.map(|(var, _)| var)
+		.filter(|var| scope.get(var).is_none() || params.components.iter().any(|(c, _)| c == var))
+		.collect::<Vec<_>>();
+	result.approximate = approximate;
+	result.samples = samples;
+	result.attribution = match attribute_change(
+		result.old.as_ref(),
+		result.new.as_ref(),
+		&result.scope,
+		&components,
+	) {
+		// The change of an overflowing weight cannot be split.
+		Err(EvalError::Overflow) => Vec::new(),
+		res => res?,
+	};
+	Ok(result)
 }
 
+/// Components whose minimum and maximum are combined in all ways; 2^16 scopes.
+///
+/// Extrinsics with more components that depend on each other are approximated: All components are
+/// at their minimum or maximum and then each component alone is at the other one.
+pub const MAX_COMBINED_COMPONENTS: usize = 16;
+
+/// The scopes in which the worst case of the relative change from `a` to `b` can be and whether
+/// they are an approximation.
+///
+/// Components that the change is monotonic in are set to their minimum or maximum; only the others
+/// need all combinations of both. See [`monotonicity`].
 // TODO handle case that both have (different) ranges.
 pub(crate) fn extend_scoped_components(
 	a: Option<&SimpleExtrinsic>,
 	b: Option<&SimpleExtrinsic>,
 	method: CompareMethod,
 	scope: &SimpleScope,
-) -> Result<Vec<SimpleScope>, String> {
+) -> Result<(Vec<SimpleScope>, bool), String> {
 	let free_a = a.map(|e| e.term.free_vars(scope)).unwrap_or_default();
 	let free_b = b.map(|e| e.term.free_vars(scope)).unwrap_or_default();
 	let frees = free_a.union(&free_b).cloned().collect::<HashSet<_>>();
@@ -532,34 +1040,61 @@ pub(crate) fn extend_scoped_components(
 
 	let (pallet, extrinsic) = a.or(b).map(|e| (e.pallet.clone(), e.name.clone())).unwrap();
 
-	if frees.len() > 16 {
-		return Err(format!(
-			"Too many components to compare: {}::{} has {} components - limit is 16",
+	let mut fixed = scope.clone();
+	let (mut unknown, mut lowest, mut highest) = (Vec::new(), Vec::new(), Vec::new());
+	for free in frees.iter() {
+		let min = instance_component(free, &ra, &rb, method.min(), &pallet, &extrinsic)?;
+		let max = instance_component(free, &ra, &rb, method.max(), &pallet, &extrinsic)?;
+		// With only one version the relative change is the same in all scopes.
+		let monotonicity = match (a, b) {
+			(Some(a), Some(b)) => component_monotonicity(&a.term, &b.term, scope, free),
+			_ => Monotonicity::Decreasing,
+		};
+		match monotonicity {
+			Monotonicity::Increasing => fixed.put_var(free, SimpleTerm::Scalar(max as u128)),
+			Monotonicity::Decreasing => fixed.put_var(free, SimpleTerm::Scalar(min as u128)),
+			Monotonicity::Unknown => {
+				unknown.push(free);
+				lowest.push(min);
+				highest.push(max);
+			},
+		}
+	}
+	let approximate = unknown.len() > MAX_COMBINED_COMPONENTS;
+	if approximate {
+		log::debug!(
+			"Approximating {}::{} with {} components that depend on each other",
 			pallet,
 			extrinsic,
-			frees.len()
-		))
-	}
-	// Combine the maximum and minimum of each component with combinatorics.
-	let (mut lowest, mut highest) = (Vec::new(), Vec::new());
-	for free in frees.iter() {
-		lowest.push(instance_component(free, &ra, &rb, method.min(), &pallet, &extrinsic)?);
-		highest.push(instance_component(free, &ra, &rb, method.max(), &pallet, &extrinsic)?);
+			unknown.len()
+		);
 	}
 
-	// cartesian product of lowest and highest
 	let mut scopes = BTreeSet::new();
-	for i in 0..(1 << frees.len()) {
-		let mut scope = scope.clone();
-		for (c, component) in frees.iter().enumerate() {
-			let value = if i & (1 << c) == 0 { lowest[c] } else { highest[c] };
+	let mut insert = |highs: &dyn Fn(usize) -> bool| {
+		let mut scope = fixed.clone();
+		for (c, component) in unknown.iter().enumerate() {
+			let value = if highs(c) { highest[c] } else { lowest[c] };
 			scope.put_var(component, SimpleTerm::Scalar(value as u128));
 		}
 		if !scope.is_empty() {
 			scopes.insert(scope);
 		}
+	};
+	if approximate {
+		for flipped in 0..unknown.len() {
+			insert(&|c| c == flipped);
+			insert(&|c| c != flipped);
+		}
+		insert(&|_| false);
+		insert(&|_| true);
+	} else {
+		// Combine the maximum and minimum of each other component with combinatorics.
+		for i in 0..(1 << unknown.len()) {
+			insert(&|c| i & (1 << c) != 0);
+		}
 	}
-	Ok(scopes.into_iter().collect())
+	Ok((scopes.into_iter().collect(), approximate))
 }
 
 fn instance_component(
@@ -577,11 +1112,13 @@ fn instance_component(
 		(Some(r), None) | (None, Some(r)) => Ok(match strategy.min_or_max {
 			Min => r.min,
 			Max => r.max,
+			Median => midpoint(r.min, r.max),
 		}),
 		// Both extrinsics have the same range? Good
 		(Some(ra), Some(rb)) if ra == rb => Ok(match strategy.min_or_max {
 			Min => ra.min,
 			Max => ra.max,
+			Median => midpoint(ra.min, ra.max),
 		}),
 		// Both extrinsics have different ranges? Bad, use the min/max.
 		(Some(ra), Some(rb)) => match (strategy.exact, strategy.min_or_max) {
@@ -591,11 +1128,13 @@ fn instance_component(
 			)),
 			(false, Min) => Ok(ra.min.min(rb.min)),
 			(false, Max) => Ok(ra.max.max(rb.max)),
+			(false, Median) => Ok(midpoint(ra.min.min(rb.min), ra.max.max(rb.max))),
 		},
 		// No ranges? Bad, just guess 100.
 		(None, None) => match (strategy.exact, strategy.min_or_max) {
 			(false, Min) => Ok(0),
 			(false, Max) => Ok(100),
+			(false, Median) => Ok(50),
 			(true, _) => Err(format!(
 				"No range for component {} of call {}::{} - use Guess instead!",
 				component, pallet, extrinsic,
@@ -604,14 +1143,24 @@ fn instance_component(
 	}
 }
 
+/// Does not overflow, even if the range is inverted; see [`monotonicity_check`].
+fn midpoint(min: u32, max: u32) -> u32 {
+	min.min(max) + min.abs_diff(max) / 2
+}
+
 pub fn compare_terms(
 	old: Option<&SimpleTerm>,
 	new: Option<&SimpleTerm>,
 	method: CompareMethod,
 	scope: &SimpleScope,
 ) -> Result<TermChange, String> {
-	let old_v = old.map(|t| t.eval(scope)).transpose()?;
-	let new_v = new.map(|t| t.eval(scope)).transpose()?;
+	// An overflowing weight is compared as the maximum; see [`overflow_check`].
+	let eval = |term: &SimpleTerm| match term.eval(scope) {
+		Err(EvalError::Overflow) => Ok(u128::MAX),
+		res => res,
+	};
+	let old_v = old.map(eval).transpose()?;
+	let new_v = new.map(eval).transpose()?;
 	let change =
 		if old == new { RelativeChange::Unchanged } else { RelativeChange::new(old_v, new_v) };
 	let p = percent(old_v.unwrap_or_default(), new_v.unwrap_or_default());
@@ -626,17 +1175,131 @@ pub fn compare_terms(
 		percent: p,
 		method,
 		scope: scope.clone(),
+		attribution: Vec::new(),
+		formula: old.zip(new).map(|(o, n)| diff_formula(o, n)).unwrap_or_default(),
+		approximate: false,
+		samples: Vec::new(),
 	})
 }
 
+/// Split the change from `old` to `new` into the change of the base weight and of the
+/// contribution of each component.
+///
+/// The base is evaluated with all `components` set to zero. The contribution of a component is
+/// the weight with only this component set, minus the base. Whatever remains is caused by
+/// products of components and attributed to [`FormulaPart::Other`]. A missing term counts as zero.
+pub fn attribute_change(
+	old: Option<&SimpleTerm>,
+	new: Option<&SimpleTerm>,
+	scope: &SimpleScope,
+	components: &[String],
+) -> Result<Vec<Attribution>, EvalError> {
+	let zero = components
+		.iter()
+		.fold(scope.clone(), |s, c| s.with_var(c, SimpleTerm::Value(0)));
+	let eval = |term: Option<&SimpleTerm>, scope: &SimpleScope| -> Result<i128, EvalError> {
+		let value = term.map(|t| t.eval(scope)).transpose()?.unwrap_or_default();
+		i128::try_from(value).map_err(|_| EvalError::Overflow)
+	};
+	let delta = |scope: &SimpleScope| {
+		eval(new, scope)?.checked_sub(eval(old, scope)?).ok_or(EvalError::Overflow)
+	};
+
+	let base = delta(&zero)?;
+	let mut res = vec![Attribution { part: FormulaPart::Base, delta: base }];
+	for c in components {
+		let value = scope.get(c).ok_or_else(|| format!("Component {} has no value", c))?;
+		let only_c = zero.with_var(c, value);
+		res.push(Attribution {
+			part: FormulaPart::Component(c.clone()),
+			delta: delta(&only_c)?.checked_sub(base).ok_or(EvalError::Overflow)?,
+		});
+	}
+
+	let attributed = res
+		.iter()
+		.try_fold(0i128, |sum, a| sum.checked_add(a.delta))
+		.ok_or(EvalError::Overflow)?;
+	let other = delta(scope)?.checked_sub(attributed).ok_or(EvalError::Overflow)?;
+	if other != 0 {
+		res.push(Attribution { part: FormulaPart::Other, delta: other });
+	}
+	Ok(res)
+}
+
 pub fn compare_files(
 	olds: Vec<ChromaticExtrinsic>,
 	news: Vec<ChromaticExtrinsic>,
 	params: &CompareParams,
 	filter: &FilterParams,
-) -> Result<TotalDiff, Box<dyn std::error::Error>> {
+) -> Result<TotalDiff, SwcError> {
+	compare_files_with_progress(olds, news, params, filter, &())
+}
+
+/// Same as [`compare_files`] but reports its progress.
+pub fn compare_files_with_progress(
+	olds: Vec<ChromaticExtrinsic>,
+	news: Vec<ChromaticExtrinsic>,
+	params: &CompareParams,
+	filter: &FilterParams,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, SwcError> {
+	if params.unit == Dimension::Fee {
+		let fees = params.fee_params.as_ref().ok_or_else(|| {
+			SwcError::InvalidInput("Comparing fees needs the `--fee-params`".into())
+		})?;
+		let times = compare_files_with_progress(
+			olds,
+			news,
+			&CompareParams { unit: Dimension::Time, ..params.clone() },
+			filter,
+			progress,
+		)?;
+		return Ok(times.into_iter().map(|ext| ext.into_fees(fees)).collect())
+	}
+	// Two passes since the worst case of each dimension can be at different component values.
+	if params.unit == Dimension::Both {
+		let times = compare_files_with_progress(
+			olds.clone(),
+			news.clone(),
+			&CompareParams { unit: Dimension::Time, ..params.clone() },
+			filter,
+			progress,
+		)?;
+		let proofs = compare_files_with_progress(
+			olds,
+			news,
+			&CompareParams { unit: Dimension::Proof, ..params.clone() },
+			filter,
+			progress,
+		)?;
+
+		// Both contain the same extrinsics in the same order since they use the same filters.
+		return Ok(times
+			.into_iter()
+			.zip(proofs)
+			.map(|(mut time, proof)| {
+				debug_assert!(time.name == proof.name && time.file == proof.file);
+				time.proof_change = Some(proof.change);
+				time
+			})
+			.collect())
+	}
 	let ext_regex = filter.extrinsic.as_ref().map(|s| Regex::new(s)).transpose()?;
 	let pallet_regex = filter.pallet.as_ref().map(|s| Regex::new(s)).transpose()?;
+	let ignore_ext_regex = filter
+		.ignore_extrinsic
+		.iter()
+		.map(|s| Regex::new(s))
+		.collect::<Result<Vec<_>, _>>()?;
+	let ignore_pallet_regex = filter
+		.ignore_pallet
+		.iter()
+		.map(|s| Regex::new(s))
+		.collect::<Result<Vec<_>, _>>()?;
+	// Before splitting the dimensions so that all of them detect the same renames.
+	let renames =
+		if params.detect_renames { rename::detect_renames(&olds, &news) } else { Vec::new() };
 	// Split them into their correct dimension.
 	let olds = olds
 		.into_iter()
@@ -648,37 +1311,90 @@ pub fn compare_files(
 		.collect::<Vec<_>>();
 
 	let mut diff = TotalDiff::new();
-	let old_names = olds.iter().cloned().map(|e| (e.pallet, e.name));
-	let new_names = news.iter().cloned().map(|e| (e.pallet, e.name));
-	let names = old_names.chain(new_names).collect::<std::collections::BTreeSet<_>>();
+	// Keyed by borrowed names, so neither the names nor the extrinsics are copied.
+	fn by_name(exts: &[SimpleExtrinsic]) -> HashMap<(&str, &str), &SimpleExtrinsic> {
+		let mut map = HashMap::new();
+		for e in exts {
+			map.entry((e.pallet.as_str(), e.name.as_str())).or_insert(e);
+		}
+		map
+	}
+	let (old_by_name, new_by_name) = (by_name(&olds), by_name(&news));
+	let names = old_by_name
+		.keys()
+		.chain(new_by_name.keys())
+		.copied()
+		// Compared under their new name.
+		.filter(|(p, n)| !renames.iter().any(|r| r.pallet == *p && r.old == *n))
+		.collect::<std::collections::BTreeSet<_>>();
 	log::trace!("Comparing {} terms", olds.len());
 
-	for (pallet, extrinsic) in names {
-		if !pallet_regex.as_ref().map_or(true, |r| r.is_match(&pallet).unwrap_or_default()) {
+	for (i, &(pallet, extrinsic)) in names.iter().enumerate() {
+		if progress.cancelled() {
+			return Err(SwcError::Cancelled(progress::Stage::new("Comparing", i, names.len())))
+		}
+		progress.extrinsic_compared(i + 1, names.len());
+		if !pallet_regex.as_ref().map_or(true, |r| r.is_match(pallet).unwrap_or_default()) {
 			// TODO add "skipped" or "ignored" result type.
 			continue
 		}
-		if !ext_regex.as_ref().map_or(true, |r| r.is_match(&extrinsic).unwrap_or_default()) {
+		if !ext_regex.as_ref().map_or(true, |r| r.is_match(extrinsic).unwrap_or_default()) {
+			continue
+		}
+		if ignore_pallet_regex.iter().any(|r| r.is_match(pallet).unwrap_or_default()) ||
+			ignore_ext_regex.iter().any(|r| r.is_match(extrinsic).unwrap_or_default())
+		{
 			continue
 		}
 
-		let new = news.iter().find(|&n| n.name == extrinsic && n.pallet == pallet);
-		let old = olds.iter().find(|&n| n.name == extrinsic && n.pallet == pallet);
+		let renamed_from = renames
+			.iter()
+			.find(|r| r.pallet == pallet && r.new == extrinsic)
+			.map(|r| r.old.clone());
+		let old_name = renamed_from.as_ref().map_or(extrinsic, |r| r.as_str());
+		let new = new_by_name.get(&(pallet, extrinsic)).copied();
+		let old = old_by_name.get(&(pallet, old_name)).copied();
 		log::trace!("Comparing {}::{}", pallet, extrinsic);
+		// Before evaluating, since the cases of an inverted range are not meaningful.
+		let inverted = [new, old].into_iter().flatten().find_map(|e| {
+			range_check(e).err().map(|err| format!("{}: {}::{}", err, e.pallet, e.name))
+		});
 
-		let change = match compare_extrinsics(old.cloned(), new.cloned(), params) {
+		let compared = compare_extrinsics(old.cloned(), new.cloned(), params).map(|mut change| {
+			if renamed_from.is_some() {
+				change.change = RelativeChange::Renamed;
+			}
+			change
+		});
+		let change = match compared {
 			Err(err) => {
-				log::warn!("Parsing failed {}: {:?}", &pallet, err);
+				log::warn!("Parsing failed {}: {:?}", pallet, err);
 				TermDiff::Failed(err)
 			},
 			Ok(change) =>
 				if let Some(ext) = new.or(old) {
-					if let Err(err) = sanity_check_term(&ext.term)
-						.map_err(|e| format!("{}: {}::{}", e, ext.pallet, ext.name))
-					{
-						TermDiff::Warning(change, err)
-					} else {
+					let mut warnings = match inverted {
+						Some(inverted) => vec![inverted],
+						None => sanity_check_term(&ext.term)
+							.and_then(|_| monotonicity_check(ext))
+							.err()
+							.map(|e| format!("{}: {}::{}", e, ext.pallet, ext.name))
+							.into_iter()
+							.collect::<Vec<_>>(),
+					};
+					warnings.extend(overflow_check(&change));
+					if change.approximate {
+						warnings.push(format!(
+							"Approximate worst case: more than {} components depend on each other",
+							MAX_COMBINED_COMPONENTS
+						));
+					}
+					warnings.extend(range_changes(old, new));
+
+					if warnings.is_empty() {
 						TermDiff::Changed(change)
+					} else {
+						TermDiff::Warning(change, warnings.join("; "))
 					}
 				} else {
 					unreachable!(
@@ -687,20 +1403,83 @@ pub fn compare_files(
 				},
 		};
 
-		diff.push(ExtrinsicDiff { name: extrinsic.clone(), file: pallet.clone(), change });
+		diff.push(ExtrinsicDiff {
+			name: extrinsic.into(),
+			file: pallet.into(),
+			change,
+			proof_change: None,
+			acknowledged: None,
+			span: new.or(old).and_then(|e| e.span),
+			renamed_from,
+			pallet_id: [new, old].into_iter().flatten().find_map(|e| e.pallet_id.clone()),
+		});
 	}
 
 	Ok(diff)
 }
 
+/// Relative change of a hardware metric between two `benchmark machine` runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MachineDiff {
+	pub metric: String,
+	/// Old throughput in MiB/s.
+	pub old: Option<f64>,
+	/// New throughput in MiB/s.
+	pub new: Option<f64>,
+
+	pub percent: Percent,
+	pub change: RelativeChange,
+}
+
+/// Compares the hardware scores of two machine benchmark runs by metric name.
+///
+/// Note that a higher score is better - in contrast to weights. An old score of zero has no
+/// relative change, so its metric counts as added.
+pub fn compare_machines(olds: Vec<MachineScore>, news: Vec<MachineScore>) -> Vec<MachineDiff> {
+	let metrics = olds
+		.iter()
+		.chain(news.iter())
+		.map(|m| m.metric.clone())
+		.collect::<BTreeSet<_>>();
+
+	metrics
+		.into_iter()
+		.map(|metric| {
+			let old = olds.iter().find(|m| m.metric == metric).map(|m| m.score);
+			let new = news.iter().find(|m| m.metric == metric).map(|m| m.score);
+			let change = match (old, new) {
+				(Some(o), Some(n)) if o == n => RelativeChange::Unchanged,
+				(Some(o), Some(_)) if o <= 0.0 => RelativeChange::Added,
+				(Some(_), Some(_)) => RelativeChange::Changed,
+				(None, Some(_)) => RelativeChange::Added,
+				(Some(_), None) => RelativeChange::Removed,
+				(None, None) => unreachable!("Metric must be in either old or new"),
+			};
+			let percent = match (old, new) {
+				(Some(o), Some(n)) if o > 0.0 => 100.0 * (n / o) - 100.0,
+				_ => 0.0,
+			};
+
+			MachineDiff { metric, old, new, percent, change }
+		})
+		.collect()
+}
+
 /// Checks some obvious stuff:
 /// - Does not have more than 1000 reads or writes
+///
+/// See [`lint`] for more checks.
 pub fn sanity_check_term(term: &SimpleTerm) -> Result<(), String> {
+	sanity_check_accesses(term, 1000)
+}
+
+/// Checks that the term does not have more than `max` reads or writes.
+pub fn sanity_check_accesses(term: &SimpleTerm, max: u128) -> Result<(), String> {
 	let reads = term.find_largest_factor("READ").unwrap_or_default();
 	let writes = term.find_largest_factor("WRITE").unwrap_or_default();
 	let larger = reads.max(writes);
 
-	if larger > 1000 {
+	if larger > max {
 		if reads > writes {
 			Err(format!("Call has {} READs", reads))
 		} else {
@@ -711,8 +1490,345 @@ pub fn sanity_check_term(term: &SimpleTerm) -> Result<(), String> {
 	}
 }
 
+/// The components whose range differs between the old and new version.
+///
+/// A changed range changes the worst case even if the formula stays the same.
+pub fn range_changes(old: Option<&SimpleExtrinsic>, new: Option<&SimpleExtrinsic>) -> Vec<String> {
+	let (Some(old), Some(new)) =
+		(old.and_then(|e| e.comp_ranges.as_ref()), new.and_then(|e| e.comp_ranges.as_ref()))
+	else {
+		return Vec::new()
+	};
+	let mut names = old.keys().filter(|n| new.contains_key(*n)).collect::<Vec<_>>();
+	names.sort();
+
+	names
+		.into_iter()
+		.filter(|n| old[*n] != new[*n])
+		.map(|n| {
+			format!(
+				"range of `{}` changed {}..{} → {}..{}",
+				n, old[n].min, old[n].max, new[n].min, new[n].max
+			)
+		})
+		.collect()
+}
+
+/// Checks that no component range has a minimum above its maximum.
+pub fn range_check<T>(ext: &parse::pallet::GenericExtrinsic<T>) -> Result<(), String> {
+	let ranges = ext.comp_ranges.iter().flatten().collect::<BTreeMap<_, _>>();
+	match ranges.into_iter().find(|(_, r)| r.min > r.max) {
+		Some((name, r)) =>
+			Err(format!("Component {} has inverted range {}..{}", name, r.min, r.max)),
+		None => Ok(()),
+	}
+}
+
+/// Checks that the weight does not decrease or overflow with increasing components.
+///
+/// Each component is increased from its minimum to its maximum while the others stay at their
+/// minimum. Components without a range and the storage accesses are set to one.
+pub fn monotonicity_check(ext: &SimpleExtrinsic) -> Result<(), String> {
+	range_check(ext)?;
+	let ranges = ext.comp_ranges.clone().unwrap_or_default();
+	let mut names = ranges.keys().collect::<Vec<_>>();
+	names.sort();
+
+	let mut min = SimpleScope::empty();
+	for var in ext.term.free_vars(&SimpleScope::empty()) {
+		let value = ranges.get(&var).map_or(1, |r| r.min as u128);
+		min.put_var(&var, SimpleTerm::Value(value));
+	}
+	let at_min = ext.term.eval(&min)?;
+	for name in names {
+		let max = min.with_var(name, SimpleTerm::Value(ranges[name].max as u128));
+		let at_max = ext
+			.term
+			.eval(&max)
+			.map_err(|e| format!("Weight overflows at the maximum of {}: {}", name, e))?;
+		if at_max < at_min {
+			return Err(format!("Weight decreases with increasing {}", name))
+		}
+	}
+	Ok(())
+}
+
+/// Warns if the old or new weight exceeds 128 bits in the compared scope.
+///
+/// Such a weight is compared as [`u128::MAX`] instead of failing the comparison.
+pub fn overflow_check(change: &TermChange) -> Option<String> {
+	let overflows = [&change.old, &change.new]
+		.into_iter()
+		.flatten()
+		.any(|t| t.eval(&change.scope) == Err(EvalError::Overflow));
+	overflows.then(|| "Weight exceeds 128 bits and is compared as the maximum".into())
+}
+
 pub fn sort_changes(diff: &mut TotalDiff) {
-	diff.sort_by(|a, b| a.change.cmp(&b.change));
+	diff.sort_by(|a, b| a.max_change().cmp(b.max_change()));
+}
+
+/// The changes of all extrinsics of one pallet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PalletSummary {
+	pub file: String,
+	/// The pallet of the file; if its weight file names it.
+	pub pallet_id: Option<parse::pallet::PalletId>,
+
+	pub changed: usize,
+	pub unchanged: usize,
+	pub added: usize,
+	pub removed: usize,
+	pub errors: usize,
+
+	/// Sum of the old weights of all extrinsics.
+	pub old: u128,
+	/// Sum of the new weights of all extrinsics.
+	pub new: u128,
+	/// Sum of the absolute changes of all extrinsics.
+	pub abs_change: u128,
+}
+
+impl PalletSummary {
+	/// Relative change of the summed weights.
+	pub fn percent(&self) -> Percent {
+		percent(self.old, self.new)
+	}
+}
+
+/// Aggregate the changes per pallet; the largest absolute change comes first.
+///
+/// The sums saturate at [`u128::MAX`], like an overflowing weight; see [`compare_terms`]. Uses the
+/// time with [`Dimension::Both`].
+pub fn summarize_pallets(diff: &TotalDiff) -> Vec<PalletSummary> {
+	let mut pallets = BTreeMap::<&str, PalletSummary>::new();
+	for ext in diff.iter() {
+		let sum = pallets
+			.entry(&ext.file)
+			.or_insert_with(|| PalletSummary { file: ext.file.clone(), ..Default::default() });
+		if sum.pallet_id.is_none() {
+			sum.pallet_id = ext.pallet_id.clone();
+		}
+		let Some(change) = ext.term() else {
+			sum.errors += 1;
+			continue
+		};
+
+		match change.change {
+			RelativeChange::Changed | RelativeChange::Renamed => sum.changed += 1,
+			RelativeChange::Unchanged => sum.unchanged += 1,
+			RelativeChange::Added => sum.added += 1,
+			RelativeChange::Removed => sum.removed += 1,
+		}
+		sum.old = sum.old.saturating_add(change.old_v.unwrap_or_default());
+		sum.new = sum.new.saturating_add(change.new_v.unwrap_or_default());
+		sum.abs_change = sum.abs_change.saturating_add(change.abs_change());
+	}
+
+	let mut pallets = pallets.into_values().collect::<Vec<_>>();
+	pallets.sort_by_key(|p| std::cmp::Reverse(p.abs_change));
+	pallets
+}
+
+/// The pallet summaries of multiple runtimes; one row per pallet and one column per runtime.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeMatrix {
+	/// Folders of the runtimes; see [`RuntimeSummary::runtime`].
+	pub runtimes: Vec<String>,
+	/// Name of each pallet and its summary per runtime. `None` if a runtime lacks the pallet.
+	pub pallets: Vec<(String, Vec<Option<PalletSummary>>)>,
+}
+
+/// Aggregate the changes per pallet and runtime. Pallets of different runtimes are matched by their
+/// [`PalletId`](parse::pallet::PalletId), or else by the name of their weight file, and sorted
+/// alphabetically.
+///
+/// Uses the time with [`Dimension::Both`].
+pub fn summarize_matrix(diff: &TotalDiff) -> RuntimeMatrix {
+	let mut cells = BTreeMap::<String, BTreeMap<String, PalletSummary>>::new();
+	for pallet in summarize_pallets(diff) {
+		let name = match &pallet.pallet_id {
+			Some(id) => id.to_string(),
+			None => Path::new(&pallet.file)
+				.file_name()
+				.map_or_else(|| pallet.file.clone(), |n| n.to_string_lossy().into_owned()),
+		};
+		cells.entry(name).or_default().insert(runtime_of(&pallet.file), pallet);
+	}
+	let runtimes = cells
+		.values()
+		.flat_map(|r| r.keys().cloned())
+		.collect::<std::collections::BTreeSet<_>>()
+		.into_iter()
+		.collect::<Vec<_>>();
+	let pallets = cells
+		.into_iter()
+		.map(|(name, mut row)| (name, runtimes.iter().map(|r| row.remove(r)).collect()))
+		.collect();
+
+	RuntimeMatrix { runtimes, pallets }
+}
+
+/// The summed weights of all extrinsics of one runtime in one dimension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeSummary {
+	/// Folder of the runtime that contains the weight files; eg. `runtime/polkadot`.
+	pub runtime: String,
+	pub unit: Dimension,
+	/// Number of extrinsics that were summed up.
+	pub extrinsics: usize,
+	pub old: u128,
+	pub new: u128,
+}
+
+impl RuntimeSummary {
+	/// Relative change of the summed weights.
+	pub fn percent(&self) -> Percent {
+		percent(self.old, self.new)
+	}
+}
+
+/// Sum up the old and new weights of all extrinsics per runtime and dimension.
+///
+/// Extrinsics that failed to compare are left out and the sums saturate at [`u128::MAX`]. The
+/// runtime of a weight file is its folder without a trailing `src/weights`.
+pub fn summarize_runtimes(diff: &TotalDiff, unit: Dimension) -> Vec<RuntimeSummary> {
+	let mut runtimes = BTreeMap::<(String, usize), RuntimeSummary>::new();
+	for (i, dim) in unit.split().into_iter().enumerate() {
+		for ext in diff.iter() {
+			let change = if i == 0 { ext.term() } else { ext.proof_term() };
+			let Some(change) = change else { continue };
+			let runtime = runtime_of(&ext.file);
+
+			let sum = runtimes.entry((runtime.clone(), i)).or_insert_with(|| RuntimeSummary {
+				runtime,
+				unit: dim,
+				extrinsics: 0,
+				old: 0,
+				new: 0,
+			});
+			sum.extrinsics += 1;
+			sum.old = sum.old.saturating_add(change.old_v.unwrap_or_default());
+			sum.new = sum.new.saturating_add(change.new_v.unwrap_or_default());
+		}
+	}
+	runtimes.into_values().collect()
+}
+
+fn runtime_of(file: &str) -> String {
+	let mut dir = Path::new(file).parent().unwrap_or_else(|| Path::new(""));
+	for suffix in ["weights", "src"] {
+		if dir.ends_with(suffix) {
+			dir = dir.parent().unwrap_or(dir);
+		}
+	}
+	match dir.to_string_lossy() {
+		d if d.is_empty() => ".".into(),
+		d => d.into(),
+	}
+}
+
+/// Bounds of the buckets of [`DiffStats::buckets`] in percent.
+pub const STATS_BUCKETS: [Percent; 10] =
+	[-50.0, -25.0, -10.0, -5.0, -1.0, 1.0, 5.0, 10.0, 25.0, 50.0];
+
+/// The distribution of the relative changes of a diff.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct DiffStats {
+	/// Extrinsics with a relative change; unchanged ones count as zero.
+	pub changed: usize,
+	pub added: usize,
+	pub removed: usize,
+	pub errors: usize,
+
+	/// Number of changes below each bound of [`STATS_BUCKETS`] and above the last one.
+	pub buckets: Vec<usize>,
+	pub median: Option<Percent>,
+	pub p95: Option<Percent>,
+	/// The extrinsic with the largest increase as `(file, extrinsic, percent)`.
+	pub max_increase: Option<(String, ExtrinsicName, Percent)>,
+	pub max_decrease: Option<(String, ExtrinsicName, Percent)>,
+}
+
+impl DiffStats {
+	/// A label like `-5 .. -1` for each bucket.
+	pub fn bucket_labels() -> Vec<String> {
+		let mut labels = vec![format!("< {}", STATS_BUCKETS[0])];
+		labels.extend(STATS_BUCKETS.windows(2).map(|w| format!("{} .. {}", w[0], w[1])));
+		labels.push(format!(">= {}", STATS_BUCKETS[STATS_BUCKETS.len() - 1]));
+		labels
+	}
+}
+
+/// Statistics of the relative changes of all extrinsics.
+///
+/// Uses the time with [`Dimension::Both`]. The percentiles are nearest-rank.
+pub fn diff_stats(diff: &TotalDiff) -> DiffStats {
+	let mut stats = DiffStats { buckets: vec![0; STATS_BUCKETS.len() + 1], ..Default::default() };
+	let mut changes = Vec::new();
+	for ext in diff.iter() {
+		let Some(change) = ext.term() else {
+			stats.errors += 1;
+			continue
+		};
+		let percent = match change.change {
+			RelativeChange::Added => {
+				stats.added += 1;
+				continue
+			},
+			RelativeChange::Removed => {
+				stats.removed += 1;
+				continue
+			},
+			RelativeChange::Unchanged => 0.0,
+			// Zero to zero.
+			RelativeChange::Changed | RelativeChange::Renamed if change.percent.is_nan() => 0.0,
+			RelativeChange::Changed | RelativeChange::Renamed => change.percent,
+		};
+		stats.buckets[STATS_BUCKETS.iter().take_while(|b| percent >= **b).count()] += 1;
+		changes.push((percent, ext));
+	}
+	changes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+	stats.changed = changes.len();
+	// The nearest rank `ceil(p * len / 100)` as zero-based index.
+	let rank = |p: usize| changes[(p * changes.len()).saturating_sub(1) / 100].0;
+	if !changes.is_empty() {
+		stats.median = Some(rank(50));
+		stats.p95 = Some(rank(95));
+	}
+	let entry = |(p, ext): &(Percent, &ExtrinsicDiff)| (ext.file.clone(), ext.name.clone(), *p);
+	stats.max_increase = changes.last().filter(|c| c.0 > 0.0).map(entry);
+	stats.max_decrease = changes.first().filter(|c| c.0 < 0.0).map(entry);
+	stats
+}
+
+/// How the changes are ordered in the output.
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum SortBy {
+	/// Largest relative change first.
+	#[default]
+	Percent,
+	/// Largest absolute change first. Uses the time with [`Dimension::Both`].
+	Abs,
+	/// Alphabetically by extrinsic.
+	Name,
+	/// Alphabetically by file and then extrinsic.
+	Pallet,
+}
+
+/// Sort the changes; errors are put last when sorting by change.
+pub fn sort_changes_by(diff: &mut TotalDiff, by: SortBy) {
+	match by {
+		SortBy::Percent => {
+			sort_changes(diff);
+			diff.reverse();
+		},
+		SortBy::Abs => diff.sort_by_key(|d| std::cmp::Reverse(d.term().map(|t| t.abs_change()))),
+		SortBy::Name => diff.sort_by(|a, b| (&a.name, &a.file).cmp(&(&b.name, &b.file))),
+		SortBy::Pallet => diff.sort_by(|a, b| (&a.file, &a.name).cmp(&(&b.file, &b.name))),
+	}
 }
 
 impl TermDiff {
@@ -749,19 +1865,10 @@ impl TermChange {
 pub fn filter_changes(diff: TotalDiff, params: &FilterParams) -> TotalDiff {
 	// Note: the pallet and extrinsic are already filtered in compare_files.
 	diff.iter()
-		.filter(|extrinsic| match extrinsic.change {
-			TermDiff::Failed(_) => true,
-			TermDiff::Warning(ref change, ..) | TermDiff::Changed(ref change) => {
-				if !params.included(&change.change) {
-					return false
-				}
-
-				match change.change {
-					RelativeChange::Changed if change.percent.abs() < params.threshold => false,
-					RelativeChange::Unchanged if params.threshold >= 0.000001 => false,
-					_ => true,
-				}
-			},
+		.filter(|extrinsic| {
+			// Any dimension being relevant makes the extrinsic relevant.
+			params.relevant(&extrinsic.change) ||
+				extrinsic.proof_change.as_ref().map_or(false, |c| params.relevant(c))
 		})
 		.cloned()
 		.collect()
@@ -783,11 +1890,70 @@ pub fn percent(old: u128, new: u128) -> Percent {
 	100.0 * (new as f64 / old as f64) - 100.0
 }
 
+/// A [`Percent`] is infinite or NaN if the old value is zero; JSON numbers cannot express that.
+mod serde_percent {
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(p: &super::Percent, s: S) -> Result<S::Ok, S::Error> {
+		if p.is_finite() {
+			s.serialize_f64(*p)
+		} else {
+			s.serialize_str(&p.to_string())
+		}
+	}
+
+	/// A number or a string like `inf`.
+	#[cfg(feature = "schema")]
+	pub fn schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+		use schemars::schema::{InstanceType, SchemaObject};
+
+		SchemaObject {
+			instance_type: Some(vec![InstanceType::Number, InstanceType::String].into()),
+			..Default::default()
+		}
+		.into()
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<super::Percent, D::Error> {
+		#[derive(Deserialize)]
+		#[serde(untagged)]
+		enum Repr {
+			Number(f64),
+			Text(String),
+		}
+
+		match Repr::deserialize(d)? {
+			Repr::Number(p) => Ok(p),
+			Repr::Text(p) => p.parse().map_err(serde::de::Error::custom),
+		}
+	}
+}
+
 impl Dimension {
 	pub fn fmt_value(&self, v: u128) -> String {
 		match self {
 			Self::Time => Self::fmt_time(v),
 			Self::Proof => Self::fmt_proof(v),
+			Self::Both => Self::fmt_scalar(v),
+			Self::Fee => Self::fmt_fee(v),
+			Self::Reads | Self::Writes => v.to_string(),
+		}
+	}
+
+	/// The storage variable whose accesses this dimension counts.
+	pub fn storage_var(&self) -> Option<&'static str> {
+		match self {
+			Self::Reads => Some(STORAGE_READ_VAR),
+			Self::Writes => Some(STORAGE_WRITE_VAR),
+			_ => None,
+		}
+	}
+
+	/// The single dimensions that this dimension consists of.
+	pub fn split(&self) -> Vec<Self> {
+		match self {
+			Self::Both => vec![Self::Time, Self::Proof],
+			d => vec![*d],
 		}
 	}
 
@@ -820,6 +1986,18 @@ impl Dimension {
 		}
 	}
 
+	/// Formats pico units of a token; see [`fee::PICO_PER_TOKEN`].
+	pub fn fmt_fee(f: u128) -> String {
+		let tokens = f as f64 / fee::PICO_PER_TOKEN as f64;
+		if tokens >= 1.0 {
+			format!("{:.2}", tokens)
+		} else if tokens >= 0.0001 {
+			format!("{:.4}", tokens)
+		} else {
+			format!("{:.8}", tokens)
+		}
+	}
+
 	pub fn fmt_proof(b: u128) -> String {
 		const BYTE_PER_KIB: u128 = 1024;
 		const BYTE_PER_MIB: u128 = BYTE_PER_KIB * 1024;
@@ -837,11 +2015,11 @@ impl Dimension {
 	}
 
 	pub fn all() -> Vec<Self> {
-		vec![Self::Time, Self::Proof]
+		vec![Self::Time, Self::Proof, Self::Both, Self::Fee, Self::Reads, Self::Writes]
 	}
 
 	pub fn variants() -> Vec<&'static str> {
-		vec!["time", "proof"]
+		vec!["time", "proof", "both", "fee", "reads", "writes"]
 	}
 
 	pub fn reflect() -> Vec<(Self, &'static str)> {
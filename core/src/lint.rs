@@ -0,0 +1,149 @@
+//! Sanity checks of single weight files that do not need a second version to compare against.
+
+use crate::{
+	component_scope, evaluate_extrinsic,
+	parse::pallet::ChromaticExtrinsic,
+	sanity_check_accesses,
+	scope::{ChromaticScope, SimpleScope, STORAGE_READ_VAR, STORAGE_WRITE_VAR},
+	Dimension,
+};
+
+/// A check that [`lint_extrinsic`] can run.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum LintCheck {
+	/// More storage reads or writes than `--max-accesses`.
+	ExcessiveAccesses,
+	/// The worst case proof size is larger than `--max-proof`.
+	ProofLimit,
+	/// The reference time is zero when all components are zero.
+	ZeroBase,
+	/// A component has a range but is not used by the weight.
+	UnusedComponents,
+	/// A component is used by the weight but has no range.
+	MissingRanges,
+}
+
+impl std::fmt::Display for LintCheck {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::ExcessiveAccesses => write!(f, "excessive-accesses"),
+			Self::ProofLimit => write!(f, "proof-limit"),
+			Self::ZeroBase => write!(f, "zero-base"),
+			Self::UnusedComponents => write!(f, "unused-components"),
+			Self::MissingRanges => write!(f, "missing-ranges"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct LintParams {
+	/// The checks to run. All by default.
+	#[cfg_attr(
+		feature = "clap",
+		clap(long, value_name = "CHECK", value_delimiter = ',', ignore_case = true)
+	)]
+	pub checks: Vec<LintCheck>,
+
+	/// Maximal number of storage reads and writes of an extrinsic.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "COUNT", default_value = "1000"))]
+	pub max_accesses: u128,
+
+	/// Proof size limit of a block.
+	#[cfg_attr(feature = "clap", clap(long, value_name = "VALUE", default_value = "5MiB", value_parser = crate::parse_abs_value))]
+	pub max_proof: u128,
+}
+
+impl Default for LintParams {
+	fn default() -> Self {
+		Self { checks: Vec::new(), max_accesses: 1000, max_proof: 5 * 1024 * 1024 }
+	}
+}
+
+impl LintParams {
+	fn enabled(&self, check: LintCheck) -> bool {
+		self.checks.is_empty() || self.checks.contains(&check)
+	}
+}
+
+/// A failed check of an extrinsic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+	pub pallet: String,
+	pub extrinsic: String,
+	pub check: LintCheck,
+	pub message: String,
+}
+
+/// Run the enabled checks on an extrinsic.
+pub fn lint_extrinsic(ext: &ChromaticExtrinsic, params: &LintParams) -> Vec<LintFinding> {
+	let finding = |check, message| LintFinding {
+		pallet: ext.pallet.to_string(),
+		extrinsic: ext.name.to_string(),
+		check,
+		message,
+	};
+	let mut findings = Vec::new();
+	let time = ext.term.simplify(Dimension::Time);
+
+	if params.enabled(LintCheck::ExcessiveAccesses) {
+		let accesses = time.as_ref().ok().map(|t| sanity_check_accesses(t, params.max_accesses));
+		if let Some(Err(err)) = accesses {
+			findings.push(finding(LintCheck::ExcessiveAccesses, err));
+		}
+	}
+	if params.enabled(LintCheck::ProofLimit) {
+		let scope = component_scope(ext, &SimpleScope::empty());
+		// Missing ranges are reported by their own check.
+		// The proof size does not depend on the time of storage accesses.
+		if let Ok(proof) = evaluate_extrinsic(ext, &scope, Dimension::Proof, Default::default()) {
+			if proof > params.max_proof {
+				findings.push(finding(
+					LintCheck::ProofLimit,
+					format!(
+						"Worst case proof size {} exceeds {}",
+						Dimension::fmt_proof(proof),
+						Dimension::fmt_proof(params.max_proof)
+					),
+				));
+			}
+		}
+	}
+	if params.enabled(LintCheck::ZeroBase) {
+		let has_base = time.as_ref().map_or(true, |t| {
+			t.monomials()
+				.iter()
+				.any(|(vars, factor)| *factor > 0 && vars.iter().all(|v| is_storage_var(v)))
+		});
+		if !has_base {
+			findings.push(finding(LintCheck::ZeroBase, "Base weight is zero".into()));
+		}
+	}
+
+	let vars = ext.term.free_vars(&ChromaticScope::empty());
+	let ranges = ext.comp_ranges.clone().unwrap_or_default();
+	if params.enabled(LintCheck::UnusedComponents) {
+		let mut unused = ranges.keys().filter(|c| !vars.contains(*c)).collect::<Vec<_>>();
+		unused.sort();
+		for c in unused {
+			findings
+				.push(finding(LintCheck::UnusedComponents, format!("Component {} is unused", c)));
+		}
+	}
+	if params.enabled(LintCheck::MissingRanges) {
+		for c in vars.iter().filter(|v| !is_storage_var(v) && !ranges.contains_key(*v)) {
+			findings
+				.push(finding(LintCheck::MissingRanges, format!("Component {} has no range", c)));
+		}
+	}
+
+	findings
+}
+
+fn is_storage_var(var: &str) -> bool {
+	var == STORAGE_READ_VAR || var == STORAGE_WRITE_VAR
+}
@@ -0,0 +1,119 @@
+//! Find the components whose worst case does not depend on the other components.
+//!
+//! The relative change of two weights is a ratio `new / old`. If both are polynomials that are
+//! linear in a component `c`, that is `new = n0 + n1·c` and `old = o0 + o1·c`, the derivative of
+//! the ratio by `c` has the sign of `n1·o0 - n0·o1`. This is a polynomial of the other components;
+//! if all its coefficients have the same sign, the ratio is monotonic in `c` wherever the other
+//! components are. The worst case then needs only one value of `c` instead of its minimum and
+//! maximum, which avoids evaluating `2^n` scopes for `n` components.
+
+use std::collections::BTreeMap;
+
+use crate::{scope::SimpleScope, term::SimpleTerm};
+
+/// How the relative change of two weights behaves when a component increases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monotonicity {
+	/// Never decreases; the maximum of the component is the worst case.
+	Increasing,
+	/// Never increases; the minimum of the component is the worst case.
+	Decreasing,
+	/// Depends on the other components or the weights are no polynomials.
+	Unknown,
+}
+
+/// Maps the sorted variables of each product to its factor.
+type Polynomial = BTreeMap<Vec<String>, i128>;
+
+/// How the relative change from `old` to `new` behaves when `component` increases.
+///
+/// The variables of the `scope` are constants, eg. the weight of a storage read.
+pub fn component_monotonicity(
+	old: &SimpleTerm,
+	new: &SimpleTerm,
+	scope: &SimpleScope,
+	component: &str,
+) -> Monotonicity {
+	let slope = || {
+		let (o0, o1) = split(&expand(old, scope)?, component)?;
+		let (n0, n1) = split(&expand(new, scope)?, component)?;
+		sub(&mul(&n1, &o0)?, &mul(&n0, &o1)?)
+	};
+	let Some(slope) = slope() else { return Monotonicity::Unknown };
+
+	if slope.values().all(|f| *f >= 0) {
+		Monotonicity::Increasing
+	} else if slope.values().all(|f| *f <= 0) {
+		Monotonicity::Decreasing
+	} else {
+		Monotonicity::Unknown
+	}
+}
+
+/// Expand a term into a polynomial; `None` if it is none or its factors overflow.
+fn expand(term: &SimpleTerm, scope: &SimpleScope) -> Option<Polynomial> {
+	match term {
+		SimpleTerm::Value(x) | SimpleTerm::Scalar(x) => constant(i128::try_from(*x).ok()?),
+		SimpleTerm::Var(var) => match scope.get(var) {
+			Some(value) => expand(&value, scope),
+			None => Some([(vec![var.to_string()], 1)].into()),
+		},
+		SimpleTerm::Add(x, y) => add(&expand(x, scope)?, &expand(y, scope)?),
+		SimpleTerm::Mul(x, y) => mul(&expand(x, scope)?, &expand(y, scope)?),
+		SimpleTerm::Sub(..) | SimpleTerm::Min(..) | SimpleTerm::Max(..) | SimpleTerm::Branch(_) =>
+			None,
+	}
+}
+
+/// Split `p` into `p0 + p1·var`; `None` if `var` has a higher power.
+fn split(p: &Polynomial, var: &str) -> Option<(Polynomial, Polynomial)> {
+	let (mut p0, mut p1) = (Polynomial::new(), Polynomial::new());
+	for (vars, factor) in p {
+		let mut rest = vars.clone();
+		match rest.iter().position(|v| v == var) {
+			None => p0.insert(rest, *factor),
+			Some(i) => {
+				rest.remove(i);
+				if rest.iter().any(|v| v == var) {
+					return None
+				}
+				p1.insert(rest, *factor)
+			},
+		};
+	}
+	Some((p0, p1))
+}
+
+fn constant(x: i128) -> Option<Polynomial> {
+	Some(if x == 0 { Polynomial::new() } else { [(Vec::new(), x)].into() })
+}
+
+fn add(x: &Polynomial, y: &Polynomial) -> Option<Polynomial> {
+	let mut res = x.clone();
+	for (vars, factor) in y {
+		let entry = res.entry(vars.clone()).or_default();
+		*entry = entry.checked_add(*factor)?;
+	}
+	Some(res)
+}
+
+fn sub(x: &Polynomial, y: &Polynomial) -> Option<Polynomial> {
+	let negated = y
+		.iter()
+		.map(|(v, f)| Some((v.clone(), f.checked_neg()?)))
+		.collect::<Option<_>>()?;
+	add(x, &negated)
+}
+
+fn mul(x: &Polynomial, y: &Polynomial) -> Option<Polynomial> {
+	let mut res = Polynomial::new();
+	for (l_vars, l) in x {
+		for (r_vars, r) in y {
+			let mut vars = [l_vars.clone(), r_vars.clone()].concat();
+			vars.sort();
+			let entry = res.entry(vars).or_default();
+			*entry = entry.checked_add(l.checked_mul(*r)?)?;
+		}
+	}
+	Some(res)
+}
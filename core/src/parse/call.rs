@@ -0,0 +1,103 @@
+//! Parses hard-coded weights from the `#[pallet::call]` definitions of a pallet.
+//!
+//! Pallets that are not benchmarked annotate their calls directly:
+//!
+//! ```ignore
+//! #[pallet::call]
+//! impl<T: Config> Pallet<T> {
+//!     #[pallet::call_index(0)]
+//!     #[pallet::weight(Weight::from_parts(10_000, 0))]
+//!     pub fn set_dummy(origin: OriginFor<T>, new_value: T::Balance) -> DispatchResult {
+//!         ..
+//!     }
+//! }
+//! ```
+//!
+//! Calls whose weight is computed by a `WeightInfo` function are skipped, since their weight file
+//! is parsed instead.
+
+use syn::{Attribute, Expr, ImplItem, Item};
+
+use crate::{
+	parse::{
+		pallet::{
+			parse_expression, parse_scalar_expression, ChromaticExtrinsic, LineRange, Result,
+			SourceSpan,
+		},
+		path_to_string,
+	},
+	term::ChromaticTerm,
+	Dimension, PalletName,
+};
+
+/// Whether the content contains a `#[pallet::call]` definition.
+pub fn is_pallet_source(content: &str) -> bool {
+	content.contains("pallet::call")
+}
+
+pub fn parse_content(pallet: PalletName, content: String) -> Result<Vec<ChromaticExtrinsic>> {
+	let ast = syn::parse_file(&content)
+		.map_err(|e| format!("syn refused to parse content: {:?}: {}", content, e))?;
+
+	let mut weights = Vec::new();
+	handle_items(&pallet, &ast.items, &mut weights);
+	if weights.is_empty() {
+		Err("No hard-coded call weights found".into())
+	} else {
+		Ok(weights)
+	}
+}
+
+/// Finds the call definitions in the items and their inline modules.
+fn handle_items(pallet: &PalletName, items: &[Item], weights: &mut Vec<ChromaticExtrinsic>) {
+	for item in items {
+		match item {
+			Item::Mod(m) =>
+				if let Some((_, items)) = &m.content {
+					handle_items(pallet, items, weights);
+				},
+			Item::Impl(imp) if imp.attrs.iter().any(|a| is_attr(a, "call")) =>
+				for f in &imp.items {
+					let ImplItem::Fn(m) = f else { continue };
+					let name = m.sig.ident.to_string();
+					let Some(attr) = m.attrs.iter().find(|a| is_attr(a, "weight")) else {
+						continue
+					};
+
+					match parse_weight_attr(attr) {
+						Ok(term) => weights.push(ChromaticExtrinsic {
+							name: name.into(),
+							pallet: pallet.clone(),
+							term,
+							comp_ranges: None,
+							span: Some(SourceSpan {
+								function: LineRange::of_fn(m),
+								weight: LineRange::of(attr),
+							}),
+							pallet_id: None,
+						}),
+						Err(err) => log::debug!("Skipped call {}: {}", name, err),
+					}
+				},
+			_ => {},
+		}
+	}
+}
+
+/// Example: `#[pallet::weight((Weight::from_parts(10_000, 0), DispatchClass::Operational))]`
+fn parse_weight_attr(attr: &Attribute) -> Result<ChromaticTerm> {
+	let expr = attr.parse_args::<Expr>().map_err(|e| format!("Invalid weight: {}", e))?;
+	// The dispatch class and pays fee can be given next to the weight.
+	let expr = match &expr {
+		Expr::Tuple(t) => t.elems.first().ok_or("Empty weight tuple")?,
+		expr => expr,
+	};
+
+	parse_expression(expr)
+		.or_else(|_| Ok(parse_scalar_expression(expr)?.into_chromatic(Dimension::Time)))
+}
+
+/// Whether the attribute is `#[pallet::{name}]`.
+fn is_attr(attr: &Attribute, name: &str) -> bool {
+	path_to_string(attr.path(), Some("::")) == format!("pallet::{}", name)
+}
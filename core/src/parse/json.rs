@@ -0,0 +1,211 @@
+//! Parses the raw benchmark results of `frame-benchmarking-cli --json`.
+//!
+//! The results contain the measured extrinsic time, storage reads, writes and proof size of each
+//! combination of component values. They are reduced to a weight formula with a least squares
+//! regression, like the benchmarking CLI does before it writes a weight file. This allows to
+//! compare a fresh benchmark run with the committed weight files.
+//!
+//! Each benchmark is returned as extrinsic of the pallet `{pallet}.rs` such that it is matched
+//! with the weight file of the pallet.
+
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+	cadd, cmul, creads, cval, cvar, cwrites,
+	parse::pallet::{ChromaticExtrinsic, ComponentRange, ComponentRanges, Result},
+	term::{ChromaticTerm, Term},
+	traits::Weight,
+	SwcError, WEIGHT_PER_NANOS,
+};
+
+/// The results of one benchmark.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BenchmarkBatch {
+	#[serde(deserialize_with = "bytes_or_string")]
+	pub pallet: String,
+	#[serde(deserialize_with = "bytes_or_string")]
+	pub benchmark: String,
+	pub time_results: Vec<BenchmarkResult>,
+	/// Older versions of the CLI measure the storage accesses separately.
+	#[serde(default)]
+	pub db_results: Vec<BenchmarkResult>,
+}
+
+/// One measurement of a benchmark.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BenchmarkResult {
+	pub components: Vec<(String, u32)>,
+	/// In nanoseconds.
+	pub extrinsic_time: u128,
+	#[serde(default)]
+	pub reads: u32,
+	#[serde(default)]
+	pub writes: u32,
+	#[serde(default)]
+	pub proof_size: u32,
+}
+
+/// Whether the file has a `.json` extension.
+pub fn is_json(file: &Path) -> bool {
+	file.extension().map_or(false, |e| e == "json")
+}
+
+pub fn parse_file(file: &Path) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let content = super::read_file(file)?;
+	parse_content(&content).map_err(|e| SwcError::Parse(format!("{}: {}", file.display(), e)))
+}
+
+pub fn parse_content(content: &str) -> Result<Vec<ChromaticExtrinsic>> {
+	let batches: Vec<BenchmarkBatch> =
+		serde_json::from_str(content).map_err(|e| format!("Invalid benchmark results: {}", e))?;
+	batches.iter().map(batch_to_extrinsic).collect()
+}
+
+fn batch_to_extrinsic(batch: &BenchmarkBatch) -> Result<ChromaticExtrinsic> {
+	if batch.time_results.is_empty() {
+		return Err(format!("Benchmark {}::{} has no results", batch.pallet, batch.benchmark))
+	}
+	let db_results =
+		if batch.db_results.is_empty() { &batch.time_results } else { &batch.db_results };
+
+	let (time_base, time_slopes) =
+		regression(&batch.time_results, |r| (r.extrinsic_time * WEIGHT_PER_NANOS) as f64);
+	let (proof_base, proof_slopes) = regression(&batch.time_results, |r| r.proof_size as f64);
+	let (reads_base, reads_slopes) = regression(db_results, |r| r.reads as f64);
+	let (writes_base, writes_slopes) = regression(db_results, |r| r.writes as f64);
+	let proof_slopes = proof_slopes.into_iter().collect::<HashMap<_, _>>();
+
+	let mut term = cval!(Weight { ref_time: time_base, proof_size: proof_base });
+	for (component, slope) in &time_slopes {
+		let proof = proof_slopes.get(component).copied().unwrap_or_default();
+		if *slope > 0 || proof > 0 {
+			term = cadd!(
+				term,
+				cmul!(
+					cval!(Weight { ref_time: *slope, proof_size: proof }),
+					cvar!(component.as_str())
+				)
+			);
+		}
+	}
+	if reads_base > 0 {
+		term = cadd!(term, creads!(ChromaticTerm::Scalar(reads_base)));
+	}
+	for (component, slope) in reads_slopes.iter().filter(|(_, s)| *s > 0) {
+		term = cadd!(term, creads!(cmul!(Term::Scalar(*slope), cvar!(component.as_str()))));
+	}
+	if writes_base > 0 {
+		term = cadd!(term, cwrites!(ChromaticTerm::Scalar(writes_base)));
+	}
+	for (component, slope) in writes_slopes.iter().filter(|(_, s)| *s > 0) {
+		term = cadd!(term, cwrites!(cmul!(Term::Scalar(*slope), cvar!(component.as_str()))));
+	}
+
+	Ok(ChromaticExtrinsic {
+		name: batch.benchmark.as_str().into(),
+		pallet: format!("{}.rs", batch.pallet).into(),
+		term,
+		comp_ranges: component_ranges(&batch.time_results),
+		span: None,
+		pallet_id: None,
+	})
+}
+
+/// Min and max value of each component.
+fn component_ranges(results: &[BenchmarkResult]) -> Option<ComponentRanges> {
+	let mut ranges = ComponentRanges::new();
+	for (name, value) in results.iter().flat_map(|r| r.components.iter()) {
+		let range = ranges
+			.entry(name.replace('_', ""))
+			.or_insert(ComponentRange { min: *value, max: *value });
+		range.min = range.min.min(*value);
+		range.max = range.max.max(*value);
+	}
+	(!ranges.is_empty()).then_some(ranges)
+}
+
+/// Least squares fit of `y = base + sum(slope_i * component_i)`.
+///
+/// Components with a constant value are part of the base. Negative values are rounded up to
+/// zero, since weights cannot be negative.
+fn regression(
+	results: &[BenchmarkResult],
+	y: impl Fn(&BenchmarkResult) -> f64,
+) -> (u128, Vec<(String, u128)>) {
+	let mut components = Vec::<String>::new();
+	for (name, value) in results.iter().flat_map(|r| r.components.iter()) {
+		let varies = results
+			.iter()
+			.any(|r| r.components.iter().any(|(n, v)| n == name && v != value));
+		if varies && !components.contains(name) {
+			components.push(name.clone());
+		}
+	}
+	let row = |r: &BenchmarkResult| {
+		let values = r.components.iter().cloned().collect::<HashMap<_, _>>();
+		std::iter::once(1.0)
+			.chain(components.iter().map(|c| values.get(c).copied().unwrap_or_default() as f64))
+			.collect::<Vec<_>>()
+	};
+
+	// Normal equations: (XᵀX) β = Xᵀy
+	let n = components.len() + 1;
+	let mut a = vec![vec![0.0; n + 1]; n];
+	for r in results {
+		let x = row(r);
+		let y = y(r);
+		for i in 0..n {
+			for j in 0..n {
+				a[i][j] += x[i] * x[j];
+			}
+			a[i][n] += x[i] * y;
+		}
+	}
+	let beta = solve(a).unwrap_or_else(|| {
+		// Can only happen for degenerate results; fall back to the mean.
+		let mean = results.iter().map(&y).sum::<f64>() / results.len() as f64;
+		std::iter::once(mean).chain(components.iter().map(|_| 0.0)).collect()
+	});
+
+	let round = |v: f64| v.max(0.0).round() as u128;
+	let slopes = components.into_iter().zip(beta.iter().skip(1)).map(|(c, s)| (c, round(*s)));
+	(round(beta[0]), slopes.collect())
+}
+
+/// Gaussian elimination of an augmented matrix. `None` if it is singular.
+fn solve(mut a: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+	let n = a.len();
+	for col in 0..n {
+		let pivot = (col..n).max_by(|x, y| a[*x][col].abs().total_cmp(&a[*y][col].abs()))?;
+		if a[pivot][col].abs() < f64::EPSILON {
+			return None
+		}
+		a.swap(col, pivot);
+		let pivot = a[col].clone();
+		for (_, row) in a.iter_mut().enumerate().filter(|(r, _)| *r != col) {
+			let factor = row[col] / pivot[col];
+			for (x, p) in row.iter_mut().zip(&pivot).skip(col) {
+				*x -= factor * p;
+			}
+		}
+	}
+	Some((0..n).map(|i| a[i][n] / a[i][i]).collect())
+}
+
+/// Older versions of the CLI serialize names as bytes.
+fn bytes_or_string<'de, D: serde::Deserializer<'de>>(
+	d: D,
+) -> std::result::Result<String, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Name {
+		String(String),
+		Bytes(Vec<u8>),
+	}
+
+	match Name::deserialize(d)? {
+		Name::String(s) => Ok(s),
+		Name::Bytes(b) => String::from_utf8(b).map_err(serde::de::Error::custom),
+	}
+}
@@ -0,0 +1,84 @@
+//! Parses the JSON output of the `benchmark machine` command.
+//!
+//! Each entry has a `metric` and a `score` which is a throughput. The reference hardware
+//! requirements use the same format but call the score `minimum`. Example:
+//! ```json
+//! [
+//!   { "metric": "Blake2256", "score": { "MiBs": 1029.13 } },
+//!   { "metric": { "Blake2256Parallel": { "num_cores": 8 } }, "minimum": 7643.2 }
+//! ]
+//! ```
+
+use serde_json::Value;
+use std::path::Path;
+
+use crate::SwcError;
+
+/// The score of a single hardware metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MachineScore {
+	/// Name of the metric like `Blake2256` or `DiskSeqWrite`.
+	pub metric: String,
+	/// Throughput in MiB/s.
+	pub score: f64,
+}
+
+pub fn parse_file(file: &Path) -> Result<Vec<MachineScore>, SwcError> {
+	let content = super::read_file(file)?;
+	parse_content(&content).map_err(|e| SwcError::Parse(format!("{}: {}", file.display(), e)))
+}
+
+pub fn parse_content(content: &str) -> Result<Vec<MachineScore>, String> {
+	let json: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+	let entries = json.as_array().ok_or("Expected a list of machine benchmark results")?;
+
+	entries.iter().map(parse_entry).collect()
+}
+
+fn parse_entry(entry: &Value) -> Result<MachineScore, String> {
+	let metric = metric_name(entry.get("metric").ok_or("Missing `metric` field")?)?;
+	let score = entry
+		.get("score")
+		.or_else(|| entry.get("minimum"))
+		.ok_or_else(|| format!("Missing `score` of metric {}", metric))?;
+	let score = parse_throughput(score).map_err(|e| format!("Metric {}: {}", metric, e))?;
+
+	Ok(MachineScore { metric, score })
+}
+
+/// Unit variants are plain strings and struct variants are single-key objects.
+fn metric_name(metric: &Value) -> Result<String, String> {
+	match metric {
+		Value::String(name) => Ok(name.clone()),
+		Value::Object(obj) if obj.len() == 1 => {
+			let (name, args) = obj.iter().next().expect("Checked length; qed");
+			match args {
+				Value::Object(args) if !args.is_empty() => {
+					let args = args.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>();
+					Ok(format!("{}({})", name, args.join(", ")))
+				},
+				_ => Ok(name.clone()),
+			}
+		},
+		_ => Err(format!("Unexpected metric: {}", metric)),
+	}
+}
+
+/// Converts a throughput into MiB/s.
+///
+/// Accepts either a plain number or an object like `{ "KiBs": 665.46 }`.
+fn parse_throughput(value: &Value) -> Result<f64, String> {
+	if let Some(mibs) = value.as_f64() {
+		return Ok(mibs)
+	}
+	let obj = value.as_object().filter(|o| o.len() == 1).ok_or("Unexpected throughput")?;
+	let (unit, value) = obj.iter().next().expect("Checked length; qed");
+	let value = value.as_f64().ok_or("Throughput must be a number")?;
+
+	match unit.as_str() {
+		"KiBs" => Ok(value / 1024.0),
+		"MiBs" => Ok(value),
+		"GiBs" => Ok(value * 1024.0),
+		_ => Err(format!("Unknown throughput unit: {}", unit)),
+	}
+}
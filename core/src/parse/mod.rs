@@ -5,14 +5,27 @@
 //! - Database weights (often rocksdb_weights.rs or paritydb_weights.rs)
 //! - Extrinsic Base weight (often extrinsic_weight.rs)
 //! - Block Execution weight (often block_weight.rs)
+//! - Machine benchmark results (JSON output of `benchmark machine`)
+//! - XCM instruction weights (often xcm/mod.rs)
+//! - Hard-coded `#[pallet::weight]` annotations of pallet calls (often lib.rs)
+//! - Raw benchmark results (JSON output of `benchmark pallet --json`)
 //!
 //! Each module corresponds to one of these categories.
 
+pub mod call;
+pub mod json;
+pub mod machine;
 pub mod overhead;
 pub mod pallet;
 pub mod storage;
+pub mod xcm;
 
-use std::{io::Read, path::Path};
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+use std::{
+	io::Read,
+	path::{Path, PathBuf},
+};
 
 pub enum ParsedFile {
 	Pallet(Vec<pallet::ChromaticExtrinsic>),
@@ -20,6 +33,17 @@ pub enum ParsedFile {
 	Overhead(overhead::Weight),
 }
 
+impl ParsedFile {
+	/// Convert the parsed file into extrinsics. The `pallet` name is only used for overhead files.
+	pub fn into_extrinsics(self, pallet: crate::PalletName) -> Vec<pallet::ChromaticExtrinsic> {
+		match self {
+			Self::Pallet(extrinsics) => extrinsics,
+			Self::Storage(weights) => weights.into_extrinsics(),
+			Self::Overhead(weight) => vec![weight.into_extrinsic(pallet)],
+		}
+	}
+}
+
 /// Defines how a path is transformed into a pallet name.
 ///
 /// Take the following example:
@@ -33,7 +57,8 @@ pub enum ParsedFile {
 ///  Compare `polkadot/pallet.rs` to `polkadot/template/pallet.rs`.
 ///  It this case the pallet name is different, since it are two distinct runtimes.
 ///  The pallets should not be compared to each other but registered as `Added`.
-#[derive(Copy, clap::ValueEnum, PartialEq, Eq, Clone, Debug)]
+#[derive(Copy, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum PathStripping {
 	/// Only the file name.
 	FileName,
@@ -84,18 +109,58 @@ pub fn try_parse_file(repo: &Path, file: &Path) -> Option<ParsedFile> {
 	None
 }
 
-pub fn read_file(file: &Path) -> Result<String, String> {
+pub fn read_file(file: &Path) -> Result<String, crate::SwcError> {
 	let mut raw = std::fs::File::options()
 		.read(true)
 		.write(false)
 		.open(file)
-		.map_err(|e| format!("{}: {:?}", file.display(), e))?;
+		.map_err(|e| crate::SwcError::Parse(format!("{}: {:?}", file.display(), e)))?;
 	let mut content = String::new();
 	raw.read_to_string(&mut content)
-		.map_err(|e| format!("{}: {:?}", file.display(), e))?;
+		.map_err(|e| crate::SwcError::Parse(format!("{}: {:?}", file.display(), e)))?;
 	Ok(content)
 }
 
+/// Whether the content looks like a weight file; see [`crate::CompareParams::auto_discover`].
+///
+/// That is an `impl` of a `WeightInfo` trait, including `XcmWeightInfo`, with a function that
+/// returns a `Weight`.
+pub fn looks_like_weights(content: &str) -> bool {
+	lazy_static! {
+		static ref IMPL: Regex = Regex::new(r"\bimpl\b[^{;]*WeightInfo\b").unwrap();
+		static ref FN: Regex =
+			Regex::new(r"\bfn\s+\w+\s*(<[^>]*>)?\s*\([^)]*\)\s*->\s*Weight\s*\{").unwrap();
+	}
+	content.contains("WeightInfo") &&
+		IMPL.is_match(content).unwrap_or_default() &&
+		FN.is_match(content).unwrap_or_default()
+}
+
+/// The weight files in `dir` and its sub-directories, sorted.
+///
+/// These are the `.rs` and `.json` files; `mod.rs` files only in `xcm` folders.
+pub fn list_weight_files(dir: &Path) -> Result<Vec<PathBuf>, crate::SwcError> {
+	let read_dir = |dir: &Path| {
+		std::fs::read_dir(dir)
+			.and_then(|entries| entries.map(|e| e.map(|e| e.path())).collect::<Result<Vec<_>, _>>())
+			.map_err(|e| crate::SwcError::Parse(format!("{}: {:?}", dir.display(), e)))
+	};
+	let (mut files, mut dirs) = (Vec::new(), vec![dir.to_path_buf()]);
+	while let Some(dir) = dirs.pop() {
+		for path in read_dir(&dir)? {
+			if path.is_dir() {
+				dirs.push(path);
+			} else if path.extension().map_or(false, |e| e == "rs" || e == "json") &&
+				(!path.ends_with("mod.rs") || path.ends_with("xcm/mod.rs"))
+			{
+				files.push(path);
+			}
+		}
+	}
+	files.sort();
+	Ok(files)
+}
+
 pub(crate) fn path_to_string(p: &syn::Path, delimiter: Option<&str>) -> String {
 	p.segments
 		.iter()
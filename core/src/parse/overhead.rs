@@ -1,7 +1,7 @@
 use syn::ItemConst;
 
 use crate::{
-	parse::path_to_string,
+	parse::{pallet::ChromaticExtrinsic, path_to_string, PathStripping},
 	term::{ChromaticTerm, SimpleTerm},
 	*,
 };
@@ -12,9 +12,65 @@ pub enum Weight {
 	ExtrinsicBase(ChromaticTerm),
 }
 
-pub fn parse_file(file: &Path) -> Result<Weight, String> {
+impl Weight {
+	/// Name of the constant that defines the weight.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::BlockExecution(_) => "BlockExecutionWeight",
+			Self::ExtrinsicBase(_) => "ExtrinsicBaseWeight",
+		}
+	}
+
+	pub fn term(&self) -> &ChromaticTerm {
+		match self {
+			Self::BlockExecution(term) | Self::ExtrinsicBase(term) => term,
+		}
+	}
+
+	/// Converts the weight into an extrinsic such that it can be compared like any other.
+	///
+	/// The `WEIGHT_PER_NANOS` constants are substituted since they are not components.
+	pub fn into_extrinsic(self, pallet: PalletName) -> ChromaticExtrinsic {
+		let name = self.name().into();
+		let mut term = match self {
+			Self::BlockExecution(term) | Self::ExtrinsicBase(term) => term,
+		};
+		for (var, value) in SimpleScope::from_substrate().as_vec() {
+			if let Some(value) = value.as_scalar() {
+				term.substitute(&var, &ChromaticTerm::Scalar(value));
+			}
+		}
+
+		ChromaticExtrinsic { name, pallet, term, comp_ranges: None, span: None, pallet_id: None }
+	}
+}
+
+pub fn parse_file(file: &Path) -> Result<Weight, SwcError> {
 	let content = super::read_file(file)?;
-	parse_content(content)
+	parse_content(content).map_err(SwcError::Parse)
+}
+
+/// Parses overhead weight files into extrinsics that are named after their file.
+pub fn parse_files(paths: &[PathBuf]) -> Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let mut res = Vec::new();
+	for path in paths {
+		let weight =
+			parse_file(path).map_err(|e| SwcError::Parse(format!("{}: {}", path.display(), e)))?;
+		let name = PathStripping::FileName.strip(Path::new("."), path);
+		res.push(weight.into_extrinsic(name.into()));
+	}
+	Ok(res)
+}
+
+pub fn try_parse_files(paths: &[PathBuf]) -> Vec<ChromaticExtrinsic> {
+	let mut res = Vec::new();
+	for path in paths {
+		if let Ok(weight) = parse_file(path) {
+			let name = PathStripping::FileName.strip(Path::new("."), path);
+			res.push(weight.into_extrinsic(name.into()));
+		}
+	}
+	res
 }
 
 pub fn parse_content(content: String) -> Result<Weight, String> {
@@ -2,13 +2,13 @@ use crate::{
 	creads, cwrites, reads,
 	term::{SimpleTerm, Term},
 	traits::*,
-	writes, Dimension, ExtrinsicName, PalletName,
+	writes, Dimension, ExtrinsicName, PalletName, SwcError,
 };
 
 use fancy_regex::Regex;
 use lazy_static::lazy_static;
 use std::{
-	collections::HashMap,
+	collections::{HashMap, HashSet},
 	path::{Path, PathBuf},
 };
 use syn::{
@@ -26,14 +26,14 @@ pub type Result<T> = std::result::Result<T, String>;
 pub type ComponentName = String;
 
 /// Inclusive range of a component.
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, serde::Serialize, serde::Deserialize)]
 pub struct ComponentRange {
 	pub min: u32,
 	pub max: u32,
 }
 pub type ComponentRanges = HashMap<ComponentName, ComponentRange>;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct GenericExtrinsic<T> {
 	pub name: ExtrinsicName,
 	pub pallet: PalletName,
@@ -41,6 +41,108 @@ pub struct GenericExtrinsic<T> {
 	pub term: T,
 	/// Min and max value that each weight component can have.
 	pub comp_ranges: Option<ComponentRanges>,
+	/// Where the extrinsic is defined in its weight file; if known.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub span: Option<SourceSpan>,
+	/// The pallet as named by its `WeightInfo` trait; if known.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub pallet_id: Option<PalletId>,
+}
+
+/// Identifies a pallet independent of the name of its weight file.
+///
+/// The crate is the first segment of the implemented trait, eg. `pallet_collective` of
+/// `impl<T: frame_system::Config> pallet_collective::WeightInfo for WeightInfo<T>`. The instance of
+/// an instanced pallet is the rest of the file name, eg. `council` of
+/// `pallet_collective_council.rs` or `collective_council.rs`. Each instance has its own weight file
+/// and is compared on its own.
+#[derive(
+	Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PalletId {
+	pub crate_name: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub instance: Option<String>,
+}
+
+impl PalletId {
+	/// The id of the pallet whose weight file `file` implements the trait `trait_path`.
+	///
+	/// Returns `None` for traits without a crate, like `WeightInfo` or `crate::WeightInfo`.
+	pub fn of_trait(trait_path: &syn::Path, file: &str) -> Option<Self> {
+		let segments = trait_path.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>();
+		let crate_name = match segments.as_slice() {
+			[krate, .., _] if !["crate", "self", "super"].contains(&krate.as_str()) =>
+				krate.clone(),
+			_ => return None,
+		};
+		let file_name = Path::new(file).file_name().unwrap_or_default().to_string_lossy();
+		let stem = file_name.split('.').next().unwrap_or_default();
+		let instance = Self::instance_of(&crate_name, stem);
+		Some(Self { crate_name, instance })
+	}
+
+	/// The instance in the file name `stem`; after the crate name with or without its prefix.
+	fn instance_of(crate_name: &str, stem: &str) -> Option<String> {
+		let short = ["pallet_", "frame_"].iter().find_map(|p| crate_name.strip_prefix(p));
+		[Some(crate_name), short]
+			.into_iter()
+			.flatten()
+			.filter_map(|name| stem.strip_prefix(name)?.strip_prefix('_'))
+			.find(|rest| !rest.is_empty())
+			.map(Into::into)
+	}
+}
+
+impl std::fmt::Display for PalletId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.instance {
+			Some(instance) => write!(f, "{} ({})", self.crate_name, instance),
+			None => write!(f, "{}", self.crate_name),
+		}
+	}
+}
+
+/// Lines of a part of a source file; one-indexed and inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LineRange {
+	pub start: usize,
+	pub end: usize,
+}
+
+impl LineRange {
+	pub fn of(node: &impl syn::spanned::Spanned) -> Self {
+		let span = node.span();
+		Self { start: span.start().line, end: span.end().line }
+	}
+
+	/// From the signature to the end of the function; without its doc comments and attributes.
+	pub fn of_fn(function: &syn::ImplItemFn) -> Self {
+		Self { start: Self::of(&function.sig).start, end: Self::of(&function.block).end }
+	}
+}
+
+/// Location of a weight function in its file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SourceSpan {
+	/// The whole weight function.
+	pub function: LineRange,
+	/// The expression that calculates the weight.
+	pub weight: LineRange,
+}
+
+impl SourceSpan {
+	/// The span of a function whose body is the weight expression.
+	pub fn of(function: &syn::ImplItemFn) -> Self {
+		let weight = match function.block.stmts.as_slice() {
+			[stmt] => LineRange::of(stmt),
+			_ => LineRange::of(&function.block),
+		};
+		Self { function: LineRange::of_fn(function), weight }
+	}
 }
 
 pub type ChromaticExtrinsic = GenericExtrinsic<ChromaticTerm>;
@@ -53,24 +155,43 @@ impl<T> GenericExtrinsic<T> {
 			name: self.name,
 			pallet: self.pallet,
 			comp_ranges: self.comp_ranges,
+			span: self.span,
+			pallet_id: self.pallet_id,
 			// ..self is experimental between different types.
 		}
 	}
 }
 
-pub fn parse_file_in_repo(repo: &Path, file: &Path) -> Result<Vec<ChromaticExtrinsic>> {
+pub fn parse_file_in_repo(
+	repo: &Path,
+	file: &Path,
+) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
+	if super::json::is_json(file) {
+		return super::json::parse_file(file)
+	}
 	let content = super::read_file(file)?;
 	let name = PathStripping::RepoRelative.strip(repo, file);
-	parse_content(name, content).map_err(|e| format!("{}: {}", file.display(), e))
+	let is_xcm = super::xcm::is_xcm_weight_info(&content);
+	match parse_content(name.into(), content) {
+		Err(_) if is_xcm => super::xcm::parse_file_in_repo(repo, file),
+		res => res.map_err(|e| SwcError::Parse(format!("{}: {}", file.display(), e))),
+	}
 }
 
-pub fn parse_file(file: &Path) -> Result<Vec<ChromaticExtrinsic>> {
+pub fn parse_file(file: &Path) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
+	if super::json::is_json(file) {
+		return super::json::parse_file(file)
+	}
 	let content = super::read_file(file)?;
 	let name = PathStripping::FileName.strip(Path::new("."), file);
-	parse_content(name, content).map_err(|e| format!("{}: {}", file.display(), e))
+	parse_content(name.into(), content)
+		.map_err(|e| SwcError::Parse(format!("{}: {}", file.display(), e)))
 }
 
-pub fn parse_files_in_repo(repo: &Path, paths: &[PathBuf]) -> Result<Vec<ChromaticExtrinsic>> {
+pub fn parse_files_in_repo(
+	repo: &Path,
+	paths: &[PathBuf],
+) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
 	let mut res = Vec::new();
 	for path in paths {
 		res.extend(parse_file_in_repo(repo, path)?);
@@ -78,7 +199,7 @@ pub fn parse_files_in_repo(repo: &Path, paths: &[PathBuf]) -> Result<Vec<Chromat
 	Ok(res)
 }
 
-pub fn parse_files(paths: &[PathBuf]) -> Result<Vec<ChromaticExtrinsic>> {
+pub fn parse_files(paths: &[PathBuf]) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
 	let mut res = Vec::new();
 	for path in paths {
 		res.extend(parse_file(path)?);
@@ -86,6 +207,30 @@ pub fn parse_files(paths: &[PathBuf]) -> Result<Vec<ChromaticExtrinsic>> {
 	Ok(res)
 }
 
+/// Parse weight files and the weight files in directories; see [`super::list_weight_files`].
+///
+/// The files of a directory are named by their path relative to it. Two directories therefore pair
+/// up by the names of their files, just like single files do. With the `archive` feature, tar and
+/// zip archives are extracted and parsed like directories.
+pub fn parse_paths(paths: &[PathBuf]) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let mut res = Vec::new();
+	for path in paths {
+		#[cfg(feature = "archive")]
+		if crate::archive::is_archive(path) {
+			let extracted = crate::archive::extract(path)?;
+			let root = extracted.root();
+			res.extend(parse_files_in_repo(root, &super::list_weight_files(root)?)?);
+			continue
+		}
+		if path.is_dir() {
+			res.extend(parse_files_in_repo(path, &super::list_weight_files(path)?)?);
+		} else {
+			res.extend(parse_file(path)?);
+		}
+	}
+	Ok(res)
+}
+
 pub fn try_parse_files_in_repo(repo: &Path, paths: &[PathBuf]) -> Vec<ChromaticExtrinsic> {
 	let mut res = Vec::new();
 	for path in paths {
@@ -106,19 +251,337 @@ pub fn try_parse_files(paths: &[PathBuf]) -> Vec<ChromaticExtrinsic> {
 	res
 }
 
-pub fn parse_content(pallet: PalletName, content: String) -> Result<Vec<ChromaticExtrinsic>> {
-	let ast = syn::parse_file(&content)
-		.map_err(|e| format!("syn refused to parse content: {:?}: {}", content, e))?;
-	for item in ast.items {
-		if let Ok(weights) = handle_item(pallet.clone(), &item) {
+/// Same as [`parse_paths`] but skips the files that can not be parsed.
+pub fn try_parse_paths(paths: &[PathBuf]) -> Vec<ChromaticExtrinsic> {
+	let mut res = Vec::new();
+	for path in paths {
+		#[cfg(feature = "archive")]
+		if crate::archive::is_archive(path) {
+			if let Ok(extracted) = crate::archive::extract(path) {
+				let root = extracted.root();
+				let files = super::list_weight_files(root).unwrap_or_default();
+				res.extend(try_parse_files_in_repo(root, &files));
+			}
+			continue
+		}
+		if path.is_dir() {
+			let files = super::list_weight_files(path).unwrap_or_default();
+			res.extend(try_parse_files_in_repo(path, &files));
+		} else if let Ok(parsed) = parse_file(path) {
+			res.extend(parsed);
+		}
+	}
+	res
+}
+
+/// Parse a weight file or raw benchmark results in JSON by their content, eg. when read from stdin.
+pub fn parse_any_content(
+	pallet: PalletName,
+	content: String,
+) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let err = |e: String| SwcError::Parse(format!("{}: {}", pallet, e));
+	if content.trim_start().starts_with('[') {
+		return super::json::parse_content(&content).map_err(err)
+	}
+	parse_content(pallet.clone(), content).map_err(|e| err(e.to_string()))
+}
+
+pub fn parse_content(
+	pallet: PalletName,
+	content: String,
+) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let mut ast = syn::parse_file(&content).map_err(|e| {
+		SwcError::Parse(format!("syn refused to parse content: {:?}: {}", content, e))
+	})?;
+	inline_constants(&mut ast);
+	inline_calls(&mut ast);
+	// Inherent impls only hold the weights if there is no trait impl, eg. in XCM benchmarks.
+	// Otherwise they hold helpers.
+	ast.items
+		.sort_by_key(|item| !matches!(item, Item::Impl(imp) if imp.trait_.is_some()));
+	for item in &ast.items {
+		if let Ok(weights) = handle_item(&pallet, item) {
+			return Ok(weights)
+		}
+	}
+	// Pallets without benchmarks can still have hard-coded weights in their calls.
+	if super::call::is_pallet_source(&content) {
+		if let Ok(weights) = super::call::parse_content(pallet.clone(), content) {
 			return Ok(weights)
 		}
 	}
 	log::warn!("Could not find a weight implementation in {}", &pallet);
-	Err("Could not find a weight implementation in the passed file".into())
+	Err(SwcError::Parse("Could not find a weight implementation in the passed file".into()))
+}
+
+/// Replace the uses of the constants of a file by their value.
+///
+/// Constants are `const` items and the `const`s of `parameter_types!`; they are used as `NAME` or
+/// `Name::get()`. The value is inlined as expression so that it is parsed like a literal weight.
+pub(crate) fn inline_constants(file: &mut syn::File) {
+	let mut uses = Uses::of(file);
+	let mut constants = HashMap::new();
+	for item in &file.items {
+		match item {
+			Item::Const(c) if uses.names.contains(&c.ident.to_string()) => {
+				constants.insert(c.ident.to_string(), (*c.expr).clone());
+			},
+			Item::Macro(m)
+				if m.mac.path.segments.last().map_or(false, |s| s.ident == "parameter_types") =>
+			{
+				// Only the `const` parameters are valid items.
+				let Ok(items) = syn::parse2::<syn::File>(m.mac.tokens.clone()) else {
+					log::debug!("Skipped parameter_types! that are not constant");
+					continue
+				};
+				// The parameters can use each other.
+				syn::visit::Visit::visit_file(&mut uses, &items);
+				for item in items.items {
+					match item {
+						Item::Const(c) if uses.names.contains(&c.ident.to_string()) => {
+							constants.insert(c.ident.to_string(), *c.expr);
+						},
+						_ => {},
+					}
+				}
+			},
+			_ => {},
+		}
+	}
+	if !constants.is_empty() {
+		syn::visit_mut::VisitMut::visit_file_mut(&mut Inliner { constants, depth: 0 }, file);
+	}
+}
+
+struct Inliner {
+	constants: HashMap<String, Expr>,
+	/// Constants that are defined by other constants are inlined recursively up to a limit.
+	depth: usize,
+}
+
+impl syn::visit_mut::VisitMut for Inliner {
+	fn visit_expr_mut(&mut self, expr: &mut Expr) {
+		let name = match &*expr {
+			Expr::Path(p) => p.path.get_ident().map(ToString::to_string),
+			Expr::Call(call) if call.args.is_empty() => match call.func.as_ref() {
+				Expr::Path(p) => match p.path.segments.iter().collect::<Vec<_>>().as_slice() {
+					[name, get] if get.ident == "get" => Some(name.ident.to_string()),
+					_ => None,
+				},
+				_ => None,
+			},
+			_ => None,
+		};
+		match name.and_then(|n| self.constants.get(&n)) {
+			Some(value) if self.depth < 16 => {
+				*expr = paren(value.clone());
+				self.depth += 1;
+				syn::visit_mut::visit_expr_mut(self, expr);
+				self.depth -= 1;
+			},
+			_ => syn::visit_mut::visit_expr_mut(self, expr),
+		}
+	}
+}
+
+/// The names that a file uses; found without cloning any of its expressions.
+#[derive(Default)]
+struct Uses {
+	/// Single identifiers and the `Name` of `Name::get()`.
+	names: HashSet<String>,
+	/// The last segment of each called path.
+	calls: HashSet<String>,
+}
+
+impl Uses {
+	fn of(file: &syn::File) -> Self {
+		let mut uses = Self::default();
+		syn::visit::Visit::visit_file(&mut uses, file);
+		uses
+	}
+}
+
+impl<'ast> syn::visit::Visit<'ast> for Uses {
+	fn visit_expr(&mut self, expr: &'ast Expr) {
+		if let Expr::Path(p) = expr {
+			self.names.extend(p.path.get_ident().map(ToString::to_string));
+		}
+		if let Expr::Call(ExprCall { func, args, .. }) = expr {
+			if let Expr::Path(p) = func.as_ref() {
+				if let [name, get] = p.path.segments.iter().collect::<Vec<_>>().as_slice() {
+					if args.is_empty() && get.ident == "get" {
+						self.names.insert(name.ident.to_string());
+					}
+				}
+				self.calls.extend(p.path.segments.last().map(|s| s.ident.to_string()));
+			}
+		}
+		syn::visit::visit_expr(self, expr)
+	}
+}
+
+/// Replace the calls of the functions of a file by their body.
+///
+/// Functions are free `fn`s, called as `name(…)`, and methods of impls, called as `Self::name(…)`
+/// or `<Self as WeightInfo>::name(…)`. Only functions whose body is a single expression are
+/// inlined; their arguments replace the parameters.
+pub(crate) fn inline_calls(file: &mut syn::File) {
+	// Only the called functions are cloned; most files call none.
+	let calls = Uses::of(file).calls;
+	let function = |sig: &syn::Signature, block: &syn::Block| {
+		let name = sig.ident.to_string();
+		if !calls.contains(&name) {
+			return None
+		}
+		Some((name, Function::of(sig, block)?))
+	};
+	let free = file
+		.items
+		.iter()
+		.filter_map(|item| match item {
+			Item::Fn(f) => function(&f.sig, &f.block),
+			_ => None,
+		})
+		.collect::<HashMap<_, _>>();
+	let methods = |imp: &syn::ItemImpl| {
+		imp.items
+			.iter()
+			.filter_map(|item| match item {
+				ImplItem::Fn(f) => function(&f.sig, &f.block),
+				_ => None,
+			})
+			.collect::<HashMap<_, _>>()
+	};
+	// Helpers of a weight impl are often in an inherent impl of the same type.
+	let inherent = file
+		.items
+		.iter()
+		.filter_map(|item| match item {
+			Item::Impl(imp) if imp.trait_.is_none() => Some(methods(imp)),
+			_ => None,
+		})
+		.flatten()
+		.collect::<HashMap<_, _>>();
+
+	for item in &mut file.items {
+		let methods = match item {
+			Item::Impl(imp) => methods(imp),
+			_ => HashMap::new(),
+		};
+		if free.is_empty() && inherent.is_empty() && methods.is_empty() {
+			continue
+		}
+		let mut inliner = CallInliner { free: &free, inherent: &inherent, methods, depth: 0 };
+		syn::visit_mut::VisitMut::visit_item_mut(&mut inliner, item);
+	}
+}
+
+/// A function whose calls can be inlined.
+struct Function {
+	params: Vec<String>,
+	body: Expr,
+}
+
+impl Function {
+	fn of(sig: &syn::Signature, block: &syn::Block) -> Option<Self> {
+		let params = sig
+			.inputs
+			.iter()
+			.map(|arg| match arg {
+				syn::FnArg::Typed(arg) => match arg.pat.as_ref() {
+					syn::Pat::Ident(p) => Some(p.ident.to_string()),
+					_ => None,
+				},
+				syn::FnArg::Receiver(_) => None,
+			})
+			.collect::<Option<Vec<_>>>()?;
+		Some(Self { params, body: block_expr(block).ok()?.clone() })
+	}
+
+	/// The body with the arguments in place of the parameters.
+	fn call(&self, args: &Punctuated<Expr, Token![,]>) -> Option<Expr> {
+		if args.len() != self.params.len() {
+			return None
+		}
+		let args = self.params.iter().cloned().zip(args.iter().cloned()).collect();
+		let mut body = self.body.clone();
+		syn::visit_mut::VisitMut::visit_expr_mut(&mut Substitute(args), &mut body);
+		Some(paren(body))
+	}
+}
+
+/// Replaces the parameters of a function by its arguments.
+struct Substitute(HashMap<String, Expr>);
+
+impl syn::visit_mut::VisitMut for Substitute {
+	fn visit_expr_mut(&mut self, expr: &mut Expr) {
+		let arg = match &*expr {
+			Expr::Path(p) => p.path.get_ident().and_then(|i| self.0.get(&i.to_string())),
+			_ => None,
+		};
+		match arg {
+			Some(arg) => *expr = paren(arg.clone()),
+			None => syn::visit_mut::visit_expr_mut(self, expr),
+		}
+	}
+}
+
+struct CallInliner<'a> {
+	free: &'a HashMap<String, Function>,
+	inherent: &'a HashMap<String, Function>,
+	/// Methods of the visited impl; they shadow the inherent methods.
+	methods: HashMap<String, Function>,
+	/// Functions that call other functions are inlined recursively up to a limit.
+	depth: usize,
 }
 
-pub(crate) fn handle_item(pallet: PalletName, item: &Item) -> Result<Vec<ChromaticExtrinsic>> {
+impl CallInliner<'_> {
+	fn method(&self, name: &syn::Ident) -> Option<&Function> {
+		let name = name.to_string();
+		self.methods.get(&name).or_else(|| self.inherent.get(&name))
+	}
+}
+
+impl syn::visit_mut::VisitMut for CallInliner<'_> {
+	fn visit_expr_mut(&mut self, expr: &mut Expr) {
+		let body = match &*expr {
+			Expr::Call(call) => match call.func.as_ref() {
+				Expr::Path(p) => {
+					let segments = p.path.segments.iter().collect::<Vec<_>>();
+					let function = match (&p.qself, segments.as_slice()) {
+						(None, [name]) => self.free.get(&name.ident.to_string()),
+						(None, [ty, name]) if ty.ident == "Self" => self.method(&name.ident),
+						(Some(q), [.., name]) if q.ty.to_token_stream().to_string() == "Self" =>
+							self.method(&name.ident),
+						_ => None,
+					};
+					function.and_then(|f| f.call(&call.args))
+				},
+				_ => None,
+			},
+			_ => None,
+		};
+		match body {
+			Some(body) if self.depth < 16 => {
+				*expr = body;
+				self.depth += 1;
+				syn::visit_mut::visit_expr_mut(self, expr);
+				self.depth -= 1;
+			},
+			_ => syn::visit_mut::visit_expr_mut(self, expr),
+		}
+	}
+}
+
+fn paren(expr: Expr) -> Expr {
+	Expr::Paren(syn::ExprParen {
+		attrs: Vec::new(),
+		paren_token: Default::default(),
+		expr: Box::new(expr),
+	})
+}
+
+pub(crate) fn handle_item(pallet: &PalletName, item: &Item) -> Result<Vec<ChromaticExtrinsic>> {
 	match item {
 		Item::Impl(imp) => {
 			match imp.self_ty.as_ref() {
@@ -149,6 +612,8 @@ pub(crate) fn handle_item(pallet: PalletName, item: &Item) -> Result<Vec<Chromat
 				_ => return Err("Skipped fn: impl type".into()),
 			}
 			// TODO validate the trait type.
+			let pallet_id =
+				imp.trait_.as_ref().and_then(|(_, path, _)| PalletId::of_trait(path, pallet));
 			let mut weights = Vec::new();
 			for f in &imp.items {
 				if let ImplItem::Fn(m) = f {
@@ -159,6 +624,8 @@ pub(crate) fn handle_item(pallet: PalletName, item: &Item) -> Result<Vec<Chromat
 						pallet: pallet.clone(),
 						term,
 						comp_ranges,
+						span: Some(SourceSpan::of(m)),
+						pallet_id: pallet_id.clone(),
 					});
 				}
 			}
@@ -183,7 +650,6 @@ pub(crate) fn handle_item(pallet: PalletName, item: &Item) -> Result<Vec<Chromat
 ///   ("c", (1_337, =2000))
 fn parse_component_attr(attr: &Attribute) -> Result<Option<(ComponentName, ComponentRange)>> {
 	lazy_static! {
-		// TODO syn seems to put a ="…" around the comment.
 		static ref REGEX: Regex = Regex::new(
 			r#"[\w\s]*`(?P<component>\w+)`[\w\s]*`\[(?P<min>[\d_]+),\s*(?P<max>[\d_]+)\]`.*"#
 		)
@@ -192,13 +658,20 @@ fn parse_component_attr(attr: &Attribute) -> Result<Option<(ComponentName, Compo
 
 	let input = match &attr.meta {
 		syn::Meta::NameValue(syn::MetaNameValue { path, value, .. }) => {
-			if path_to_string(path, None) != "doc" {
+			if !path.is_ident("doc") {
 				return Ok(None)
 			}
-			value.to_token_stream().to_string()
+			match value {
+				Expr::Lit(syn::ExprLit { lit: Lit::Str(doc), .. }) => doc.value(),
+				_ => return Ok(None),
+			}
 		},
 		_ => return Ok(None),
 	};
+	// Most doc lines are no ranges; the regex is slow on them.
+	if !input.contains('[') {
+		return Ok(None)
+	}
 	let caps = REGEX.captures(&input).expect("Regex is known good");
 	if caps.is_none() {
 		return Ok(None)
@@ -284,7 +757,7 @@ fn handle_method(
 	// as global constants could mess up the counting.
 	let comp_ranges = parse_component_attrs(&m.attrs)?;
 
-	Ok((name, weight, comp_ranges))
+	Ok((name.into(), weight, comp_ranges))
 }
 
 pub(crate) fn parse_expression(expr: &Expr) -> Result<ChromaticTerm> {
@@ -299,6 +772,11 @@ pub(crate) fn parse_expression(expr: &Expr) -> Result<ChromaticTerm> {
 			Ok(ChromaticTerm::Var(ident.into()))
 		},
 		Expr::Call(call) => parse_call(call),
+		Expr::Block(block) => parse_expression(block_expr(&block.block)?),
+		Expr::If(_) | Expr::Match(_) => {
+			let arms = branch_arms(expr)?.into_iter().map(parse_expression);
+			Ok(ChromaticTerm::Branch(flatten_arms(arms.collect::<Result<_>>()?)))
+		},
 		e => Err(format!("Unexpected expression in pallet expr: {:?}", e.into_token_stream())),
 	}
 }
@@ -314,10 +792,50 @@ pub(crate) fn parse_scalar_expression(expr: &Expr) -> Result<Term<u128>> {
 			Ok(Term::Var(ident.into()))
 		},
 		Expr::Call(call) => parse_scalar_call(call),
+		Expr::Block(block) => parse_scalar_expression(block_expr(&block.block)?),
+		Expr::If(_) | Expr::Match(_) => {
+			let arms = branch_arms(expr)?.into_iter().map(parse_scalar_expression);
+			Ok(Term::Branch(flatten_arms(arms.collect::<Result<_>>()?)))
+		},
 		e => Err(format!("Expected scalar but got: {:?}", e.into_token_stream())),
 	}
 }
 
+/// The expressions of the arms of an `if` with `else` or of a `match`.
+///
+/// The conditions are ignored; eg. `if s > 0 { a } else { b }` has the arms `a` and `b`.
+fn branch_arms(expr: &Expr) -> Result<Vec<&Expr>> {
+	match expr {
+		Expr::If(expr_if) => {
+			let (_, else_branch) = expr_if
+				.else_branch
+				.as_ref()
+				.ok_or("An `if` needs an `else` to return a weight")?;
+			Ok(vec![block_expr(&expr_if.then_branch)?, else_branch.as_ref()])
+		},
+		Expr::Match(expr_match) => Ok(expr_match.arms.iter().map(|a| a.body.as_ref()).collect()),
+		_ => Err("Expected an `if` or `match`".into()),
+	}
+}
+
+/// Merge the arms of nested branches, eg. of `else if`.
+fn flatten_arms<T>(arms: Vec<Term<T>>) -> Vec<Term<T>> {
+	arms.into_iter()
+		.flat_map(|arm| match arm {
+			Term::Branch(inner) => inner,
+			arm => vec![arm],
+		})
+		.collect()
+}
+
+/// The expression of a block that consists of only that expression.
+fn block_expr(block: &syn::Block) -> Result<&Expr> {
+	match block.stmts.as_slice() {
+		[Stmt::Expr(expr, None)] => Ok(expr),
+		_ => Err("Expected a block with a single expression".into()),
+	}
+}
+
 // Example: T::DbWeight::get()
 fn validate_db_call(call: &Expr) -> Result<()> {
 	match call {
@@ -370,6 +888,12 @@ fn parse_call(call: &ExprCall) -> Result<ChromaticTerm> {
 			return Err("Unexpected arguments for `zero`".into())
 		}
 		Ok(ChromaticTerm::Value(Zero::zero()))
+	} else if let Some(cmp) = cmp_function(&name) {
+		let (a, b) = extract_two_args(&call.args)?;
+		let (a, b) = (parse_expression(a)?.into(), parse_expression(b)?.into());
+		Ok(if cmp == "min" { ChromaticTerm::Min(a, b) } else { ChromaticTerm::Max(a, b) })
+	} else if is_conversion(&name) {
+		parse_args(&call.args)
 	} else {
 		Err(format!("Unexpected call: {}", name))
 	}
@@ -387,11 +911,43 @@ fn parse_scalar_call(call: &ExprCall) -> Result<SimpleTerm> {
 			return Err("Unexpected arguments for `zero`".into())
 		}
 		Ok(SimpleTerm::Value(Zero::zero()))
+	} else if let Some(cmp) = cmp_function(&name) {
+		let (a, b) = extract_two_args(&call.args)?;
+		let (a, b) = (parse_scalar_expression(a)?.into(), parse_scalar_expression(b)?.into());
+		Ok(if cmp == "min" { SimpleTerm::Min(a, b) } else { SimpleTerm::Max(a, b) })
+	} else if is_conversion(&name) {
+		parse_scalar_args(&call.args)
 	} else {
 		Err(format!("Unexpected call: {}", name))
 	}
 }
 
+/// Whether the function converts an integer, eg. `u64::from` or `From::from`.
+fn is_conversion(name: &str) -> bool {
+	let Some((ty, function)) = name.rsplit_once("::") else { return false };
+	let ty = ty.rsplit("::").next().unwrap_or(ty);
+	match function {
+		"from" | "saturated_from" | "unique_saturated_from" =>
+			["u8", "u16", "u32", "u64", "u128", "usize", "From"].contains(&ty),
+		"into" => ty == "Into",
+		_ => false,
+	}
+}
+
+/// `min` or `max` if the function compares two values, eg. `core::cmp::max` or `Weight::max`.
+fn cmp_function(name: &str) -> Option<&'static str> {
+	let (path, cmp) = match name.rsplit_once("::") {
+		Some((path, cmp)) => (Some(path), cmp),
+		None => (None, name),
+	};
+	let cmp = ["min", "max"].into_iter().find(|c| *c == cmp)?;
+	match path {
+		None => Some(cmp),
+		Some(path) if path.ends_with("cmp") || path.ends_with("Weight") => Some(cmp),
+		Some(_) => None,
+	}
+}
+
 pub(crate) fn parse_parts_args(args: &Punctuated<Expr, Token![,]>) -> Result<ChromaticTerm> {
 	if args.len() != 2 {
 		return Err(format!("Expected two arguments for `from_parts`, got {}", args.len()))
@@ -466,7 +1022,19 @@ pub(crate) fn parse_method_call(call: &ExprMethodCall) -> Result<ChromaticTerm>
 			parse_expression(&call.receiver)?.into(),
 			parse_args(&call.args)?.into(),
 		)),
-		"into" => parse_expression(&call.receiver),
+		"saturating_sub" => Ok(ChromaticTerm::Sub(
+			parse_expression(&call.receiver)?.into(),
+			parse_args(&call.args)?.into(),
+		)),
+		"min" => Ok(ChromaticTerm::Min(
+			parse_expression(&call.receiver)?.into(),
+			parse_args(&call.args)?.into(),
+		)),
+		"max" => Ok(ChromaticTerm::Max(
+			parse_expression(&call.receiver)?.into(),
+			parse_args(&call.args)?.into(),
+		)),
+		"into" | "saturated_into" | "unique_saturated_into" => parse_expression(&call.receiver),
 		_ => Err(format!("Unknown function: {}", name)),
 	}
 }
@@ -501,7 +1069,20 @@ pub(crate) fn parse_scalar_method_call(call: &ExprMethodCall) -> Result<Term<u12
 			parse_scalar_expression(&call.receiver)?.into(),
 			parse_scalar_args(&call.args)?.into(),
 		)),
-		"into" => parse_scalar_expression(&call.receiver),
+		"saturating_sub" => Ok(Term::Sub(
+			parse_scalar_expression(&call.receiver)?.into(),
+			parse_scalar_args(&call.args)?.into(),
+		)),
+		"min" => Ok(Term::Min(
+			parse_scalar_expression(&call.receiver)?.into(),
+			parse_scalar_args(&call.args)?.into(),
+		)),
+		"max" => Ok(Term::Max(
+			parse_scalar_expression(&call.receiver)?.into(),
+			parse_scalar_args(&call.args)?.into(),
+		)),
+		"into" | "saturated_into" | "unique_saturated_into" =>
+			parse_scalar_expression(&call.receiver),
 		_ => Err(format!("Unknown function: {}", name)),
 	}
 }
@@ -513,6 +1094,13 @@ fn extract_arg(args: &Punctuated<Expr, Token![,]>) -> Result<&Expr> {
 	args.first().ok_or_else(|| "Empty args".into())
 }
 
+fn extract_two_args(args: &Punctuated<Expr, Token![,]>) -> Result<(&Expr, &Expr)> {
+	match args.iter().collect::<Vec<_>>().as_slice() {
+		[a, b] => Ok((a, b)),
+		_ => Err(format!("Expected two arguments, got {}", args.len())),
+	}
+}
+
 fn parse_args(args: &Punctuated<Expr, Token![,]>) -> Result<ChromaticTerm> {
 	let arg = extract_arg(args)?;
 	parse_expression(arg)
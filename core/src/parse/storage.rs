@@ -1,7 +1,12 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use syn::{BinOp, Expr, ExprStruct, Item, ItemConst, Type};
 
-use crate::{parse::path_to_string, term::SimpleTerm as Term};
+use crate::{
+	parse::{pallet::ChromaticExtrinsic, path_to_string},
+	scope::SimpleScope,
+	term::SimpleTerm as Term,
+	Dimension, SwcError,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Db {
@@ -9,6 +14,16 @@ pub enum Db {
 	Rocks,
 }
 
+impl Db {
+	/// Name of the constant that defines the weights of this DB.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Parity => "ParityDbWeight",
+			Self::Rocks => "RocksDbWeight",
+		}
+	}
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct RWs {
 	pub read: Term,
@@ -21,6 +36,32 @@ pub struct Weights {
 	pub weights: RWs,
 }
 
+impl Weights {
+	/// Converts the weights into a `read` and `write` extrinsic of the DB backend.
+	///
+	/// The backend name is used as pallet name such that the same backends are compared to each
+	/// other. The `WEIGHT_PER_NANOS` constants are substituted since they are not components.
+	pub fn into_extrinsics(self) -> Vec<ChromaticExtrinsic> {
+		let pallet = self.db.name();
+		[("read", self.weights.read), ("write", self.weights.write)]
+			.into_iter()
+			.map(|(name, mut term)| {
+				for (var, value) in SimpleScope::from_substrate().as_vec() {
+					term.substitute(&var, &value);
+				}
+				ChromaticExtrinsic {
+					name: name.into(),
+					pallet: pallet.into(),
+					term: term.into_chromatic(Dimension::Time),
+					comp_ranges: None,
+					span: None,
+					pallet_id: None,
+				}
+			})
+			.collect()
+	}
+}
+
 /// Multiplies a [`Term`] with the [`crate::scope::STORAGE_READ_VAR`] constant.
 #[macro_export]
 macro_rules! reads {
@@ -56,9 +97,30 @@ macro_rules! cwrites {
 /// Parses a storage weight file.
 ///
 /// These files are often named: `paritydb_weights.rs.txt` or `rocksdb_weights.rs.txt`.
-pub fn parse_file(file: &Path) -> Result<Weights, String> {
+pub fn parse_file(file: &Path) -> Result<Weights, SwcError> {
 	let content = super::read_file(file)?;
-	parse_content(content)
+	parse_content(content).map_err(SwcError::Parse)
+}
+
+/// Parses storage weight files into one `read` and `write` extrinsic per DB backend.
+pub fn parse_files(paths: &[PathBuf]) -> Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let mut res = Vec::new();
+	for path in paths {
+		let weights =
+			parse_file(path).map_err(|e| SwcError::Parse(format!("{}: {}", path.display(), e)))?;
+		res.extend(weights.into_extrinsics());
+	}
+	Ok(res)
+}
+
+pub fn try_parse_files(paths: &[PathBuf]) -> Vec<ChromaticExtrinsic> {
+	let mut res = Vec::new();
+	for path in paths {
+		if let Ok(weights) = parse_file(path) {
+			res.extend(weights.into_extrinsics());
+		}
+	}
+	res
 }
 
 pub fn parse_content(content: String) -> Result<Weights, String> {
@@ -170,7 +232,7 @@ fn parse_expression(expr: &Expr) -> Result<Term, String> {
 			Ok(term)
 		},
 		Expr::Lit(lit) => Ok(Term::Scalar(super::pallet::lit_to_value(&lit.lit))),
-		Expr::Path(p) => Ok(Term::Var(crate::term::VarValue(path_to_string(&p.path, Some("::"))))),
+		Expr::Path(p) => Ok(Term::Var(path_to_string(&p.path, Some("::")).into())),
 		_ => Err("Unexpected expression storage expr".into()),
 	}
 }
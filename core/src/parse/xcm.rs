@@ -0,0 +1,201 @@
+//! Parses the XCM instruction weights of a runtime.
+//!
+//! The `XcmWeightInfo` implementation in `weights/xcm/mod.rs` defines the weight of each XCM
+//! instruction by calling a function of the `pallet_xcm_benchmarks` weight files next to it:
+//!
+//! ```ignore
+//! mod pallet_xcm_benchmarks_fungible;
+//! use pallet_xcm_benchmarks_fungible::WeightInfo as XcmBalancesWeight;
+//!
+//! impl<RuntimeCall> XcmWeightInfo<RuntimeCall> for KusamaXcmWeight<RuntimeCall> {
+//!     fn withdraw_asset(assets: &MultiAssets) -> Weight {
+//!         assets.weigh_multi_assets(XcmBalancesWeight::<Runtime>::withdraw_asset())
+//!     }
+//! }
+//! ```
+//!
+//! Each instruction is returned as extrinsic of the `mod.rs` file with the weight of its
+//! benchmark. Instructions that are weighed per asset use the weight of a single asset.
+//! Instructions with a weight of `Weight::MAX` are not supported by the runtime and skipped, as
+//! are instructions with a weight formula that cannot be parsed.
+
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+use syn::{Expr, ImplItem, Item, Stmt, UseTree};
+
+use crate::{
+	parse::{
+		pallet::{parse_expression, ChromaticExtrinsic, Result, SourceSpan},
+		path_to_string, PathStripping,
+	},
+	ExtrinsicName, PalletName, SwcError,
+};
+
+pub fn parse_file_in_repo(
+	repo: &Path,
+	file: &Path,
+) -> std::result::Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let content = super::read_file(file)?;
+	let name = PathStripping::RepoRelative.strip(repo, file);
+	let dir = file.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+
+	parse_content(name.into(), content, |module| {
+		super::pallet::parse_file(&dir.join(format!("{}.rs", module))).map_err(|e| e.to_string())
+	})
+	.map_err(|e| SwcError::Parse(format!("{}: {}", file.display(), e)))
+}
+
+/// Whether the content contains an `XcmWeightInfo` implementation.
+pub fn is_xcm_weight_info(content: &str) -> bool {
+	content.contains("XcmWeightInfo")
+}
+
+/// Parse the `XcmWeightInfo` implementation of a runtime.
+///
+/// `benchmarks` parses the weight file of a module that is declared next to the implementation.
+pub fn parse_content(
+	pallet: PalletName,
+	content: String,
+	benchmarks: impl Fn(&str) -> Result<Vec<ChromaticExtrinsic>>,
+) -> Result<Vec<ChromaticExtrinsic>> {
+	let ast = syn::parse_file(&content)
+		.map_err(|e| format!("syn refused to parse content: {:?}: {}", content, e))?;
+
+	let mut aliases = HashMap::new();
+	for item in &ast.items {
+		if let Item::Use(u) = item {
+			collect_aliases(&u.tree, None, &mut aliases);
+		}
+	}
+	let imp = ast
+		.items
+		.iter()
+		.find_map(|item| match item {
+			Item::Impl(imp)
+				if imp.trait_.as_ref().map_or(false, |(_, t, _)| {
+					t.segments.last().map_or(false, |s| s.ident == "XcmWeightInfo")
+				}) =>
+				Some(imp),
+			_ => None,
+		})
+		.ok_or("No XcmWeightInfo impl found")?;
+
+	let mut parsed = HashMap::<String, Vec<ChromaticExtrinsic>>::new();
+	let mut weights = Vec::new();
+	for item in &imp.items {
+		let ImplItem::Fn(m) = item else { continue };
+		let name: ExtrinsicName = m.sig.ident.to_string().into();
+		let expr = match m.block.stmts.as_slice() {
+			[Stmt::Expr(expr, None)] => expr,
+			_ => {
+				log::debug!("Skipped XCM instruction {}: more than one statement", name);
+				continue
+			},
+		};
+
+		let Some(instruction) = resolve(expr, &aliases)? else { continue };
+		let ext = match instruction {
+			Instruction::Hardcoded(expr) => match parse_expression(expr) {
+				Ok(term) => ChromaticExtrinsic {
+					name,
+					pallet: pallet.clone(),
+					term,
+					comp_ranges: None,
+					span: Some(SourceSpan::of(m)),
+					pallet_id: None,
+				},
+				Err(err) => {
+					log::warn!("Skipped XCM instruction {}: {}", name, err);
+					continue
+				},
+			},
+			Instruction::Benchmark { module, function } => {
+				if !parsed.contains_key(&module) {
+					parsed.insert(module.clone(), benchmarks(&module)?);
+				}
+				let bench =
+					parsed[&module].iter().find(|e| e.name == function).ok_or_else(|| {
+						format!(
+							"Benchmark {}::{} of instruction {} not found",
+							module, function, name
+						)
+					})?;
+				ChromaticExtrinsic {
+					name,
+					pallet: pallet.clone(),
+					term: bench.term.clone(),
+					comp_ranges: bench.comp_ranges.clone(),
+					span: Some(SourceSpan::of(m)),
+					pallet_id: None,
+				}
+			},
+		};
+		weights.push(ext);
+	}
+
+	if weights.is_empty() {
+		Err("No XCM instructions found in XcmWeightInfo impl".into())
+	} else {
+		Ok(weights)
+	}
+}
+
+enum Instruction<'a> {
+	/// A function of a benchmark weight file.
+	Benchmark {
+		module: String,
+		function: String,
+	},
+	Hardcoded(&'a Expr),
+}
+
+/// Resolve the weight expression of an instruction. Returns `None` for unsupported instructions.
+fn resolve<'a>(
+	expr: &'a Expr,
+	aliases: &HashMap<String, String>,
+) -> Result<Option<Instruction<'a>>> {
+	match expr {
+		// Example: assets.weigh_multi_assets(XcmBalancesWeight::<Runtime>::withdraw_asset())
+		Expr::MethodCall(call) if call.method.to_string().starts_with("weigh_") => {
+			match call.args.first() {
+				Some(arg) if call.args.len() == 1 => resolve(arg, aliases),
+				_ => Err(format!("Expected one argument for `{}`", call.method)),
+			}
+		},
+		// Example: XcmGeneric::<Runtime>::query_response()
+		Expr::Call(call) if call.args.is_empty() => {
+			let Expr::Path(p) = call.func.as_ref() else {
+				return Err("Unexpected XCM weight function".into())
+			};
+			let segments = p.path.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>();
+			match segments.as_slice() {
+				[alias, function] if aliases.contains_key(alias) =>
+					Ok(Some(Instruction::Benchmark {
+						module: aliases[alias].clone(),
+						function: function.clone(),
+					})),
+				_ => Ok(Some(Instruction::Hardcoded(expr))),
+			}
+		},
+		Expr::Path(p) if path_to_string(&p.path, Some("::")) == "Weight::MAX" => Ok(None),
+		expr => Ok(Some(Instruction::Hardcoded(expr))),
+	}
+}
+
+/// Collect imports like `use pallet_xcm_benchmarks_fungible::WeightInfo as XcmBalancesWeight;`.
+fn collect_aliases(tree: &UseTree, module: Option<String>, aliases: &mut HashMap<String, String>) {
+	match tree {
+		UseTree::Path(p) => collect_aliases(&p.tree, Some(p.ident.to_string()), aliases),
+		UseTree::Group(g) =>
+			for tree in &g.items {
+				collect_aliases(tree, module.clone(), aliases);
+			},
+		UseTree::Rename(r) if r.ident == "WeightInfo" =>
+			if let Some(module) = module {
+				aliases.insert(r.rename.to_string(), module);
+			},
+		_ => {},
+	}
+}
@@ -0,0 +1,60 @@
+//! Report the progress of long running comparisons, eg. to show a progress bar.
+
+use std::{path::Path, time::Duration};
+
+/// Callbacks that are invoked while comparing commits.
+///
+/// All methods do nothing by default and `()` ignores any progress. Both commits are parsed at the
+/// same time, so the callbacks of one commit can be interleaved with those of the other.
+pub trait Progress: Sync {
+	/// Objects were received while fetching `refname`.
+	fn fetched(&self, _refname: &str, _received: usize, _total: usize) {}
+
+	/// Fetching `refname` from `origin` finished after `took`.
+	fn fetch_finished(&self, _refname: &str, _took: Duration) {}
+
+	/// The path pattern matched `count` files at `refname`.
+	fn files_discovered(&self, _refname: &str, _count: usize) {}
+
+	/// A weight file was parsed. Also called when it failed but errors are ignored.
+	fn file_parsed(&self, _file: &Path) {}
+
+	/// All weight files of `refname` were parsed after `took`. Not called for cached commits.
+	fn files_parsed(&self, _refname: &str, _took: Duration) {}
+
+	/// The scopes of `done` out of `total` extrinsics were evaluated.
+	fn extrinsic_compared(&self, _done: usize, _total: usize) {}
+
+	/// Whether the comparison should stop with [`SwcError::Cancelled`](crate::SwcError::Cancelled).
+	///
+	/// Checked between fetched objects, parsed files and compared extrinsics.
+	fn cancelled(&self) -> bool {
+		false
+	}
+}
+
+impl Progress for () {}
+
+/// How far a comparison got, eg. `Parsing v1.0.0 (12/80)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stage {
+	pub name: String,
+	pub done: usize,
+	pub total: usize,
+}
+
+impl Stage {
+	pub fn new(name: impl Into<String>, done: usize, total: usize) -> Self {
+		Self { name: name.into(), done, total }
+	}
+}
+
+impl std::fmt::Display for Stage {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if self.total == 0 {
+			write!(f, "{}", self.name)
+		} else {
+			write!(f, "{} ({}/{})", self.name, self.done, self.total)
+		}
+	}
+}
@@ -0,0 +1,191 @@
+//! Fetch weight files from a GitHub repository without a local clone.
+//!
+//! The file tree of a ref is listed with the GitHub API and only the files that match the path
+//! pattern are downloaded. Set `GITHUB_TOKEN` to avoid hitting the API rate limit.
+
+use std::path::Path;
+
+use crate::{
+	compare_files,
+	parse::pallet::{parse_files_in_repo, try_parse_files_in_repo, ChromaticExtrinsic},
+	CompareParams, FilterParams, SwcError, TotalDiff,
+};
+
+/// A repository that is hosted on GitHub.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GithubRepo {
+	pub owner: String,
+	pub name: String,
+}
+
+impl std::str::FromStr for GithubRepo {
+	type Err = String;
+
+	/// Parse a repository from its URL like `https://github.com/paritytech/polkadot-sdk`.
+	fn from_str(url: &str) -> Result<Self, String> {
+		let path = url
+			.trim_end_matches('/')
+			.trim_end_matches(".git")
+			.strip_prefix("https://github.com/")
+			.ok_or_else(|| format!("Not a GitHub repository URL: {}", url))?;
+
+		match path.split('/').collect::<Vec<_>>().as_slice() {
+			[owner, name] if !owner.is_empty() && !name.is_empty() =>
+				Ok(Self { owner: owner.to_string(), name: name.to_string() }),
+			_ => Err(format!("Not a GitHub repository URL: {}", url)),
+		}
+	}
+}
+
+impl GithubRepo {
+	/// URL to clone the repository from.
+	pub fn url(&self) -> String {
+		format!("https://github.com/{}/{}", self.owner, self.name)
+	}
+
+	/// List all files of the ref that match any of the comma separated glob patterns.
+	///
+	/// Patterns that start with `!` exclude files instead.
+	pub fn list_files(
+		&self,
+		refname: &str,
+		path_pattern: &str,
+		max_files: usize,
+	) -> Result<Vec<String>, String> {
+		let (include, exclude) = crate::split_path_pattern(path_pattern)?;
+		let patterns = include
+			.into_iter()
+			.map(glob::Pattern::new)
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| format!("Invalid path pattern: {:?}", e))?;
+		// Same as for local files: `*` does not match across folders.
+		let options = glob::MatchOptions { require_literal_separator: true, ..Default::default() };
+
+		let url = format!(
+			"https://api.github.com/repos/{}/{}/git/trees/{}?recursive=1",
+			self.owner, self.name, refname
+		);
+		let tree: serde_json::Value = serde_json::from_str(&get(&url)?)
+			.map_err(|e| format!("Invalid response from {}: {}", url, e))?;
+		if tree["truncated"].as_bool() == Some(true) {
+			log::warn!("The file tree of {} is truncated; some files may be missing", refname);
+		}
+		let entries = tree["tree"]
+			.as_array()
+			.ok_or_else(|| format!("Invalid response from {}: no tree", url))?;
+
+		let mut paths = entries
+			.iter()
+			.filter(|e| e["type"] == "blob")
+			.filter_map(|e| e["path"].as_str())
+			.filter(|p| !p.ends_with("mod.rs") || p.ends_with("xcm/mod.rs"))
+			.filter(|p| patterns.iter().any(|pattern| pattern.matches_with(p, options)))
+			.filter(|p| !exclude.iter().any(|pattern| pattern.matches_with(p, options)))
+			.map(String::from)
+			.collect::<Vec<_>>();
+		if paths.len() > max_files {
+			return Err(format!("Found too many files. Found: {}, Max: {}", paths.len(), max_files))
+		}
+		paths.sort();
+		Ok(paths)
+	}
+
+	/// Download the files of the ref into the directory `into`.
+	///
+	/// The paths in the directory are the same as in the repository.
+	pub fn download(&self, refname: &str, files: &[String], into: &Path) -> Result<(), String> {
+		for file in files {
+			let url = format!(
+				"https://raw.githubusercontent.com/{}/{}/{}/{}",
+				self.owner, self.name, refname, file
+			);
+			log::info!("Downloading {}", url);
+			let content = get(&url)?;
+
+			let path = into.join(file);
+			if let Some(parent) = path.parent() {
+				std::fs::create_dir_all(parent)
+					.map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+			}
+			std::fs::write(&path, content)
+				.map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+		}
+		Ok(())
+	}
+}
+
+/// Download a single weight file, eg. from `https://raw.githubusercontent.com/…`.
+///
+/// The `GITHUB_TOKEN` is only sent to GitHub.
+pub fn fetch_url(url: &str) -> Result<String, SwcError> {
+	get(url).map_err(SwcError::Remote)
+}
+
+/// Hosts that get the `GITHUB_TOKEN`.
+const GITHUB_HOSTS: [&str; 2] = ["https://api.github.com/", "https://raw.githubusercontent.com/"];
+
+fn get(url: &str) -> Result<String, String> {
+	let mut request = ureq::get(url).set("User-Agent", "subweight");
+	if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+		if GITHUB_HOSTS.iter().any(|host| url.starts_with(host)) {
+			request = request.set("Authorization", &format!("Bearer {}", token));
+		}
+	}
+	request
+		.call()
+		.map_err(|e| format!("Failed to fetch {}: {}", url, e))?
+		.into_string()
+		.map_err(|e| format!("Failed to read {}: {}", url, e))
+}
+
+/// Compare two refs of a GitHub repository without the need of a local clone.
+///
+/// Only the weight files that match the `path_pattern` are downloaded.
+pub fn compare_remote(
+	remote: &GithubRepo,
+	old: &str,
+	new: &str,
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+) -> Result<TotalDiff, SwcError> {
+	if params.offline {
+		return Err(SwcError::InvalidInput(
+			"Cannot compare a remote repository in offline mode".into(),
+		))
+	}
+	if path_pattern.contains("..") {
+		return Err(SwcError::Glob("Path pattern cannot contain '..'".into()))
+	}
+	if params.auto_discover {
+		// It would download every file of the path pattern.
+		return Err(SwcError::InvalidInput(
+			"Auto discovery needs a clone of the remote repository".into(),
+		))
+	}
+	let olds = parse_remote_commit(remote, old, params, path_pattern, max_files)?;
+	let news = parse_remote_commit(remote, new, params, path_pattern, max_files)?;
+
+	compare_files(olds, news, params, filter)
+}
+
+fn parse_remote_commit(
+	remote: &GithubRepo,
+	refname: &str,
+	params: &CompareParams,
+	path_pattern: &str,
+	max_files: usize,
+) -> Result<Vec<ChromaticExtrinsic>, SwcError> {
+	let files = remote.list_files(refname, path_pattern, max_files).map_err(SwcError::Remote)?;
+	let dir = tempfile::Builder::new().prefix("subweight-").tempdir()?;
+	remote.download(refname, &files, dir.path()).map_err(SwcError::Remote)?;
+
+	let paths = files.iter().map(|f| dir.path().join(f)).collect::<Vec<_>>();
+	// Ignore any parsing errors.
+	if params.ignore_errors {
+		Ok(try_parse_files_in_repo(dir.path(), &paths))
+	} else {
+		Ok(parse_files_in_repo(dir.path(), &paths)?)
+	}
+}
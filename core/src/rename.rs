@@ -0,0 +1,166 @@
+//! Detect extrinsics that were renamed between two versions, eg. `set_balance` →
+//! `force_set_balance`, and weight files that moved.
+//!
+//! Without this, a renamed extrinsic shows up as one removed and one added extrinsic and its weight
+//! is not compared. An extrinsic counts as renamed if it was removed and an added extrinsic of the
+//! same pallet has the same components and a similar name.
+//!
+//! A moved file would likewise show up with all its extrinsics removed and added again since the
+//! pallet name is the path of the file. Git detects most moves; [`detect_moves`] is the fallback.
+
+use crate::{
+	parse::pallet::{ChromaticExtrinsic, PalletId},
+	scope::{ChromaticScope, STORAGE_READ_VAR, STORAGE_WRITE_VAR},
+	ExtrinsicName, PalletName,
+};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::Path,
+};
+
+/// Minimal [`name_similarity`] of a rename.
+pub const MIN_SIMILARITY: f64 = 0.5;
+
+/// An extrinsic of a pallet that got a new name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+	pub pallet: PalletName,
+	pub old: ExtrinsicName,
+	pub new: ExtrinsicName,
+}
+
+/// A weight file that got a new path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+	pub old: PalletName,
+	pub new: PalletName,
+}
+
+/// Match the removed with the added files by their file name, or else by their [`PalletId`].
+///
+/// A file only counts as moved if no other removed or added file has the same name, eg.
+/// `runtime/polkadot/src/weights/pallet_balances.rs` →
+/// `relay/polkadot/src/weights/pallet_balances.rs`. The same goes for the pallet id, which matches
+/// `pallet_collective_council.rs` with `collective_council.rs` but never with
+/// `pallet_collective_technical_committee.rs`.
+pub fn detect_moves(olds: &[ChromaticExtrinsic], news: &[ChromaticExtrinsic]) -> Vec<Move> {
+	let pallets = |exts: &[ChromaticExtrinsic]| {
+		let mut pallets = BTreeMap::new();
+		for ext in exts {
+			pallets.entry(ext.pallet.clone()).or_insert_with(|| ext.pallet_id.clone());
+		}
+		pallets
+	};
+	let (old_pallets, new_pallets) = (pallets(olds), pallets(news));
+	let mut removed = old_pallets
+		.iter()
+		.filter(|(p, _)| !new_pallets.contains_key(*p))
+		.collect::<Vec<_>>();
+	let mut added = new_pallets
+		.iter()
+		.filter(|(p, _)| !old_pallets.contains_key(*p))
+		.collect::<Vec<_>>();
+
+	fn file_name(pallet: &PalletName, _: &Option<PalletId>) -> Option<String> {
+		Path::new(pallet.as_str()).file_name().map(|n| n.to_string_lossy().into_owned())
+	}
+	fn pallet_id(_: &PalletName, id: &Option<PalletId>) -> Option<String> {
+		id.as_ref().map(PalletId::to_string)
+	}
+	type Key = fn(&PalletName, &Option<PalletId>) -> Option<String>;
+
+	let mut moves = Vec::new();
+	for key in [file_name as Key, pallet_id] {
+		let unique = |files: &[(&PalletName, &Option<PalletId>)], k: &String| {
+			let same =
+				files.iter().filter(|f| key(f.0, f.1).as_ref() == Some(k)).collect::<Vec<_>>();
+			(same.len() == 1).then(|| same[0].0.clone())
+		};
+		let found = removed
+			.iter()
+			.filter_map(|(old, id)| {
+				let k = key(old, id)?;
+				unique(&removed, &k)?;
+				Some(Move { old: (*old).clone(), new: unique(&added, &k)? })
+			})
+			.collect::<Vec<_>>();
+		removed.retain(|(p, _)| !found.iter().any(|m| &m.old == *p));
+		added.retain(|(p, _)| !found.iter().any(|m| &m.new == *p));
+		moves.extend(found);
+	}
+	moves
+}
+
+/// Rename the pallets of the moved files to their new path.
+pub fn apply_moves(exts: &mut [ChromaticExtrinsic], moves: &[Move]) {
+	for ext in exts {
+		if let Some(m) = moves.iter().find(|m| m.old == ext.pallet) {
+			ext.pallet = m.new.clone();
+		}
+	}
+}
+
+/// Match the removed with the added extrinsics of each pallet.
+///
+/// The most similar names are matched first and each extrinsic is part of at most one rename.
+pub fn detect_renames(olds: &[ChromaticExtrinsic], news: &[ChromaticExtrinsic]) -> Vec<Rename> {
+	let contains = |exts: &[ChromaticExtrinsic], ext: &ChromaticExtrinsic| {
+		exts.iter().any(|e| e.pallet == ext.pallet && e.name == ext.name)
+	};
+	let removed = olds.iter().filter(|o| !contains(news, o)).collect::<Vec<_>>();
+	let added = news.iter().filter(|n| !contains(olds, n)).collect::<Vec<_>>();
+
+	let mut candidates = Vec::new();
+	for old in &removed {
+		for new in added.iter().filter(|n| n.pallet == old.pallet) {
+			let similarity = name_similarity(&old.name, &new.name);
+			if similarity >= MIN_SIMILARITY && components(old) == components(new) {
+				candidates.push((similarity, *old, *new));
+			}
+		}
+	}
+	// Names break ties to not depend on the order of the files.
+	candidates.sort_by(|a, b| {
+		b.0.total_cmp(&a.0).then_with(|| {
+			(&a.1.pallet, &a.1.name, &a.2.name).cmp(&(&b.1.pallet, &b.1.name, &b.2.name))
+		})
+	});
+
+	let mut renames = Vec::<Rename>::new();
+	for (_, old, new) in candidates {
+		let taken = renames
+			.iter()
+			.any(|r| r.pallet == old.pallet && (r.old == old.name || r.new == new.name));
+		if !taken {
+			renames.push(Rename {
+				pallet: old.pallet.clone(),
+				old: old.name.clone(),
+				new: new.name.clone(),
+			});
+		}
+	}
+	renames
+}
+
+/// How similar two names are from zero to one; by their words that are separated by `_`.
+///
+/// This is the Dice coefficient of the words, so `set_balance` and `force_set_balance` have a
+/// similarity of 0.8 while `bond` and `unbond` have none.
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+	fn words(name: &str) -> BTreeSet<&str> {
+		name.split('_').filter(|w| !w.is_empty()).collect()
+	}
+	let (a, b) = (words(a), words(b));
+	if a.is_empty() && b.is_empty() {
+		return 0.0
+	}
+	2.0 * a.intersection(&b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+/// The components of an extrinsic; without the storage accesses.
+fn components(ext: &ChromaticExtrinsic) -> BTreeSet<String> {
+	let mut vars = ext.term.free_vars(&ChromaticScope::empty());
+	vars.remove(STORAGE_READ_VAR);
+	vars.remove(STORAGE_WRITE_VAR);
+	vars.into_iter().collect()
+}
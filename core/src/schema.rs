@@ -0,0 +1,59 @@
+//! The versioned JSON output of a comparison.
+//!
+//! `--format json` and the web API wrap the [`TotalDiff`] in a [`VersionedDiff`] so that consumers
+//! can check which schema they got. The [`SCHEMA_VERSION`] is increased on every change that can
+//! break a consumer, like renaming or removing a field. New optional fields do not increase it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{SwcError, TotalDiff};
+
+/// Version of the schema of [`VersionedDiff`].
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A comparison together with the version of its schema.
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct VersionedDiff {
+	/// The [`SCHEMA_VERSION`] that the output was written with.
+	pub schema_version: u32,
+	pub changes: TotalDiff,
+}
+
+impl VersionedDiff {
+	pub fn new(changes: TotalDiff) -> Self {
+		Self { schema_version: SCHEMA_VERSION, changes }
+	}
+
+	/// Parse a comparison that was exported as JSON.
+	///
+	/// Also accepts the plain list of changes that was written before the output was versioned.
+	pub fn from_json(content: &str) -> Result<TotalDiff, SwcError> {
+		#[derive(Deserialize)]
+		struct Version {
+			schema_version: u32,
+		}
+
+		let parse_err = |e: serde_json::Error| SwcError::Parse(e.to_string());
+		if content.trim_start().starts_with('[') {
+			return serde_json::from_str(content).map_err(parse_err)
+		}
+		// Checked first since newer versions may not parse.
+		let version = serde_json::from_str::<Version>(content).map_err(parse_err)?.schema_version;
+		if version > SCHEMA_VERSION {
+			return Err(SwcError::Parse(format!(
+				"Schema version {} is newer than the supported version {}",
+				version, SCHEMA_VERSION
+			)))
+		}
+		serde_json::from_str::<Self>(content).map(|d| d.changes).map_err(parse_err)
+	}
+}
+
+/// The JSON Schema of [`VersionedDiff`] in the current [`SCHEMA_VERSION`].
+#[cfg(feature = "schema")]
+pub fn json_schema() -> serde_json::Value {
+	let mut schema = schemars::schema_for!(VersionedDiff);
+	schema.schema.metadata().title = Some(format!("subweight diff v{}", SCHEMA_VERSION));
+	serde_json::to_value(schema).expect("Schemas are valid JSON")
+}
@@ -13,6 +13,7 @@ pub const STORAGE_WRITE_VAR: &str = "WRITE";
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq)]
 #[cfg_attr(feature = "bloat", derive(Default))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Scope<T> {
 	vars: Map<String, T>,
 }
@@ -32,6 +33,14 @@ impl SimpleScope {
 	pub fn with_storage_weights(self, read: SimpleTerm, write: SimpleTerm) -> Self {
 		self.with_var(STORAGE_READ_VAR, read).with_var(STORAGE_WRITE_VAR, write)
 	}
+
+	/// Set the components to fixed values.
+	pub fn with_components(mut self, values: &[(String, u128)]) -> Self {
+		for (name, value) in values {
+			self.put_var(name, SimpleTerm::Value(*value));
+		}
+		self
+	}
 }
 
 impl<T> Scope<T>
@@ -0,0 +1,150 @@
+//! Compare commits on a tokio runtime with a deadline and cancellation.
+//!
+//! Fetching, checking out and parsing block, so they run on the blocking thread pool of tokio. An
+//! interrupted comparison returns right away with the [`Stage`] that it reached. The blocking work
+//! stops at its next check of [`Progress::cancelled`]; between fetched objects, parsed files and
+//! compared extrinsics.
+
+use std::{
+	path::Path,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	time::Duration,
+};
+
+pub use tokio_util::sync::CancellationToken;
+
+use crate::{
+	compare_commits_with_progress,
+	progress::{Progress, Stage},
+	CompareParams, FilterParams, SwcError, TotalDiff,
+};
+
+/// When to give up on a comparison.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+	/// Stops the comparison once it is cancelled.
+	pub cancel: CancellationToken,
+	/// Stops the comparison when it is reached.
+	pub deadline: Option<tokio::time::Instant>,
+}
+
+impl Limits {
+	/// Stop the comparison after `timeout`.
+	pub fn timeout(timeout: Duration) -> Self {
+		Self { deadline: Some(tokio::time::Instant::now() + timeout), ..Default::default() }
+	}
+}
+
+/// Same as [`compare_commits_with_progress`] but stops when the `limits` are exceeded.
+///
+/// Returns [`SwcError::Cancelled`] or [`SwcError::TimedOut`] with the last reported stage.
+#[allow(clippy::too_many_arguments)]
+pub async fn compare_commits_async(
+	repo: &Path,
+	old: &str,
+	new: &str,
+	params: &CompareParams,
+	filter: &FilterParams,
+	path_pattern: &str,
+	max_files: usize,
+	limits: Limits,
+	progress: Arc<dyn Progress + Send + Sync>,
+) -> Result<TotalDiff, SwcError> {
+	let tracker = Arc::new(Tracker::new(progress));
+	let task = {
+		let (repo, old, new) = (repo.to_path_buf(), old.to_string(), new.to_string());
+		let (params, filter, path_pattern) =
+			(params.clone(), filter.clone(), path_pattern.to_string());
+		let tracker = tracker.clone();
+		tokio::task::spawn_blocking(move || {
+			compare_commits_with_progress(
+				&repo,
+				&old,
+				&new,
+				&params,
+				&filter,
+				&path_pattern,
+				max_files,
+				&*tracker,
+			)
+		})
+	};
+	let deadline = async {
+		match limits.deadline {
+			Some(deadline) => tokio::time::sleep_until(deadline).await,
+			None => std::future::pending().await,
+		}
+	};
+
+	tokio::select! {
+		// An exceeded limit wins over a result that is ready at the same time.
+		biased;
+		_ = limits.cancel.cancelled() => Err(SwcError::Cancelled(tracker.stop())),
+		_ = deadline => Err(SwcError::TimedOut(tracker.stop())),
+		res = task => res.unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic())),
+	}
+}
+
+/// Forwards the progress and remembers the last stage.
+struct Tracker {
+	inner: Arc<dyn Progress + Send + Sync>,
+	stage: Mutex<Stage>,
+	stopped: AtomicBool,
+}
+
+impl Tracker {
+	fn new(inner: Arc<dyn Progress + Send + Sync>) -> Self {
+		Self {
+			inner,
+			stage: Mutex::new(Stage::new("Queued", 0, 0)),
+			stopped: AtomicBool::new(false),
+		}
+	}
+
+	/// Stop the comparison and return its last stage.
+	fn stop(&self) -> Stage {
+		self.stopped.store(true, Ordering::SeqCst);
+		self.stage.lock().expect("Lock is not poisoned").clone()
+	}
+
+	fn set(&self, stage: Stage) {
+		*self.stage.lock().expect("Lock is not poisoned") = stage;
+	}
+}
+
+impl Progress for Tracker {
+	fn fetched(&self, refname: &str, received: usize, total: usize) {
+		self.set(Stage::new(format!("Fetching {}", refname), received, total));
+		self.inner.fetched(refname, received, total);
+	}
+
+	fn fetch_finished(&self, refname: &str, took: Duration) {
+		self.inner.fetch_finished(refname, took);
+	}
+
+	fn files_discovered(&self, refname: &str, count: usize) {
+		self.set(Stage::new(format!("Parsing {}", refname), 0, count));
+		self.inner.files_discovered(refname, count);
+	}
+
+	fn file_parsed(&self, file: &Path) {
+		self.stage.lock().expect("Lock is not poisoned").done += 1;
+		self.inner.file_parsed(file);
+	}
+
+	fn files_parsed(&self, refname: &str, took: Duration) {
+		self.inner.files_parsed(refname, took);
+	}
+
+	fn extrinsic_compared(&self, done: usize, total: usize) {
+		self.set(Stage::new("Comparing", done, total));
+		self.inner.extrinsic_compared(done, total);
+	}
+
+	fn cancelled(&self) -> bool {
+		self.stopped.load(Ordering::SeqCst) || self.inner.cancelled()
+	}
+}
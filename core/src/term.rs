@@ -3,15 +3,50 @@
 use crate::traits::{One, Zero};
 use lazy_static::__Deref;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeSet as Set, fmt};
+use std::{
+	collections::{BTreeMap, BTreeSet as Set},
+	fmt,
+	sync::Arc,
+};
 use syn::{BinOp, ExprBinary};
 
 use crate::{scope::Scope, traits::*};
 
+/// Why a [`Term`] could not be evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+	/// The value does not fit into 128 bits; eg. a large component times a large coefficient.
+	Overflow,
+	/// Eg. a variable without a value.
+	Invalid(String),
+}
+
+impl fmt::Display for EvalError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Overflow => write!(f, "Value exceeds 128 bits"),
+			Self::Invalid(err) => write!(f, "{}", err),
+		}
+	}
+}
+
+impl From<String> for EvalError {
+	fn from(err: String) -> Self {
+		Self::Invalid(err)
+	}
+}
+
+impl From<EvalError> for String {
+	fn from(err: EvalError) -> Self {
+		err.to_string()
+	}
+}
+
 /// A symbolic term that can be used to express simple arithmetic.
 ///
 /// Can only be evaluated to a concrete value within a [`crate::scope::Scope`].
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Term<T> {
 	Value(T),
 	Scalar(u128),
@@ -19,30 +54,40 @@ pub enum Term<T> {
 
 	Add(Box<Self>, Box<Self>),
 	Mul(Box<Self>, Box<Self>),
+	/// Subtraction that saturates at zero, like `saturating_sub`.
+	Sub(Box<Self>, Box<Self>),
+	/// Minimum of both terms; per dimension for weights.
+	Min(Box<Self>, Box<Self>),
+	/// Maximum of both terms; per dimension for weights.
+	Max(Box<Self>, Box<Self>),
+	/// The arms of an `if` or `match`. The condition is not known, so the worst arm counts; per
+	/// dimension for weights.
+	Branch(Vec<Self>),
 }
 
 pub type SimpleTerm = Term<u128>;
 pub type ChromaticTerm = Term<Weight>;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialOrd, Ord, Eq)]
-/// A `VarValue` is an opaque string.
-pub struct VarValue(pub String);
+#[serde(from = "String", into = "String")]
+/// A `VarValue` is an opaque string; [interned](crate::intern) since terms repeat their variables.
+pub struct VarValue(pub Arc<String>);
 
 impl From<VarValue> for String {
 	fn from(v: VarValue) -> String {
-		v.0
+		v.0.as_ref().clone()
 	}
 }
 
 impl From<String> for VarValue {
 	fn from(s: String) -> Self {
-		Self(s)
+		s.as_str().into()
 	}
 }
 
 impl From<&str> for VarValue {
 	fn from(s: &str) -> Self {
-		Self(s.into())
+		Self(crate::intern::intern(s))
 	}
 }
 
@@ -56,7 +101,22 @@ impl std::ops::Deref for VarValue {
 
 impl PartialEq for VarValue {
 	fn eq(&self, other: &Self) -> bool {
-		self.0.replace('_', "") == other.0.replace('_', "")
+		// Underscores do not matter, eg. `max_n` and `maxn`.
+		fn letters(v: &str) -> impl Iterator<Item = char> + '_ {
+			v.chars().filter(|c| *c != '_')
+		}
+		Arc::ptr_eq(&self.0, &other.0) || letters(self).eq(letters(other))
+	}
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for VarValue {
+	fn schema_name() -> String {
+		"VarValue".into()
+	}
+
+	fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+		String::json_schema(gen)
 	}
 }
 
@@ -133,17 +193,25 @@ macro_rules! cmul {
 
 impl SimpleTerm {
 	/// Evaluates the term within the given scope to a concrete value.
-	pub fn eval(&self, ctx: &crate::scope::SimpleScope) -> Result<u128, String> {
+	pub fn eval(&self, ctx: &crate::scope::SimpleScope) -> Result<u128, EvalError> {
 		match self {
 			Self::Value(x) => Ok(*x),
 			Self::Scalar(x) => Ok(*x),
-			Self::Add(x, y) => Ok(x.eval(ctx)? + y.eval(ctx)?),
-			Self::Mul(x, y) => Ok(x.eval(ctx)? * y.eval(ctx)?),
+			Self::Add(x, y) => x.eval(ctx)?.checked_add(y.eval(ctx)?).ok_or(EvalError::Overflow),
+			Self::Mul(x, y) => x.eval(ctx)?.checked_mul(y.eval(ctx)?).ok_or(EvalError::Overflow),
+			Self::Sub(x, y) => Ok(x.eval(ctx)?.saturating_sub(y.eval(ctx)?)),
+			Self::Min(x, y) => Ok(x.eval(ctx)?.min(y.eval(ctx)?)),
+			Self::Max(x, y) => Ok(x.eval(ctx)?.max(y.eval(ctx)?)),
+			Self::Branch(arms) => arms
+				.iter()
+				.map(|a| a.eval(ctx))
+				.try_fold(None, |worst: Option<u128>, v| Ok::<_, EvalError>(worst.max(Some(v?))))?
+				.ok_or_else(|| "Branch without arms".to_string().into()),
 			Self::Var(x) =>
 				if let Some(var) = ctx.get(x) {
 					var.eval(ctx)
 				} else {
-					Err(format!("Variable '{}' not found", x.deref()))
+					Err(format!("Variable '{}' not found", x.deref()).into())
 				},
 		}
 	}
@@ -160,14 +228,190 @@ impl SimpleTerm {
 				Box::new(x.into_chromatic(unit)),
 				Box::new(y.into_chromatic(unit)),
 			),
+			Self::Sub(x, y) => ChromaticTerm::Sub(
+				Box::new(x.into_chromatic(unit)),
+				Box::new(y.into_chromatic(unit)),
+			),
+			Self::Min(x, y) => ChromaticTerm::Min(
+				Box::new(x.into_chromatic(unit)),
+				Box::new(y.into_chromatic(unit)),
+			),
+			Self::Max(x, y) => ChromaticTerm::Max(
+				Box::new(x.into_chromatic(unit)),
+				Box::new(y.into_chromatic(unit)),
+			),
+			Self::Branch(arms) =>
+				ChromaticTerm::Branch(arms.into_iter().map(|a| a.into_chromatic(unit)).collect()),
 			Self::Var(x) => ChromaticTerm::Var(x),
 		}
 	}
 
+	/// Expands the term into a sum of products.
+	///
+	/// Maps the sorted variables of each product to its constant factor. The constant part of the
+	/// term has no variables. Eg: `2 * (n + 3)` is `{[]: 6, [n]: 2}`.
+	///
+	/// Minimum, maximum, saturating subtraction and branches are no polynomials. They are bounded
+	/// from above by the larger factors of all terms and by the minuend respectively. Factors
+	/// saturate at [`u128::MAX`], like an overflowing weight is compared as the maximum.
+	pub fn monomials(&self) -> BTreeMap<Vec<String>, u128> {
+		match self {
+			Self::Value(0) | Self::Scalar(0) => BTreeMap::new(),
+			Self::Value(x) | Self::Scalar(x) => [(Vec::new(), *x)].into(),
+			Self::Var(x) => [(vec![x.to_string()], 1)].into(),
+			Self::Add(x, y) => {
+				let mut res = x.monomials();
+				for (vars, factor) in y.monomials() {
+					let entry = res.entry(vars).or_default();
+					*entry = entry.saturating_add(factor);
+				}
+				res
+			},
+			Self::Min(x, y) | Self::Max(x, y) => {
+				let mut res = x.monomials();
+				for (vars, factor) in y.monomials() {
+					let entry = res.entry(vars).or_default();
+					*entry = (*entry).max(factor);
+				}
+				res
+			},
+			Self::Branch(arms) => {
+				let mut res = BTreeMap::<_, u128>::new();
+				for (vars, factor) in arms.iter().flat_map(Self::monomials) {
+					let entry = res.entry(vars).or_default();
+					*entry = (*entry).max(factor);
+				}
+				res
+			},
+			Self::Sub(x, _) => x.monomials(),
+			Self::Mul(x, y) => {
+				let mut res = BTreeMap::<_, u128>::new();
+				for (l_vars, l) in x.monomials() {
+					for (r_vars, r) in y.monomials() {
+						let mut vars = [l_vars.clone(), r_vars].concat();
+						vars.sort();
+						let entry = res.entry(vars).or_default();
+						*entry = entry.saturating_add(l.saturating_mul(r));
+					}
+				}
+				res
+			},
+		}
+	}
+
+	/// The number of accesses of the storage variable `var`, eg. `READ`, as term of the components.
+	///
+	/// Eg: `26.30us + 4·READ + 2·n·READ + WRITE` has `4 + 2·n` reads.
+	pub fn storage_accesses(&self, var: &str) -> Self {
+		self.monomials()
+			.into_iter()
+			.filter_map(|(mut vars, factor)| {
+				vars.remove(vars.iter().position(|v| v == var)?);
+				Some(vars.into_iter().fold(Self::Scalar(factor), |term, v| {
+					Self::Mul(term.into(), Self::Var(v.into()).into())
+				}))
+			})
+			.reduce(|a, b| Self::Add(a.into(), b.into()))
+			.unwrap_or(Self::Scalar(0))
+	}
+
+	/// Readable formula like `26.30us + 1.20us·n + 4·READ + 2·WRITE`.
+	///
+	/// The values are formatted in the `unit`, except for the factors of storage accesses which are
+	/// counts. The constant part comes first. Minima, maxima and subtractions keep their structure,
+	/// eg. `max(10.00us, 2.00us·n) - 1.00us`.
+	pub fn fmt_math(&self, unit: crate::Dimension) -> String {
+		self.fmt_structure(unit, false)
+	}
+
+	/// Same as [`Self::fmt_math`] but as LaTeX math, eg. for docs.
+	pub fn fmt_latex(&self, unit: crate::Dimension) -> String {
+		self.fmt_structure(unit, true)
+	}
+
+	/// Whether the term only adds and multiplies.
+	pub fn is_polynomial(&self) -> bool {
+		match self {
+			Self::Value(_) | Self::Scalar(_) | Self::Var(_) => true,
+			Self::Add(x, y) | Self::Mul(x, y) => x.is_polynomial() && y.is_polynomial(),
+			Self::Sub(..) | Self::Min(..) | Self::Max(..) | Self::Branch(_) => false,
+		}
+	}
+
+	fn fmt_structure(&self, unit: crate::Dimension, latex: bool) -> String {
+		if self.is_polynomial() {
+			return self.fmt_monomials(unit, latex)
+		}
+		let fmt = |t: &Self| t.fmt_structure(unit, latex);
+		// Sums need brackets within products and as subtrahend.
+		let bracket = |t: &Self| match fmt(t) {
+			f if f.contains(" + ") || f.contains(" - ") => format!("({})", f),
+			f => f,
+		};
+		let (min, max, times) =
+			if latex { ("\\min", "\\max", " \\cdot ") } else { ("min", "max", "·") };
+		match self {
+			Self::Add(x, y) => format!("{} + {}", fmt(x), fmt(y)),
+			Self::Mul(x, y) => format!("{}{}{}", bracket(x), times, bracket(y)),
+			Self::Sub(x, y) => format!("{} - {}", fmt(x), bracket(y)),
+			Self::Min(x, y) => format!("{}({}, {})", min, fmt(x), fmt(y)),
+			Self::Max(x, y) => format!("{}({}, {})", max, fmt(x), fmt(y)),
+			Self::Branch(arms) => format!(
+				"{}({})",
+				if latex { "\\operatorname{branch}" } else { "branch" },
+				arms.iter().map(fmt).collect::<Vec<_>>().join(", ")
+			),
+			_ => unreachable!("Only polynomials are leaves; qed"),
+		}
+	}
+
+	fn fmt_monomials(&self, unit: crate::Dimension, latex: bool) -> String {
+		let is_storage = |v: &String| {
+			v == crate::scope::STORAGE_READ_VAR || v == crate::scope::STORAGE_WRITE_VAR
+		};
+		let times = if latex { " \\cdot " } else { "·" };
+		let mut monomials = self.monomials().into_iter().collect::<Vec<_>>();
+		// The storage accesses come last.
+		monomials.sort_by_key(|(vars, _)| (vars.iter().any(is_storage), vars.clone()));
+
+		let parts = monomials
+			.into_iter()
+			.map(|(vars, factor)| {
+				let count = vars.iter().any(is_storage);
+				let mut factors = Vec::new();
+				if !count || factor != 1 || vars.is_empty() {
+					factors.push(match (count, latex) {
+						(true, _) => factor.to_string(),
+						(false, true) => format!("\\text{{{}}}", unit.fmt_value(factor)),
+						(false, false) => unit.fmt_value(factor),
+					});
+				}
+				factors.extend(vars.iter().map(|v| match (is_storage(v), latex) {
+					(true, true) => format!("\\mathrm{{{}}}", v),
+					(false, true) => v.replace('_', "\\_"),
+					_ => v.clone(),
+				}));
+				factors.join(times)
+			})
+			.collect::<Vec<_>>();
+
+		if parts.is_empty() {
+			"0".into()
+		} else {
+			parts.join(" + ")
+		}
+	}
+
 	fn scalar_into_term(s: u128, unit: crate::Dimension) -> Weight {
 		match unit {
-			crate::Dimension::Time => Weight { time: s, proof: 0 },
-			crate::Dimension::Proof => Weight { proof: s, time: 0 },
+			crate::Dimension::Time => Weight { ref_time: s, proof_size: 0 },
+			crate::Dimension::Proof => Weight { proof_size: s, ref_time: 0 },
+			// A plain number is a reference time, like `Weight::from_parts(s, 0)`. Fees and storage
+			// accesses are derived from the reference time.
+			crate::Dimension::Both |
+			crate::Dimension::Fee |
+			crate::Dimension::Reads |
+			crate::Dimension::Writes => Weight { ref_time: s, proof_size: 0 },
 		}
 	}
 }
@@ -202,8 +446,12 @@ where
 			Self::Var(var) => Set::from([var.clone().into()]),
 			Self::Scalar(_) => Set::default(),
 			Self::Value(_) => Set::default(),
-			Self::Mul(l, r) | Self::Add(l, r) =>
-				l.free_vars(scope).union(&r.free_vars(scope)).cloned().collect(),
+			Self::Mul(l, r) |
+			Self::Add(l, r) |
+			Self::Sub(l, r) |
+			Self::Min(l, r) |
+			Self::Max(l, r) => l.free_vars(scope).union(&r.free_vars(scope)).cloned().collect(),
+			Self::Branch(arms) => arms.iter().flat_map(|a| a.free_vars(scope)).collect(),
 		}
 	}
 
@@ -217,8 +465,12 @@ where
 			Self::Var(_var) => Set::default(),
 			Self::Scalar(_) => Set::default(),
 			Self::Value(_) => Set::default(),
-			Self::Mul(l, r) | Self::Add(l, r) =>
-				l.bound_vars(scope).union(&r.bound_vars(scope)).cloned().collect(),
+			Self::Mul(l, r) |
+			Self::Add(l, r) |
+			Self::Sub(l, r) |
+			Self::Min(l, r) |
+			Self::Max(l, r) => l.bound_vars(scope).union(&r.bound_vars(scope)).cloned().collect(),
+			Self::Branch(arms) => arms.iter().flat_map(|a| a.bound_vars(scope)).collect(),
 		}
 	}
 
@@ -245,14 +497,19 @@ where
 
 	pub fn substitute(&mut self, var: &str, term: &Term<T>) {
 		match self {
-			Self::Var(v) if v.0 == var => *self = term.clone(),
+			Self::Var(v) if v.as_str() == var => *self = term.clone(),
 			Self::Var(_) => {},
 			Self::Scalar(_) => {},
 			Self::Value(_) => {},
-			Self::Mul(l, r) | Self::Add(l, r) => {
+			Self::Mul(l, r) |
+			Self::Add(l, r) |
+			Self::Sub(l, r) |
+			Self::Min(l, r) |
+			Self::Max(l, r) => {
 				l.substitute(var, term);
 				r.substitute(var, term);
 			},
+			Self::Branch(arms) => arms.iter_mut().for_each(|a| a.substitute(var, term)),
 		}
 	}
 
@@ -303,6 +560,22 @@ where
 					}
 				}
 			},
+			Self::Sub(l, r) => {
+				let (l, r) = (l.fmt_with_bracket(false), r.fmt_with_bracket(false));
+				if has_bracket {
+					Some(format!("{} - {}", l, r))
+				} else {
+					Some(format!("({} - {})", l, r))
+				}
+			},
+			Self::Min(l, r) =>
+				Some(format!("min({}, {})", l.fmt_with_bracket(true), r.fmt_with_bracket(true))),
+			Self::Max(l, r) =>
+				Some(format!("max({}, {})", l.fmt_with_bracket(true), r.fmt_with_bracket(true))),
+			Self::Branch(arms) => Some(format!(
+				"branch({})",
+				arms.iter().map(|a| a.fmt_with_bracket(true)).collect::<Vec<_>>().join(", ")
+			)),
 			Self::Value(val) => Some(val.format_scalar()),
 			Self::Scalar(val) => Some(crate::Dimension::fmt_scalar(*val)),
 			Self::Var(var) => Some(var.clone().into()),
@@ -320,11 +593,21 @@ where
 			v @ Self::Value(_) => Ok(vec![f(v)?]),
 			v @ Self::Scalar(_) => Ok(vec![f(v)?]),
 			v @ Self::Var(_) => Ok(vec![f(v)?]),
-			Self::Add(l, r) | Self::Mul(l, r) => {
+			Self::Add(l, r) |
+			Self::Mul(l, r) |
+			Self::Sub(l, r) |
+			Self::Min(l, r) |
+			Self::Max(l, r) => {
 				res.append(&mut l.visit(f)?);
 				res.append(&mut r.visit(f)?);
 				Ok(res)
 			},
+			Self::Branch(arms) => {
+				for arm in arms {
+					res.append(&mut arm.visit(f)?);
+				}
+				Ok(res)
+			},
 		}
 	}
 
@@ -364,53 +647,54 @@ where
 
 impl ChromaticTerm {
 	/// Evaluates the term within the given scope to a concrete value.
-	pub fn eval(&self, ctx: &crate::scope::ChromaticScope) -> Result<Weight, String> {
+	///
+	/// Both dimensions are evaluated at once; scalars apply to each of them. This gives the same
+	/// values as evaluating the [`Self::simplify`]d term of each dimension.
+	pub fn eval(&self, ctx: &crate::scope::ChromaticScope) -> Result<Weight, EvalError> {
+		let both = |x: &Self, y: &Self, f: fn(u128, u128) -> Option<u128>| {
+			x.eval(ctx)?.zip(&y.eval(ctx)?, f).ok_or(EvalError::Overflow)
+		};
 		match self {
 			Self::Value(x) => Ok(x.clone()),
-			Self::Scalar(_) => unreachable!("Scalars cannot be evaluated; qed"),
-			Self::Add(x, y) => {
-				let (a, b) = x.eval(ctx)?.into();
-				let (m, n) = y.eval(ctx)?.into();
-				Ok((a + m, b + n).into())
-			},
-			Self::Mul(x, y) => match (x.as_ref(), y.as_ref()) {
-				(Self::Scalar(x), y) => {
-					let (a, b) = y.eval(ctx)?.into();
-					Ok((*x * a, *x * b).into())
-				},
-				(x, Self::Scalar(y)) => {
-					let (a, b) = x.eval(ctx)?.into();
-					Ok((*y * a, *y * b).into())
-				},
-				(Self::Var(x), y) => match ctx.get(x) {
-					Some(Self::Scalar(x)) => Ok(y.eval(ctx)?.mul_scalar(x)),
-					Some(_) => Err(format!("Variable '{}' is not a scalar", x.deref())),
-					None => Err(format!("Variable '{}' not found", x.deref())),
-				},
-				(x, Self::Var(y)) => match ctx.get(y) {
-					Some(Self::Scalar(y)) => Ok(x.eval(ctx)?.mul_scalar(y)),
-					Some(_) => Err(format!("Variable '{}' is not a scalar", y.deref())),
-					None => Err(format!("Variable '{}' not found", y.deref())),
-				},
-				_ => unreachable!("Cannot multiply two terms; qed"),
-			},
+			Self::Scalar(x) => Ok(Weight::scalar(*x)),
+			Self::Add(x, y) => both(x, y, u128::checked_add),
+			Self::Mul(x, y) => both(x, y, u128::checked_mul),
+			Self::Sub(x, y) => both(x, y, |a, b| Some(a.saturating_sub(b))),
+			Self::Min(x, y) => both(x, y, |a, b| Some(a.min(b))),
+			Self::Max(x, y) => both(x, y, |a, b| Some(a.max(b))),
+			Self::Branch(arms) => arms
+				.iter()
+				.map(|a| a.eval(ctx))
+				.try_fold(None, |worst: Option<Weight>, w| {
+					let w = w?;
+					Ok::<_, EvalError>(Some(match worst {
+						Some(worst) => worst.zip(&w, u128::max).expect("Max cannot fail; qed"),
+						None => w,
+					}))
+				})?
+				.ok_or_else(|| "Branch without arms".to_string().into()),
 			Self::Var(x) =>
 				if let Some(var) = ctx.get(x) {
 					var.eval(ctx)
 				} else {
-					Err(format!("Variable '{}' not found", x.deref()))
+					Err(format!("Variable '{}' not found", x.deref()).into())
 				},
 		}
 	}
 
 	pub fn simplify(&self, unit: crate::Dimension) -> Result<SimpleTerm, String> {
+		// Storage accesses are only part of the reference time.
+		if let Some(var) = unit.storage_var() {
+			return Ok(self.simplify(crate::Dimension::Time)?.storage_accesses(var))
+		}
 		self.for_values(|t| match t {
-			Self::Value(Weight { time, .. }) if unit == crate::Dimension::Time =>
-				Ok(SimpleTerm::Value(*time)),
-			Self::Value(Weight { proof, .. }) if unit == crate::Dimension::Proof =>
-				Ok(SimpleTerm::Value(*proof)),
+			Self::Value(Weight { ref_time, .. }) if unit == crate::Dimension::Time =>
+				Ok(SimpleTerm::Value(*ref_time)),
+			Self::Value(Weight { proof_size, .. }) if unit == crate::Dimension::Proof =>
+				Ok(SimpleTerm::Value(*proof_size)),
 			Self::Scalar(val) => Ok(SimpleTerm::Scalar(*val)),
 			Self::Var(var) => Ok(SimpleTerm::Var(var.clone())),
+			Self::Value(_) => Err(format!("Cannot simplify to dimension {:?}", unit)),
 			_ => unreachable!(),
 		})
 	}
@@ -429,6 +713,21 @@ impl ChromaticTerm {
 				l.for_values::<F>(f.clone())?.into(),
 				r.for_values::<F>(f)?.into(),
 			)),
+			Self::Sub(l, r) => Ok(SimpleTerm::Sub(
+				l.for_values::<F>(f.clone())?.into(),
+				r.for_values::<F>(f)?.into(),
+			)),
+			Self::Min(l, r) => Ok(SimpleTerm::Min(
+				l.for_values::<F>(f.clone())?.into(),
+				r.for_values::<F>(f)?.into(),
+			)),
+			Self::Max(l, r) => Ok(SimpleTerm::Max(
+				l.for_values::<F>(f.clone())?.into(),
+				r.for_values::<F>(f)?.into(),
+			)),
+			Self::Branch(arms) => Ok(SimpleTerm::Branch(
+				arms.iter().map(|a| a.for_values::<F>(f.clone())).collect::<Result<_, _>>()?,
+			)),
 		}
 	}
 
@@ -439,10 +738,10 @@ impl ChromaticTerm {
 				Self::Add(Box::new(t1.splice_add(*t2)), Box::new(p1.splice_add(*p2))),
 			(Self::Value(x), Self::Value(y)) => {
 				// check for orthogonality
-				if x.time == 0 && y.proof == 0 {
-					Self::Value(Weight { time: y.time, proof: x.proof })
-				} else if x.proof == 0 && y.time == 0 {
-					Self::Value(Weight { time: x.time, proof: y.proof })
+				if x.ref_time == 0 && y.proof_size == 0 {
+					Self::Value(Weight { ref_time: y.ref_time, proof_size: x.proof_size })
+				} else if x.proof_size == 0 && y.ref_time == 0 {
+					Self::Value(Weight { ref_time: x.ref_time, proof_size: y.proof_size })
 				} else {
 					Self::Add(Box::new(Self::Value(x)), Box::new(Self::Value(y)))
 				}
@@ -471,6 +770,7 @@ impl TryInto<SimpleTerm> for &ExprBinary {
 		let term = match self.op {
 			BinOp::Mul(_) => SimpleTerm::Mul(left, right),
 			BinOp::Add(_) => SimpleTerm::Add(left, right),
+			BinOp::Sub(_) => SimpleTerm::Sub(left, right),
 			_ => return Err("Unexpected operator".into()),
 		};
 		Ok(term)
@@ -0,0 +1,158 @@
+use rstest::*;
+
+use crate::{access::*, RelativeChange};
+
+const OLD: &str = r#"
+pub trait WeightInfo {
+	fn bond() -> Weight;
+	fn chill() -> Weight;
+}
+
+impl<T: frame_system::Config> pallet_staking::WeightInfo for WeightInfo<T> {
+	// Storage: Staking Bonded (r:1 w:1)
+	// Storage: Staking Ledger (r:1 w:1)
+	// Storage: Staking HistoryDepth (r:1 w:0)
+	fn bond() -> Weight {
+		(43_992_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	fn chill() -> Weight {
+		(43_992_000 as Weight)
+	}
+}
+"#;
+
+const NEW: &str = r#"
+impl<T: frame_system::Config> pallet_staking::WeightInfo for WeightInfo<T> {
+	/// Storage: `Staking::Bonded` (r:1 w:1)
+	/// Proof: `Staking::Bonded` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	/// Storage: `Staking::Ledger` (r:2 w:1)
+	/// Storage: `Balances::Locks` (r:1 w:1)
+	fn bond() -> Weight {
+		Weight::from_parts(43_992_000, 4764)
+	}
+	/// Storage: `Staking::Ledger` (r:1 w:0)
+	fn chill() -> Weight {
+		Weight::from_parts(43_992_000, 0)
+	}
+	/// Storage: `Staking::Ledger` (r:1 w:0)
+	fn kick() -> Weight {
+		Weight::from_parts(43_992_000, 0)
+	}
+}
+
+impl WeightInfo for () {
+	fn bond() -> Weight {
+		Weight::from_parts(43_992_000, 4764)
+	}
+}
+"#;
+
+#[test]
+fn parse_content_works() {
+	let old = parse_content(OLD);
+	assert_eq!(old.keys().collect::<Vec<_>>(), ["bond", "chill"]);
+	assert_eq!(
+		old["bond"][0],
+		StorageAccess { item: "Staking::Bonded".into(), reads: 1, writes: 1, proof: None }
+	);
+	assert!(old["chill"].is_empty());
+
+	// The second impl does not overwrite the first.
+	let new = parse_content(NEW);
+	assert_eq!(new["bond"].len(), 3);
+	assert_eq!(
+		new["bond"][0].proof,
+		Some(ProofSize { mode: "MaxEncodedLen".into(), added: Some(2547) })
+	);
+	assert_eq!(
+		new["bond"][1],
+		StorageAccess { item: "Staking::Ledger".into(), reads: 2, writes: 1, proof: None }
+	);
+}
+
+#[rstest]
+#[case("Staking Bonded", "Staking::Bonded")]
+#[case("`Staking::Bonded`", "Staking::Bonded")]
+#[case(
+	"unknown `0x3a7472616e73616374696f6e5f6c6576656c3a`",
+	"unknown::0x3a7472616e73616374696f6e5f6c6576656c3a"
+)]
+#[case("`Balances::Account` `0x1234`", "Balances::Account 0x1234")]
+fn item_names_are_unified(#[case] raw: &str, #[case] item: &str) {
+	let content = format!("// Storage: {} (r:1 w:0)\nfn bond() -> Weight {{", raw);
+	assert_eq!(parse_content(&content)["bond"][0].item, item);
+}
+
+#[rstest]
+#[case(
+	"/// Proof Skipped: Staking Bonded (max_values: None, max_size: None, mode: Measured)",
+	"Measured",
+	None
+)]
+#[case(
+	"/// Proof: `Staking::Bonded` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)",
+	"MaxEncodedLen",
+	Some(2547)
+)]
+fn parse_proof_works(#[case] line: &str, #[case] mode: &str, #[case] added: Option<u32>) {
+	let content =
+		format!("/// Storage: Staking Bonded (r:1 w:0)\n{}\nfn bond() -> Weight {{", line);
+	assert_eq!(
+		parse_content(&content)["bond"][0].proof,
+		Some(ProofSize { mode: mode.into(), added })
+	);
+}
+
+#[test]
+fn compare_proof_sizes_works() {
+	let accesses = |proof: &str| {
+		let content =
+			format!("/// Storage: Staking Bonded (r:1 w:0)\n{}\nfn bond() -> Weight {{", proof);
+		parse_content(&content)
+			.into_iter()
+			.map(|(ext, items)| (("staking.rs".into(), ext), items))
+			.collect::<Accesses>()
+	};
+	let max_encoded_len = accesses(
+		"/// Proof: Staking Bonded (max_values: None, max_size: Some(72), added: 2547, mode: MaxEncodedLen)",
+	);
+	let measured = accesses(
+		"/// Proof Skipped: Staking Bonded (max_values: None, max_size: None, mode: Measured)",
+	);
+
+	let changes = compare_accesses(&max_encoded_len, &measured);
+	assert_eq!(changes.len(), 1);
+	assert_eq!((changes[0].old, changes[0].new), (Some((1, 0)), Some((1, 0))));
+	assert_eq!(changes[0].new_proof.as_ref().unwrap().mode, "Measured");
+	// Older templates without proof comments.
+	assert!(compare_accesses(&accesses(""), &measured).is_empty());
+	assert!(compare_accesses(&measured, &measured).is_empty());
+}
+
+#[test]
+fn compare_accesses_works() {
+	let accesses = |content: &str| {
+		parse_content(content)
+			.into_iter()
+			.map(|(ext, items)| (("staking.rs".into(), ext), items))
+			.collect::<Accesses>()
+	};
+	let changes = compare_accesses(&accesses(OLD), &accesses(NEW));
+	let changes = changes
+		.iter()
+		.map(|c| (c.extrinsic.as_str(), c.item.as_str(), c.old, c.new, c.change))
+		.collect::<Vec<_>>();
+
+	// `kick` was added and is therefore not reported.
+	assert_eq!(
+		changes,
+		[
+			("bond", "Balances::Locks", None, Some((1, 1)), RelativeChange::Added),
+			("bond", "Staking::HistoryDepth", Some((1, 0)), None, RelativeChange::Removed),
+			("bond", "Staking::Ledger", Some((1, 1)), Some((2, 1)), RelativeChange::Changed),
+			("chill", "Staking::Ledger", None, Some((1, 0)), RelativeChange::Added),
+		]
+	);
+}
@@ -0,0 +1,86 @@
+use rstest::*;
+use std::{
+	io::Write,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	archive::*,
+	parse::pallet::{parse_file, parse_paths, try_parse_paths},
+	testing::root_dir,
+};
+
+#[rstest]
+#[case("weights.tar", true)]
+#[case("weights.tar.gz", true)]
+#[case("WEIGHTS.TGZ", true)]
+#[case("artifacts/weights.zip", true)]
+#[case("pallet_balances.rs", false)]
+#[case("weights.gz", false)]
+fn is_archive_works(#[case] path: &str, #[case] expected: bool) {
+	assert_eq!(is_archive(Path::new(path)), expected);
+}
+
+/// A `.tar.gz` with a wrapping folder pairs up with a flat `.zip`.
+#[test]
+fn parse_paths_archives_works() {
+	let old = std::fs::read(root_dir().join("test_data/old/pallet_staking.rs.txt")).unwrap();
+	let new = std::fs::read(root_dir().join("test_data/new/pallet_staking.rs.txt")).unwrap();
+	let dir = tempfile::tempdir().unwrap();
+
+	let tar_gz = dir.path().join("weights-old.tar.gz");
+	let gz = flate2::write::GzEncoder::new(
+		std::fs::File::create(&tar_gz).unwrap(),
+		flate2::Compression::default(),
+	);
+	let mut tar = tar::Builder::new(gz);
+	for (name, content) in
+		[("weights-old/pallet_staking.rs", &old[..]), ("weights-old/mod.rs", b"pub mod x;")]
+	{
+		let mut header = tar::Header::new_gnu();
+		header.set_size(content.len() as u64);
+		header.set_mode(0o644);
+		tar.append_data(&mut header, name, content).unwrap();
+	}
+	tar.into_inner().unwrap().finish().unwrap();
+
+	let zip_path = dir.path().join("weights-new.zip");
+	let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+	zip.start_file("pallet_staking.rs", Default::default()).unwrap();
+	zip.write_all(&new).unwrap();
+	zip.finish().unwrap();
+
+	let olds = parse_paths(&[tar_gz]).unwrap();
+	let news = parse_paths(std::slice::from_ref(&zip_path)).unwrap();
+	assert!(olds.iter().chain(&news).all(|e| e.pallet == "pallet_staking.rs"));
+	let expected = parse_file(&root_dir().join("test_data/new/pallet_staking.rs.txt")).unwrap();
+	assert_eq!(news.len(), expected.len());
+	assert!(!olds.is_empty());
+
+	let broken = dir.path().join("broken.zip");
+	std::fs::write(&broken, "no zip").unwrap();
+	assert!(parse_paths(std::slice::from_ref(&broken))
+		.unwrap_err()
+		.to_string()
+		.contains("broken.zip"));
+	assert_eq!(try_parse_paths(&[broken, zip_path]).len(), news.len());
+}
+
+#[test]
+fn extract_skips_wrapping_folders() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("weights.tar");
+	let mut tar = tar::Builder::new(std::fs::File::create(&path).unwrap());
+	let mut header = tar::Header::new_gnu();
+	header.set_size(2);
+	header.set_mode(0o644);
+	tar.append_data(&mut header, "artifacts/weights/a.rs", &b"{}"[..]).unwrap();
+	tar.into_inner().unwrap();
+
+	let extracted = extract(&path).unwrap();
+	assert!(extracted.root().ends_with("artifacts/weights"));
+	assert_eq!(std::fs::read_dir(extracted.root()).unwrap().count(), 1);
+	let root: PathBuf = extracted.root().into();
+	drop(extracted);
+	assert!(!root.exists());
+}
@@ -0,0 +1,39 @@
+use crate::auth::Credentials;
+use std::{io::Write, process::Stdio};
+
+/// The `git` command gets the token from the environment instead of its arguments.
+#[test]
+fn git_env_passes_token() {
+	let credentials = Credentials {
+		token: Some("secret".into()),
+		proxy: Some("http://proxy:3128".into()),
+		..Default::default()
+	};
+	let env = credentials.git_env();
+	assert!(env.contains(&("GIT_CONFIG_KEY_2".into(), "http.proxy".into())));
+
+	let mut git = std::process::Command::new("git")
+		.args(["credential", "fill"])
+		.envs(env)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.spawn()
+		.unwrap();
+	git.stdin
+		.take()
+		.unwrap()
+		.write_all(b"protocol=https\nhost=example.com\n\n")
+		.unwrap();
+	let output = String::from_utf8(git.wait_with_output().unwrap().stdout).unwrap();
+	assert!(output.contains("username=x-access-token\n"), "{}", output);
+	assert!(output.contains("password=secret\n"), "{}", output);
+}
+
+#[test]
+fn git_env_quotes_ssh_key() {
+	let credentials = Credentials { ssh_key: Some("/keys/it's".into()), ..Default::default() };
+	assert_eq!(
+		credentials.git_env(),
+		vec![("GIT_SSH_COMMAND".into(), r"ssh -i '/keys/it'\''s' -o IdentitiesOnly=yes".into())]
+	);
+}
@@ -3,6 +3,7 @@ use rstest::*;
 
 use crate::{parse::pallet::*, scope::*, term::*, *};
 use maplit::hashmap;
+use std::collections::BTreeSet;
 
 #[test]
 fn extend_scoped_components_works() {
@@ -13,12 +14,15 @@ fn extend_scoped_components_works() {
 			pallet: "".into(),
 			term: var!("a"),
 			comp_ranges: None,
+			span: None,
+			pallet_id: None,
 		};
 		let base = SimpleScope::empty();
 
 		// base
 		let scopes = extend_scoped_components(Some(&a), None, CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -26,6 +30,7 @@ fn extend_scoped_components_works() {
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -33,6 +38,7 @@ fn extend_scoped_components_works() {
 
 		let scopes = extend_scoped_components(Some(&a), Some(&a), CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -40,24 +46,27 @@ fn extend_scoped_components_works() {
 		// guess worst
 		let scopes = extend_scoped_components(Some(&a), None, CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(100))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(100))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(100))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 		// exact worst
 		let _err =
 			extend_scoped_components(None, Some(&a), CompareMethod::ExactWorst, &base).unwrap_err();
@@ -75,12 +84,15 @@ fn extend_scoped_components_works() {
 			pallet: "".into(),
 			term: var!("a"),
 			comp_ranges: Some(comp_ranges),
+			span: None,
+			pallet_id: None,
 		};
 		let base = SimpleScope::empty();
 
 		// base
 		let scopes = extend_scoped_components(Some(&a), None, CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -88,6 +100,7 @@ fn extend_scoped_components_works() {
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -95,6 +108,7 @@ fn extend_scoped_components_works() {
 
 		let scopes = extend_scoped_components(Some(&a), Some(&a), CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -102,46 +116,52 @@ fn extend_scoped_components_works() {
 		// guess worst
 		let scopes = extend_scoped_components(Some(&a), None, CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(200))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(200))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(200))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 
 		// exact worst
 		let scopes = extend_scoped_components(Some(&a), None, CompareMethod::ExactWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(200))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::ExactWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(200))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 
 		let scopes = extend_scoped_components(None, Some(&a), CompareMethod::ExactWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))], vec![("a".into(), scalar!(200))]]);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0))]]);
 	}
 	// Two components without ranges
 	{
@@ -150,18 +170,23 @@ fn extend_scoped_components_works() {
 			pallet: "".into(),
 			term: var!("a"),
 			comp_ranges: None,
+			span: None,
+			pallet_id: None,
 		};
 		let b = SimpleExtrinsic {
 			name: "".into(),
 			pallet: "".into(),
 			term: var!("b"),
 			comp_ranges: None,
+			span: None,
+			pallet_id: None,
 		};
 		let base = SimpleScope::empty();
 
 		// base
 		let scopes = extend_scoped_components(Some(&a), Some(&b), CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -169,18 +194,11 @@ fn extend_scoped_components_works() {
 		// guess worst
 		let scopes = extend_scoped_components(Some(&a), Some(&b), CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(
-			scopes,
-			vec![
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(100))],
-				vec![("a".into(), scalar!(100)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(100)), ("b".into(), scalar!(100))]
-			]
-		);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0)), ("b".into(), scalar!(100))]]);
 		// exact worst
 		let _err = extend_scoped_components(Some(&a), Some(&b), CompareMethod::ExactWorst, &base)
 			.unwrap_err();
@@ -194,18 +212,23 @@ fn extend_scoped_components_works() {
 			pallet: "".into(),
 			term: var!("a"),
 			comp_ranges: Some(comp_ranges.clone()),
+			span: None,
+			pallet_id: None,
 		};
 		let b = SimpleExtrinsic {
 			name: "".into(),
 			pallet: "".into(),
 			term: var!("b"),
 			comp_ranges: Some(comp_ranges),
+			span: None,
+			pallet_id: None,
 		};
 		let base = SimpleScope::empty();
 
 		// base
 		let scopes = extend_scoped_components(Some(&a), Some(&b), CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -213,18 +236,11 @@ fn extend_scoped_components_works() {
 		// guess worst
 		let scopes = extend_scoped_components(Some(&a), Some(&b), CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(
-			scopes,
-			vec![
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(100))],
-				vec![("a".into(), scalar!(200)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(200)), ("b".into(), scalar!(100))]
-			]
-		);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0)), ("b".into(), scalar!(100))]]);
 		// exact worst
 		let _err = extend_scoped_components(Some(&a), Some(&b), CompareMethod::ExactWorst, &base)
 			.unwrap_err();
@@ -239,18 +255,23 @@ fn extend_scoped_components_works() {
 			pallet: "".into(),
 			term: var!("a"),
 			comp_ranges: Some(comp_ranges.clone()),
+			span: None,
+			pallet_id: None,
 		};
 		let b = SimpleExtrinsic {
 			name: "".into(),
 			pallet: "".into(),
 			term: var!("b"),
 			comp_ranges: Some(comp_ranges.clone()),
+			span: None,
+			pallet_id: None,
 		};
 		let base = SimpleScope::empty();
 
 		// base
 		let scopes = extend_scoped_components(Some(&a), Some(&b), CompareMethod::Base, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
@@ -258,36 +279,135 @@ fn extend_scoped_components_works() {
 		// guess worst
 		let scopes = extend_scoped_components(Some(&a), Some(&b), CompareMethod::GuessWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(
-			scopes,
-			vec![
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(200))],
-				vec![("a".into(), scalar!(200)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(200)), ("b".into(), scalar!(200))]
-			]
-		);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0)), ("b".into(), scalar!(200))]]);
 		// exact worst
 		let scopes = extend_scoped_components(Some(&a), Some(&b), CompareMethod::ExactWorst, &base)
 			.unwrap()
+			.0
 			.into_iter()
 			.map(|s| s.as_vec())
 			.collect::<Vec<_>>();
-		assert_eq!(
-			scopes,
-			vec![
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(0)), ("b".into(), scalar!(200))],
-				vec![("a".into(), scalar!(200)), ("b".into(), scalar!(0))],
-				vec![("a".into(), scalar!(200)), ("b".into(), scalar!(200))]
-			]
-		);
+		assert_eq!(scopes, vec![vec![("a".into(), scalar!(0)), ("b".into(), scalar!(200))]]);
 	}
 }
 
+/// Monotonic components need no combinations, so there is no limit on them.
+#[test]
+fn extend_scoped_components_skips_monotonic() {
+	let ext = |term| SimpleExtrinsic {
+		name: "".into(),
+		pallet: "".into(),
+		term,
+		comp_ranges: None,
+		span: None,
+		pallet_id: None,
+	};
+	let sum = |factor: u128| {
+		(0..20).fold(scalar!(10), |t, c| add!(t, mul!(scalar!(factor), var!(format!("c{}", c)))))
+	};
+	let (old, new) = (ext(sum(1)), ext(sum(2)));
+
+	let (scopes, approximate) = extend_scoped_components(
+		Some(&old),
+		Some(&new),
+		CompareMethod::GuessWorst,
+		&SimpleScope::empty(),
+	)
+	.unwrap();
+	assert_eq!(scopes.len(), 1);
+	assert!(scopes[0].as_vec().iter().all(|(_, v)| *v == scalar!(100)));
+	assert!(!approximate);
+}
+
+#[rstest]
+#[case(CompareMethod::GuessWorst, Some("worst at {m=2, n=5}"))]
+#[case(CompareMethod::Base, None)]
+fn fmt_worst_case_works(#[case] method: CompareMethod, #[case] want: Option<&str>) {
+	let change = TermChange {
+		old: Some(add!(var!("n"), var!("READ"))),
+		new: Some(mul!(var!("m"), var!("n"))),
+		scope: SimpleScope::empty()
+			.with_var("n", scalar!(5))
+			.with_var("m", SimpleTerm::Value(2))
+			.with_var("READ", scalar!(100)),
+		method,
+		..mocked_change(1, 2)
+	};
+	assert_eq!(change.fmt_worst_case().as_deref(), want);
+}
+
+/// The weights of all evaluated scopes are kept on request.
+#[rstest]
+#[case(false, vec![])]
+#[case(true, vec![(0, Some(10), Some(10)), (100, Some(110), Some(10_010))])]
+fn compare_keeps_samples(
+	#[case] keep: bool,
+	#[case] want: Vec<(u128, Option<u128>, Option<u128>)>,
+) {
+	let ext = |term| ChromaticExtrinsic {
+		name: "".into(),
+		pallet: "".into(),
+		term,
+		comp_ranges: Some(hashmap! { "n".into() => ComponentRange { min: 0, max: 100 } }),
+		span: None,
+		pallet_id: None,
+	};
+	let linear = cmul!(cval!((1, 0).into()), cvar!("n"));
+	let old = ext(cadd!(cval!((10, 0).into()), linear.clone()));
+	// Quadratic, so both ends of `n` are evaluated.
+	let new = ext(cadd!(cval!((10, 0).into()), cmul!(linear, cvar!("n"))));
+	let params = CompareParams {
+		method: CompareMethod::ExactWorst,
+		offline: true,
+		keep_samples: keep,
+		..Default::default()
+	};
+
+	let diff = compare_files(vec![old], vec![new], &params, &Default::default()).unwrap();
+	let samples = &diff[0].term().unwrap().samples;
+	let got = samples
+		.iter()
+		.map(|s| (s.scope.get("n").and_then(|n| n.as_scalar()).unwrap(), s.old_v, s.new_v))
+		.collect::<Vec<_>>();
+	assert_eq!(got, want);
+}
+
+/// Too many components that depend on each other are approximated with a warning.
+#[test]
+fn compare_many_components_approximates() {
+	let ext = |term: &dyn Fn(usize) -> ChromaticTerm| ChromaticExtrinsic {
+		name: "".into(),
+		pallet: "".into(),
+		term: (0..20).fold(cval!((1000, 0).into()), |t, c| cadd!(t, term(c))),
+		comp_ranges: None,
+		span: None,
+		pallet_id: None,
+	};
+	let linear =
+		|factor: u128| move |c: usize| cmul!(cval!((factor, 0).into()), cvar!(format!("c{}", c)));
+	let square = |c: usize| {
+		cmul!(cmul!(cval!((1, 0).into()), cvar!(format!("c{}", c))), cvar!(format!("c{}", c)))
+	};
+	let params = CompareParams { offline: true, ..Default::default() };
+
+	let (old, new) = (ext(&linear(10)), ext(&linear(20)));
+	let diff = compare_files(vec![old], vec![new], &params, &Default::default()).unwrap();
+	assert!(!diff[0].term().unwrap().approximate);
+	assert!(diff[0].warning().is_none());
+
+	// The squares have no known monotonicity.
+	let (old, new) = (ext(&linear(10)), ext(&square));
+	let diff = compare_files(vec![old], vec![new], &params, &Default::default()).unwrap();
+	let change = diff[0].term().unwrap();
+	assert!(change.approximate);
+	assert_eq!((change.old_v, change.new_v), (Some(1000 + 20 * 1000), Some(1000 + 20 * 10_000)));
+	assert!(diff[0].warning().unwrap().contains("Approximate worst case"));
+}
+
 #[rstest]
 #[case(
 	// 7.57M + 13.03M * n + 485.56K * l + 2 * READ + 2 * WRITE
@@ -333,7 +453,8 @@ fn extend_scoped_components_works() {
 	add!(scalar!(999), mul!(scalar!(1000), var!("n"))),
 	&[(CompareMethod::Asymptotic, RelativeChange::Changed, 98.037),
 	  (CompareMethod::ExactWorst, RelativeChange::Changed, 98.037),
-	  (CompareMethod::Base, RelativeChange::Changed, -0.1)],
+	  (CompareMethod::Base, RelativeChange::Changed, -0.1),
+	  (CompareMethod::Median, RelativeChange::Changed, 96.15)],
 )]
 fn compare_extrinsics_works(
 	#[case] old: SimpleTerm,
@@ -349,6 +470,8 @@ fn compare_extrinsics_works(
 			"n".into() => ComponentRange { min: 0, max: 100 },
 			"l".into() => ComponentRange { min: 0, max: 255 },
 		}),
+		span: None,
+		pallet_id: None,
 	};
 	let new = SimpleExtrinsic {
 		name: "".into(),
@@ -359,15 +482,11 @@ fn compare_extrinsics_works(
 			"n".into() => ComponentRange { min: 0, max: 100 },
 			"l".into() => ComponentRange { min: 0, max: 255 },
 		}),
+		span: None,
+		pallet_id: None,
 	};
 	for expected in expected {
-		let params = CompareParams {
-			method: expected.0,
-			unit: Dimension::Time,
-			ignore_errors: false,
-			git_pull: false,
-			offline: true,
-		};
+		let params = CompareParams { method: expected.0, offline: true, ..Default::default() };
 
 		let change = compare_extrinsics(Some(old.clone()), Some(new.clone()), &params).unwrap();
 		assert_eq!(change.change, expected.1);
@@ -380,6 +499,34 @@ fn compare_extrinsics_works(
 	}
 }
 
+/// Components with a user supplied value are not instantiated by the method.
+#[rstest]
+#[case(CompareMethod::Base)]
+#[case(CompareMethod::ExactWorst)]
+#[case(CompareMethod::Asymptotic)]
+fn compare_extrinsics_with_components_works(#[case] method: CompareMethod) {
+	let ext = |term| SimpleExtrinsic {
+		name: "".into(),
+		pallet: "".into(),
+		term,
+		comp_ranges: Some(hashmap! { "n".into() => ComponentRange { min: 0, max: 100 } }),
+		span: None,
+		pallet_id: None,
+	};
+	// 1K + 0.5K * n vs 999 + 1K * n
+	let old = ext(add!(scalar!(1000), mul!(scalar!(500), var!("n"))));
+	let new = ext(add!(scalar!(999), mul!(scalar!(1000), var!("n"))));
+	let params = CompareParams {
+		method,
+		offline: true,
+		components: vec![("n".into(), 10)],
+		..Default::default()
+	};
+
+	let change = compare_extrinsics(Some(old), Some(new), &params).unwrap();
+	assert_eq!((change.old_v, change.new_v), (Some(6000), Some(10999)));
+}
+
 #[rstest]
 #[case(scalar!(30), Ok(()))]
 #[case(var!("READ"), Ok(()))]
@@ -413,11 +560,7 @@ fn filter_rel_threshold_works(
 	#[case] threshold: f64,
 	#[case] kept: bool,
 ) {
-	let diffs = vec![ExtrinsicDiff {
-		name: String::new(),
-		file: String::new(),
-		change: TermDiff::Changed(mocked_change(old, new)),
-	}];
+	let diffs = vec![mocked_diff("", TermDiff::Changed(mocked_change(old, new)))];
 	let params = FilterParams { threshold, ..Default::default() };
 
 	assert_eq!(
@@ -431,6 +574,1481 @@ fn filter_rel_threshold_works(
 	);
 }
 
+#[rstest]
+#[case(100, 200, ThresholdMode::And, false)]
+#[case(100, 200, ThresholdMode::Or, true)]
+#[case(100_000, 101_000, ThresholdMode::And, false)]
+#[case(100_000, 101_000, ThresholdMode::Or, true)]
+#[case(100_000, 200_000, ThresholdMode::And, true)]
+#[case(100_000, 100_100, ThresholdMode::Or, false)]
+fn filter_abs_threshold_works(
+	#[case] old: u128,
+	#[case] new: u128,
+	#[case] threshold_mode: ThresholdMode,
+	#[case] kept: bool,
+) {
+	let diffs = vec![mocked_diff("", TermDiff::Changed(mocked_change(old, new)))];
+	let params = FilterParams {
+		threshold: 5.,
+		threshold_abs: Some(1_000),
+		threshold_mode,
+		..Default::default()
+	};
+
+	assert_eq!(filter_changes(diffs, &params).is_empty(), !kept);
+}
+
+#[rstest]
+#[case("1000", Ok(1000))]
+#[case("200us", Ok(200_000_000))]
+#[case("1.5ms", Ok(1_500_000_000))]
+#[case("4KiB", Ok(4096))]
+#[case("4 KiB", Ok(4096))]
+#[case("4kb", Err("Unknown unit: kb"))]
+fn parse_abs_value_works(#[case] input: &str, #[case] expected: std::result::Result<u128, &str>) {
+	assert_eq!(parse_abs_value(input), expected.map_err(String::from));
+}
+
+#[rstest]
+#[case(SortBy::Percent, vec!["b", "a", "c"])]
+#[case(SortBy::Abs, vec!["c", "a", "b"])]
+#[case(SortBy::Name, vec!["a", "b", "c"])]
+#[case(SortBy::Pallet, vec!["c", "b", "a"])]
+fn sort_changes_by_works(#[case] by: SortBy, #[case] expected: Vec<&str>) {
+	let diff = |name: &str, file: &str, old, new| ExtrinsicDiff {
+		file: file.into(),
+		..mocked_diff(name, TermDiff::Changed(mocked_change(old, new)))
+	};
+	let mut diffs =
+		vec![diff("a", "z", 100, 115), diff("b", "y", 10, 20), diff("c", "x", 1000, 1050)];
+
+	sort_changes_by(&mut diffs, by);
+	assert_eq!(diffs.iter().map(|d| d.name.as_str()).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn summarize_pallets_works() {
+	let diff = |file: &str, change| ExtrinsicDiff { file: file.into(), ..mocked_diff("", change) };
+	let mut added = mocked_change(0, 50);
+	(added.old_v, added.change) = (None, RelativeChange::Added);
+	let diffs = vec![
+		diff("a", TermDiff::Changed(mocked_change(100, 110))),
+		diff("a", TermDiff::Changed(mocked_change(100, 90))),
+		diff("b", TermDiff::Changed(added)),
+		diff("b", TermDiff::Failed("error".into())),
+	];
+
+	let pallets = summarize_pallets(&diffs);
+	assert_eq!(pallets.len(), 2);
+	assert_eq!(
+		pallets[0],
+		PalletSummary {
+			file: "b".into(),
+			added: 1,
+			errors: 1,
+			new: 50,
+			abs_change: 50,
+			..Default::default()
+		}
+	);
+	assert_eq!((pallets[1].changed, pallets[1].abs_change), (2, 20));
+	assert_eq!(pallets[1].percent(), 0.0);
+}
+
+#[test]
+fn summarize_runtimes_works() {
+	let diff = |file: &str, time, proof| ExtrinsicDiff {
+		file: file.into(),
+		proof_change: Some(TermDiff::Changed(mocked_change(10, proof))),
+		..mocked_diff("", TermDiff::Changed(mocked_change(100, time)))
+	};
+	let diffs = vec![
+		diff("runtime/kusama/src/weights/pallet_a.rs", 150, 10),
+		diff("runtime/kusama/src/weights/pallet_b.rs", 50, 30),
+		diff("runtime/polkadot/src/weights/pallet_a.rs", 100, 10),
+	];
+
+	let totals = summarize_runtimes(&diffs, Dimension::Both);
+	let totals = totals
+		.iter()
+		.map(|t| (t.runtime.as_str(), t.unit, t.extrinsics, t.old, t.new))
+		.collect::<Vec<_>>();
+	assert_eq!(
+		totals,
+		vec![
+			("runtime/kusama", Dimension::Time, 2, 200, 200),
+			("runtime/kusama", Dimension::Proof, 2, 20, 40),
+			("runtime/polkadot", Dimension::Time, 1, 100, 100),
+			("runtime/polkadot", Dimension::Proof, 1, 10, 10),
+		]
+	);
+}
+
+/// An overflowing weight is the maximum; the sums of its pallet and runtime saturate.
+#[test]
+fn summarize_overflow_saturates() {
+	let diff = |change| ExtrinsicDiff {
+		file: "runtime/kusama/src/weights/pallet_a.rs".into(),
+		..mocked_diff("", TermDiff::Changed(change))
+	};
+	let diffs = vec![diff(mocked_change(100, u128::MAX)), diff(mocked_change(100, 200))];
+
+	let pallets = summarize_pallets(&diffs);
+	assert_eq!(
+		(pallets[0].changed, pallets[0].old, pallets[0].new, pallets[0].abs_change),
+		(2, 200, u128::MAX, u128::MAX)
+	);
+	let totals = summarize_runtimes(&diffs, Dimension::Time);
+	assert_eq!((totals[0].extrinsics, totals[0].old, totals[0].new), (2, 200, u128::MAX));
+}
+
+#[test]
+fn diff_stats_works() {
+	let diff = |name: &str, old, new, change| ExtrinsicDiff {
+		file: "pallet_a.rs".into(),
+		..mocked_diff(name, TermDiff::Changed(TermChange { change, ..mocked_change(old, new) }))
+	};
+	let mut diffs = (0..20)
+		.map(|i| diff(&i.to_string(), 100, 100 + i, RelativeChange::Changed))
+		.collect::<Vec<_>>();
+	diffs.push(diff("fast", 100, 40, RelativeChange::Changed));
+	diffs.push(diff("same", 100, 100, RelativeChange::Unchanged));
+	diffs.push(diff("new", 0, 100, RelativeChange::Added));
+	diffs.push(ExtrinsicDiff {
+		change: TermDiff::Failed("err".into()),
+		..diff("err", 0, 0, RelativeChange::Changed)
+	});
+
+	let stats = diff_stats(&diffs);
+	assert_eq!((stats.changed, stats.added, stats.removed, stats.errors), (22, 1, 0, 1));
+	// -60 | 0 0 | 1..4 | 5..9 | 10..19
+	assert_eq!(stats.buckets, [1, 0, 0, 0, 0, 2, 4, 5, 10, 0, 0]);
+	assert_eq!(stats.buckets.len(), DiffStats::bucket_labels().len());
+	assert_eq!(stats.median, Some(8.0));
+	assert_eq!(stats.p95, Some(18.0));
+	assert_eq!(stats.max_increase, Some(("pallet_a.rs".into(), "19".into(), 19.0)));
+	assert_eq!(stats.max_decrease, Some(("pallet_a.rs".into(), "fast".into(), -60.0)));
+
+	let empty = diff_stats(&Vec::new());
+	assert_eq!((empty.median, empty.max_increase), (None, None));
+}
+
+#[test]
+fn versioned_diff_works() {
+	use crate::schema::{VersionedDiff, SCHEMA_VERSION};
+
+	let diffs = vec![ExtrinsicDiff {
+		file: "pallet_a.rs".into(),
+		..mocked_diff("bond", TermDiff::Changed(mocked_change(100, 120)))
+	}];
+	let json = serde_json::to_value(VersionedDiff::new(diffs.clone())).unwrap();
+	assert_eq!(json["schema_version"], SCHEMA_VERSION);
+	let parsed = VersionedDiff::from_json(&json.to_string()).unwrap();
+	assert_eq!(parsed[0].name, "bond");
+
+	// Before the output was versioned.
+	let unversioned = serde_json::to_string(&diffs).unwrap();
+	assert_eq!(VersionedDiff::from_json(&unversioned).unwrap().len(), 1);
+
+	let newer = format!(r#"{{"schema_version": {}, "changes": []}}"#, SCHEMA_VERSION + 1);
+	assert!(VersionedDiff::from_json(&newer).unwrap_err().to_string().contains("is newer"));
+	assert!(VersionedDiff::from_json("{}").is_err());
+}
+
+#[test]
+fn summarize_matrix_works() {
+	let diff = |file: &str, old, new| ExtrinsicDiff {
+		file: file.into(),
+		..mocked_diff("", TermDiff::Changed(mocked_change(old, new)))
+	};
+	let diffs = vec![
+		diff("runtime/polkadot/src/weights/pallet_b.rs", 100, 125),
+		diff("runtime/kusama/src/weights/pallet_a.rs", 100, 150),
+		diff("runtime/kusama/src/weights/pallet_b.rs", 100, 75),
+	];
+
+	let matrix = summarize_matrix(&diffs);
+	assert_eq!(matrix.runtimes, vec!["runtime/kusama", "runtime/polkadot"]);
+	let percents = matrix
+		.pallets
+		.iter()
+		.map(|(name, row)| {
+			(name.as_str(), row.iter().map(|p| p.as_ref().map(|p| p.percent())).collect())
+		})
+		.collect::<Vec<(_, Vec<_>)>>();
+	assert_eq!(
+		percents,
+		vec![
+			("pallet_a.rs", vec![Some(50.0), None]),
+			("pallet_b.rs", vec![Some(-25.0), Some(25.0)])
+		]
+	);
+}
+
+/// Weight files with other names are matched by the pallet that they name.
+#[test]
+fn summarize_matrix_matches_pallet_ids() {
+	let diff = |file: &str| ExtrinsicDiff {
+		file: file.into(),
+		pallet_id: Some(PalletId { crate_name: "frame_system".into(), instance: None }),
+		..mocked_diff("", TermDiff::Changed(mocked_change(100, 110)))
+	};
+	let diffs = vec![
+		diff("runtime/polkadot/src/weights/frame_system.rs"),
+		diff("runtime/kusama/src/weights/system.rs"),
+	];
+
+	let matrix = summarize_matrix(&diffs);
+	assert_eq!(matrix.pallets.len(), 1);
+	assert_eq!(matrix.pallets[0].0, "frame_system");
+	assert!(matrix.pallets[0].1.iter().all(Option::is_some));
+}
+
+#[rstest]
+#[case("", "2024-01-01", None, true)]
+#[case("expires = 2024-01-31", "2024-01-31", None, true)]
+#[case("expires = 2024-01-31", "2024-02-01", None, false)]
+#[case("commit = \"abc\"", "2024-01-01", Some("abcdef"), true)]
+#[case("commit = \"abc\"", "2024-01-01", Some("fedcba"), false)]
+#[case("commit = \"abc\"", "2024-01-01", None, false)]
+fn allowlist_acknowledge_works(
+	#[case] extra: &str,
+	#[case] today: &str,
+	#[case] commit: Option<&str>,
+	#[case] acknowledged: bool,
+) {
+	let list = format!(
+		"[[acknowledged]]\npallet = \"pallet_staking\"\nextrinsic = \"bond\"\nreason = \"ok\"\n{}",
+		extra
+	);
+	let list: allowlist::Allowlist = toml::from_str(&list).unwrap();
+	let diff = |name: &str| ExtrinsicDiff {
+		file: "runtime/src/weights/pallet_staking.rs".into(),
+		..mocked_diff(name, TermDiff::Changed(mocked_change(100, 200)))
+	};
+	let mut diffs = vec![diff("bond"), diff("unbond")];
+
+	list.acknowledge(&mut diffs, today, commit);
+	assert_eq!(diffs[0].acknowledged.is_some(), acknowledged);
+	assert_eq!(diffs[1].acknowledged, None);
+}
+
+#[test]
+fn reset_errors_without_repo() {
+	let err = reset(&crate::testing::root_dir().join("test_data"), "master", false).unwrap_err();
+	assert!(matches!(err, SwcError::Git(_)), "{:?}", err);
+	assert!(err.to_string().starts_with("Failed to open repository"), "{}", err);
+}
+
+#[test]
+fn checkout_temp_keeps_repo_untouched() {
+	let dir = tempfile::tempdir().unwrap();
+	let old = commit_files(dir.path(), &[("weights.rs", "old")]);
+	commit_files(dir.path(), &[("weights.rs", "new")]);
+	// Uncommitted changes must survive.
+	std::fs::write(dir.path().join("weights.rs"), "dirty").unwrap();
+
+	let checkout = checkout_temp(dir.path(), &old.to_string(), false).unwrap();
+	assert_eq!(std::fs::read_to_string(checkout.path().join("weights.rs")).unwrap(), "old");
+	assert_eq!(std::fs::read_to_string(dir.path().join("weights.rs")).unwrap(), "dirty");
+}
+
+#[test]
+fn comparison_builder_works() {
+	let read = |p: &str| std::fs::read_to_string(crate::testing::root_dir().join(p)).unwrap();
+	let dir = tempfile::tempdir().unwrap();
+	let old = commit_files(
+		dir.path(),
+		&[("weights/pallet_staking.rs", &read("test_data/old/pallet_staking.rs.txt"))],
+	);
+	commit_files(
+		dir.path(),
+		&[("weights/pallet_staking.rs", &read("test_data/new/pallet_staking.rs.txt"))],
+	);
+
+	let builder = Comparison::builder().repo(dir.path()).old(old.to_string()).new("HEAD");
+	let err = builder.clone().run().unwrap_err();
+	assert_eq!(err.to_string(), "Comparison needs a path pattern");
+
+	let diff = builder
+		.path_pattern("weights/*.rs")
+		.method(CompareMethod::Base)
+		.threshold(10.0)
+		.run()
+		.unwrap();
+	assert!(!diff.is_empty());
+	assert!(diff.iter().filter_map(|d| d.term()).all(|t| t.percent.abs() >= 10.0));
+	assert!(diff.iter().all(|d| d.term().map_or(true, |t| t.method == CompareMethod::Base)));
+}
+
+#[derive(Default)]
+struct RecordProgress {
+	discovered: std::sync::Mutex<Vec<(String, usize)>>,
+	parsed: std::sync::Mutex<usize>,
+	parsed_refs: std::sync::Mutex<Vec<String>>,
+	compared: std::sync::Mutex<(usize, usize)>,
+}
+
+impl crate::progress::Progress for RecordProgress {
+	fn files_discovered(&self, refname: &str, count: usize) {
+		self.discovered.lock().unwrap().push((refname.into(), count));
+	}
+
+	fn file_parsed(&self, _file: &Path) {
+		*self.parsed.lock().unwrap() += 1;
+	}
+
+	fn files_parsed(&self, refname: &str, _took: std::time::Duration) {
+		self.parsed_refs.lock().unwrap().push(refname.into());
+	}
+
+	fn extrinsic_compared(&self, done: usize, total: usize) {
+		*self.compared.lock().unwrap() = (done, total);
+	}
+}
+
+#[test]
+fn compare_repos_reports_progress() {
+	let read = |p: &str| std::fs::read_to_string(crate::testing::root_dir().join(p)).unwrap();
+	let dir = tempfile::tempdir().unwrap();
+	let old = commit_files(
+		dir.path(),
+		&[
+			("weights/pallet_staking.rs", &read("test_data/old/pallet_staking.rs.txt")),
+			("weights/mod.rs", ""),
+		],
+	);
+	commit_files(
+		dir.path(),
+		&[("weights/pallet_staking.rs", &read("test_data/new/pallet_staking.rs.txt"))],
+	);
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+
+	let progress = RecordProgress::default();
+	let diff = compare_repos_with_progress(
+		(dir.path(), &old.to_string()),
+		(dir.path(), "HEAD"),
+		&params,
+		&FilterParams::default(),
+		"weights/*.rs",
+		100,
+		&progress,
+	)
+	.unwrap();
+
+	// Both commits are parsed at the same time, in any order.
+	let discovered = progress.discovered.lock().unwrap().iter().cloned().collect::<BTreeSet<_>>();
+	assert_eq!(discovered, [(old.to_string(), 1), ("HEAD".into(), 1)].into());
+	assert_eq!(*progress.parsed.lock().unwrap(), 2);
+	let parsed_refs = progress.parsed_refs.lock().unwrap().iter().cloned().collect::<BTreeSet<_>>();
+	assert_eq!(parsed_refs, [old.to_string(), "HEAD".into()].into());
+	assert_eq!(*progress.compared.lock().unwrap(), (diff.len(), diff.len()));
+}
+
+/// Comparison results survive a JSON round-trip.
+#[test]
+fn total_diff_serde_round_trip_works() {
+	let root = crate::testing::root_dir();
+	let olds = parse_file(&root.join("test_data/old/pallet_staking.rs.txt")).unwrap();
+	let news = parse_file(&root.join("test_data/new/pallet_staking.rs.txt")).unwrap();
+	let params = CompareParams { unit: Dimension::Both, offline: true, ..Default::default() };
+	let diff = compare_files(olds, news, &params, &FilterParams::default()).unwrap();
+	assert!(diff.iter().any(|d| d.term().map_or(false, |t| !t.formula.is_empty())));
+
+	let json = serde_json::to_string(&diff).unwrap();
+	let decoded: TotalDiff = serde_json::from_str(&json).unwrap();
+	assert_eq!(decoded.len(), diff.len());
+	// Compared as values since floats are not parsed bit-exact.
+	let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+	assert_eq!(serde_json::to_value(&decoded).unwrap(), value);
+}
+
+/// Storage reads and writes are compared as counts; independent of the time weights.
+#[rstest]
+#[case(Dimension::Reads, "bond", 5, 4)]
+#[case(Dimension::Writes, "withdraw_unbonded_kill", 11, 112)]
+fn compare_storage_accesses_works(
+	#[case] unit: Dimension,
+	#[case] extrinsic: &str,
+	#[case] old: u128,
+	#[case] new: u128,
+) {
+	let root = crate::testing::root_dir();
+	let olds = parse_file(&root.join("test_data/old/pallet_staking.rs.txt")).unwrap();
+	let news = parse_file(&root.join("test_data/new/pallet_staking.rs.txt")).unwrap();
+	let params = CompareParams { unit, offline: true, ..Default::default() };
+	let diff = compare_files(olds, news, &params, &FilterParams::default()).unwrap();
+
+	let change = diff.iter().find(|d| d.name == extrinsic).unwrap().term().unwrap();
+	assert_eq!((change.old_v, change.new_v), (Some(old), Some(new)));
+	assert_eq!(unit.fmt_value(new), new.to_string());
+}
+
+/// The weight files can live in different repos and in different sub-folders.
+#[test]
+fn compare_repos_works() {
+	let read = |p: &str| std::fs::read_to_string(crate::testing::root_dir().join(p)).unwrap();
+	let (old, new) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+	commit_files(
+		old.path(),
+		&[("weights/pallet_staking.rs", &read("test_data/old/pallet_staking.rs.txt"))],
+	);
+	commit_files(
+		new.path(),
+		&[("polkadot/weights/pallet_staking.rs", &read("test_data/new/pallet_staking.rs.txt"))],
+	);
+
+	let cache = tempfile::tempdir().unwrap();
+	let params = CompareParams {
+		method: CompareMethod::Base,
+		offline: true,
+		cache_dir: Some(cache.path().into()),
+		..Default::default()
+	};
+	let compare = || {
+		compare_repos(
+			(old.path(), "HEAD"),
+			(&new.path().join("polkadot"), "HEAD"),
+			&params,
+			&FilterParams::default(),
+			"weights/*.rs",
+			100,
+		)
+		.unwrap()
+	};
+	let diff = compare();
+
+	assert!(diff.iter().all(|c| c.file == "weights/pallet_staking.rs"));
+	// Most extrinsics exist in both versions and are therefore matched.
+	let matched = diff
+		.iter()
+		.filter_map(|c| c.term())
+		.filter(|t| t.old_v.is_some() && t.new_v.is_some())
+		.count();
+	assert!(matched > 20, "Only {} of {} matched", matched, diff.len());
+
+	// Both commits are cached; emptying the entries shows that they are used.
+	let entries = std::fs::read_dir(cache.path()).unwrap().map(|e| e.unwrap()).collect::<Vec<_>>();
+	assert_eq!(entries.len(), 2);
+	for entry in entries {
+		std::fs::write(entry.path(), "[]").unwrap();
+	}
+	assert!(compare().is_empty());
+}
+
+/// Files that moved keep their extrinsics; git tells apart files with the same name.
+#[test]
+fn compare_commits_follows_moved_files() {
+	let weights = |bond: u64| {
+		weight_file(&[("bond", bond), ("unbond", 100), ("rebond", 100), ("chill", 100)])
+	};
+	let dir = tempfile::tempdir().unwrap();
+	let old = commit_files(
+		dir.path(),
+		&[
+			("runtime/polkadot/weights/pallet_staking.rs", &weights(100)),
+			("runtime/kusama/weights/pallet_staking.rs", &weights(1000)),
+		],
+	);
+	let repo = git2::Repository::open(dir.path()).unwrap();
+	let mut index = repo.index().unwrap();
+	index.remove_dir(Path::new("runtime"), 0).unwrap();
+	index.write().unwrap();
+	std::fs::remove_dir_all(dir.path().join("runtime")).unwrap();
+	let new = commit_files(
+		dir.path(),
+		&[
+			("relay/polkadot/weights/pallet_staking.rs", &weights(110)),
+			("relay/kusama/weights/pallet_staking.rs", &weights(1000)),
+		],
+	);
+
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let diff = compare_commits(
+		dir.path(),
+		&old.to_string(),
+		&new.to_string(),
+		&params,
+		&FilterParams::default(),
+		"*/*/weights/*.rs",
+		100,
+	)
+	.unwrap();
+
+	assert_eq!(diff.len(), 8);
+	assert!(diff.iter().all(|d| d.file.starts_with("relay/")));
+	let changed = diff
+		.iter()
+		.filter(|d| d.term().unwrap().change != RelativeChange::Unchanged)
+		.collect::<Vec<_>>();
+	assert_eq!(changed.len(), 1);
+	assert_eq!(changed[0].file, "relay/polkadot/weights/pallet_staking.rs");
+	assert_eq!(changed[0].name, "bond");
+	let change = changed[0].term().unwrap();
+	assert_eq!((change.old_v, change.new_v), (Some(100), Some(110)));
+}
+
+/// Checked out commits compare like any other parsed versions.
+#[test]
+fn checkout_and_compare_works() {
+	let weights = |bond: u64| weight_file(&[("bond", bond), ("unbond", 100)]);
+	let dir = tempfile::tempdir().unwrap();
+	let old = commit_files(dir.path(), &[("old/weights/pallet_staking.rs", &weights(100))]);
+	let new = commit_files(dir.path(), &[("new/weights/pallet_staking.rs", &weights(120))]);
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let filter = FilterParams::default();
+
+	let olds =
+		checkout(dir.path(), &old.to_string(), &params, "old/weights/*.rs", 10, &()).unwrap();
+	let news = checkout(dir.path(), &new.to_string(), &params, "*/weights/*.rs", 10, &()).unwrap();
+	assert_eq!(olds.origin, Some((dir.path().into(), old.to_string())));
+	assert_eq!((olds.extrinsics.len(), news.extrinsics.len()), (2, 4));
+
+	// Data of other sources has no origin; the moved file is still matched by its name.
+	let news = ParsedSet::from(
+		news.extrinsics
+			.into_iter()
+			.filter(|e| e.pallet.starts_with("new/"))
+			.collect::<Vec<_>>(),
+	);
+	let diff = compare(olds, news, &params, &filter, &()).unwrap();
+	assert_eq!(diff.len(), 2);
+	assert!(diff.iter().all(|d| d.file == "new/weights/pallet_staking.rs"));
+	let bond = diff.iter().find(|d| d.name == "bond").unwrap().term().unwrap();
+	assert_eq!((bond.old_v, bond.new_v), (Some(100), Some(120)));
+
+	let err = checkout(dir.path(), "HEAD", &params, "../*.rs", 10, &()).unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		SwcError::Glob("Path pattern cannot contain '..'".into()).to_string()
+	);
+}
+
+/// The cache file names do not depend on the Rust version; see `DefaultHasher`.
+#[test]
+fn cache_file_name_is_stable() {
+	// Test vectors of the FNV-1a hash.
+	assert_eq!(cache::fnv1a(b""), 0xcbf29ce484222325);
+	assert_eq!(cache::fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+	assert_eq!(cache::fnv1a(b"foobar"), 0x85944171f73967e8);
+
+	let key = cache::CacheKey {
+		commit: "abc".into(),
+		sub_dir: "".into(),
+		path_pattern: "*.rs".into(),
+		ignore_errors: false,
+		auto_discover: false,
+	};
+	assert!(key.file_name().starts_with("abc-"));
+	assert_eq!(key.file_name(), key.clone().file_name());
+	let other = cache::CacheKey { path_pattern: "weights/*.rs".into(), ..key.clone() };
+	assert_ne!(key.file_name(), other.file_name());
+}
+
+/// Files with the same blob are parsed once; the changed ones again.
+#[test]
+fn checkout_reuses_parsed_blobs() {
+	let weights = |bond: u64| weight_file(&[("bond", bond)]);
+	let dir = tempfile::tempdir().unwrap();
+	let old = commit_files(
+		dir.path(),
+		&[("weights/pallet_staking.rs", &weights(100)), ("weights/pallet_nis.rs", &weights(7))],
+	);
+	let new = commit_files(dir.path(), &[("weights/pallet_staking.rs", &weights(120))]);
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let olds = checkout(dir.path(), &old.to_string(), &params, "weights/*.rs", 10, &()).unwrap();
+
+	let repo = git2::Repository::open(dir.path()).unwrap();
+	let blob = |commit: git2::Oid, path: &str| {
+		let tree = repo.find_commit(commit).unwrap().tree().unwrap();
+		tree.get_path(Path::new(path)).unwrap().id().to_string()
+	};
+	let nis = blob(old, "weights/pallet_nis.rs");
+	assert_eq!(nis, blob(new, "weights/pallet_nis.rs"));
+	let parsed = cache::load_blob(&nis, "weights/pallet_nis.rs").unwrap();
+	assert_eq!(
+		parsed,
+		olds.extrinsics
+			.iter()
+			.filter(|e| e.pallet.ends_with("nis.rs"))
+			.cloned()
+			.collect::<Vec<_>>()
+	);
+	assert!(cache::load_blob(&blob(new, "weights/pallet_staking.rs"), "weights/pallet_staking.rs")
+		.is_none());
+
+	let news = checkout(dir.path(), &new.to_string(), &params, "weights/*.rs", 10, &()).unwrap();
+	let bonds = news
+		.extrinsics
+		.iter()
+		.map(|e| (e.pallet.as_str(), e.term.clone()))
+		.collect::<Vec<_>>();
+	assert_eq!(
+		bonds,
+		[
+			("weights/pallet_nis.rs", cval!(crate::traits::Weight::from(7))),
+			("weights/pallet_staking.rs", cval!(crate::traits::Weight::from(120)))
+		]
+	);
+}
+
+/// Uncommitted and untracked files of the working tree are compared as they are.
+#[test]
+fn compare_workdir_works() {
+	let weights = |bond: u64| weight_file(&[("bond", bond)]);
+	let dir = tempfile::tempdir().unwrap();
+	let commit = commit_files(dir.path(), &[("runtime/weights/pallet_staking.rs", &weights(411))]);
+	let weights_dir = dir.path().join("runtime/weights");
+	std::fs::write(weights_dir.join("pallet_staking.rs"), weights(822)).unwrap();
+	std::fs::write(weights_dir.join("pallet_nis.rs"), weights(7)).unwrap();
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let filter = FilterParams::default();
+	let repo = dir.path().join("runtime");
+
+	let diff =
+		compare_workdir(&repo, &commit.to_string(), &params, &filter, "weights/*.rs", 10, &())
+			.unwrap();
+	let changes = diff
+		.iter()
+		.map(|d| {
+			let term = d.term().unwrap();
+			(d.file.as_str(), term.old_v, term.new_v, term.change)
+		})
+		.collect::<BTreeSet<_>>();
+	assert_eq!(
+		changes,
+		[
+			("weights/pallet_nis.rs", None, Some(7), RelativeChange::Added),
+			("weights/pallet_staking.rs", Some(411), Some(822), RelativeChange::Changed),
+		]
+		.into()
+	);
+	let local = std::fs::read_to_string(weights_dir.join("pallet_staking.rs")).unwrap();
+	assert_eq!(local, weights(822));
+}
+
+/// Only the staged files are compared with `HEAD`; unstaged changes are ignored.
+#[test]
+fn compare_local_staged_works() {
+	let weights = |bond: u64| weight_file(&[("bond", bond)]);
+	let dir = tempfile::tempdir().unwrap();
+	commit_files(
+		dir.path(),
+		&[("weights/pallet_staking.rs", &weights(411)), ("weights/pallet_nis.rs", &weights(7))],
+	);
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let filter = FilterParams::default();
+	let staged = || {
+		compare_local(dir.path(), "HEAD", None, &params, &filter, "weights/*.rs", 10, &()).unwrap()
+	};
+
+	// Nothing is staged.
+	std::fs::write(dir.path().join("weights/pallet_nis.rs"), weights(8)).unwrap();
+	assert!(staged().is_empty());
+
+	std::fs::write(dir.path().join("weights/pallet_staking.rs"), weights(822)).unwrap();
+	let repo = git2::Repository::open(dir.path()).unwrap();
+	let mut index = repo.index().unwrap();
+	index.add_path(Path::new("weights/pallet_staking.rs")).unwrap();
+	index.write().unwrap();
+
+	let diff = staged();
+	let changes = diff
+		.iter()
+		.map(|d| {
+			let term = d.term().unwrap();
+			(d.file.as_str(), term.old_v, term.new_v, term.change)
+		})
+		.collect::<BTreeSet<_>>();
+	assert_eq!(
+		changes,
+		[
+			("weights/pallet_nis.rs", Some(7), Some(7), RelativeChange::Unchanged),
+			("weights/pallet_staking.rs", Some(411), Some(822), RelativeChange::Changed),
+		]
+		.into()
+	);
+	// A missing upstream is nothing to compare.
+	let pushed =
+		compare_local(dir.path(), "@{upstream}", Some("HEAD"), &params, &filter, "*", 10, &());
+	assert!(pushed.unwrap().is_empty());
+}
+
+/// Auto discovery finds weight files outside of the usual folders and skips the others.
+#[rstest]
+fn checkout_auto_discover_works(#[values(false, true)] git_objects: bool) {
+	let weights = &weight_file(&[("bond", 321)]);
+	let dir = tempfile::tempdir().unwrap();
+	let commit = commit_files(
+		dir.path(),
+		&[
+			("pallets/staking/src/weights.rs", weights),
+			("pallets/staking/src/lib.rs", "pub trait WeightInfo { fn bond() -> Weight; }"),
+			("pallets/staking/src/mock.rs", "impl WeightInfo for Mock { fn bond() -> Weight {} }"),
+			("runtime/src/lib.rs", "impl pallet_staking::Config for Runtime {}"),
+		],
+	);
+	let params = CompareParams {
+		method: CompareMethod::Base,
+		offline: true,
+		git_objects,
+		auto_discover: true,
+		..Default::default()
+	};
+
+	let parsed = checkout(dir.path(), &commit.to_string(), &params, "**/*.rs", 2, &()).unwrap();
+	let pallets = parsed.extrinsics.iter().map(|e| e.pallet.as_str()).collect::<Vec<_>>();
+	assert_eq!(pallets, ["pallets/staking/src/weights.rs"]);
+	let err = checkout(dir.path(), &commit.to_string(), &params, "**/*.rs", 1, &()).unwrap_err();
+	assert!(err.to_string().contains("Found: 2, Max: 1"), "{}", err);
+}
+
+/// Reading the git objects ignores local modifications and leaves them in place.
+#[test]
+fn checkout_git_objects_works() {
+	let weights = |bond: u64| weight_file(&[("bond", bond)]);
+	let dir = tempfile::tempdir().unwrap();
+	let commit = commit_files(
+		dir.path(),
+		&[
+			("runtime/weights/pallet_staking.rs", &weights(311)),
+			("runtime/weights/mod.rs", "pub mod pallet_staking;"),
+			("runtime/weights/pallet_nis.rs", &weights(312)),
+			("runtime/pallet_nis.rs", &weights(313)),
+		],
+	);
+	std::fs::write(dir.path().join("runtime/weights/pallet_staking.rs"), weights(314)).unwrap();
+	std::fs::remove_file(dir.path().join("runtime/weights/pallet_nis.rs")).unwrap();
+	let mut params = CompareParams {
+		method: CompareMethod::Base,
+		offline: true,
+		git_objects: true,
+		..Default::default()
+	};
+	let sub_dir = dir.path().join("runtime");
+	let pattern = "weights/*.rs,!weights/*_nis.rs";
+
+	let parsed = checkout(&sub_dir, &commit.to_string(), &params, pattern, 10, &()).unwrap();
+	let bonds = parsed
+		.extrinsics
+		.iter()
+		.map(|e| (e.pallet.as_str(), e.term.clone()))
+		.collect::<Vec<_>>();
+	assert_eq!(bonds, [("weights/pallet_staking.rs", cval!(crate::traits::Weight::from(311)))]);
+	let local = std::fs::read_to_string(dir.path().join("runtime/weights/pallet_staking.rs"));
+	assert_eq!(local.unwrap(), weights(314));
+	assert!(!dir.path().join("runtime/weights/pallet_nis.rs").exists());
+	let err = checkout(&sub_dir, &commit.to_string(), &params, "**/*.rs", 2, &()).unwrap_err();
+	assert!(err.to_string().contains("Found: 3, Max: 2"), "{}", err);
+
+	params.git_objects = false;
+	let checked_out = checkout(&sub_dir, &commit.to_string(), &params, pattern, 10, &()).unwrap();
+	assert_eq!(checked_out.extrinsics, parsed.extrinsics);
+}
+
+/// Changes of the old commit since the merge base are not reported.
+#[test]
+fn compare_commits_since_base_works() {
+	let weights = |bond: u64, unbond: u64| weight_file(&[("bond", bond), ("unbond", unbond)]);
+	let dir = tempfile::tempdir().unwrap();
+	let base = commit_files(dir.path(), &[("weights.rs", &weights(100, 100))]);
+	// The target branch changes `bond` while the pull request changes `unbond`.
+	let master = commit_files(dir.path(), &[("weights.rs", &weights(200, 100))]);
+	let repo = git2::Repository::open(dir.path()).unwrap();
+	repo.reset(&repo.find_object(base, None).unwrap(), git2::ResetType::Hard, None)
+		.unwrap();
+	let pr = commit_files(dir.path(), &[("weights.rs", &weights(100, 150))]);
+
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let compare = |base: &str| {
+		compare_commits_since_base(
+			dir.path(),
+			&master.to_string(),
+			&pr.to_string(),
+			base,
+			&params,
+			&FilterParams::default(),
+			"*.rs",
+			100,
+			&(),
+		)
+		.unwrap()
+	};
+
+	assert_eq!(
+		merge_base(dir.path(), &master.to_string(), &pr.to_string(), false).unwrap(),
+		base.to_string()
+	);
+	let diff = compare(&master.to_string());
+	assert_eq!(diff.len(), 1);
+	assert_eq!(diff[0].name, "unbond");
+	let change = diff[0].term().unwrap();
+	assert_eq!((change.old_v, change.new_v), (Some(100), Some(150)));
+	// Nothing changed since the commit itself.
+	assert!(compare(&pr.to_string()).is_empty());
+}
+
+#[tokio::test]
+async fn compare_commits_async_works() {
+	use crate::task::{compare_commits_async, Limits};
+
+	let weights = |bond: u64| weight_file(&[("bond", bond)]);
+	let dir = tempfile::tempdir().unwrap();
+	let old = commit_files(dir.path(), &[("weights.rs", &weights(100))]).to_string();
+	let new = commit_files(dir.path(), &[("weights.rs", &weights(200))]).to_string();
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let filter = FilterParams::default();
+	let compare = |limits| {
+		compare_commits_async(
+			dir.path(),
+			&old,
+			&new,
+			&params,
+			&filter,
+			"*.rs",
+			100,
+			limits,
+			std::sync::Arc::new(()),
+		)
+	};
+
+	let diff = compare(Limits::default()).await.unwrap();
+	assert_eq!(diff.len(), 1);
+	let limits = Limits::default();
+	limits.cancel.cancel();
+	assert!(matches!(compare(limits).await, Err(SwcError::Cancelled(_))));
+	let limits = Limits::timeout(std::time::Duration::ZERO);
+	assert!(matches!(compare(limits).await, Err(SwcError::TimedOut(_))));
+}
+
+#[test]
+fn progress_cancels_comparison() {
+	struct Cancel;
+	impl Progress for Cancel {
+		fn cancelled(&self) -> bool {
+			true
+		}
+	}
+	let dir = tempfile::tempdir().unwrap();
+	let commit = commit_files(dir.path(), &[("weights.rs", "")]).to_string();
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+
+	let err = compare_commits_with_progress(
+		dir.path(),
+		&commit,
+		&commit,
+		&params,
+		&FilterParams::default(),
+		"*.rs",
+		100,
+		&Cancel,
+	)
+	.unwrap_err();
+	assert_eq!(err.to_string(), format!("Cancelled: Parsing {} (0/1)", commit));
+}
+
+/// Only commits that change the weight file are part of the history.
+#[test]
+fn extrinsic_history_works() {
+	let read = |p: &str| std::fs::read_to_string(crate::testing::root_dir().join(p)).unwrap();
+	let dir = tempfile::tempdir().unwrap();
+	let first = commit_files(
+		dir.path(),
+		&[("pallet_staking.rs", &read("test_data/old/pallet_staking.rs.txt"))],
+	);
+	commit_files(dir.path(), &[("README.md", "unrelated")]);
+	let second = commit_files(
+		dir.path(),
+		&[("pallet_staking.rs", &read("test_data/new/pallet_staking.rs.txt"))],
+	);
+	commit_files(dir.path(), &[("README.md", "unrelated 2")]);
+
+	let history = crate::history::extrinsic_history(
+		dir.path(),
+		&format!("{}..HEAD", first),
+		Path::new("pallet_staking.rs"),
+		"bond",
+		&SimpleScope::empty(),
+		DbWeights::default(),
+	)
+	.unwrap();
+
+	let commits = history.iter().map(|p| p.commit.clone()).collect::<Vec<_>>();
+	assert_eq!(commits, vec![first.to_string(), second.to_string()]);
+	assert_ne!(history[0].ref_time, history[1].ref_time);
+}
+
+#[rstest]
+#[case(5., Some(3))]
+#[case(1000., None)]
+fn bisect_regression_works(#[case] threshold: Percent, #[case] first_bad: Option<usize>) {
+	let weights = |t: u64| weight_file(&[("bond", t)]);
+	let dir = tempfile::tempdir().unwrap();
+	let commits = [100, 101, 102, 120, 121, 90]
+		.into_iter()
+		.map(|t| commit_files(dir.path(), &[("weights.rs", &weights(t))]))
+		.collect::<Vec<_>>();
+
+	let res = crate::history::bisect_regression(
+		dir.path(),
+		(&commits[0].to_string(), &commits[4].to_string()),
+		Path::new("weights.rs"),
+		"bond",
+		&SimpleScope::empty(),
+		DbWeights::default(),
+		Dimension::Time,
+		threshold,
+	);
+
+	match first_bad {
+		Some(i) => assert_eq!(res.unwrap().first_bad.commit, commits[i].to_string()),
+		None => assert!(matches!(res, Err(SwcError::NotFound(_)))),
+	}
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn weight_db_works() {
+	let weights = |bond: u64| {
+		format!(
+			"impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {{
+				fn bond() -> Weight {{ Weight::from_parts({}, 10) }}
+				fn unbond() -> Weight {{ Weight::from_parts(100, 0) }}
+			}}",
+			bond
+		)
+	};
+	let dir = tempfile::tempdir().unwrap();
+	let first = commit_files(dir.path(), &[("weights.rs", &weights(100))]);
+	let second = commit_files(dir.path(), &[("weights.rs", &weights(150))]);
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+
+	let mut db = db::WeightDb::open_in_memory().unwrap();
+	for commit in [first, second, first] {
+		let stored = db.ingest(dir.path(), &commit.to_string(), &params, "*.rs", 100, &());
+		assert_eq!(stored.unwrap(), 2);
+	}
+	assert_eq!(db.query(None, None).unwrap().len(), 4);
+	assert!(db.query(Some("other.rs"), None).unwrap().is_empty());
+
+	// Ingesting a commit again replaces its weights.
+	let mut bonds = db.query(Some("weights"), Some("bond")).unwrap();
+	bonds.sort_by_key(|r| r.ref_time);
+	let bonds = bonds.iter().map(|r| (r.commit.clone(), r.ref_time, r.proof_size));
+	assert_eq!(
+		bonds.collect::<Vec<_>>(),
+		[(first.to_string(), 100, 10), (second.to_string(), 150, 10)]
+	);
+}
+
+#[test]
+fn watch_works() {
+	let weights = |bond: u64| weight_file(&[("bond", bond)]);
+	let dir = tempfile::tempdir().unwrap();
+	commit_files(dir.path(), &[("weights.rs", &weights(100))]);
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let filter = FilterParams::default();
+	let mut watch = watch::Watch::new(dir.path(), "HEAD", &params, "*.rs", 100, &()).unwrap();
+	let bond = |watch: &watch::Watch| {
+		let diff = watch.compare(&params, &filter).unwrap();
+		let change = diff[0].term().unwrap().clone();
+		(change.old_v, change.new_v)
+	};
+	assert_eq!(bond(&watch), (Some(100), Some(100)));
+	assert!(watch.poll().unwrap().is_empty());
+
+	// Make sure that the modification time changes.
+	std::thread::sleep(std::time::Duration::from_millis(50));
+	std::fs::write(dir.path().join("weights.rs"), weights(150)).unwrap();
+	assert_eq!(watch.poll().unwrap(), [dir.path().join("weights.rs")]);
+	assert_eq!(bond(&watch), (Some(100), Some(150)));
+
+	std::fs::remove_file(dir.path().join("weights.rs")).unwrap();
+	assert_eq!(watch.poll().unwrap(), [dir.path().join("weights.rs")]);
+	assert_eq!(bond(&watch), (Some(100), None));
+}
+
+/// Only the commits of the refs and the blobs of the weight files are cloned.
+#[test]
+fn sparse_clone_works() {
+	let weights = |bond: u64| weight_file(&[("bond", bond)]);
+	let origin = tempfile::tempdir().unwrap();
+	let old = commit_files(
+		origin.path(),
+		&[("weights/staking.rs", &weights(100)), ("README.md", "unrelated")],
+	);
+	let new = commit_files(origin.path(), &[("weights/staking.rs", &weights(150))]);
+	let mut config = git2::Repository::open(origin.path()).unwrap().config().unwrap();
+	config.set_bool("uploadpack.allowFilter", true).unwrap();
+	config.set_bool("uploadpack.allowAnySHA1InWant", true).unwrap();
+
+	let dir = tempfile::tempdir().unwrap();
+	let clone = dir.path().join("clone");
+	let url = format!("file://{}", origin.path().display());
+	let (old, new) = (old.to_string(), new.to_string());
+	sparse_clone(&url, &clone, &[&old, &new], "weights/*.rs").unwrap();
+
+	let repo = git2::Repository::open(&clone).unwrap();
+	assert!(repo.is_shallow());
+	let tree = repo
+		.revparse_single(&format!("origin/{}", old))
+		.unwrap()
+		.peel_to_tree()
+		.unwrap();
+	let readme = tree.get_path(Path::new("README.md")).unwrap();
+	assert!(!repo.odb().unwrap().exists(readme.id()), "Unrelated blobs are not fetched");
+
+	let params = CompareParams { method: CompareMethod::Base, offline: true, ..Default::default() };
+	let diff =
+		compare_commits(&clone, &old, &new, &params, &FilterParams::default(), "weights/*.rs", 10)
+			.unwrap();
+	assert_eq!(diff.len(), 1);
+	let change = diff[0].term().unwrap();
+	assert_eq!((change.old_v, change.new_v), (Some(100), Some(150)));
+}
+
+#[rstest]
+#[case("pull/7/head")]
+#[case("refs/pull/7/head")]
+fn resolve_pull_request_works(#[case] refname: &str) {
+	let origin = tempfile::tempdir().unwrap();
+	commit_files(origin.path(), &[("weights.rs", "base")]);
+	let head = commit_files(origin.path(), &[("weights.rs", "head")]);
+	let repo = git2::Repository::open(origin.path()).unwrap();
+	repo.reference("refs/pull/7/head", head, false, "").unwrap();
+
+	let dir = tempfile::tempdir().unwrap();
+	let clone = git2::Repository::clone(origin.path().to_str().unwrap(), dir.path()).unwrap();
+	assert!(resolve_ref(&clone, refname, false, &()).is_err(), "Not fetched by default");
+
+	let resolved = resolve_ref(&clone, refname, true, &()).unwrap();
+	assert_eq!(resolved.id(), head);
+	// Afterwards it also resolves without fetching.
+	assert_eq!(resolve_ref(&clone, refname, false, &()).unwrap().id(), head);
+}
+
+#[rstest]
+#[case("master", None)]
+#[case("v1.0.0", None)]
+#[case("refs/heads/master", None)]
+#[case("refs/tags/v1.0.0", None)]
+#[case("pull/1234/head", Some("pull/1234/head"))]
+#[case("refs/pull/1234/merge", Some("pull/1234/merge"))]
+#[case("merge-requests/5/head", Some("merge-requests/5/head"))]
+#[case("refs/changes/34/1234/1", Some("changes/34/1234/1"))]
+fn hidden_ref_works(#[case] refname: &str, #[case] hidden: Option<&str>) {
+	assert_eq!(hidden_ref(refname), hidden);
+}
+
+#[test]
+fn commit_before_works() {
+	let day = 86_400;
+	// 2024-01-01 is 19723 days after 1970-01-01.
+	let jan = 19_723 * day;
+	let dir = tempfile::tempdir().unwrap();
+	let first = commit_files_at(dir.path(), &[("weights.rs", "1")], jan - 1);
+	let second = commit_files_at(dir.path(), &[("weights.rs", "2")], jan);
+	let third = commit_files_at(dir.path(), &[("weights.rs", "3")], jan + 40 * day);
+	let before = |date| history::commit_before(dir.path(), "HEAD", date, false);
+
+	assert_eq!(before("2024-01-01").unwrap(), first.to_string());
+	assert_eq!(before("2024-01-02").unwrap(), second.to_string());
+	assert_eq!(before("2024-02-10").unwrap(), second.to_string());
+	assert_eq!(before("2024-02-11").unwrap(), third.to_string());
+	assert!(matches!(before("2023-12-31"), Err(SwcError::NotFound(_))));
+	assert!(matches!(before("2024-13-01"), Err(SwcError::InvalidInput(_))));
+	assert!(matches!(before("01.01.2024"), Err(SwcError::InvalidInput(_))));
+}
+
+#[test]
+fn release_tags_works() {
+	let dir = tempfile::tempdir().unwrap();
+	let repo = git2::Repository::init(dir.path()).unwrap();
+	// Tagged in a different order than released.
+	for (i, tag) in ["v1.10.0", "v1.9.0", "v1.9.1", "v2.0", "other"].iter().enumerate() {
+		let commit = commit_files_at(dir.path(), &[("weights.rs", tag)], 1_000 - i as i64);
+		repo.tag_lightweight(tag, &repo.find_object(commit, None).unwrap(), false)
+			.unwrap();
+	}
+
+	let tags = history::release_tags(dir.path(), "v*").unwrap();
+	assert_eq!(tags, ["v1.9.0", "v1.9.1", "v1.10.0", "v2.0"]);
+	// `other` is not a version, so all are ordered by commit time.
+	let tags = history::release_tags(dir.path(), "*").unwrap();
+	assert_eq!(tags, ["other", "v2.0", "v1.9.1", "v1.9.0", "v1.10.0"]);
+}
+
+/// A weight file with one extrinsic per `(name, ref_time)`.
+fn weight_file(fns: &[(&str, u64)]) -> String {
+	let fns = fns
+		.iter()
+		.map(|(name, t)| format!("\tfn {}() -> Weight {{ Weight::from_parts({}, 0) }}\n", name, t))
+		.collect::<String>();
+	format!("impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {{\n{}}}", fns)
+}
+
+/// Commit the files to the repository at `path`; creating it if necessary.
+fn commit_files(path: &Path, files: &[(&str, &str)]) -> git2::Oid {
+	let now = git2::Signature::now("test", "test@example.com").unwrap().when().seconds();
+	commit_files_at(path, files, now)
+}
+
+/// Same as [`commit_files`] with `time` as UNIX timestamp of the commit.
+fn commit_files_at(path: &Path, files: &[(&str, &str)], time: i64) -> git2::Oid {
+	let repo = git2::Repository::open(path).or_else(|_| git2::Repository::init(path)).unwrap();
+	let mut index = repo.index().unwrap();
+	for (name, content) in files {
+		std::fs::create_dir_all(path.join(name).parent().unwrap()).unwrap();
+		std::fs::write(path.join(name), content).unwrap();
+		index.add_path(Path::new(name)).unwrap();
+	}
+	index.write().unwrap();
+	let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+	let sig = git2::Signature::new("test", "test@example.com", &git2::Time::new(time, 0)).unwrap();
+	let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+	let parents = parent.iter().collect::<Vec<_>>();
+	repo.commit(Some("HEAD"), &sig, &sig, "test", &tree, &parents).unwrap()
+}
+
+#[test]
+fn evaluate_extrinsic_works() {
+	let file = crate::testing::root_dir().join("test_data/new/staking_chromatic.rs.txt");
+	let extrinsics = parse_file(&file).unwrap();
+	let ext = extrinsics.iter().find(|e| e.name == "payout_stakers_alive_staked").unwrap();
+
+	let db = DbWeights::default();
+	let scope = SimpleScope::empty().with_var("n", SimpleTerm::Value(0));
+	// Base weight plus 10 reads with 25µs and 3 writes with 100µs.
+	assert_eq!(evaluate_extrinsic(ext, &scope, Dimension::Time, db), Ok(704_033_219));
+	assert_eq!(evaluate_extrinsic(ext, &scope, Dimension::Proof, db), Ok(83594));
+	// The same with 8µs per read and 50µs per write.
+	let parity_db = DbWeights::PARITY_DB;
+	assert_eq!(evaluate_extrinsic(ext, &scope, Dimension::Time, parity_db), Ok(384_033_219));
+	assert_eq!(evaluate_extrinsic(ext, &scope, Dimension::Proof, parity_db), Ok(83594));
+
+	let scope = SimpleScope::empty().with_var("n", SimpleTerm::Value(2));
+	assert_eq!(evaluate_extrinsic(ext, &scope, Dimension::Proof, db), Ok(83594 + 2 * 16026));
+
+	let err = evaluate_extrinsic(ext, &SimpleScope::empty(), Dimension::Time, db).unwrap_err();
+	assert!(err.ends_with("payout_stakers_alive_staked: n"), "{}", err);
+}
+
+/// Both dimensions are evaluated at once with the same values as one at a time.
+#[test]
+fn evaluate_weight_works() {
+	let file = crate::testing::root_dir().join("test_data/new/staking_chromatic.rs.txt");
+	for ext in parse_file(&file).unwrap() {
+		for values in [SimpleScope::empty(), SimpleScope::empty().with_var("n", scalar!(2))] {
+			for db in [DbWeights::ROCKS_DB, DbWeights::PARITY_DB] {
+				let scope = component_scope(&ext, &values);
+				let time = evaluate_extrinsic(&ext, &scope, Dimension::Time, db).unwrap();
+				let proof = evaluate_extrinsic(&ext, &scope, Dimension::Proof, db).unwrap();
+				assert_eq!(
+					evaluate_weight(&ext, &scope, db),
+					Ok((time, proof).into()),
+					"{}",
+					ext.name
+				);
+			}
+		}
+	}
+
+	let weight = crate::traits::Weight { ref_time: 704_033_219, proof_size: 83594 };
+	assert_eq!(weight.fmt_value(), "704.03us, 81.63KiB");
+	assert_eq!(weight.get(Dimension::Proof), Some(83594));
+	assert_eq!(weight.get(Dimension::Fee), None);
+}
+
+#[test]
+fn attribute_change_works() {
+	let old = add!(val!(1000), mul!(val!(500), var!("n")));
+	let new = add!(add!(val!(999), mul!(val!(1000), var!("n"))), var!("m"));
+	let scope = SimpleScope::empty().with_var("n", val!(10)).with_var("m", val!(7));
+
+	let attr = attribute_change(Some(&old), Some(&new), &scope, &["n".into()]).unwrap();
+	assert_eq!(
+		attr,
+		vec![
+			Attribution { part: FormulaPart::Base, delta: 6 },
+			Attribution { part: FormulaPart::Component("n".into()), delta: 5000 },
+		]
+	);
+
+	let attr = attribute_change(Some(&old), Some(&new), &scope, &["n".into(), "m".into()]).unwrap();
+	assert_eq!(attr[0], Attribution { part: FormulaPart::Base, delta: -1 });
+	assert_eq!(attr[2], Attribution { part: FormulaPart::Component("m".into()), delta: 7 });
+}
+
+#[test]
+fn diff_formula_works() {
+	let old = add!(add!(val!(25_100_000), mul!(val!(4), var!("READ"))), mul!(val!(100), var!("n")));
+	let new = add!(add!(val!(26_400_000), mul!(val!(5), var!("READ"))), mul!(val!(100), var!("n")));
+
+	let parts = diff_formula(&old, &new)
+		.iter()
+		.map(|p| p.fmt(Dimension::Time))
+		.collect::<Vec<_>>();
+	assert_eq!(parts, vec!["base 25.10us → 26.40us", "READ count 4 → 5", "slope of n unchanged"]);
+}
+
+/// An extrinsic is kept if any of its dimensions passes the filter.
+#[rstest]
+#[case((10, 10), (10, 20), true)]
+#[case((10, 20), (10, 10), true)]
+#[case((10, 10), (10, 10), false)]
+fn filter_both_dimensions_works(
+	#[case] time: (u128, u128),
+	#[case] proof: (u128, u128),
+	#[case] kept: bool,
+) {
+	let diffs = vec![ExtrinsicDiff {
+		proof_change: Some(TermDiff::Changed(mocked_change(proof.0, proof.1))),
+		..mocked_diff("", TermDiff::Changed(mocked_change(time.0, time.1)))
+	}];
+	let params = FilterParams { threshold: 5., ..Default::default() };
+
+	assert_eq!(filter_changes(diffs, &params).is_empty(), !kept);
+}
+
+#[rstest]
+#[case("polkadot", 1_070_000_000, 10_000_000_000)]
+#[case("westend", 1_070_000_000, 10_000_000_000)]
+#[case("kusama", 1_070_000_000, 33_333_333)]
+fn fee_presets_work(#[case] preset: &str, #[case] weight: u128, #[case] pico: u128) {
+	let fees: fee::FeeParams = preset.parse().unwrap();
+	assert_eq!(fees.fee(weight), pico);
+}
+
+#[test]
+fn fee_params_reject_zero_denominator() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("fees.toml");
+	std::fs::write(
+		&path,
+		"symbol = \"UNIT\"\ndecimals = 12\n[[coefficients]]\ndegree = 1\nnumerator = 1\ndenominator = 0",
+	)
+	.unwrap();
+
+	let err = path.to_str().unwrap().parse::<fee::FeeParams>().unwrap_err();
+	assert!(err.ends_with("denominator is zero"), "{}", err);
+}
+
+#[test]
+fn compare_fees_works() {
+	let ext = |time: u128| ChromaticExtrinsic {
+		name: "transfer".into(),
+		pallet: "balances.rs".into(),
+		term: cval!(crate::traits::Weight { ref_time: time, proof_size: 0 }),
+		comp_ranges: None,
+		span: None,
+		pallet_id: None,
+	};
+	let mut params = CompareParams {
+		method: CompareMethod::Base,
+		unit: Dimension::Fee,
+		offline: true,
+		..Default::default()
+	};
+	let filter = FilterParams::default();
+
+	let err = compare_files(vec![ext(1)], vec![ext(2)], &params, &filter).unwrap_err();
+	assert!(err.to_string().contains("--fee-params"), "{}", err);
+
+	params.fee_params = Some(fee::FeeParams::preset("polkadot").unwrap());
+	let diff = compare_files(vec![ext(1_070_000_000)], vec![ext(2_140_000_000)], &params, &filter)
+		.unwrap();
+	let change = diff[0].term().unwrap();
+	assert_eq!((change.old_v, change.new_v), (Some(10_000_000_000), Some(20_000_000_000)));
+	assert_eq!(change.percent, 100.0);
+}
+
+/// An overflowing weight is compared as the maximum with a warning instead of failing.
+#[test]
+fn compare_overflow_warns() {
+	let ext = |coefficient: u128| ChromaticExtrinsic {
+		name: "transfer".into(),
+		pallet: "balances.rs".into(),
+		term: cmul!(
+			cval!(crate::traits::Weight { ref_time: coefficient, proof_size: 0 }),
+			cvar!("n")
+		),
+		comp_ranges: Some(hashmap! { "n".into() => ComponentRange { min: 0, max: 1_000 } }),
+		span: None,
+		pallet_id: None,
+	};
+	let params =
+		CompareParams { method: CompareMethod::ExactWorst, offline: true, ..Default::default() };
+
+	let diff =
+		compare_files(vec![ext(10)], vec![ext(u128::MAX / 10)], &params, &Default::default())
+			.unwrap();
+	let change = diff[0].term().unwrap();
+	assert_eq!((change.old_v, change.new_v), (Some(10_000), Some(u128::MAX)));
+	assert!(change.attribution.is_empty());
+	assert!(diff[0].warning().unwrap().contains("exceeds 128 bits"), "{:?}", diff[0].warning());
+}
+
+#[rstest]
+#[case("rocksdb", Ok(DbWeights::ROCKS_DB))]
+#[case("ParityDB", Ok(DbWeights::PARITY_DB))]
+#[case("20us,80us", Ok(DbWeights { read: 20_000_000, write: 80_000_000 }))]
+#[case("1000,2000", Ok(DbWeights { read: 1000, write: 2000 }))]
+#[case("sled", Err("Expected a preset or READ,WRITE"))]
+#[case("1,2x", Err("Unknown unit: x"))]
+fn parse_db_weights_works(
+	#[case] input: &str,
+	#[case] expected: std::result::Result<DbWeights, &str>,
+) {
+	assert_eq!(input.parse::<DbWeights>(), expected.map_err(String::from));
+}
+
+#[rstest]
+#[case(DbWeights::ROCKS_DB, 1_000 + 2 * 25_000_000 + 100_000_000)]
+#[case(DbWeights::PARITY_DB, 1_000 + 2 * 8_000_000 + 50_000_000)]
+fn compare_with_db_weights_works(#[case] db_weights: DbWeights, #[case] expected: u128) {
+	let ext = SimpleExtrinsic {
+		name: "".into(),
+		pallet: "".into(),
+		term: add!(add!(val!(1_000), mul!(scalar!(2), var!("READ"))), var!("WRITE")),
+		comp_ranges: None,
+		span: None,
+		pallet_id: None,
+	};
+	let params = CompareParams {
+		method: CompareMethod::Base,
+		offline: true,
+		db_weights,
+		..Default::default()
+	};
+
+	let change = compare_extrinsics(Some(ext.clone()), Some(ext), &params).unwrap();
+	assert_eq!(change.new_v, Some(expected));
+}
+
+/// An inverted range is reported instead of its meaningless worst case.
+#[test]
+fn compare_files_warns_inverted_range() {
+	let ext = SimpleExtrinsic {
+		name: "bond".into(),
+		pallet: "staking.rs".into(),
+		term: mul!(val!(10), var!("n")),
+		comp_ranges: Some(hashmap! { "n".into() => ComponentRange { min: 100, max: 10 } }),
+		span: None,
+		pallet_id: None,
+	}
+	.map_term(|t| t.into_chromatic(Dimension::Time));
+	let params = CompareParams { offline: true, ..Default::default() };
+
+	let diff = compare_files(vec![ext.clone()], vec![ext], &params, &Default::default()).unwrap();
+	assert_eq!(
+		diff[0].warning().map(String::as_str),
+		Some("Component n has inverted range 100..10: staking.rs::bond")
+	);
+}
+
+/// An inverted range has the same median as the ordered one.
+#[test]
+fn compare_median_inverted_range_works() {
+	let ext = |range: (u32, u32)| SimpleExtrinsic {
+		name: "".into(),
+		pallet: "".into(),
+		term: mul!(val!(10), var!("n")),
+		comp_ranges: Some(hashmap! { "n".into() => ComponentRange { min: range.0, max: range.1 } }),
+		span: None,
+		pallet_id: None,
+	};
+	let params =
+		CompareParams { method: CompareMethod::Median, offline: true, ..Default::default() };
+
+	for range in [(100, 10), (10, 100)] {
+		let change = compare_extrinsics(Some(ext(range)), Some(ext(range)), &params).unwrap();
+		assert_eq!(change.new_v, Some(550), "{:?}", range);
+	}
+}
+
+#[rstest]
+#[case(add!(val!(100), mul!(val!(10), var!("n"))), (0, 100), Ok(()))]
+#[case(mul!(val!(10), var!("READ")), (0, 100), Ok(()))]
+#[case(var!("n"), (100, 1), Err("Component n has inverted range 100..1"))]
+#[case(
+	SimpleTerm::Sub(Box::new(val!(1000)), Box::new(mul!(val!(10), var!("n")))),
+	(0, 10),
+	Err("Weight decreases with increasing n")
+)]
+#[case(
+	mul!(val!(u128::MAX / 2), var!("n")),
+	(0, 3),
+	Err("Weight overflows at the maximum of n: Value exceeds 128 bits")
+)]
+fn monotonicity_check_works(
+	#[case] term: SimpleTerm,
+	#[case] range: (u32, u32),
+	#[case] res: std::result::Result<(), &str>,
+) {
+	let ext = SimpleExtrinsic {
+		name: "".into(),
+		pallet: "".into(),
+		term,
+		comp_ranges: Some(hashmap! { "n".into() => ComponentRange { min: range.0, max: range.1 } }),
+		span: None,
+		pallet_id: None,
+	};
+
+	assert_eq!(monotonicity_check(&ext), res.map_err(String::from));
+}
+
+#[test]
+fn range_changes_works() {
+	let ext = |n: (u32, u32), m: u32| SimpleExtrinsic {
+		name: "bond".into(),
+		pallet: "staking.rs".into(),
+		term: add!(var!("n"), var!("m")),
+		comp_ranges: Some(hashmap! {
+			"n".into() => ComponentRange { min: n.0, max: n.1 },
+			"m".into() => ComponentRange { min: 0, max: m },
+		}),
+		span: None,
+		pallet_id: None,
+	};
+	let (old, new) = (ext((0, 64), 10), ext((0, 1024), 10));
+
+	assert_eq!(range_changes(Some(&old), Some(&new)), vec!["range of `n` changed 0..64 → 0..1024"]);
+	assert!(range_changes(Some(&old), Some(&old)).is_empty());
+	assert!(range_changes(None, Some(&new)).is_empty());
+
+	let params = CompareParams { offline: true, ..Default::default() };
+	let olds = vec![old.map_term(|t| t.into_chromatic(Dimension::Time))];
+	let news = vec![new.map_term(|t| t.into_chromatic(Dimension::Time))];
+	let diff = compare_files(olds, news, &params, &FilterParams::default()).unwrap();
+	assert_eq!(diff[0].warning().unwrap(), "range of `n` changed 0..64 → 0..1024");
+}
+
+#[rstest]
+#[case("runtime/*/src/weights/**/*.rs", vec!["runtime/kusama/src/weights/pallet_xcm.rs", "runtime/kusama/src/weights/xcm/mod.rs", "runtime/polkadot/src/weights/pallet_xcm.rs"])]
+#[case("runtime/*/src/weights/**/*.rs,!**/xcm/*", vec!["runtime/kusama/src/weights/pallet_xcm.rs", "runtime/polkadot/src/weights/pallet_xcm.rs"])]
+#[case("runtime/*/src/weights/**/*.rs, !runtime/kusama/**", vec!["runtime/polkadot/src/weights/pallet_xcm.rs"])]
+#[case("!**/xcm/*", vec![])]
+fn list_files_excludes_works(#[case] pattern: &str, #[case] expected: Vec<&str>) {
+	let dir = tempfile::tempdir().unwrap();
+	let commit = commit_files(
+		dir.path(),
+		&[
+			("runtime/kusama/src/weights/pallet_xcm.rs", ""),
+			("runtime/kusama/src/weights/xcm/mod.rs", ""),
+			("runtime/polkadot/src/weights/pallet_xcm.rs", ""),
+		],
+	);
+	let files = list_files(dir.path(), pattern, 10).unwrap();
+	let files = files.iter().map(|f| f.strip_prefix(dir.path()).unwrap()).collect::<Vec<_>>();
+	assert_eq!(files, expected.iter().map(Path::new).collect::<Vec<_>>());
+
+	// The committed files match alike.
+	let repo = git2::Repository::open(dir.path()).unwrap();
+	let tree = repo.find_commit(commit).unwrap().tree().unwrap();
+	let blobs = list_blobs(&repo, &tree, Path::new(""), pattern, 10).unwrap();
+	assert_eq!(blobs, expected.iter().map(PathBuf::from).collect::<Vec<_>>());
+}
+
 fn mocked_change(old: u128, new: u128) -> TermChange {
 	TermChange {
 		old: None,
@@ -441,5 +2059,22 @@ fn mocked_change(old: u128, new: u128) -> TermChange {
 		percent: percent(old, new),
 		change: RelativeChange::Changed,
 		method: CompareMethod::GuessWorst,
+		attribution: Vec::new(),
+		formula: Vec::new(),
+		approximate: false,
+		samples: Vec::new(),
+	}
+}
+
+fn mocked_diff(name: &str, change: TermDiff) -> ExtrinsicDiff {
+	ExtrinsicDiff {
+		name: name.into(),
+		file: String::new(),
+		change,
+		proof_change: None,
+		acknowledged: None,
+		span: None,
+		renamed_from: None,
+		pallet_id: None,
 	}
 }
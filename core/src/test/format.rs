@@ -0,0 +1,74 @@
+use rstest::*;
+use std::collections::BTreeMap;
+
+use crate::{
+	format::format_content, parse::pallet::parse_content, term::SimpleTerm, testing::root_dir,
+	Dimension,
+};
+
+/// The monomials of each extrinsic in both dimensions.
+fn weights(content: &str) -> BTreeMap<String, [BTreeMap<Vec<String>, u128>; 2]> {
+	parse_content("pallet".into(), content.into())
+		.unwrap()
+		.into_iter()
+		.map(|e| {
+			let monomials =
+				|unit| e.term.simplify(unit).as_ref().map(SimpleTerm::monomials).unwrap();
+			(e.name.to_string(), [monomials(Dimension::Time), monomials(Dimension::Proof)])
+		})
+		.collect()
+}
+
+/// Formatting keeps the weights and is idempotent.
+#[rstest]
+#[case("test_data/new/pallet_staking.rs.txt")]
+#[case("test_data/new/staking_chromatic.rs.txt")]
+#[case("test_data/old/pallet_staking.rs.txt")]
+fn format_content_keeps_weights(#[case] file: &str) {
+	let content = std::fs::read_to_string(root_dir().join(file)).unwrap();
+	let formatted = format_content(&content).unwrap();
+
+	assert_eq!(weights(&formatted), weights(&content));
+	assert_eq!(format_content(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn format_content_works() {
+	let content = "impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Staking Bonded (r:1 w:1)
+	fn bond(n: u32, ) -> Weight {
+		// Minimum execution time: 94_560 nanoseconds.
+		Weight::from_parts(154033219, 83594)
+			// Standard Error: 26_663
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+			.saturating_add(Weight::from_proof_size(16026).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(10 as u64))
+			.saturating_add(Weight::from_ref_time(31_269_223).saturating_mul(n.into()))
+	}
+}
+impl WeightInfo for () {
+	fn unbond() -> Weight {
+		Weight::from_ref_time(1_000).saturating_add(RocksDbWeight::get().reads(1))
+	}
+}
+";
+	let expected = "impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Staking Bonded (r:1 w:1)
+	fn bond(n: u32, ) -> Weight {
+		// Minimum execution time: 94_560 nanoseconds.
+		Weight::from_parts(154_033_219, 83_594)
+			.saturating_add(Weight::from_parts(31_269_223, 16_026).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+}
+impl WeightInfo for () {
+	fn unbond() -> Weight {
+		Weight::from_parts(1_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+	}
+}
+";
+
+	assert_eq!(format_content(content).unwrap(), expected);
+}
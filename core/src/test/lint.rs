@@ -0,0 +1,81 @@
+use rstest::*;
+
+use crate::{
+	cadd, cmul, creads, cval, cvar,
+	lint::*,
+	parse::pallet::{ChromaticExtrinsic, ComponentRange},
+	term::{ChromaticTerm, Term},
+	traits::Weight,
+};
+
+fn extrinsic(term: ChromaticTerm, ranges: &[(&str, u32)]) -> ChromaticExtrinsic {
+	ChromaticExtrinsic {
+		name: "bond".into(),
+		pallet: "staking.rs".into(),
+		term,
+		comp_ranges: Some(
+			ranges
+				.iter()
+				.map(|(n, max)| (n.to_string(), ComponentRange { min: 0, max: *max }))
+				.collect(),
+		),
+		span: None,
+		pallet_id: None,
+	}
+}
+
+#[rstest]
+#[case(
+	cadd!(cval!(Weight { ref_time: 1000, proof_size: 10 }), cmul!(cval!(Weight { ref_time: 10, proof_size: 1 }), cvar!("n"))),
+	&[("n", 100)],
+	vec![]
+)]
+#[case(
+	cadd!(cval!(Weight { ref_time: 1000, proof_size: 0 }), creads!(ChromaticTerm::Scalar(2000))),
+	&[],
+	vec![(LintCheck::ExcessiveAccesses, "Call has 2000 READs")]
+)]
+#[case(
+	cmul!(cval!(Weight { ref_time: 0, proof_size: 1024 }), cvar!("n")),
+	&[("n", 10_000)],
+	vec![
+		(LintCheck::ProofLimit, "Worst case proof size 9.77MiB exceeds 5.00MiB"),
+		(LintCheck::ZeroBase, "Base weight is zero"),
+	]
+)]
+#[case(
+	creads!(ChromaticTerm::Scalar(1)),
+	&[],
+	vec![]
+)]
+#[case(
+	cadd!(cval!(Weight { ref_time: 1000, proof_size: 0 }), cvar!("m")),
+	&[("n", 10)],
+	vec![
+		(LintCheck::UnusedComponents, "Component n is unused"),
+		(LintCheck::MissingRanges, "Component m has no range"),
+	]
+)]
+fn lint_extrinsic_works(
+	#[case] term: ChromaticTerm,
+	#[case] ranges: &[(&str, u32)],
+	#[case] expected: Vec<(LintCheck, &str)>,
+) {
+	let findings = lint_extrinsic(&extrinsic(term, ranges), &LintParams::default())
+		.into_iter()
+		.map(|f| (f.check, f.message))
+		.collect::<Vec<_>>();
+	let expected = expected.into_iter().map(|(c, m)| (c, m.to_string())).collect::<Vec<_>>();
+
+	assert_eq!(findings, expected);
+}
+
+#[test]
+fn lint_only_runs_selected_checks() {
+	let ext = extrinsic(cvar!("m"), &[("n", 10)]);
+	let params = LintParams { checks: vec![LintCheck::MissingRanges], ..Default::default() };
+
+	let findings = lint_extrinsic(&ext, &params);
+	assert_eq!(findings.len(), 1);
+	assert_eq!(findings[0].check, LintCheck::MissingRanges);
+}
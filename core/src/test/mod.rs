@@ -1,3 +1,12 @@
+pub mod access;
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod auth;
 pub mod core;
+pub mod format;
+pub mod lint;
+pub mod monotonicity;
 pub mod parse;
+pub mod remote;
+pub mod rename;
 pub mod term;
@@ -0,0 +1,46 @@
+use rstest::*;
+
+use crate::{
+	add,
+	monotonicity::{component_monotonicity, Monotonicity},
+	mul, scalar,
+	scope::SimpleScope,
+	term::SimpleTerm,
+	var,
+};
+
+#[rstest]
+// Only in the new weight.
+#[case(scalar!(10), add!(scalar!(10), var!("n")), Monotonicity::Increasing)]
+// Only in the old weight.
+#[case(add!(scalar!(10), var!("n")), scalar!(10), Monotonicity::Decreasing)]
+// The same slope approaches the smaller base.
+#[case(add!(scalar!(10), var!("n")), add!(scalar!(20), var!("n")), Monotonicity::Decreasing)]
+#[case(add!(scalar!(20), var!("n")), add!(scalar!(10), var!("n")), Monotonicity::Increasing)]
+// Unchanged weights have the same relative change everywhere.
+#[case(add!(scalar!(10), var!("n")), add!(scalar!(10), var!("n")), Monotonicity::Increasing)]
+// Depends on whether `m` is below or above 10.
+#[case(add!(scalar!(10), var!("n")), add!(var!("m"), var!("n")), Monotonicity::Unknown)]
+// Quadratic.
+#[case(scalar!(10), mul!(var!("n"), var!("n")), Monotonicity::Unknown)]
+// No polynomial.
+#[case(scalar!(10), SimpleTerm::Max(scalar!(5).into(), var!("n").into()), Monotonicity::Unknown)]
+fn component_monotonicity_works(
+	#[case] old: SimpleTerm,
+	#[case] new: SimpleTerm,
+	#[case] want: Monotonicity,
+) {
+	assert_eq!(component_monotonicity(&old, &new, &SimpleScope::empty(), "n"), want);
+}
+
+/// Variables of the scope are constants.
+#[test]
+fn component_monotonicity_binds_scope() {
+	let old = add!(mul!(scalar!(2), var!("READ")), var!("n"));
+	let new = add!(scalar!(10), var!("n"));
+
+	let scope = SimpleScope::empty().with_var("READ", scalar!(1));
+	assert_eq!(component_monotonicity(&old, &new, &scope, "n"), Monotonicity::Decreasing);
+	let scope = SimpleScope::empty().with_var("READ", scalar!(100));
+	assert_eq!(component_monotonicity(&old, &new, &scope, "n"), Monotonicity::Increasing);
+}
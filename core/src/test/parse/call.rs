@@ -0,0 +1,64 @@
+use rstest::*;
+
+use crate::{parse::pallet::parse_content, scalar, term::ChromaticTerm};
+
+const PALLET: &str = "
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::call_index(0)]
+		#[pallet::weight(Weight::from_parts(10_000, 0))]
+		pub fn set_dummy(origin: OriginFor<T>, new_value: T::Balance) -> DispatchResult {
+			Ok(())
+		}
+
+		#[pallet::call_index(1)]
+		#[pallet::weight((Weight::from_parts(5_000, 64), DispatchClass::Operational))]
+		pub fn kill(origin: OriginFor<T>) -> DispatchResult {
+			Ok(())
+		}
+
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::accumulate_dummy())]
+		pub fn accumulate_dummy(origin: OriginFor<T>, increase_by: T::Balance) -> DispatchResult {
+			Ok(())
+		}
+	}
+}";
+
+#[rstest]
+#[case("set_dummy", Some((10_000, 0)))]
+#[case("kill", Some((5_000, 64)))]
+#[case("accumulate_dummy", None)]
+fn parse_call_weights_works(#[case] call: &str, #[case] weight: Option<(u128, u128)>) {
+	let got = parse_content("pallet_example".into(), PALLET.into()).unwrap();
+	let term = got.iter().find(|e| e.name == call).map(|e| e.term.clone());
+
+	assert_eq!(term, weight.map(|w| ChromaticTerm::Value(w.into())));
+}
+
+#[test]
+fn parse_call_weights_v1_works() {
+	let pallet = "
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::weight(10_000)]
+		pub fn set_dummy(origin: OriginFor<T>) -> DispatchResult { Ok(()) }
+	}";
+	let got = parse_content("pallet_example".into(), pallet.into()).unwrap();
+
+	assert_eq!(got.len(), 1);
+	assert_eq!(got[0].term, scalar!(10_000).into_chromatic(crate::Dimension::Time));
+}
+
+#[test]
+fn parse_call_weights_errors_without_hard_coded_weights() {
+	let pallet = PALLET
+		.replace("Weight::from_parts(10_000, 0)", "T::WeightInfo::set_dummy()")
+		.replace("(Weight::from_parts(5_000, 64), ", "(T::WeightInfo::kill(), ");
+
+	assert!(parse_content("pallet_example".into(), pallet).is_err());
+}
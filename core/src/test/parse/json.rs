@@ -0,0 +1,77 @@
+use rstest::*;
+
+use crate::{
+	cadd, cmul, creads, cval, cvar, cwrites,
+	parse::{json::parse_content, pallet::ComponentRange},
+	term::{ChromaticTerm, Term},
+	traits::Weight,
+};
+
+/// Results of a benchmark with `1µs + n * 100ns`, `1 + n` reads, one write and `500 + n * 10`
+/// bytes of proof.
+fn results(pallet: &str) -> String {
+	let results = (0..=10)
+		.map(|n| {
+			format!(
+				r#"{{ "components": [["n", {}], ["c", 3]], "extrinsic_time": {}, "storage_root_time": 0, "reads": {}, "repeat_reads": 0, "writes": 1, "repeat_writes": 0, "proof_size": {}, "keys": [] }}"#,
+				n,
+				1_000 + n * 100,
+				1 + n,
+				500 + n * 10
+			)
+		})
+		.collect::<Vec<_>>()
+		.join(",");
+
+	format!(
+		r#"[{{ "pallet": {}, "instance": "Instance1", "benchmark": "transfer", "time_results": [{}], "db_results": [] }}]"#,
+		pallet, results
+	)
+}
+
+#[rstest]
+#[case(r#""pallet_balances""#)]
+#[case("[112,97,108,108,101,116,95,98,97,108,97,110,99,101,115]")]
+fn parse_content_works(#[case] pallet: &str) {
+	let got = parse_content(&results(pallet)).unwrap();
+
+	assert_eq!(got.len(), 1);
+	assert_eq!(got[0].name, "transfer");
+	assert_eq!(got[0].pallet, "pallet_balances.rs");
+	let want: ChromaticTerm = cadd!(
+		cadd!(
+			cadd!(
+				cadd!(
+					cval!(Weight { ref_time: 1_000_000, proof_size: 500 }),
+					cmul!(cval!(Weight { ref_time: 100_000, proof_size: 10 }), cvar!("n"))
+				),
+				creads!(ChromaticTerm::Scalar(1))
+			),
+			creads!(cmul!(Term::Scalar(1), cvar!("n")))
+		),
+		cwrites!(ChromaticTerm::Scalar(1))
+	);
+	assert_eq!(got[0].term, want);
+
+	let ranges = got[0].comp_ranges.as_ref().unwrap();
+	assert_eq!(ranges["n"], ComponentRange { min: 0, max: 10 });
+	assert_eq!(ranges["c"], ComponentRange { min: 3, max: 3 });
+}
+
+#[rstest]
+#[case(r#"{ "pallet": "pallet_balances" }"#)]
+#[case(r#"[{ "pallet": "pallet_balances", "benchmark": "transfer", "time_results": [] }]"#)]
+fn parse_content_errors(#[case] input: &str) {
+	assert!(parse_content(input).is_err());
+}
+
+/// Raw results are told apart from weight files by their content, eg. when read from stdin.
+#[test]
+fn parse_any_content_detects_json() {
+	let content = format!("\n  {}", results(r#""pallet_balances""#));
+	let got = crate::parse::pallet::parse_any_content("stdin".into(), content.clone()).unwrap();
+	assert_eq!(got, parse_content(&content).unwrap());
+
+	let err = crate::parse::pallet::parse_any_content("stdin".into(), "fn x() {}".into());
+	assert!(err.unwrap_err().to_string().starts_with("stdin: "));
+}
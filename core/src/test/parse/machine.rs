@@ -0,0 +1,65 @@
+use rstest::*;
+use std::path::PathBuf;
+
+use crate::{
+	compare_machines,
+	parse::machine::{parse_content, parse_file, MachineScore},
+	RelativeChange,
+};
+
+#[rstest]
+#[case(r#"[{ "metric": "Blake2256", "score": { "MiBs": 2.5 } }]"#, "Blake2256", 2.5)]
+#[case(r#"[{ "metric": "Blake2256", "score": 2.5 }]"#, "Blake2256", 2.5)]
+#[case(r#"[{ "metric": "MemCopy", "minimum": { "GiBs": 2 } }]"#, "MemCopy", 2048.0)]
+#[case(r#"[{ "metric": "Sr25519Verify", "score": { "KiBs": 512 } }]"#, "Sr25519Verify", 0.5)]
+#[case(
+	r#"[{ "metric": { "Blake2256Parallel": { "num_cores": 8 } }, "score": 1 }]"#,
+	"Blake2256Parallel(num_cores=8)",
+	1.0
+)]
+fn parse_content_works(#[case] input: &str, #[case] metric: &str, #[case] score: f64) {
+	let got = parse_content(input).unwrap();
+
+	assert_eq!(got, vec![MachineScore { metric: metric.into(), score }]);
+}
+
+#[rstest]
+#[case(r#"{ "metric": "Blake2256", "score": 1 }"#)]
+#[case(r#"[{ "metric": "Blake2256" }]"#)]
+#[case(r#"[{ "metric": "Blake2256", "score": { "TiBs": 1 } }]"#)]
+fn parse_content_errors(#[case] input: &str) {
+	assert!(parse_content(input).is_err());
+}
+
+#[test]
+fn compare_machines_works() {
+	let old = parse_file(&PathBuf::from("../test_data/machine/old.json")).unwrap();
+	let new = parse_file(&PathBuf::from("../test_data/machine/new.json")).unwrap();
+
+	let got = compare_machines(old, new)
+		.into_iter()
+		.map(|d| (d.metric, d.change))
+		.collect::<Vec<_>>();
+	assert_eq!(
+		got,
+		vec![
+			("Blake2256".into(), RelativeChange::Changed),
+			("Blake2256Parallel(num_cores=8)".into(), RelativeChange::Added),
+			("DiskRndWrite".into(), RelativeChange::Removed),
+			("DiskSeqWrite".into(), RelativeChange::Changed),
+			("MemCopy".into(), RelativeChange::Changed),
+			("Sr25519Verify".into(), RelativeChange::Unchanged),
+		]
+	);
+}
+
+/// A zero score has no relative change; the metric counts as added.
+#[test]
+fn compare_machines_zero_score_works() {
+	let score = |score| vec![MachineScore { metric: "DiskSeqWrite".into(), score }];
+
+	let got = compare_machines(score(0.0), score(100.0));
+	assert_eq!((got[0].change, got[0].percent), (RelativeChange::Added, 0.0));
+	let got = compare_machines(score(0.0), score(0.0));
+	assert_eq!((got[0].change, got[0].percent), (RelativeChange::Unchanged, 0.0));
+}
@@ -1,13 +1,17 @@
+mod call;
 pub mod helper;
 mod integration;
+mod json;
+mod machine;
 mod overhead;
 mod pallet;
 mod storage;
+mod xcm;
 
 use rstest::*;
 use std::path::Path;
 
-use crate::parse::{PathStripping, PathStripping::*};
+use crate::parse::{looks_like_weights, PathStripping, PathStripping::*};
 
 #[rstest]
 #[case("repo/pallet.rs", ".", FileName, "pallet.rs")]
@@ -47,3 +51,17 @@ fn fancy_regex_works() {
 		assert!(regex.is_match(input).unwrap());
 	}
 }
+
+#[rstest]
+#[case("impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> { fn bond() -> Weight { todo!() } }", true)]
+#[case(
+	"impl WeightInfo for () {\n\tfn transfer(n: u32, ) -> Weight {\n\t\tWeight::zero()\n\t}\n}",
+	true
+)]
+#[case("impl<Call> XcmWeightInfo<Call> for KusamaXcmWeight<Call> { fn withdraw_asset(assets: &MultiAssets) -> Weight { todo!() } }", true)]
+#[case("pub trait WeightInfo { fn bond() -> Weight; }", false)]
+#[case("impl pallet_staking::Config for Runtime { type WeightInfo = (); }", false)]
+#[case("fn bond() -> Weight { Weight::zero() }", false)]
+fn looks_like_weights_works(#[case] content: &str, #[case] expected: bool) {
+	assert_eq!(looks_like_weights(content), expected);
+}
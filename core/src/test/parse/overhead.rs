@@ -4,6 +4,8 @@ use std::path::PathBuf;
 use crate::{
 	cadd, cmul, cval, cvar,
 	parse::overhead::{parse_content, parse_file, Weight},
+	scope::SimpleScope,
+	Dimension,
 };
 
 // Parses hard-coded Storage weight files correctly.
@@ -50,3 +52,17 @@ fn parses_chromatic_expression(#[case] input: &str, #[case] want: Weight) {
 
 	assert_eq!(want, got);
 }
+
+/// The `WEIGHT_PER_NANOS` constants are substituted such that the term has no free variables.
+#[rstest]
+#[case("../test_data/new/extrinsic_weights.rs.txt", "ExtrinsicBaseWeight", 85_212_000)]
+#[case("../test_data/chromatic/block_weights.rs.txt", "BlockExecutionWeight", 381_015_000)]
+fn into_extrinsic_works(#[case] file: PathBuf, #[case] name: &str, #[case] want: u128) {
+	let ext = parse_file(&file).unwrap().into_extrinsic("file".into());
+	assert_eq!(ext.name, name);
+
+	let term = ext.term.simplify(Dimension::Time).unwrap();
+	let scope = SimpleScope::empty();
+	assert!(term.free_vars(&scope).is_empty());
+	assert_eq!(term.eval(&scope).unwrap(), want);
+}
@@ -6,7 +6,7 @@ use crate::{
 	add, creads, cwrites, mul,
 	parse::pallet::{
 		parse_content, parse_expression, parse_file, parse_scalar_expression, ChromaticExtrinsic,
-		ComponentRange,
+		ComponentRange, LineRange, PalletId, SourceSpan,
 	},
 	reads, scalar,
 	scope::{Scope, *},
@@ -43,13 +43,15 @@ fn parses_weight_files(#[case] path: PathBuf) {
 }"
 )]
 fn parse_function_v1_works(#[case] input: String) {
-	let got = parse_content("".into(), input).unwrap();
+	let got = parse_content("".into(), input.clone()).unwrap();
 
 	let want = vec![ChromaticExtrinsic {
 		name: "ext".into(),
 		pallet: "".into(),
 		term: Term::Value((5, 0).into()),
 		comp_ranges: None,
+		span: span(1, 1, 1),
+		pallet_id: my_pallet(&input),
 	}];
 	assert_eq!(want, got);
 }
@@ -62,7 +64,8 @@ fn parse_function_v1_works(#[case] input: String) {
 	}
 }",
 	5,
-	0
+	0,
+	(2, 3, 4)
 )]
 #[case(
 	"impl WeightInfo for () {
@@ -71,7 +74,8 @@ fn parse_function_v1_works(#[case] input: String) {
 	}
 }",
 	0,
-	5
+	5,
+	(2, 3, 4)
 )]
 #[case(
 	"impl<T: frame_system::Config> my_pallet::WeightInfo for WeightInfo<T> {
@@ -80,7 +84,8 @@ fn parse_function_v1_works(#[case] input: String) {
 	}
 }",
 	5,
-	0
+	0,
+	(2, 3, 4)
 )]
 #[case(
 	"impl<T: frame_system::Config> my_pallet::WeightInfo for WeightInfo<T> {
@@ -95,20 +100,42 @@ fn parse_function_v1_works(#[case] input: String) {
 			Weight::from_parts(15, 30)
 		}
 }",
-	15, 30
+	15, 30, (6, 10, 11)
 )]
-fn parse_chromatic_function_works(#[case] input: String, #[case] t: u64, #[case] p: u64) {
-	let got = parse_content("".into(), input).unwrap();
+fn parse_chromatic_function_works(
+	#[case] input: String,
+	#[case] t: u64,
+	#[case] p: u64,
+	#[case] lines: (usize, usize, usize),
+) {
+	let got = parse_content("".into(), input.clone()).unwrap();
 
 	let want = vec![ChromaticExtrinsic {
 		name: "ext".into(),
 		pallet: "".into(),
 		term: Term::Value((t as u128, p as u128).into()),
 		comp_ranges: None,
+		span: span(lines.0, lines.1, lines.2),
+		pallet_id: my_pallet(&input),
 	}];
 	assert_eq!(want, got);
 }
 
+/// The id of `my_pallet` if the input implements its trait.
+fn my_pallet(input: &str) -> Option<PalletId> {
+	input
+		.contains("my_pallet::WeightInfo")
+		.then(|| PalletId { crate_name: "my_pallet".into(), instance: None })
+}
+
+/// The span of a weight function from its signature to its end.
+fn span(start: usize, weight: usize, end: usize) -> Option<SourceSpan> {
+	Some(SourceSpan {
+		function: LineRange { start, end },
+		weight: LineRange { start: weight, end: weight },
+	})
+}
+
 // NOTE: Try not to put // into a multiline comment, it will break!
 // Rather use the r# syntax.
 
@@ -123,7 +150,7 @@ fn parse_chromatic_function_works(#[case] input: String, #[case] t: u64, #[case]
 	}"#
 )]
 fn parse_component_range_works(#[case] input: String) {
-	let got = parse_content("".into(), input).unwrap();
+	let got = parse_content("".into(), input.clone()).unwrap();
 
 	let ranges = HashMap::from([
 		("c".into(), ComponentRange { min: 1_337, max: 2000 }),
@@ -134,6 +161,8 @@ fn parse_component_range_works(#[case] input: String) {
 		pallet: "".into(),
 		term: Term::Value((5, 0).into()),
 		comp_ranges: Some(ranges),
+		span: span(4, 5, 6),
+		pallet_id: my_pallet(&input),
 	}];
 	assert_eq!(want, got);
 }
@@ -254,7 +283,7 @@ fn parse_expression_works_v15(#[case] input: &str, #[case] want: SimpleTerm) {
 	Term::Add(
 		Box::new(Term::Value((10, 20).into())),
 		Box::new(cwrites!(Term::Mul(
-			Box::new(Term::Value(Weight{time: 1, proof: 0})),
+			Box::new(Term::Value(Weight{ref_time: 1, proof_size: 0})),
 			Box::new(Term::Var("s".into())),
 		))),
 ))]
@@ -275,3 +304,182 @@ fn chromatic_syntax(#[case] input: &str, #[case] want: ChromaticTerm) {
 	// Eval does not panic
 	let _ = got.eval(&Scope::empty());
 }
+
+#[rstest]
+#[case("pallet_collective::WeightInfo", "weights/pallet_collective_council.rs", Some(("pallet_collective", Some("council"))))]
+#[case("pallet_collective::WeightInfo", "collective_technical_committee.rs", Some(("pallet_collective", Some("technical_committee"))))]
+#[case("pallet_collective::WeightInfo", "pallet_collective.rs", Some(("pallet_collective", None)))]
+#[case("pallet_collective::WeightInfo", "council.rs", Some(("pallet_collective", None)))]
+#[case("frame_system::WeightInfo", "system.rs", Some(("frame_system", None)))]
+#[case("pallet_staking::WeightInfo", "../test_data/new/pallet_staking.rs.txt", Some(("pallet_staking", None)))]
+#[case("WeightInfo", "pallet_staking.rs", None)]
+#[case("crate::WeightInfo", "pallet_staking.rs", None)]
+fn pallet_id_works(
+	#[case] trait_path: &str,
+	#[case] file: &str,
+	#[case] want: Option<(&str, Option<&str>)>,
+) {
+	let path: Path = syn::parse_str(trait_path).unwrap();
+	let want = want.map(|(c, i)| PalletId { crate_name: c.into(), instance: i.map(Into::into) });
+	assert_eq!(PalletId::of_trait(&path, file), want);
+}
+
+/// The pallet id is parsed from the implemented trait.
+#[test]
+fn parse_content_sets_pallet_id() {
+	let content = "impl<T: frame_system::Config> pallet_collective::WeightInfo for WeightInfo<T> {
+		fn vote() -> Weight { Weight::from_parts(100, 0) }
+	}";
+	let exts = parse_content("pallet_collective_council.rs".into(), content.into()).unwrap();
+	let id = exts[0].pallet_id.as_ref().unwrap();
+	assert_eq!(id.to_string(), "pallet_collective (council)");
+
+	let exts = parse_file(&PathBuf::from("../test_data/old/pallet_staking.rs.txt")).unwrap();
+	assert!(exts
+		.iter()
+		.all(|e| e.pallet_id.as_ref().unwrap().crate_name == "pallet_staking"));
+	let exts = parse_file(&PathBuf::from("../test_data/new/pallet_staking.rs.txt")).unwrap();
+	assert!(exts.iter().all(|e| e.pallet_id.is_none()));
+}
+
+/// Constants of the file are inlined; also when they are defined by other constants.
+#[rstest]
+#[case("const BASE: u64 = 5;", "Weight::from_parts(BASE, 0)")]
+#[case("const BASE: Weight = Weight::from_parts(5, 0);", "BASE")]
+#[case("const BASE: u64 = FIVE; const FIVE: u64 = 5;", "Weight::from_parts(BASE as u64, 0)")]
+#[case("parameter_types! { pub const Base: u64 = 5; }", "Weight::from_parts(Base::get(), 0)")]
+#[case(
+	"parameter_types! { pub const Base: u64 = Five::get(); pub const Five: u64 = 5; }",
+	"Weight::from_parts(Base::get(), 0)"
+)]
+#[case(
+	"frame_support::parameter_types! { pub const Base: Weight = Weight::from_parts(5, 0); }",
+	"Base::get()"
+)]
+fn parse_constants_works(#[case] constants: &str, #[case] weight: &str) {
+	let content = format!(
+		"{}
+		impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {{
+			fn ext() -> Weight {{ {} }}
+		}}",
+		constants, weight
+	);
+	let got = parse_content("".into(), content).unwrap();
+	assert_eq!(got[0].term.simplify(crate::Dimension::Time), Ok(SimpleTerm::Value(5)));
+}
+
+/// Calls of functions and methods of the same file are inlined.
+#[rstest]
+#[case("fn base() -> Weight { Weight::from_parts(5, 0) }", "base()")]
+#[case("fn base(n: u64) -> Weight { Weight::from_parts(n, 0) }", "base(5)")]
+#[case("", "Self::other()")]
+#[case("", "<Self as WeightInfo>::other()")]
+#[case(
+	"impl<T> SubstrateWeight<T> { fn worst(n: u64) -> Weight { Weight::from_parts(n, 0) } }",
+	"Self::worst(5)"
+)]
+#[case(
+	"fn twice(n: u64) -> Weight { once(n).saturating_add(once(n)) } fn once(n: u64) -> Weight { Weight::from_parts(n, 0) }",
+	"twice(2).saturating_add(Weight::from_parts(1, 0))"
+)]
+fn parse_calls_works(#[case] functions: &str, #[case] weight: &str) {
+	let content = format!(
+		"{}
+		impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {{
+			fn ext() -> Weight {{ {} }}
+			fn other() -> Weight {{ Weight::from_parts(5, 0) }}
+		}}",
+		functions, weight
+	);
+	let got = parse_content("".into(), content).unwrap();
+	assert_eq!(got.len(), 2);
+	assert_eq!(got[0].term.eval(&Scope::empty()), Ok((5, 0).into()));
+}
+
+/// Casts and conversions of components are the component itself.
+#[rstest]
+#[case("Weight::from_parts(1, 2).saturating_mul(n.into())")]
+#[case("Weight::from_parts(1, 2).saturating_mul(u64::from(n))")]
+#[case("Weight::from_parts(1, 2).saturating_mul(From::from(n))")]
+#[case("Weight::from_parts(1, 2).saturating_mul((n as u64).into())")]
+#[case("Weight::from_parts(1, 2).saturating_mul(n.saturated_into())")]
+#[case("Weight::from_parts(1_u64.saturating_mul(u64::from(n)), 2_u64.saturating_mul(n as u64))")]
+#[case(
+	"Weight::from_parts(1_u64.saturating_mul(core::primitive::u64::from(n)), n.saturating_mul(2))"
+)]
+fn parse_conversion_works(#[case] input: &str) {
+	let expr: Expr = syn::parse_str(input).unwrap();
+	let got = parse_expression(&expr).unwrap();
+	assert_eq!(got.free_vars(&crate::scope::ChromaticScope::empty()), ["n".to_string()].into());
+
+	let scope = Scope::empty().with_var("n", Term::Scalar(3));
+	assert_eq!(got.eval(&scope), Ok((3, 6).into()));
+}
+
+/// Minima and maxima are per dimension; the subtraction saturates.
+#[rstest]
+#[case("Weight::from_parts(10, 0).max(Weight::from_parts(5, 7))", (10, 7))]
+#[case("Weight::from_parts(10, 0).min(Weight::from_parts(5, 7))", (5, 0))]
+#[case("core::cmp::max(Weight::from_parts(10, 0), Weight::from_parts(5, 7))", (10, 7))]
+#[case("sp_std::cmp::min(Weight::from_parts(10, 0), Weight::from_parts(5, 7))", (5, 0))]
+#[case("Weight::from_parts(10, 8).saturating_sub(Weight::from_parts(5, 9))", (5, 0))]
+#[case("Weight::from_parts(core::cmp::max(10, 4 as u64), 0)", (10, 0))]
+#[case("Weight::from_parts(10_u64.saturating_sub(4), 3_u64.max(2))", (6, 3))]
+fn parse_cmp_works(#[case] input: &str, #[case] want: (u128, u128)) {
+	let expr: Expr = syn::parse_str(input).unwrap();
+	let got = parse_expression(&expr).unwrap();
+	assert_eq!(got.eval(&Scope::empty()), Ok(want.into()));
+}
+
+/// Each branch is an arm; the worst arm counts per scope and dimension.
+#[rstest]
+#[case("if s > 0 { Weight::from_parts(10, 0) } else { Weight::from_parts(5, 7) }", 2, 0, (10, 7))]
+#[case(
+	"if s == 0 { Weight::from_parts(1, 0) } else if s == 1 { Weight::from_parts(2, 0) } else { Weight::from_parts(3, 0) }",
+	3,
+	0,
+	(3, 0)
+)]
+#[case(
+	"match s { 0 => Weight::from_parts(1, 0), _ => Weight::from_parts(2, 0).saturating_mul(s.into()) }",
+	2,
+	10,
+	(20, 0)
+)]
+#[case("Weight::from_parts(if s > 0 { 10 } else { 20 }, 0)", 2, 0, (20, 0))]
+fn parse_branch_works(
+	#[case] input: &str,
+	#[case] arms: usize,
+	#[case] s: u128,
+	#[case] want: (u128, u128),
+) {
+	let expr: Expr = syn::parse_str(input).unwrap();
+	let got = parse_expression(&expr).unwrap();
+	let branch = got.visit(&mut |t| Ok(matches!(t, Term::Branch(a) if a.len() == arms))).unwrap();
+	assert!(branch.into_iter().any(|b| b));
+
+	let scope = Scope::empty().with_var("s", Term::Scalar(s));
+	assert_eq!(got.eval(&scope), Ok(want.into()));
+}
+
+#[test]
+fn parse_branch_needs_else() {
+	let expr: Expr = syn::parse_str("if s > 0 { Weight::from_parts(10, 0) }").unwrap();
+	assert!(parse_expression(&expr).is_err());
+}
+
+/// Parsed versions share the names of their pallets and extrinsics; they serialize as strings.
+#[test]
+fn parse_content_interns_names() {
+	let content = "impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+		fn bond() -> Weight { Weight::from_parts(5, 0) }
+	}";
+	let old = parse_content("staking.rs".into(), content.into()).unwrap();
+	let new = parse_content(String::from("staking.rs").into(), content.into()).unwrap();
+	assert_eq!(old[0].pallet.as_ptr(), new[0].pallet.as_ptr());
+	assert_eq!(old[0].name.as_ptr(), new[0].name.as_ptr());
+
+	let json = serde_json::to_value(&old[0]).unwrap();
+	assert_eq!((&json["pallet"], &json["name"]), (&"staking.rs".into(), &"bond".into()));
+	assert_eq!(serde_json::from_value::<ChromaticExtrinsic>(json).unwrap(), old[0]);
+}
@@ -3,7 +3,9 @@ use std::path::PathBuf;
 
 use crate::{
 	parse::storage::{parse_file, Db, RWs, Weights},
+	scope::SimpleScope,
 	term::SimpleTerm as Term,
+	Dimension,
 };
 
 /// Parses hard-coded DB weight files correctly.
@@ -34,3 +36,25 @@ fn make_weights(read: u128, write: u128, db: Db, per_nanos: bool) -> Weights {
 	}
 	Weights { weights: RWs { read, write }, db }
 }
+
+#[rstest]
+#[case("../test_data/new/rocksdb_weights.rs.txt", "RocksDbWeight", 25_000, 100_000)]
+#[case("../test_data/new/paritydb_weights.rs.txt", "ParityDbWeight", 8, 50)]
+fn into_extrinsics_works(
+	#[case] file: PathBuf,
+	#[case] pallet: &str,
+	#[case] read: u128,
+	#[case] write: u128,
+) {
+	let exts = parse_file(&file).unwrap().into_extrinsics();
+	let scope = SimpleScope::empty();
+
+	let got = exts
+		.into_iter()
+		.map(|e| {
+			assert_eq!(e.pallet, pallet);
+			(e.name, e.term.simplify(Dimension::Time).unwrap().eval(&scope).unwrap())
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(got, vec![("read".into(), read), ("write".into(), write)]);
+}
@@ -0,0 +1,63 @@
+use rstest::*;
+
+use crate::{
+	parse::{pallet, xcm::parse_content},
+	term::ChromaticTerm,
+};
+
+const FUNGIBLE: &str = "
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo<T> {
+	pub(crate) fn withdraw_asset() -> Weight {
+		Weight::from_parts(20_385_000, 3593)
+	}
+	pub(crate) fn transfer_asset() -> Weight {
+		Weight::from_parts(32_000_000, 6196)
+	}
+}";
+
+const MOD: &str = "
+mod pallet_xcm_benchmarks_fungible;
+use pallet_xcm_benchmarks_fungible::WeightInfo as XcmBalancesWeight;
+
+impl<RuntimeCall> XcmWeightInfo<RuntimeCall> for KusamaXcmWeight<RuntimeCall> {
+	fn withdraw_asset(assets: &MultiAssets) -> Weight {
+		assets.weigh_multi_assets(XcmBalancesWeight::<Runtime>::withdraw_asset())
+	}
+	fn transfer_asset(assets: &MultiAssets, _dest: &MultiLocation) -> Weight {
+		XcmBalancesWeight::<Runtime>::transfer_asset()
+	}
+	fn clear_origin() -> Weight {
+		Weight::from_parts(1_000, 0)
+	}
+	fn hrmp_channel_closing(_initiator: &u32, _sender: &u32, _recipient: &u32) -> Weight {
+		// XCM Executor does not currently support HRMP channel operations
+		Weight::MAX
+	}
+}";
+
+fn benchmarks(module: &str) -> Result<Vec<pallet::ChromaticExtrinsic>, String> {
+	assert_eq!(module, "pallet_xcm_benchmarks_fungible");
+	pallet::parse_content(module.into(), FUNGIBLE.into()).map_err(|e| e.to_string())
+}
+
+#[rstest]
+#[case("withdraw_asset", Some((20_385_000, 3593)))]
+#[case("transfer_asset", Some((32_000_000, 6196)))]
+#[case("clear_origin", Some((1_000, 0)))]
+#[case("hrmp_channel_closing", None)]
+fn parse_content_works(#[case] instruction: &str, #[case] weight: Option<(u128, u128)>) {
+	let got = parse_content("xcm/mod.rs".into(), MOD.into(), benchmarks).unwrap();
+	let term = got.iter().find(|e| e.name == instruction).map(|e| e.term.clone());
+
+	assert_eq!(term, weight.map(|w| ChromaticTerm::Value(w.into())));
+	assert!(got.iter().all(|e| e.pallet == "xcm/mod.rs"));
+}
+
+#[test]
+fn parse_content_errors_on_unknown_benchmark() {
+	let content = MOD.replace("::transfer_asset()", "::teleport_asset()");
+	let err = parse_content("xcm/mod.rs".into(), content, benchmarks).unwrap_err();
+
+	assert!(err.contains("pallet_xcm_benchmarks_fungible::teleport_asset"), "{}", err);
+}
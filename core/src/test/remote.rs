@@ -0,0 +1,18 @@
+use rstest::*;
+
+use crate::remote::GithubRepo;
+
+#[rstest]
+#[case("https://github.com/paritytech/polkadot-sdk", Some(("paritytech", "polkadot-sdk")))]
+#[case("https://github.com/paritytech/polkadot-sdk/", Some(("paritytech", "polkadot-sdk")))]
+#[case("https://github.com/paritytech/polkadot-sdk.git", Some(("paritytech", "polkadot-sdk")))]
+#[case("https://github.com/paritytech", None)]
+#[case("https://github.com/paritytech/polkadot-sdk/tree/master", None)]
+#[case("https://gitlab.com/paritytech/polkadot-sdk", None)]
+#[case("../polkadot-sdk", None)]
+fn github_repo_from_str_works(#[case] url: &str, #[case] expected: Option<(&str, &str)>) {
+	let expected =
+		expected.map(|(owner, name)| GithubRepo { owner: owner.into(), name: name.into() });
+
+	assert_eq!(url.parse::<GithubRepo>().ok(), expected);
+}
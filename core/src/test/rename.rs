@@ -0,0 +1,155 @@
+use rstest::*;
+
+use crate::{parse::pallet::parse_content, rename::*, *};
+
+const OLD: &str = r#"
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn set_balance() -> Weight {
+		Weight::from_parts(20_000, 0)
+	}
+	fn transfer(n: u32, ) -> Weight {
+		Weight::from_parts(10_000, 0).saturating_add(Weight::from_parts(100, 0).saturating_mul(n.into()))
+	}
+	fn bond() -> Weight {
+		Weight::from_parts(10_000, 0)
+	}
+}
+"#;
+
+const NEW: &str = r#"
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn force_set_balance() -> Weight {
+		Weight::from_parts(30_000, 0)
+	}
+	fn transfer_allow_death(n: u32, ) -> Weight {
+		Weight::from_parts(10_000, 0).saturating_add(Weight::from_parts(100, 0).saturating_mul(n.into()))
+	}
+	fn transfer_keep_alive() -> Weight {
+		Weight::from_parts(10_000, 0)
+	}
+	fn unbond() -> Weight {
+		Weight::from_parts(10_000, 0)
+	}
+}
+"#;
+
+#[rstest]
+#[case("set_balance", "force_set_balance", 0.8)]
+#[case("transfer", "transfer_allow_death", 0.5)]
+#[case("bond", "unbond", 0.0)]
+#[case("bond", "bond", 1.0)]
+#[case("", "", 0.0)]
+fn name_similarity_works(#[case] a: &str, #[case] b: &str, #[case] similarity: f64) {
+	assert_eq!(name_similarity(a, b), similarity);
+	assert_eq!(name_similarity(b, a), similarity);
+}
+
+/// `transfer_keep_alive` has other components and `unbond` a different name.
+#[test]
+fn detect_renames_works() {
+	let olds = parse_content("pallet_balances".into(), OLD.into()).unwrap();
+	let news = parse_content("pallet_balances".into(), NEW.into()).unwrap();
+
+	let renames = detect_renames(&olds, &news);
+	let pairs = renames.iter().map(|r| (r.old.as_str(), r.new.as_str())).collect::<Vec<_>>();
+	assert_eq!(pairs, [("set_balance", "force_set_balance"), ("transfer", "transfer_allow_death")]);
+	// Other pallets are never matched.
+	let news = parse_content("pallet_assets".into(), NEW.into()).unwrap();
+	assert!(detect_renames(&olds, &news).is_empty());
+}
+
+#[test]
+fn compare_files_detects_renames() {
+	let olds = parse_content("pallet_balances".into(), OLD.into()).unwrap();
+	let news = parse_content("pallet_balances".into(), NEW.into()).unwrap();
+	let params = CompareParams {
+		method: CompareMethod::Base,
+		offline: true,
+		detect_renames: true,
+		..Default::default()
+	};
+
+	let diff = compare_files(olds, news, &params, &FilterParams::default()).unwrap();
+	let names = diff.iter().map(|d| d.display_name()).collect::<Vec<_>>();
+	assert_eq!(
+		names,
+		[
+			"bond",
+			"set_balance → force_set_balance",
+			"transfer → transfer_allow_death",
+			"transfer_keep_alive",
+			"unbond"
+		]
+	);
+	let change = diff[1].term().unwrap();
+	assert_eq!(change.change, RelativeChange::Renamed);
+	assert_eq!((change.old_v, change.new_v), (Some(20_000), Some(30_000)));
+	assert_eq!(diff[1].renamed_from.as_ref().unwrap(), "set_balance");
+	assert_eq!(diff[2].term().unwrap().percent, 0.0);
+}
+
+/// Files with the same name in multiple runtimes are ambiguous.
+#[test]
+fn detect_moves_works() {
+	let parse = |pallet: &str| parse_content(pallet.into(), OLD.into()).unwrap();
+	let olds = [
+		parse("runtime/polkadot/src/weights/pallet_balances.rs"),
+		parse("runtime/polkadot/src/weights/pallet_staking.rs"),
+		parse("runtime/kusama/src/weights/pallet_staking.rs"),
+		parse("runtime/polkadot/src/weights/pallet_proxy.rs"),
+	]
+	.concat();
+	let news = [
+		parse("relay/polkadot/src/weights/pallet_balances.rs"),
+		parse("relay/polkadot/src/weights/pallet_staking.rs"),
+		parse("relay/kusama/src/weights/pallet_staking.rs"),
+		parse("runtime/polkadot/src/weights/pallet_proxy.rs"),
+	]
+	.concat();
+
+	let moves = detect_moves(&olds, &news);
+	assert_eq!(
+		moves,
+		[Move {
+			old: "runtime/polkadot/src/weights/pallet_balances.rs".into(),
+			new: "relay/polkadot/src/weights/pallet_balances.rs".into(),
+		}]
+	);
+
+	let mut moved = olds.clone();
+	apply_moves(&mut moved, &moves);
+	assert_eq!(moved.iter().filter(|e| e.pallet.starts_with("relay/")).count(), 3);
+}
+
+/// Instances of a pallet are only matched with the same instance.
+#[test]
+fn detect_moves_matches_instances() {
+	let collective =
+		"impl<T: frame_system::Config> pallet_collective::WeightInfo for WeightInfo<T> {
+		fn vote() -> Weight { Weight::from_parts(100, 0) }
+	}";
+	let parse = |pallet: &str| parse_content(pallet.into(), collective.into()).unwrap();
+	let olds = [
+		parse("weights/pallet_collective_council.rs"),
+		parse("weights/pallet_collective_technical_committee.rs"),
+	]
+	.concat();
+	let news = [
+		parse("weights/collective_technical_committee.rs"),
+		parse("weights/collective_council.rs"),
+	]
+	.concat();
+
+	let moves = detect_moves(&olds, &news);
+	let pairs = moves.iter().map(|m| (m.old.as_str(), m.new.as_str())).collect::<Vec<_>>();
+	assert_eq!(
+		pairs,
+		[
+			("weights/pallet_collective_council.rs", "weights/collective_council.rs"),
+			(
+				"weights/pallet_collective_technical_committee.rs",
+				"weights/collective_technical_committee.rs"
+			),
+		]
+	);
+}
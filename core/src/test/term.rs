@@ -1,7 +1,13 @@
 use rstest::*;
 use std::collections::BTreeSet as Set;
 
-use crate::{add, mul, scalar, scope::SimpleScope, term::SimpleTerm, var};
+use crate::{
+	add, cadd, cmul, cval, cvar, mul, scalar,
+	scope::{ChromaticScope, SimpleScope},
+	term::{ChromaticTerm, EvalError, SimpleTerm, VarValue},
+	traits::Weight,
+	var, Dimension,
+};
 
 #[rstest]
 #[case(scalar!(123), vec![], vec![])]
@@ -56,7 +62,150 @@ fn term_fmt_with_brackets_works(#[case] term: SimpleTerm, #[case] expected: &str
 	assert_eq!(format!("{}", term), expected);
 }
 
+#[rstest]
+#[case(scalar!(0), vec![])]
+#[case(mul!(scalar!(2), add!(var!("n"), scalar!(3))), vec![(vec![], 6), (vec!["n"], 2)])]
+#[case(add!(mul!(var!("n"), var!("READ")), mul!(var!("READ"), scalar!(4))), vec![(vec!["READ"], 4), (vec!["READ", "n"], 1)])]
+#[case(add!(mul!(scalar!(0), var!("n")), var!("m")), vec![(vec!["m"], 1)])]
+fn term_monomials_works(#[case] term: SimpleTerm, #[case] expected: Vec<(Vec<&str>, u128)>) {
+	let expected = expected
+		.into_iter()
+		.map(|(vars, f)| (vars.into_iter().map(String::from).collect::<Vec<_>>(), f))
+		.collect::<Vec<_>>();
+	assert_eq!(term.monomials().into_iter().collect::<Vec<_>>(), expected);
+}
+
+#[rstest]
+#[case(scalar!(0), "READ", vec![])]
+#[case(var!("READ"), "READ", vec![(vec![], 1)])]
+#[case(
+	add!(add!(scalar!(26_300_000), mul!(scalar!(4), var!("READ"))), add!(mul!(mul!(scalar!(2), var!("n")), var!("READ")), var!("WRITE"))),
+	"READ",
+	vec![(vec![], 4), (vec!["n"], 2)]
+)]
+#[case(add!(mul!(scalar!(4), var!("READ")), mul!(scalar!(2), var!("WRITE"))), "WRITE", vec![(vec![], 2)])]
+fn term_storage_accesses_works(
+	#[case] term: SimpleTerm,
+	#[case] var: &str,
+	#[case] expected: Vec<(Vec<&str>, u128)>,
+) {
+	let expected = expected
+		.into_iter()
+		.map(|(vars, f)| (vars.into_iter().map(String::from).collect::<Vec<_>>(), f))
+		.collect::<Vec<_>>();
+	assert_eq!(term.storage_accesses(var).monomials().into_iter().collect::<Vec<_>>(), expected);
+}
+
+#[rstest]
+#[case(scalar!(0), "0", "0")]
+#[case(
+	add!(add!(scalar!(26_300_000), mul!(scalar!(1_200_000), var!("n"))), add!(mul!(scalar!(4), var!("READ")), mul!(scalar!(2), var!("WRITE")))),
+	"26.30us + 1.20us·n + 4·READ + 2·WRITE",
+	"\\text{26.30us} + \\text{1.20us} \\cdot n + 4 \\cdot \\mathrm{READ} + 2 \\cdot \\mathrm{WRITE}"
+)]
+#[case(
+	add!(var!("READ"), mul!(mul!(scalar!(5), var!("READ")), var!("max_n"))),
+	"READ + 5·READ·max_n",
+	"\\mathrm{READ} + 5 \\cdot \\mathrm{READ} \\cdot max\\_n"
+)]
+#[case(
+	SimpleTerm::Sub(SimpleTerm::Max(mul!(scalar!(2_000_000), var!("n")).into(), scalar!(10_000_000).into()).into(), add!(scalar!(1_000_000), mul!(scalar!(1_000_000), var!("m"))).into()),
+	"max(2.00us·n, 10.00us) - (1.00us + 1.00us·m)",
+	"\\max(\\text{2.00us} \\cdot n, \\text{10.00us}) - (\\text{1.00us} + \\text{1.00us} \\cdot m)"
+)]
+fn term_fmt_math_works(#[case] term: SimpleTerm, #[case] math: &str, #[case] latex: &str) {
+	assert_eq!(term.fmt_math(Dimension::Time), math);
+	assert_eq!(term.fmt_latex(Dimension::Time), latex);
+}
+
 /*#[case(scalar!(123), scalar!(123))]
 fn test_substitute_works(#[case] term: SimpleTern, #[case] expected: SimpleTerm) {
 	term
 }*/
+
+#[rstest]
+#[case(SimpleTerm::Sub(scalar!(5).into(), var!("n").into()), 0, 5)]
+#[case(SimpleTerm::Sub(scalar!(5).into(), var!("n").into()), 7, 0)]
+#[case(SimpleTerm::Min(scalar!(5).into(), var!("n").into()), 7, 5)]
+#[case(SimpleTerm::Max(scalar!(5).into(), var!("n").into()), 7, 7)]
+#[case(SimpleTerm::Max(scalar!(5).into(), mul!(var!("n"), scalar!(2)).into()), 2, 5)]
+#[case(SimpleTerm::Branch(vec![scalar!(5), var!("n"), scalar!(1)]), 7, 7)]
+#[case(SimpleTerm::Branch(vec![scalar!(5), var!("n"), scalar!(1)]), 2, 5)]
+fn term_eval_cmp_works(#[case] term: SimpleTerm, #[case] n: u128, #[case] expected: u128) {
+	let scope = SimpleScope::empty().with_var("n", scalar!(n));
+	assert_eq!(term.eval(&scope), Ok(expected));
+	assert!(!term.is_polynomial());
+}
+
+/// A plain number is a reference time unless the proof size is asked for.
+#[rstest]
+#[case(Dimension::Time, Weight { ref_time: 5, proof_size: 0 })]
+#[case(Dimension::Proof, Weight { ref_time: 0, proof_size: 5 })]
+#[case(Dimension::Both, Weight { ref_time: 5, proof_size: 0 })]
+fn scalar_into_chromatic_works(#[case] unit: Dimension, #[case] expected: Weight) {
+	assert_eq!(scalar!(5).into_chromatic(unit), ChromaticTerm::Value(expected));
+}
+
+#[rstest]
+#[case(add!(scalar!(u128::MAX), scalar!(1)))]
+#[case(mul!(scalar!(u128::MAX / 2), var!("n")))]
+#[case(SimpleTerm::Branch(vec![scalar!(1), mul!(scalar!(8), var!("n"))]))]
+fn term_eval_overflow_works(#[case] term: SimpleTerm) {
+	let scope = SimpleScope::empty().with_var("n", scalar!(u128::MAX / 4));
+	assert_eq!(term.eval(&scope), Err(EvalError::Overflow));
+
+	let term = term.into_chromatic(Dimension::Proof);
+	let scope = ChromaticScope::empty().with_var("n", ChromaticTerm::Scalar(u128::MAX / 4));
+	assert_eq!(term.eval(&scope), Err(EvalError::Overflow));
+}
+
+/// Both dimensions evaluate to the values of their simplified terms.
+#[rstest]
+#[case(cmul!(cval!(Weight::from((10, 2))), cadd!(ChromaticTerm::Scalar(1), cvar!("n"))))]
+#[case(cmul!(cmul!(cval!(Weight::from((10, 2))), cvar!("n")), cvar!("n")))]
+#[case(ChromaticTerm::Sub(cval!(Weight::from((50, 1))).into(), cmul!(cval!(Weight::from((5, 1))), cvar!("n")).into()))]
+#[case(ChromaticTerm::Branch(vec![cval!(Weight::from((100, 1))), cmul!(cval!(Weight::from((1, 10))), cvar!("n"))]))]
+fn chromatic_term_eval_works(#[case] term: ChromaticTerm) {
+	let scope = ChromaticScope::empty().with_var("n", ChromaticTerm::Scalar(7));
+	let simple = |unit| {
+		let scope = SimpleScope::empty().with_var("n", scalar!(7));
+		term.simplify(unit).unwrap().eval(&scope).unwrap()
+	};
+	let expected = Weight::from((simple(Dimension::Time), simple(Dimension::Proof)));
+	assert_eq!(term.eval(&scope), Ok(expected));
+}
+
+/// Variables share their interned names and serialize as plain strings.
+#[test]
+fn var_value_interns_works() {
+	let (a, b) = (VarValue::from("max_n"), VarValue::from(String::from("max_n")));
+	assert!(std::sync::Arc::ptr_eq(&a.0, &b.0));
+	assert_eq!(a, VarValue::from("maxn"));
+	assert_ne!(a, VarValue::from("max_m"));
+
+	let json = serde_json::to_string(&var!("max_n")).unwrap();
+	assert_eq!(json, r#"{"Var":"max_n"}"#);
+	assert_eq!(serde_json::from_str::<SimpleTerm>(&json).unwrap(), var!("max_n"));
+}
+
+/// The monomials bound minima, maxima and subtractions from above.
+#[test]
+fn term_monomials_bound_cmp() {
+	let max =
+		SimpleTerm::Max(add!(scalar!(5), var!("n")).into(), mul!(scalar!(2), var!("n")).into());
+	let monomials = max.monomials();
+	assert_eq!(monomials, [(vec![], 5), (vec!["n".to_string()], 2)].into());
+
+	let sub = SimpleTerm::Sub(var!("n").into(), scalar!(5).into());
+	assert_eq!(sub.monomials(), [(vec!["n".to_string()], 1)].into());
+}
+
+/// Factors that exceed 128 bits saturate instead of panicking.
+#[test]
+fn term_monomials_saturate() {
+	let add = add!(mul!(scalar!(u128::MAX), var!("n")), var!("n"));
+	assert_eq!(add.monomials(), [(vec!["n".to_string()], u128::MAX)].into());
+
+	let mul = mul!(scalar!(u128::MAX / 2), mul!(scalar!(4), var!("n")));
+	assert_eq!(mul.monomials(), [(vec!["n".to_string()], u128::MAX)].into());
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::Dimension;
+
 pub trait One {
 	fn one() -> Self;
 }
@@ -30,56 +32,107 @@ pub trait ValueFormatter {
 	fn format_scalar(&self) -> String;
 }
 
+/// The weight of both dimensions; picoseconds of reference time and bytes of proof size.
+///
+/// The serialized names are the ones of older versions to keep caches and JSON files readable.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq)]
 pub struct Weight {
-	pub time: u128,
-	pub proof: u128,
+	#[serde(rename = "time")]
+	pub ref_time: u128,
+	#[serde(rename = "proof")]
+	pub proof_size: u128,
 }
 
 impl Zero for Weight {
 	fn zero() -> Self {
-		Self { time: 0, proof: 0 }
+		Self { ref_time: 0, proof_size: 0 }
 	}
 }
 
 impl One for Weight {
 	fn one() -> Self {
-		Self { time: 1, proof: 1 }
+		Self { ref_time: 1, proof_size: 1 }
 	}
 }
 
 impl From<(u128, u128)> for Weight {
-	fn from((time, proof): (u128, u128)) -> Self {
-		Self { time, proof }
+	fn from((ref_time, proof_size): (u128, u128)) -> Self {
+		Self { ref_time, proof_size }
 	}
 }
 
 impl From<Weight> for (u128, u128) {
 	fn from(val: Weight) -> Self {
-		(val.time, val.proof)
+		(val.ref_time, val.proof_size)
 	}
 }
 
 impl From<u128> for Weight {
-	fn from(time: u128) -> Self {
-		Self { time, proof: 0 }
+	fn from(ref_time: u128) -> Self {
+		Self { ref_time, proof_size: 0 }
 	}
 }
 
 impl ValueFormatter for Weight {
 	fn format_scalar(&self) -> String {
-		format!("({}, {})", self.time, self.proof)
+		format!("({}, {})", self.ref_time, self.proof_size)
 	}
 }
 
 impl core::fmt::Display for Weight {
 	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-		write!(f, "({}, {})", self.time, self.proof)
+		write!(f, "({}, {})", self.ref_time, self.proof_size)
 	}
 }
 
 impl Weight {
+	/// A scalar that applies to both dimensions.
+	pub fn scalar(x: u128) -> Self {
+		Self { ref_time: x, proof_size: x }
+	}
+
+	/// The value of a single dimension; `None` for dimensions that are not part of a weight.
+	pub fn get(&self, unit: Dimension) -> Option<u128> {
+		match unit {
+			Dimension::Time => Some(self.ref_time),
+			Dimension::Proof => Some(self.proof_size),
+			_ => None,
+		}
+	}
+
+	/// Both dimensions with their units, eg. `26.30us, 3.59KiB`.
+	pub fn fmt_value(&self) -> String {
+		format!("{}, {}", Dimension::fmt_time(self.ref_time), Dimension::fmt_proof(self.proof_size))
+	}
+
 	pub fn mul_scalar(&self, other: u128) -> Self {
-		Self { time: self.time * other, proof: self.proof * other }
+		Self { ref_time: self.ref_time * other, proof_size: self.proof_size * other }
+	}
+
+	/// `None` if a dimension overflows.
+	pub fn checked_mul_scalar(&self, other: u128) -> Option<Self> {
+		self.checked_mul(&Self::scalar(other))
+	}
+
+	/// `None` if a dimension overflows.
+	pub fn checked_add(&self, other: &Self) -> Option<Self> {
+		self.zip(other, u128::checked_add)
+	}
+
+	/// Multiplies each dimension; `None` if one overflows.
+	pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+		self.zip(other, u128::checked_mul)
+	}
+
+	/// Combines each dimension with the same dimension of `other`.
+	pub fn zip<R: Into<Option<u128>>>(
+		&self,
+		other: &Self,
+		f: impl Fn(u128, u128) -> R,
+	) -> Option<Self> {
+		Some(Self {
+			ref_time: f(self.ref_time, other.ref_time).into()?,
+			proof_size: f(self.proof_size, other.proof_size).into()?,
+		})
 	}
 }
@@ -0,0 +1,99 @@
+//! Compare the weight files of a working tree with a baseline ref whenever they change.
+//!
+//! Changes are detected by polling the modification times of the files that match the path
+//! pattern. Only files that were added, removed or modified since the last poll are parsed again.
+
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
+use crate::{
+	compare_files, list_files,
+	parse::pallet::{parse_file_in_repo, ChromaticExtrinsic},
+	parse_commit,
+	progress::Progress,
+	CompareParams, FilterParams, SwcError, TotalDiff,
+};
+
+/// The parsed weight files of a working tree and the baseline that they are compared with.
+pub struct Watch {
+	repo: PathBuf,
+	path_pattern: String,
+	max_files: usize,
+	ignore_errors: bool,
+	baseline: Vec<ChromaticExtrinsic>,
+	/// Modification time and extrinsics of each file.
+	files: BTreeMap<PathBuf, (SystemTime, Vec<ChromaticExtrinsic>)>,
+}
+
+impl Watch {
+	/// Parse the weight files of `baseline` and of the working tree at `repo`.
+	pub fn new(
+		repo: &Path,
+		baseline: &str,
+		params: &CompareParams,
+		path_pattern: &str,
+		max_files: usize,
+		progress: &dyn Progress,
+	) -> Result<Self, SwcError> {
+		let baseline = parse_commit(repo, baseline, params, path_pattern, max_files, progress)?;
+		let mut watch = Self {
+			repo: repo.into(),
+			path_pattern: path_pattern.into(),
+			max_files,
+			ignore_errors: params.ignore_errors,
+			baseline,
+			files: BTreeMap::new(),
+		};
+		watch.poll()?;
+		Ok(watch)
+	}
+
+	/// Parse the files that changed since the last poll and return their paths.
+	///
+	/// A file that cannot be parsed counts as removed until it changes again. Its error is returned
+	/// unless errors are ignored.
+	pub fn poll(&mut self) -> Result<Vec<PathBuf>, SwcError> {
+		let paths = list_files(&self.repo, &self.path_pattern, self.max_files)?;
+		let mut changed =
+			self.files.keys().filter(|p| !paths.contains(p)).cloned().collect::<Vec<_>>();
+		self.files.retain(|p, _| paths.contains(p));
+
+		let mut errors = Vec::new();
+		for path in paths {
+			let modified = std::fs::metadata(&path)?.modified()?;
+			if self.files.get(&path).map_or(false, |(m, _)| *m == modified) {
+				continue
+			}
+			let extrinsics = match parse_file_in_repo(&self.repo, &path) {
+				Ok(extrinsics) => extrinsics,
+				Err(err) => {
+					if !self.ignore_errors {
+						errors.push(err.to_string());
+					}
+					Vec::new()
+				},
+			};
+			self.files.insert(path.clone(), (modified, extrinsics));
+			changed.push(path);
+		}
+
+		if errors.is_empty() {
+			Ok(changed)
+		} else {
+			Err(SwcError::Parse(errors.join("\n")))
+		}
+	}
+
+	/// Compare the working tree with the baseline.
+	pub fn compare(
+		&self,
+		params: &CompareParams,
+		filter: &FilterParams,
+	) -> Result<TotalDiff, SwcError> {
+		let news = self.files.values().flat_map(|(_, e)| e.iter().cloned()).collect();
+		compare_files(self.baseline.clone(), news, params, filter)
+	}
+}
@@ -0,0 +1,208 @@
+//! Python bindings to parse and compare weight files; build them with `maturin develop`.
+//!
+//! ```python
+//! import subweight
+//!
+//! changes = subweight.compare_files(["old/pallet_balances.rs"], ["new/pallet_balances.rs"])
+//! for change in changes:
+//!     print(change.extrinsic, change.old, change.new, change.percent)
+//! ```
+//!
+//! The values are in picoseconds or bytes. `to_json` returns all details of a change in the same
+//! format as `--format json` of the CLI.
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{collections::HashMap, path::PathBuf};
+use subweight_core::{
+	compare_files as compare, filter_changes,
+	parse::pallet::{self, ChromaticExtrinsic},
+	sort_changes, CompareMethod, CompareParams, Dimension, ExtrinsicDiff, FilterParams,
+	RelativeChange, TermDiff, ThresholdMode,
+};
+
+/// The weight of an extrinsic as parsed from a weight file.
+#[pyclass(module = "subweight")]
+struct Extrinsic {
+	#[pyo3(get)]
+	pallet: String,
+	#[pyo3(get)]
+	name: String,
+	/// Formula of the reference time, eg. `26.30us + 1.20us·n + 4·READ + 2·WRITE`.
+	#[pyo3(get)]
+	time: String,
+	/// Formula of the proof size.
+	#[pyo3(get)]
+	proof: String,
+	/// Inclusive range of each component.
+	#[pyo3(get)]
+	components: HashMap<String, (u32, u32)>,
+	inner: ChromaticExtrinsic,
+}
+
+#[pymethods]
+impl Extrinsic {
+	fn to_json(&self) -> PyResult<String> {
+		serde_json::to_string(&self.inner).map_err(to_py_err)
+	}
+
+	fn __repr__(&self) -> String {
+		format!("Extrinsic({}::{})", self.pallet, self.name)
+	}
+}
+
+impl TryFrom<ChromaticExtrinsic> for Extrinsic {
+	type Error = PyErr;
+
+	fn try_from(ext: ChromaticExtrinsic) -> PyResult<Self> {
+		let fmt = |unit| ext.term.simplify(unit).map(|t| t.fmt_math(unit)).map_err(to_py_err);
+		Ok(Self {
+			pallet: ext.pallet.to_string(),
+			name: ext.name.to_string(),
+			time: fmt(Dimension::Time)?,
+			proof: fmt(Dimension::Proof)?,
+			components: ext
+				.comp_ranges
+				.iter()
+				.flatten()
+				.map(|(n, r)| (n.clone(), (r.min, r.max)))
+				.collect(),
+			inner: ext,
+		})
+	}
+}
+
+/// The change of the weight of an extrinsic between two versions.
+#[pyclass(module = "subweight")]
+struct Change {
+	#[pyo3(get)]
+	file: String,
+	#[pyo3(get)]
+	extrinsic: String,
+	/// Old name of a `renamed` extrinsic.
+	#[pyo3(get)]
+	renamed_from: Option<String>,
+	/// `changed`, `unchanged`, `added`, `removed`, `renamed` or `error`.
+	#[pyo3(get)]
+	kind: &'static str,
+	#[pyo3(get)]
+	old: Option<u128>,
+	#[pyo3(get)]
+	new: Option<u128>,
+	#[pyo3(get)]
+	percent: Option<f64>,
+	#[pyo3(get)]
+	warning: Option<String>,
+	#[pyo3(get)]
+	error: Option<String>,
+	inner: ExtrinsicDiff,
+}
+
+#[pymethods]
+impl Change {
+	fn to_json(&self) -> PyResult<String> {
+		serde_json::to_string(&self.inner).map_err(to_py_err)
+	}
+
+	fn __repr__(&self) -> String {
+		format!("Change({}::{}, {})", self.file, self.extrinsic, self.kind)
+	}
+}
+
+impl From<ExtrinsicDiff> for Change {
+	fn from(diff: ExtrinsicDiff) -> Self {
+		let term = diff.term();
+		let kind = match term.map(|t| &t.change) {
+			None => "error",
+			Some(RelativeChange::Unchanged) => "unchanged",
+			Some(RelativeChange::Changed) => "changed",
+			Some(RelativeChange::Added) => "added",
+			Some(RelativeChange::Removed) => "removed",
+			Some(RelativeChange::Renamed) => "renamed",
+		};
+		Self {
+			file: diff.file.clone(),
+			extrinsic: diff.name.to_string(),
+			renamed_from: diff.renamed_from.as_ref().map(ToString::to_string),
+			kind,
+			old: term.and_then(|t| t.old_v),
+			new: term.and_then(|t| t.new_v),
+			percent: term.map(|t| t.percent),
+			warning: diff.warning().cloned(),
+			error: match &diff.change {
+				TermDiff::Failed(err) => Some(err.clone()),
+				_ => None,
+			},
+			inner: diff,
+		}
+	}
+}
+
+/// Parse the extrinsics of a weight file.
+#[pyfunction]
+fn parse_file(path: PathBuf) -> PyResult<Vec<Extrinsic>> {
+	pallet::parse_file(&path)
+		.map_err(to_py_err)?
+		.into_iter()
+		.map(TryInto::try_into)
+		.collect()
+}
+
+/// Compare the old with the new weight files and return the changes above the threshold.
+///
+/// Files are matched by their name. The arguments behave like the options of `subweight compare`.
+#[pyfunction]
+#[pyo3(signature = (old, new, method = "guess-worst", unit = "time", threshold = 5.0, ignore_errors = false, detect_renames = false))]
+fn compare_files(
+	old: Vec<PathBuf>,
+	new: Vec<PathBuf>,
+	method: &str,
+	unit: &str,
+	threshold: f64,
+	ignore_errors: bool,
+	detect_renames: bool,
+) -> PyResult<Vec<Change>> {
+	let params = CompareParams {
+		method: method.parse::<CompareMethod>().map_err(to_py_err)?,
+		unit: unit.parse::<Dimension>().map_err(to_py_err)?,
+		ignore_errors,
+		offline: true,
+		detect_renames,
+		..Default::default()
+	};
+	let filter = FilterParams {
+		threshold,
+		threshold_abs: None,
+		threshold_mode: ThresholdMode::And,
+		change: None,
+		extrinsic: None,
+		pallet: None,
+		ignore_extrinsic: Vec::new(),
+		ignore_pallet: Vec::new(),
+		allowlist: None,
+	};
+	let parse = |paths: &[PathBuf]| {
+		if ignore_errors {
+			Ok(pallet::try_parse_files(paths))
+		} else {
+			pallet::parse_files(paths).map_err(to_py_err)
+		}
+	};
+
+	let diff = compare(parse(&old)?, parse(&new)?, &params, &filter).map_err(to_py_err)?;
+	let mut diff = filter_changes(diff, &filter);
+	sort_changes(&mut diff);
+	Ok(diff.into_iter().map(Into::into).collect())
+}
+
+fn to_py_err(err: impl ToString) -> PyErr {
+	PyValueError::new_err(err.to_string())
+}
+
+#[pymodule]
+fn subweight(_py: Python, m: &PyModule) -> PyResult<()> {
+	m.add_class::<Extrinsic>()?;
+	m.add_class::<Change>()?;
+	m.add_function(wrap_pyfunction!(parse_file, m)?)?;
+	m.add_function(wrap_pyfunction!(compare_files, m)?)?;
+	Ok(())
+}
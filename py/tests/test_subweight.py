@@ -0,0 +1,35 @@
+# Run with `maturin develop -m py/Cargo.toml && python -m pytest py/tests` from the repository root.
+
+import json
+import pathlib
+import unittest
+
+import subweight
+
+DATA = pathlib.Path(__file__).parents[2] / "test_data"
+OLD = str(DATA / "old" / "pallet_staking.rs.txt")
+NEW = str(DATA / "new" / "pallet_staking.rs.txt")
+
+
+class SubweightTest(unittest.TestCase):
+    def test_parse_file(self):
+        extrinsics = subweight.parse_file(NEW)
+        bond = next(e for e in extrinsics if e.name == "bond")
+        self.assertEqual(bond.pallet, "pallet_staking.rs.txt")
+        self.assertIn("READ", bond.time)
+        self.assertEqual(json.loads(bond.to_json())["name"], "bond")
+
+    def test_compare_files(self):
+        changes = subweight.compare_files([OLD], [NEW], method="base", threshold=0)
+        self.assertTrue(changes)
+        for change in changes:
+            self.assertIn(change.kind, ["changed", "unchanged", "added", "removed"])
+            self.assertEqual(json.loads(change.to_json())["name"], change.extrinsic)
+
+    def test_invalid_method(self):
+        with self.assertRaises(ValueError):
+            subweight.compare_files([OLD], [NEW], method="fastest")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,109 @@
+//! Serve several repositories that are described in a TOML file.
+//!
+//! ```toml
+//! [[repo]]
+//! name = "polkadot-sdk"
+//! url = "https://github.com/paritytech/polkadot-sdk"
+//! path_patterns = ["polkadot/runtime/*/src/weights/*.rs", "substrate/frame/*/src/weights.rs"]
+//! allowed_refs = ["master", "release-*", "polkadot-v*"]
+//! ```
+//!
+//! Repositories with an `url` are cloned into `--root` unless they exist there already.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+	#[serde(default, rename = "repo")]
+	pub repos: Vec<RepoConfig>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoConfig {
+	/// Name of the repo in the URLs and the folder in `--root`.
+	pub name: String,
+	/// Clone URL of the repo.
+	pub url: Option<String>,
+	/// Used when a request does not specify a path pattern.
+	#[serde(default)]
+	pub path_patterns: Vec<String>,
+	/// Glob patterns of the branches and tags that can be compared. All if empty.
+	#[serde(default)]
+	pub allowed_refs: Vec<String>,
+}
+
+impl Config {
+	pub fn load(path: &Path) -> Result<Self, String> {
+		let content = std::fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+		Self::parse(&content).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+	}
+
+	pub fn parse(content: &str) -> Result<Self, String> {
+		let config: Self = toml::from_str(content).map_err(|e| e.to_string())?;
+		for repo in &config.repos {
+			for pattern in &repo.allowed_refs {
+				glob::Pattern::new(pattern).map_err(|e| {
+					format!("Invalid allowed ref '{}' of repo '{}': {}", pattern, repo.name, e)
+				})?;
+			}
+		}
+		Ok(config)
+	}
+}
+
+impl RepoConfig {
+	/// Whether the ref is allowed by the config. Full commit hashes are always allowed.
+	///
+	/// Abbreviated hashes are not, since a branch can have a name that looks like one.
+	pub fn allows(&self, refname: &str) -> bool {
+		if self.allowed_refs.is_empty() ||
+			(refname.len() == 40 && refname.chars().all(|c| c.is_ascii_hexdigit()))
+		{
+			return true
+		}
+		self.allowed_refs
+			.iter()
+			.filter_map(|p| glob::Pattern::new(p).ok())
+			.any(|p| p.matches(refname))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rstest::*;
+
+	#[rstest]
+	#[case("master", true)]
+	#[case("release-v1.0.0", true)]
+	#[case("oty-experiment", false)]
+	#[case("4f3c2a1d9e8b7c6f5a4b3c2d1e0f9a8b7c6d5e4f", true)]
+	#[case("4f3c2a1", false)]
+	#[case("deadbeef", false)]
+	#[case("4f3c", false)]
+	fn allows_works(#[case] refname: &str, #[case] allowed: bool) {
+		let config = Config::parse(
+			r#"
+			[[repo]]
+			name = "polkadot-sdk"
+			url = "https://github.com/paritytech/polkadot-sdk"
+			allowed_refs = ["master", "release-*"]
+		"#,
+		)
+		.unwrap();
+
+		assert_eq!(config.repos[0].allows(refname), allowed);
+	}
+
+	#[test]
+	fn parse_errors_on_invalid_pattern() {
+		let err =
+			Config::parse("[[repo]]\nname = \"polkadot\"\nallowed_refs = [\"[\"]").unwrap_err();
+		assert!(err.starts_with("Invalid allowed ref '['"), "{}", err);
+	}
+}
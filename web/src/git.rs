@@ -2,6 +2,7 @@
 
 use fancy_regex::Regex;
 use std::{path::Path, process::Command};
+use subweight_core::auth;
 
 /// Returns the GitHub organization name for a given repository.
 ///
@@ -23,6 +24,65 @@ pub fn get_origin_org(repo: &Path) -> Result<String, String> {
 		.map(|c| c.get(1).unwrap().as_str().to_string())
 }
 
+/// Clone the repo at `url` into `path`.
+pub fn clone(url: &str, path: &Path) -> Result<(), String> {
+	let output = Command::new("git")
+		.arg("clone")
+		.arg(url)
+		.arg(path)
+		.envs(auth::credentials().git_env())
+		.output()
+		.map_err(|e| format!("Failed to clone {}: {}", url, e))?;
+	if !output.status.success() {
+		return Err(format!("Failed to clone {}: {}", url, String::from_utf8_lossy(&output.stderr)))
+	}
+	Ok(())
+}
+
+/// Fetch the head of a pull request and the branch it is based on.
+pub fn fetch_pull(repo: &Path, number: u64, base_ref: &str) -> Result<(), String> {
+	let output = Command::new("git")
+		.args(["fetch", "origin", &format!("refs/pull/{}/head", number), base_ref])
+		.current_dir(repo)
+		.envs(auth::credentials().git_env())
+		.output()
+		.map_err(|e| format!("Failed to fetch pull request: {}", e))?;
+	if !output.status.success() {
+		return Err(format!(
+			"Failed to fetch pull request: {}",
+			String::from_utf8_lossy(&output.stderr)
+		))
+	}
+	Ok(())
+}
+
+/// The commit that a branch, tag or commit resolves to; prefers the branches of `origin`.
+pub fn resolve_commit(repo: &Path, refname: &str) -> Option<String> {
+	[format!("origin/{}", refname), refname.to_string()].iter().find_map(|name| {
+		let output = Command::new("git")
+			.args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", name)])
+			.current_dir(repo)
+			.output()
+			.ok()?;
+		output
+			.status
+			.success()
+			.then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+	})
+}
+
+/// Whether the ref is a tag or commit and therefore does not move.
+pub fn is_pinned(repo: &Path, refname: &str) -> bool {
+	if refname.len() >= 7 && refname.chars().all(|c| c.is_ascii_hexdigit()) {
+		return resolve_commit(repo, refname).map_or(false, |c| c.starts_with(refname))
+	}
+	Command::new("git")
+		.args(["rev-parse", "--verify", "--quiet", &format!("refs/tags/{}", refname)])
+		.current_dir(repo)
+		.output()
+		.map_or(false, |o| o.status.success())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -2,7 +2,7 @@
 
 use actix_web::HttpResponse;
 
-use subweight_core::{Dimension, Percent, RelativeChange, TermChange};
+use subweight_core::{parse::pallet::LineRange, Dimension, Percent, RelativeChange, TermChange};
 
 pub mod templates {
 	use super::*;
@@ -32,6 +32,14 @@ pub mod templates {
 		was_cached: bool,
 	}
 
+	#[cfg(feature = "sqlite")]
+	#[derive(TemplateOnce)]
+	#[template(path = "trend.stpl")]
+	pub struct Trend<'a> {
+		file: &'a str,
+		extrinsic: &'a str,
+	}
+
 	#[derive(TemplateOnce)]
 	#[template(path = "error.stpl")]
 	pub struct Error<'a> {
@@ -65,6 +73,14 @@ pub mod templates {
 		}
 	}
 
+	#[cfg(feature = "sqlite")]
+	impl<'a> Trend<'a> {
+		pub fn render(file: &'a str, extrinsic: &'a str) -> String {
+			let ctx = Self { file, extrinsic };
+			ctx.render_once().expect("Must render static template; qed")
+		}
+	}
+
 	impl<'a> Error<'a> {
 		pub fn render(msg: &'a str) -> String {
 			let ctx = Self { msg };
@@ -93,13 +109,25 @@ pub(crate) fn readme_link(name: &str) -> String {
 	format!("{} <a href=\"https://github.com/ggwpez/substrate-weight-compare/#{}\" target=\"_blank\"><sup><small>HELP</small></sup></a>", name, anchor)
 }
 
-pub(crate) fn code_link(repo_name: &str, org: &str, name: &str, file: &str, rev: &str) -> String {
-	format!("<a href=\"https://github.com/{}/{}/tree/{}/{}#:~:text=fn {}\" target=\"_blank\"><sup><small>CODE</small></sup></a>", &org, &repo_name, rev, file, name)
+/// Link to the weight function. Jumps to its lines if they are known, otherwise to its name.
+pub(crate) fn code_link(
+	repo_name: &str,
+	org: &str,
+	name: &str,
+	file: &str,
+	rev: &str,
+	lines: Option<LineRange>,
+) -> String {
+	let anchor = match lines {
+		Some(l) => format!("L{}-L{}", l.start, l.end),
+		None => format!(":~:text=fn {}", name),
+	};
+	format!("<a href=\"https://github.com/{}/{}/blob/{}/{}#{}\" target=\"_blank\"><sup><small>CODE</small></sup></a>", &org, &repo_name, rev, file, anchor)
 }
 
 pub(crate) fn html_color_percent(p: Percent, change: RelativeChange) -> String {
 	match change {
-		RelativeChange::Changed => {
+		RelativeChange::Changed | RelativeChange::Renamed => {
 			if p < 0.0 {
 				format!("<p style='color:green'>-{:.2?}%</p>", p.abs())
 			} else if p > 0.0 {
@@ -117,7 +145,7 @@ pub(crate) fn html_color_percent(p: Percent, change: RelativeChange) -> String {
 
 pub(crate) fn html_color_abs(change: &TermChange, unit: Dimension) -> String {
 	match change.change {
-		RelativeChange::Changed => {
+		RelativeChange::Changed | RelativeChange::Renamed => {
 			let diff = change.new_v.unwrap() as i128 - change.old_v.unwrap() as i128;
 			if diff < 0 {
 				format!("<p style='color:green'>-{}</p>", unit.fmt_value(diff.unsigned_abs()))
@@ -140,7 +168,7 @@ pub(crate) fn html_color_abs(change: &TermChange, unit: Dimension) -> String {
 fn order_percent(change: &TermChange) -> i128 {
 	match change.change {
 		// This only considers the first three digits of the percent since the UI only shows these.
-		RelativeChange::Changed => {
+		RelativeChange::Changed | RelativeChange::Renamed => {
 			// The ordering will be wrong for very large relative changes, but still better than
 			// having them rank above 'Added'.
 			((change.percent * 1000.0) as i128).min(u32::MAX as i128)
@@ -153,7 +181,8 @@ fn order_percent(change: &TermChange) -> i128 {
 
 fn order_abs(change: &TermChange) -> i128 {
 	match change.change {
-		RelativeChange::Changed => change.new_v.unwrap() as i128 - change.old_v.unwrap() as i128,
+		RelativeChange::Changed | RelativeChange::Renamed =>
+			change.new_v.unwrap() as i128 - change.old_v.unwrap() as i128,
 		RelativeChange::Unchanged => 0,
 		RelativeChange::Added => i128::MAX,
 		RelativeChange::Removed => i128::MIN,
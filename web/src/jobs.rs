@@ -0,0 +1,129 @@
+//! Run comparisons in the background and poll their progress.
+//!
+//! Large comparisons take longer than most reverse proxies wait for a response. Identical requests
+//! share one job and completed jobs keep their result for [`JOB_TTL`], like the cache of the
+//! synchronous comparisons.
+
+use std::{
+	path::Path,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use subweight_core::{progress::Progress, schema::VersionedDiff};
+
+use crate::CompareArgs;
+
+lazy_static! {
+	static ref JOBS: DashMap<u64, Arc<Job>> = DashMap::new();
+	/// The job of each request. Failed jobs are removed so that they can be retried.
+	static ref JOB_IDS: DashMap<CompareArgs, u64> = DashMap::new();
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// How long a finished job is shared with identical requests and can be polled.
+pub const JOB_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum JobState {
+	Queued,
+	Running { stage: String, done: usize, total: usize },
+	Done { result: VersionedDiff },
+	Failed { error: String },
+}
+
+pub struct Job {
+	state: Mutex<JobState>,
+	finished: Mutex<Option<Instant>>,
+}
+
+impl Job {
+	pub fn state(&self) -> JobState {
+		self.state.lock().expect("Lock is not poisoned").clone()
+	}
+
+	fn expired(&self, now: Instant) -> bool {
+		let finished = *self.finished.lock().expect("Lock is not poisoned");
+		finished.map_or(false, |f| now.duration_since(f) > JOB_TTL)
+	}
+
+	fn set(&self, state: JobState) {
+		*self.state.lock().expect("Lock is not poisoned") = state;
+	}
+}
+
+impl Progress for Job {
+	fn fetched(&self, refname: &str, received: usize, total: usize) {
+		self.set(JobState::Running {
+			stage: format!("Fetching {}", refname),
+			done: received,
+			total,
+		});
+	}
+
+	fn files_discovered(&self, refname: &str, count: usize) {
+		self.set(JobState::Running {
+			stage: format!("Parsing {}", refname),
+			done: 0,
+			total: count,
+		});
+	}
+
+	fn file_parsed(&self, _file: &Path) {
+		if let JobState::Running { done, .. } =
+			&mut *self.state.lock().expect("Lock is not poisoned")
+		{
+			*done += 1;
+		}
+	}
+
+	fn extrinsic_compared(&self, done: usize, total: usize) {
+		self.set(JobState::Running { stage: "Comparing".into(), done, total });
+	}
+}
+
+/// Start a job for the comparison or return the id of the existing one.
+pub fn submit(args: CompareArgs) -> u64 {
+	evict(Instant::now());
+	*JOB_IDS.entry(args.clone()).or_insert_with(|| {
+		let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+		let job = Arc::new(Job { state: Mutex::new(JobState::Queued), finished: Mutex::new(None) });
+		JOBS.insert(id, job.clone());
+
+		std::thread::spawn(move || run(args, job));
+		id
+	})
+}
+
+/// Forget the jobs that finished more than [`JOB_TTL`] ago, so that a request runs again.
+fn evict(now: Instant) {
+	JOBS.retain(|_, job| !job.expired(now));
+	JOB_IDS.retain(|_, id| JOBS.contains_key(id));
+}
+
+pub fn get(id: u64) -> Option<Arc<Job>> {
+	evict(Instant::now());
+	JOBS.get(&id).map(|job| job.value().clone())
+}
+
+fn run(args: CompareArgs, job: Arc<Job>) {
+	crate::metrics::COMPARISONS.inc();
+	let state = match crate::do_compare(args.clone(), &*job) {
+		Ok(result) => JobState::Done { result: VersionedDiff::new(result) },
+		Err(err) => {
+			crate::metrics::ERRORS.inc();
+			JOB_IDS.remove(&args);
+			JobState::Failed { error: err.to_string() }
+		},
+	};
+	job.set(state);
+	*job.finished.lock().expect("Lock is not poisoned") = Some(Instant::now());
+}
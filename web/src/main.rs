@@ -6,11 +6,11 @@ use actix_web::{
 	http::header::{CacheControl, CacheDirective},
 	middleware,
 	middleware::Logger,
-	web, App, HttpRequest, HttpResponse, HttpServer, Responder, Result,
+	post, web, App, HttpRequest, HttpResponse, HttpServer, Responder, Result,
 };
 use badge_maker::BadgeBuilder;
 use cached::proc_macro::cached;
-use clap::Parser;
+use clap::{builder::ArgPredicate, Parser};
 use dashmap::DashMap;
 use lazy_static::{__Deref, lazy_static};
 use log::info;
@@ -22,12 +22,20 @@ use std::{
 };
 
 use subweight_core::{
-	compare_commits, filter_changes, sort_changes, CompareMethod, CompareParams, Dimension,
-	FilterParams, TotalDiff, VERSION,
+	compare_commits_with_progress, filter_changes, progress::Progress, schema::VersionedDiff,
+	sort_changes, CompareMethod, CompareParams, Dimension, FilterParams, TotalDiff, VERSION,
 };
 
+mod config;
 mod git;
 mod html;
+mod jobs;
+mod metrics;
+#[cfg(feature = "sqlite")]
+mod store;
+#[cfg(feature = "sqlite")]
+mod trends;
+mod webhook;
 use html::*;
 
 #[derive(Debug, Parser, Clone)]
@@ -39,9 +47,18 @@ pub(crate) struct MainCmd {
 	#[clap(long = "static", short, default_value = "web/static")]
 	pub static_path: PathBuf,
 
-	#[clap(long, num_args = 0.., default_value = "polkadot")]
+	#[clap(
+		long,
+		num_args = 0..,
+		default_value = "polkadot",
+		default_value_if("config", ArgPredicate::IsPresent, None)
+	)]
 	pub repos: Vec<String>,
 
+	/// TOML file that describes the repos to serve; see the `config` module.
+	#[clap(long)]
+	pub config: Option<PathBuf>,
+
 	#[clap(long, short, default_value = "localhost")]
 	pub endpoint: String,
 
@@ -55,6 +72,45 @@ pub(crate) struct MainCmd {
 	/// PEM format key.
 	#[clap(long, requires("cert"))]
 	pub key: Option<String>,
+
+	/// Persistently cache the parsed weight files of each commit in this directory.
+	#[clap(long)]
+	pub cache_dir: Option<PathBuf>,
+
+	/// Keep the results of all comparisons in this SQLite database.
+	#[cfg(feature = "sqlite")]
+	#[clap(long)]
+	pub store: Option<PathBuf>,
+
+	/// Serve trend charts from this weight database of `subweight db ingest`.
+	#[cfg(feature = "sqlite")]
+	#[clap(long)]
+	pub history: Option<PathBuf>,
+
+	/// `WeightToFee` of the runtimes for comparisons in the `fee` unit; a preset or TOML file.
+	#[clap(long, value_parser = <subweight_core::fee::FeeParams as std::str::FromStr>::from_str)]
+	pub fee_params: Option<subweight_core::fee::FeeParams>,
+
+	/// Time weight of one storage read and write; `rocksdb`, `paritydb` or `READ,WRITE`.
+	#[clap(long, default_value = "rocksdb", value_parser = <subweight_core::DbWeights as std::str::FromStr>::from_str)]
+	pub db_weights: subweight_core::DbWeights,
+
+	/// Secret of the GitHub webhook. Enables the `/webhook/github` endpoint.
+	#[clap(long)]
+	pub webhook_secret: Option<String>,
+
+	/// Weight files that are compared for pull requests of the webhook. Only used for repos
+	/// without `path_patterns` in the `--config`.
+	#[clap(long, default_value = "**/weights/*.rs")]
+	pub webhook_path_pattern: String,
+
+	/// Minimal change in percent that is reported for pull requests of the webhook.
+	#[clap(long, default_value = "5")]
+	pub webhook_threshold: u32,
+
+	/// Token to comment the result of the webhook on the pull request.
+	#[clap(long, requires("webhook_secret"))]
+	pub github_token: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
@@ -72,6 +128,16 @@ pub struct CompareArgs {
 	method: CompareMethod,
 }
 
+impl CompareArgs {
+	/// HTML decode the branch names and the path pattern. TODO clean this up
+	fn decode_html(mut self) -> Self {
+		self.new = html_escape::decode_html_entities(&self.new).to_string();
+		self.old = html_escape::decode_html_entities(&self.old).to_string();
+		self.path_pattern = html_escape::decode_html_entities(&self.path_pattern).to_string();
+		self
+	}
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct VersionArgs {
 	is: Option<String>,
@@ -82,6 +148,7 @@ pub struct Repo {
 	name: String,
 	path: PathBuf,
 	organization: String,
+	config: config::RepoConfig,
 }
 
 lazy_static! {
@@ -101,37 +168,65 @@ async fn main() -> std::io::Result<()> {
 	let cmd = CONFIG.clone();
 	let static_path = cmd.static_path.into_os_string();
 
-	if cmd.repos.is_empty() {
+	let mut repos = cmd
+		.repos
+		.iter()
+		.map(|name| config::RepoConfig { name: name.clone(), ..Default::default() })
+		.collect::<Vec<_>>();
+	if let Some(path) = &cmd.config {
+		let config = config::Config::load(path)
+			.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+		repos.extend(config.repos);
+	}
+	if repos.is_empty() {
 		return Err(std::io::Error::new(
 			std::io::ErrorKind::Other,
-			"Need at least one value to --repos",
+			"Need at least one value to --repos or one repo in --config",
 		))
 	}
-	for repo_name in cmd.repos {
-		// TODO check that the folder exists
+	for repo_config in repos {
+		let repo_name = repo_config.name.clone();
 		let path = cmd.root_path.join(&repo_name);
+		if !path.exists() {
+			if let Some(url) = &repo_config.url {
+				info!("Cloning '{}' into '{}'", url, path.display());
+				git::clone(url, &path)
+					.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+			}
+		}
+		// Check if the repo directory exists.
+		if !path.exists() {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::Other,
+				format!("Repo directory '{}' does not exist", path.display()),
+			))
+		}
 		let organization = git::get_origin_org(&path).map_err(|e| {
 			std::io::Error::new(
 				std::io::ErrorKind::Other,
 				format!("Failed to get origin of {}: {}", repo_name, e),
 			)
 		})?;
+		info!("Exposing repo '{}/{}' at '{}'", &organization, &repo_name, path.display());
 		REPOS.insert(
 			repo_name.clone(),
-			Repo {
-				name: repo_name.clone(),
-				path: path.clone(),
-				organization: organization.clone(),
-			},
+			Repo { name: repo_name, path, organization, config: repo_config },
 		);
-		// Check if the repo directory exists.
-		if !path.exists() {
-			return Err(std::io::Error::new(
+	}
+	#[cfg(feature = "sqlite")]
+	if let Some(path) = &cmd.store {
+		store::init(path).map_err(|e| {
+			std::io::Error::new(
 				std::io::ErrorKind::Other,
-				format!("Repo directory '{}' does not exist", path.display()),
-			))
-		}
-		info!("Exposing repo '{}/{}' at '{}'", &organization, &repo_name, path.display());
+				format!("Failed to open store '{}': {}", path.display(), e),
+			)
+		})?;
+		info!("Storing results in '{}'", path.display());
+	}
+	#[cfg(feature = "sqlite")]
+	if let Some(path) = &cmd.history {
+		trends::init(path).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+		info!("Serving trends of '{}'", path.display());
 	}
 	// check that static_path is a dir
 	if !Path::new(&static_path).is_dir() {
@@ -144,18 +239,28 @@ async fn main() -> std::io::Result<()> {
 	let endpoint = format!("{}:{}", cmd.endpoint, cmd.port);
 	info!("Listening to http://{}", endpoint);
 
+	// The app is only re-bound with the `sqlite` feature.
+	#[allow(clippy::let_and_return)]
 	let server = HttpServer::new(move || {
-		App::new()
+		let app = App::new()
 			.wrap(middleware::Compress::default())
 			.wrap(Logger::new("%a %r %s %b %{Referer}i %Ts"))
 			.service(fs::Files::new("/static", &static_path).show_files_listing())
 			.service(compare)
+			.service(api_compare)
+			.service(api_submit)
+			.service(api_job)
+			.service(github_webhook)
+			.service(metrics_endpoint)
 			.service(version_badge)
 			.service(version)
 			.service(root)
 			.service(branches)
 			.service(compare_mrs)
-			.service(compare_commit)
+			.service(compare_commit);
+		#[cfg(feature = "sqlite")]
+		let app = app.service(trend).service(api_trend);
+		app
 	})
 	.workers(4);
 
@@ -228,6 +333,7 @@ async fn branches(req: HttpRequest) -> Result<impl Responder> {
 			.arg("--prune")
 			.arg("--tags")
 			.current_dir(repo.path.deref())
+			.envs(subweight_core::auth::credentials().git_env())
 			.output()
 			.map_err(|e| {
 				std::io::Error::new(
@@ -292,11 +398,7 @@ async fn compare(req: HttpRequest) -> HttpResponse {
 	if let Err(err) = args {
 		return http_500(templates::Error::render(&err.to_string()))
 	}
-	let mut args = args.unwrap().into_inner();
-	// HTML decode the new and old branch names. TODO clean this up
-	args.new = html_escape::decode_html_entities(&args.new).to_string();
-	args.old = html_escape::decode_html_entities(&args.old).to_string();
-	args.path_pattern = html_escape::decode_html_entities(&args.path_pattern).to_string();
+	let args = args.unwrap().into_inner().decode_html();
 
 	let repos = REPOS.iter().map(|r| r.key().clone()).collect();
 	// TODO dont do two lookups here…
@@ -309,7 +411,7 @@ async fn compare(req: HttpRequest) -> HttpResponse {
 		)))
 	}
 
-	match do_compare_cached(args.clone()) {
+	match compare_cached(args.clone()) {
 		Ok(res) => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(
 			templates::Compare::render(
 				&res.value,
@@ -323,6 +425,112 @@ async fn compare(req: HttpRequest) -> HttpResponse {
 	}
 }
 
+/// Same as `/compare` but returns the diff as JSON.
+///
+/// The schema is the same as for `--format json` of the CLI.
+#[get("/api/v1/compare")]
+async fn api_compare(req: HttpRequest) -> HttpResponse {
+	let args = match api_args(&req) {
+		Ok(args) => args,
+		Err(err) => return err,
+	};
+
+	match compare_cached(args) {
+		Ok(res) => HttpResponse::Ok().json(VersionedDiff::new(res.value)),
+		Err(e) => HttpResponse::InternalServerError().json(ApiError { error: e.to_string() }),
+	}
+}
+
+/// Start the comparison in the background and return the id of its job.
+///
+/// Takes the same query parameters as `GET /api/v1/compare`.
+#[post("/api/v1/compare")]
+async fn api_submit(req: HttpRequest) -> HttpResponse {
+	let args = match api_args(&req) {
+		Ok(args) => args,
+		Err(err) => return err,
+	};
+
+	#[derive(Serialize)]
+	struct Submitted {
+		id: u64,
+		url: String,
+	}
+
+	let id = jobs::submit(args);
+	HttpResponse::Accepted().json(Submitted { id, url: format!("/api/v1/jobs/{}", id) })
+}
+
+/// The progress of a job and its result once it is done.
+#[get("/api/v1/jobs/{id}")]
+async fn api_job(id: web::Path<u64>) -> HttpResponse {
+	match jobs::get(*id) {
+		Some(job) => HttpResponse::Ok().json(job.state()),
+		None => HttpResponse::NotFound().json(ApiError { error: format!("Unknown job {}", id) }),
+	}
+}
+
+/// Chart of the weight of an extrinsic across the commits of the `--history` database.
+///
+/// Takes the same query parameters as `GET /api/v1/trend`.
+#[cfg(feature = "sqlite")]
+#[get("/trend")]
+async fn trend(web::Query(args): web::Query<trends::TrendArgs>) -> HttpResponse {
+	http_200(templates::Trend::render(&args.file, &args.extrinsic))
+}
+
+/// The weight of an extrinsic across the commits of the `--history` database; one series per
+/// dimension.
+#[cfg(feature = "sqlite")]
+#[get("/api/v1/trend")]
+async fn api_trend(web::Query(args): web::Query<trends::TrendArgs>) -> HttpResponse {
+	match trends::query(&args) {
+		Ok(trend) => HttpResponse::Ok().json(trend),
+		Err(error) => HttpResponse::NotFound().json(ApiError { error }),
+	}
+}
+
+/// Receives the `pull_request` events of a GitHub webhook; see [`webhook`].
+#[post("/webhook/github")]
+async fn github_webhook(req: HttpRequest, body: web::Bytes) -> HttpResponse {
+	let Some(secret) = &CONFIG.webhook_secret else { return HttpResponse::NotFound().finish() };
+	let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+	if !webhook::verify_signature(secret, &body, header("X-Hub-Signature-256").unwrap_or_default())
+	{
+		return HttpResponse::Unauthorized().json(ApiError { error: "Invalid signature".into() })
+	}
+	// Other events like `ping` are acknowledged but ignored.
+	if header("X-GitHub-Event") != Some("pull_request") {
+		return HttpResponse::Ok().finish()
+	}
+
+	match serde_json::from_slice::<webhook::PullRequestEvent>(&body) {
+		Ok(event) => {
+			webhook::spawn(event);
+			HttpResponse::Accepted().finish()
+		},
+		Err(e) => HttpResponse::BadRequest().json(ApiError { error: e.to_string() }),
+	}
+}
+
+#[derive(Serialize)]
+struct ApiError {
+	error: String,
+}
+
+fn api_args(req: &HttpRequest) -> Result<CompareArgs, HttpResponse> {
+	let args = web::Query::<CompareArgs>::from_query(req.query_string())
+		.map_err(|e| HttpResponse::BadRequest().json(ApiError { error: e.to_string() }))?
+		.into_inner()
+		.decode_html();
+	if !REPOS.contains_key(&args.repo) {
+		return Err(HttpResponse::BadRequest()
+			.json(ApiError { error: format!("Unknown repo '{}'", &args.repo) }))
+	}
+	Ok(args)
+}
+
 #[derive(Deserialize)]
 struct MrArgs {}
 
@@ -362,6 +570,14 @@ async fn version(web::Query(args): web::Query<VersionArgs>) -> HttpResponse {
 	}
 }
 
+/// Metrics for Prometheus; see [`metrics`].
+#[get("/metrics")]
+async fn metrics_endpoint() -> HttpResponse {
+	HttpResponse::Ok()
+		.content_type("text/plain; version=0.0.4")
+		.body(metrics::render())
+}
+
 /// Returns a version badge in the style of <https://shields.io>.
 #[get("/version/badge")]
 async fn version_badge() -> HttpResponse {
@@ -383,15 +599,49 @@ async fn version_badge() -> HttpResponse {
 		.body(svg)
 }
 
+/// Same as [`do_compare_cached`] but records the metrics of the request.
+fn compare_cached(
+	args: CompareArgs,
+) -> Result<cached::Return<TotalDiff>, Box<dyn std::error::Error>> {
+	metrics::COMPARISONS.inc();
+	let res = do_compare_cached(args);
+	match &res {
+		Ok(res) if res.was_cached => metrics::CACHE_HITS.inc(),
+		Ok(_) => (),
+		Err(_) => metrics::ERRORS.inc(),
+	}
+	res
+}
+
 #[cached(time = 600, result = true, sync_writes = true, with_cached_flag = true)]
 fn do_compare_cached(
 	args: CompareArgs,
 ) -> Result<cached::Return<TotalDiff>, Box<dyn std::error::Error>> {
+	Ok(cached::Return::new(do_compare(args, &())?))
+}
+
+fn do_compare(
+	mut args: CompareArgs,
+	progress: &dyn Progress,
+) -> Result<TotalDiff, Box<dyn std::error::Error>> {
 	// Call get_mut to acquire an exclusive permit.
 	// Assumption tested in `dashmap_exclusive_permit_works`.
 	let repo = REPOS
 		.get_mut(&args.repo)
 		.ok_or(format!("Value '{}' is invalid for argument 'repo'.", &args.repo))?;
+	for refname in [args.old.trim(), args.new.trim()] {
+		if !repo.config.allows(refname) {
+			return Err(format!("Ref '{}' is not allowed for repo '{}'", refname, &args.repo).into())
+		}
+	}
+	if args.path_pattern.trim().is_empty() {
+		args.path_pattern = repo.config.path_patterns.join(",");
+	}
+	#[cfg(feature = "sqlite")]
+	if let Some(diff) = store::lookup(&repo.path, &args) {
+		metrics::STORE_HITS.inc();
+		return Ok(diff)
+	}
 
 	let (new, old) = (args.new.trim(), args.old.trim());
 	let (_thresh, unit, method, path_pattern, ignore_errors, git_pull) = (
@@ -403,19 +653,46 @@ fn do_compare_cached(
 		args.git_pull.unwrap_or(true),
 	);
 
-	let params = CompareParams { method, ignore_errors, unit, git_pull, offline: false };
+	let params = CompareParams {
+		method,
+		ignore_errors,
+		unit,
+		git_pull,
+		cache_dir: CONFIG.cache_dir.clone(),
+		fee_params: CONFIG.fee_params.clone(),
+		db_weights: CONFIG.db_weights,
+		// Concurrent requests must not share a checkout.
+		git_objects: true,
+		..Default::default()
+	};
 	let filter = FilterParams {
 		threshold: args.threshold as f64,
+		threshold_abs: None,
+		threshold_mode: Default::default(),
 		change: None,
-		pallet: args.pallet,
-		extrinsic: args.extrinsic,
+		pallet: args.pallet.clone(),
+		extrinsic: args.extrinsic.clone(),
+		ignore_extrinsic: Vec::new(),
+		ignore_pallet: Vec::new(),
+		allowlist: None,
 	};
 
-	let mut diff = compare_commits(&repo.path, old, new, &params, &filter, path_pattern, 6000)?;
+	let mut diff = compare_commits_with_progress(
+		&repo.path,
+		old,
+		new,
+		&params,
+		&filter,
+		path_pattern,
+		6000,
+		&metrics::Timed(progress),
+	)?;
 	diff = filter_changes(diff, &filter);
 	sort_changes(&mut diff);
+	#[cfg(feature = "sqlite")]
+	store::save(&repo.path, &args, &diff);
 
-	Ok(cached::Return::new(diff))
+	Ok(diff)
 }
 
 #[cfg(test)]
@@ -0,0 +1,175 @@
+//! Prometheus metrics of the web service that are exposed at `/metrics`.
+//!
+//! There are only a handful of metrics, so they are kept in atomics and rendered in the text
+//! exposition format by hand.
+
+use std::{
+	fmt::Write,
+	path::Path,
+	sync::atomic::{AtomicU64, Ordering},
+	time::Duration,
+};
+
+use subweight_core::progress::Progress;
+
+pub static COMPARISONS: Counter = Counter::new();
+pub static CACHE_HITS: Counter = Counter::new();
+pub static STORE_HITS: Counter = Counter::new();
+pub static ERRORS: Counter = Counter::new();
+pub static FETCH_DURATION: Histogram = Histogram::new();
+pub static PARSE_DURATION: Histogram = Histogram::new();
+
+/// Upper bounds of the histogram buckets in seconds.
+const BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+pub struct Counter(AtomicU64);
+
+impl Counter {
+	const fn new() -> Self {
+		Self(AtomicU64::new(0))
+	}
+
+	pub fn inc(&self) {
+		self.0.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn get(&self) -> u64 {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+pub struct Histogram {
+	/// Non-cumulative count of each bucket; the cumulative counts are computed when rendering.
+	buckets: [AtomicU64; BUCKETS.len()],
+	count: AtomicU64,
+	sum_micros: AtomicU64,
+}
+
+impl Histogram {
+	const fn new() -> Self {
+		#[allow(clippy::declare_interior_mutable_const)]
+		const ZERO: AtomicU64 = AtomicU64::new(0);
+		Self { buckets: [ZERO; BUCKETS.len()], count: ZERO, sum_micros: ZERO }
+	}
+
+	pub fn observe(&self, took: Duration) {
+		let secs = took.as_secs_f64();
+		if let Some(i) = BUCKETS.iter().position(|b| secs <= *b) {
+			self.buckets[i].fetch_add(1, Ordering::Relaxed);
+		}
+		self.count.fetch_add(1, Ordering::Relaxed);
+		self.sum_micros.fetch_add(took.as_micros() as u64, Ordering::Relaxed);
+	}
+
+	fn render(&self, out: &mut String, name: &str, help: &str) {
+		let _ = writeln!(out, "# HELP {} {}\n# TYPE {} histogram", name, help, name);
+		let mut cumulative = 0;
+		for (bound, bucket) in BUCKETS.iter().zip(&self.buckets) {
+			cumulative += bucket.load(Ordering::Relaxed);
+			let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+		}
+		let count = self.count.load(Ordering::Relaxed);
+		let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+		let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+		let _ = writeln!(out, "{}_sum {}\n{}_count {}", name, sum, name, count);
+	}
+}
+
+fn render_counter(out: &mut String, name: &str, help: &str, values: &[(&str, &Counter)]) {
+	let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+	for (labels, counter) in values {
+		let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+		let _ = writeln!(out, "{}{} {}", name, labels, counter.get());
+	}
+}
+
+/// All metrics in the Prometheus text format.
+pub fn render() -> String {
+	let mut out = String::new();
+	render_counter(
+		&mut out,
+		"subweight_comparisons_total",
+		"Comparisons that were served, including cached ones.",
+		&[("", &COMPARISONS)],
+	);
+	render_counter(
+		&mut out,
+		"subweight_cache_hits_total",
+		"Comparisons that were answered from a cache.",
+		&[("cache=\"memory\"", &CACHE_HITS), ("cache=\"store\"", &STORE_HITS)],
+	);
+	render_counter(
+		&mut out,
+		"subweight_errors_total",
+		"Comparisons that failed.",
+		&[("", &ERRORS)],
+	);
+	FETCH_DURATION.render(
+		&mut out,
+		"subweight_git_fetch_duration_seconds",
+		"Duration of fetching a ref from the origin of a repo.",
+	);
+	PARSE_DURATION.render(
+		&mut out,
+		"subweight_parse_duration_seconds",
+		"Duration of parsing the weight files of a ref.",
+	);
+	out
+}
+
+/// Records the durations that a comparison reports and forwards everything to `0`.
+pub struct Timed<'a>(pub &'a dyn Progress);
+
+impl Progress for Timed<'_> {
+	fn fetched(&self, refname: &str, received: usize, total: usize) {
+		self.0.fetched(refname, received, total);
+	}
+
+	fn fetch_finished(&self, refname: &str, took: Duration) {
+		FETCH_DURATION.observe(took);
+		self.0.fetch_finished(refname, took);
+	}
+
+	fn files_discovered(&self, refname: &str, count: usize) {
+		self.0.files_discovered(refname, count);
+	}
+
+	fn file_parsed(&self, file: &Path) {
+		self.0.file_parsed(file);
+	}
+
+	fn files_parsed(&self, refname: &str, took: Duration) {
+		PARSE_DURATION.observe(took);
+		self.0.files_parsed(refname, took);
+	}
+
+	fn extrinsic_compared(&self, done: usize, total: usize) {
+		self.0.extrinsic_compared(done, total);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn histogram_render_works() {
+		let histogram = Histogram::new();
+		histogram.observe(Duration::from_millis(200));
+		histogram.observe(Duration::from_secs(3));
+		histogram.observe(Duration::from_secs(600));
+
+		let mut out = String::new();
+		histogram.render(&mut out, "test", "Help.");
+		let lines = out.lines().collect::<Vec<_>>();
+
+		assert_eq!(lines[..2], ["# HELP test Help.", "# TYPE test histogram"]);
+		assert!(lines.contains(&"test_bucket{le=\"0.1\"} 0"));
+		assert!(lines.contains(&"test_bucket{le=\"0.25\"} 1"));
+		assert!(lines.contains(&"test_bucket{le=\"5\"} 2"));
+		assert!(lines.contains(&"test_bucket{le=\"120\"} 2"));
+		assert!(lines.contains(&"test_bucket{le=\"+Inf\"} 3"));
+		assert!(lines.contains(&"test_sum 603.2"));
+		assert!(lines.contains(&"test_count 3"));
+	}
+}
@@ -0,0 +1,146 @@
+//! Keep the results of comparisons in an SQLite database so that they survive restarts.
+//!
+//! Results are stored by the commits that were compared and not by the requested refs. Requests
+//! that pull moving branches therefore only read from the store once the branches were fetched by
+//! an earlier comparison; requests for tags and commits are served from it right away.
+
+use std::{
+	path::Path,
+	sync::{Mutex, OnceLock},
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use subweight_core::TotalDiff;
+
+use crate::{git, CompareArgs};
+
+static STORE: OnceLock<Store> = OnceLock::new();
+
+pub struct Store {
+	conn: Mutex<Connection>,
+}
+
+/// Identifies a comparison by its commits and all other parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+	pub repo: String,
+	pub old: String,
+	pub new: String,
+	pub params: String,
+}
+
+impl Store {
+	/// Open or create the database at `path`.
+	pub fn open(path: &Path) -> rusqlite::Result<Self> {
+		let conn = Connection::open(path)?;
+		conn.execute(
+			"CREATE TABLE IF NOT EXISTS comparisons (
+				repo TEXT NOT NULL,
+				old TEXT NOT NULL,
+				new TEXT NOT NULL,
+				params TEXT NOT NULL,
+				result TEXT NOT NULL,
+				created INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+				PRIMARY KEY (repo, old, new, params)
+			)",
+			[],
+		)?;
+		Ok(Self { conn: Mutex::new(conn) })
+	}
+
+	pub fn get(&self, key: &Key) -> rusqlite::Result<Option<TotalDiff>> {
+		let conn = self.conn.lock().expect("Lock is not poisoned");
+		let result: Option<String> = conn
+			.query_row(
+				"SELECT result FROM comparisons WHERE repo = ?1 AND old = ?2 AND new = ?3 AND params = ?4",
+				params![key.repo, key.old, key.new, key.params],
+				|row| row.get(0),
+			)
+			.optional()?;
+
+		Ok(result.and_then(|r| serde_json::from_str(&r).ok()))
+	}
+
+	pub fn insert(&self, key: &Key, diff: &TotalDiff) -> rusqlite::Result<()> {
+		let result = serde_json::to_string(diff).expect("Diffs can be serialized");
+		self.conn.lock().expect("Lock is not poisoned").execute(
+			"INSERT OR REPLACE INTO comparisons (repo, old, new, params, result) VALUES (?1, ?2, ?3, ?4, ?5)",
+			params![key.repo, key.old, key.new, key.params, result],
+		)?;
+		Ok(())
+	}
+}
+
+/// Use the database at `path` for all comparisons.
+pub fn init(path: &Path) -> rusqlite::Result<()> {
+	let store = Store::open(path)?;
+	let _ = STORE.set(store);
+	Ok(())
+}
+
+/// The stored result of the comparison if its refs can be resolved without fetching.
+pub fn lookup(repo: &Path, args: &CompareArgs) -> Option<TotalDiff> {
+	let store = STORE.get()?;
+	let pinned = |r: &str| git::is_pinned(repo, r.trim());
+	if args.git_pull.unwrap_or(true) && !(pinned(&args.old) && pinned(&args.new)) {
+		return None
+	}
+
+	let key = key(repo, args)?;
+	store.get(&key).unwrap_or_else(|e| {
+		log::warn!("Failed to read the result store: {}", e);
+		None
+	})
+}
+
+/// Store the result of a comparison.
+pub fn save(repo: &Path, args: &CompareArgs, diff: &TotalDiff) {
+	let (Some(store), Some(key)) = (STORE.get(), key(repo, args)) else { return };
+
+	if let Err(e) = store.insert(&key, diff) {
+		log::warn!("Failed to write the result store: {}", e);
+	}
+}
+
+fn key(repo: &Path, args: &CompareArgs) -> Option<Key> {
+	let params = serde_json::json!({
+		"path_pattern": args.path_pattern.trim(),
+		"extrinsic": args.extrinsic,
+		"pallet": args.pallet,
+		"ignore_errors": args.ignore_errors,
+		"threshold": args.threshold,
+		"unit": args.unit,
+		"method": args.method,
+	});
+
+	Some(Key {
+		repo: args.repo.clone(),
+		old: git::resolve_commit(repo, args.old.trim())?,
+		new: git::resolve_commit(repo, args.new.trim())?,
+		params: params.to_string(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn store_round_trip_works() {
+		let dir = tempfile::tempdir().unwrap();
+		let store = Store::open(&dir.path().join("results.sqlite")).unwrap();
+		let key = Key {
+			repo: "polkadot".into(),
+			old: "a".repeat(40),
+			new: "b".repeat(40),
+			params: "{}".into(),
+		};
+
+		assert!(store.get(&key).unwrap().is_none());
+		store.insert(&key, &TotalDiff::new()).unwrap();
+		assert_eq!(store.get(&key).unwrap().map(|d| d.len()), Some(0));
+		// Other parameters are not mixed up.
+		let other = Key { params: "{\"threshold\":5}".into(), ..key };
+		assert!(store.get(&other).unwrap().is_none());
+	}
+}
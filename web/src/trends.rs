@@ -0,0 +1,135 @@
+//! Trend charts of the weight of an extrinsic across the commits of the history database.
+//!
+//! The database is filled with `subweight db ingest` and only read here. Each trend has one series
+//! per dimension so that the frontend can plot them without further processing.
+
+use std::{
+	path::Path,
+	sync::{Mutex, OnceLock},
+};
+
+use fancy_regex::Regex;
+use serde::{Deserialize, Serialize};
+use subweight_core::db::{WeightDb, WeightRecord};
+
+static HISTORY: OnceLock<Mutex<WeightDb>> = OnceLock::new();
+
+#[derive(Debug, Deserialize)]
+pub struct TrendArgs {
+	/// Any part of the path of the weight file.
+	pub file: String,
+	pub extrinsic: String,
+	/// Only use the commits that were ingested as a ref matching this regex, eg. `^v` for tags.
+	pub refs: Option<String>,
+	/// Only use the last N commits.
+	pub last: Option<usize>,
+}
+
+/// The weight of an extrinsic at each commit; ordered by commit time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trend {
+	pub file: String,
+	pub extrinsic: String,
+	/// The ref that each commit was ingested as.
+	pub labels: Vec<String>,
+	pub commits: Vec<String>,
+	pub ref_time: Vec<u128>,
+	pub proof_size: Vec<u128>,
+}
+
+/// Read the trends from the database at `path`.
+pub fn init(path: &Path) -> Result<(), String> {
+	if !path.exists() {
+		return Err(format!("History database '{}' does not exist", path.display()))
+	}
+	let db = WeightDb::open(path).map_err(|e| e.to_string())?;
+	let _ = HISTORY.set(Mutex::new(db));
+	Ok(())
+}
+
+pub fn query(args: &TrendArgs) -> Result<Trend, String> {
+	let db = HISTORY.get().ok_or("No history database configured")?;
+	let records = db
+		.lock()
+		.expect("Lock is not poisoned")
+		.query(Some(&args.file), Some(&args.extrinsic))
+		.map_err(|e| e.to_string())?;
+	let refs = args.refs.as_deref().map(Regex::new).transpose().map_err(|e| e.to_string())?;
+
+	trend(records, refs.as_ref(), args.last)
+}
+
+fn trend(
+	records: Vec<WeightRecord>,
+	refs: Option<&Regex>,
+	last: Option<usize>,
+) -> Result<Trend, String> {
+	let mut records = records
+		.into_iter()
+		.filter(|r| refs.map_or(true, |re| re.is_match(&r.refname).unwrap_or_default()))
+		.collect::<Vec<_>>();
+	let first = records.first().ok_or("No weights found")?;
+	if let Some(other) = records.iter().find(|r| r.file != first.file) {
+		return Err(format!("Ambiguous file; matches {} and {}", first.file, other.file))
+	}
+	if let Some(last) = last {
+		records.drain(..records.len().saturating_sub(last));
+	}
+
+	let mut trend = Trend {
+		file: records[0].file.clone(),
+		extrinsic: records[0].extrinsic.clone(),
+		labels: Vec::new(),
+		commits: Vec::new(),
+		ref_time: Vec::new(),
+		proof_size: Vec::new(),
+	};
+	for r in records {
+		trend.labels.push(r.refname);
+		trend.commits.push(r.commit);
+		trend.ref_time.push(r.ref_time);
+		trend.proof_size.push(r.proof_size);
+	}
+	Ok(trend)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn record(refname: &str, file: &str, ref_time: u128) -> WeightRecord {
+		WeightRecord {
+			commit: refname.repeat(8),
+			refname: refname.into(),
+			time: 0,
+			file: file.into(),
+			extrinsic: "bond".into(),
+			ref_time,
+			proof_size: ref_time / 10,
+		}
+	}
+
+	#[test]
+	fn trend_works() {
+		let records = vec![
+			record("v1", "pallet_staking.rs", 100),
+			record("master", "pallet_staking.rs", 120),
+			record("v2", "pallet_staking.rs", 150),
+		];
+
+		let all = trend(records.clone(), None, None).unwrap();
+		assert_eq!(all.labels, ["v1", "master", "v2"]);
+		assert_eq!(all.ref_time, [100, 120, 150]);
+		assert_eq!(all.proof_size, [10, 12, 15]);
+
+		let tags = trend(records.clone(), Some(&Regex::new("^v").unwrap()), None).unwrap();
+		assert_eq!(tags.labels, ["v1", "v2"]);
+		let last = trend(records.clone(), None, Some(2)).unwrap();
+		assert_eq!(last.ref_time, [120, 150]);
+
+		assert!(trend(Vec::new(), None, None).is_err());
+		let mut ambiguous = records;
+		ambiguous.push(record("v3", "pallet_staking_async.rs", 150));
+		assert!(trend(ambiguous, None, None).unwrap_err().starts_with("Ambiguous file"));
+	}
+}
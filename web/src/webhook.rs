@@ -0,0 +1,199 @@
+//! Compare the weights of pull requests that are reported by a GitHub webhook.
+//!
+//! Configure a webhook for `pull_request` events with content type `application/json` that
+//! points to `/webhook/github`. Each opened or updated pull request of a served repository is
+//! compared with its base as background job; see [`crate::jobs`]. With `--github-token` the
+//! result is also posted as comment on the pull request.
+
+use std::time::Duration;
+
+use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
+use serde::Deserialize;
+use subweight_core::{CompareMethod, Dimension, TotalDiff};
+
+use crate::{
+	git,
+	jobs::{self, JobState},
+	CompareArgs, CONFIG, REPOS,
+};
+
+/// Maximal number of changes in a comment.
+const MAX_ROWS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequestEvent {
+	pub action: String,
+	pub number: u64,
+	pub pull_request: PullRequest,
+	pub repository: Repository,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequest {
+	pub head: GitRef,
+	pub base: GitRef,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitRef {
+	#[serde(rename = "ref")]
+	pub name: String,
+	pub sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Repository {
+	pub name: String,
+	pub owner: Owner,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Owner {
+	pub login: String,
+}
+
+/// Check the `X-Hub-Signature-256` header of a delivery.
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+	let Some(expected) = signature.strip_prefix("sha256=") else { return false };
+	let mac = PKey::hmac(secret.as_bytes())
+		.and_then(|key| {
+			let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+			signer.update(body)?;
+			signer.sign_to_vec()
+		})
+		.map(|mac| mac.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+
+	match mac {
+		Ok(mac) => mac.len() == expected.len() && memcmp::eq(mac.as_bytes(), expected.as_bytes()),
+		Err(_) => false,
+	}
+}
+
+/// Compare the pull request in the background if it was opened or updated.
+pub fn spawn(event: PullRequestEvent) {
+	if !["opened", "reopened", "synchronize"].contains(&event.action.as_str()) {
+		return
+	}
+
+	std::thread::spawn(move || {
+		if let Err(err) = compare_pull(&event) {
+			log::error!(
+				"Failed to compare pull request #{} of {}: {}",
+				event.number,
+				event.repository.name,
+				err
+			);
+		}
+	});
+}
+
+fn compare_pull(event: &PullRequestEvent) -> Result<(), String> {
+	let (owner, name) = (&event.repository.owner.login, &event.repository.name);
+	let (path, mut path_pattern) = REPOS
+		.get(name)
+		.filter(|r| &r.organization == owner)
+		.map(|r| (r.path.clone(), r.config.path_patterns.join(",")))
+		.ok_or_else(|| format!("Repo {}/{} is not served", owner, name))?;
+	git::fetch_pull(&path, event.number, &event.pull_request.base.name)?;
+	if path_pattern.is_empty() {
+		path_pattern = CONFIG.webhook_path_pattern.clone();
+	}
+
+	let args = CompareArgs {
+		old: event.pull_request.base.sha.clone(),
+		new: event.pull_request.head.sha.clone(),
+		repo: name.clone(),
+		path_pattern,
+		extrinsic: None,
+		pallet: None,
+		ignore_errors: true,
+		threshold: CONFIG.webhook_threshold,
+		unit: Dimension::Time,
+		git_pull: Some(false),
+		method: CompareMethod::GuessWorst,
+	};
+	let id = jobs::submit(args);
+	log::info!("Comparing pull request #{} of {}/{} as job {}", event.number, owner, name, id);
+
+	let diff = loop {
+		match jobs::get(id).map(|job| job.state()) {
+			Some(JobState::Done { result }) => break result.changes,
+			Some(JobState::Failed { error }) => return Err(error),
+			Some(_) => std::thread::sleep(Duration::from_secs(1)),
+			None => return Err(format!("Job {} disappeared", id)),
+		}
+	};
+
+	match &CONFIG.github_token {
+		Some(token) => post_comment(token, event, &comment(event, &diff)),
+		None => Ok(()),
+	}
+}
+
+/// Markdown table of the largest changes.
+fn comment(event: &PullRequestEvent, diff: &TotalDiff) -> String {
+	let short = |sha: &str| sha.chars().take(12).collect::<String>();
+	let mut lines = vec![format!(
+		"Weight changes of {} compared to {}:",
+		short(&event.pull_request.head.sha),
+		short(&event.pull_request.base.sha)
+	)];
+	if diff.is_empty() {
+		lines.push("No changes above the threshold.".into());
+		return lines.join("\n")
+	}
+
+	let fmt = |v: Option<u128>| v.map_or("-".into(), Dimension::fmt_time);
+	lines.push(String::new());
+	lines.push("| File | Extrinsic | Old | New | Change [%] |".into());
+	lines.push("|------|-----------|----:|----:|-----------:|".into());
+	for change in diff.iter().take(MAX_ROWS) {
+		let row = match change.term() {
+			Some(term) => format!(
+				"| {} | {} | {} | {} | {:+.2} |",
+				change.file,
+				change.display_name(),
+				fmt(term.old_v),
+				fmt(term.new_v),
+				term.percent
+			),
+			None => format!("| {} | {} | | | error |", change.file, change.name),
+		};
+		lines.push(row);
+	}
+	if diff.len() > MAX_ROWS {
+		lines.push(format!("\n{} more changes are not shown.", diff.len() - MAX_ROWS));
+	}
+	lines.join("\n")
+}
+
+fn post_comment(token: &str, event: &PullRequestEvent, body: &str) -> Result<(), String> {
+	let url = format!(
+		"https://api.github.com/repos/{}/{}/issues/{}/comments",
+		event.repository.owner.login, event.repository.name, event.number
+	);
+	ureq::post(&url)
+		.set("User-Agent", "subweight")
+		.set("Authorization", &format!("Bearer {}", token))
+		.set("Content-Type", "application/json")
+		.send_string(&serde_json::json!({ "body": body }).to_string())
+		.map_err(|e| format!("Failed to comment on {}: {}", url, e))?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Example from the GitHub documentation.
+	#[test]
+	fn verify_signature_works() {
+		let secret = "It's a Secret to Everybody";
+		let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+		assert!(verify_signature(secret, b"Hello, World!", signature));
+		assert!(!verify_signature(secret, b"Hello, World?", signature));
+		assert!(!verify_signature("wrong", b"Hello, World!", signature));
+		assert!(!verify_signature(secret, b"Hello, World!", &signature[7..]));
+	}
+}
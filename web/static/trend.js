@@ -0,0 +1,39 @@
+// Plots the weight of an extrinsic with one series per dimension.
+//
+// Takes the same query parameters as `/api/v1/trend`.
+$(document).ready(function () {
+	fetch("/api/v1/trend" + window.location.search)
+		.then(response => response.json().then(body => ({ ok: response.ok, body })))
+		.then(({ ok, body }) => {
+			if (!ok) {
+				$("#trendError").text(body.error);
+				return;
+			}
+			const series = (label, data, axis) => ({ label, data, yAxisID: axis, tension: 0.1 });
+
+			new Chart($("#trendChart"), {
+				type: "line",
+				data: {
+					labels: body.labels,
+					datasets: [
+						series("Ref Time [ps]", body.ref_time, "time"),
+						series("Proof Size [bytes]", body.proof_size, "proof"),
+					],
+				},
+				options: {
+					scales: {
+						time: { type: "linear", position: "left", beginAtZero: true },
+						proof: { type: "linear", position: "right", beginAtZero: true },
+					},
+					plugins: {
+						tooltip: {
+							callbacks: {
+								footer: items => body.commits[items[0].dataIndex].substring(0, 12),
+							},
+						},
+					},
+				},
+			});
+		})
+		.catch(err => $("#trendError").text(err));
+});
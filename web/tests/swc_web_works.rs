@@ -97,3 +97,85 @@ fn subweight_web_compare_works() {
 	}
 	panic!("Failed to make request in time");
 }
+
+#[test]
+#[serial]
+#[cfg_attr(not(feature = "polkadot"), ignore)]
+fn subweight_web_api_compare_works() {
+	let _cmd = KillChildOnDrop(
+		Command::cargo_bin("subweight-web")
+			.unwrap()
+			.args([
+				"--root",
+				root_dir().join("repos").to_str().unwrap(),
+				"--repos",
+				"polkadot",
+				"--static",
+				"../web/static",
+			])
+			.env("RUST_LOG", "error")
+			.spawn()
+			.unwrap(),
+	);
+
+	for _ in 0..20 {
+		std::thread::sleep(std::time::Duration::from_millis(100));
+
+		let url = "http://localhost:8080/api/v1/compare?old=v0.9.19&new=v0.9.20&repo=polkadot&threshold=10&unit=weight&path_pattern=runtime/polkadot/src/weights/*.rs&method=base&ignore_errors=false&git_pull=false";
+		let Ok(resp) = reqwest::blocking::get(url) else { continue };
+		let resp = resp.text().unwrap();
+
+		if !resp.starts_with('[') || !resp.contains("\"name\":\"batch_all\"") {
+			panic!("Unexpected response: {}", resp);
+		} else {
+			return
+		}
+	}
+	panic!("Failed to make request in time");
+}
+
+#[test]
+#[serial]
+#[cfg_attr(not(feature = "polkadot"), ignore)]
+fn subweight_web_api_jobs_work() {
+	let _cmd = KillChildOnDrop(
+		Command::cargo_bin("subweight-web")
+			.unwrap()
+			.args([
+				"--root",
+				root_dir().join("repos").to_str().unwrap(),
+				"--repos",
+				"polkadot",
+				"--static",
+				"../web/static",
+			])
+			.env("RUST_LOG", "error")
+			.spawn()
+			.unwrap(),
+	);
+	let client = reqwest::blocking::Client::new();
+
+	for _ in 0..20 {
+		std::thread::sleep(std::time::Duration::from_millis(100));
+
+		let url = "http://localhost:8080/api/v1/compare?old=v0.9.19&new=v0.9.20&repo=polkadot&threshold=10&unit=weight&path_pattern=runtime/polkadot/src/weights/*.rs&method=base&ignore_errors=false&git_pull=false";
+		let Ok(resp) = client.post(url).send() else { continue };
+		let resp = resp.text().unwrap();
+		assert!(resp.contains("\"url\":\"/api/v1/jobs/1\""), "Unexpected response: {}", resp);
+
+		for _ in 0..600 {
+			let job = reqwest::blocking::get("http://localhost:8080/api/v1/jobs/1")
+				.expect("Request error")
+				.text()
+				.unwrap();
+			if job.contains("\"status\":\"done\"") {
+				assert!(job.contains("\"name\":\"batch_all\""), "Unexpected result: {}", job);
+				return
+			}
+			assert!(!job.contains("\"status\":\"failed\""), "Job failed: {}", job);
+			std::thread::sleep(std::time::Duration::from_millis(100));
+		}
+		panic!("Job did not finish in time");
+	}
+	panic!("Failed to make request in time");
+}